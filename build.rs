@@ -7,6 +7,17 @@ use std::{
 
 const CORE_HEADER: &str = "core/include/triton/core/tritonserver.h";
 const CORE_BINDINGS_FILE_NAME: &str = "tritonserver.rs";
+const LIBRARY_FILE_NAME: &str = "libtritonserver.so";
+
+/// Directory of the Triton library to link with. Has precedence over the probing and the download.
+const LIB_DIR_ENV: &str = "TRITONSERVER_LIB_DIR";
+/// Directory of the Triton installation (`$TRITONSERVER_HOME/lib` is probed).
+const HOME_ENV: &str = "TRITONSERVER_HOME";
+/// URL of the `.tar.gz` archive with the Triton library, used with the `download` feature.
+const DOWNLOAD_URL_ENV: &str = "TRITONSERVER_DOWNLOAD_URL";
+/// Directories probed when the location is not set explicitly (the Triton containers install to `/opt/tritonserver`).
+/// The multiarch directory of the target (e.g. `/usr/lib/aarch64-linux-gnu`) is probed after them.
+const DEFAULT_LIB_DIRS: [&str; 2] = ["/opt/tritonserver/lib", "/usr/local/lib"];
 
 fn main() -> Result<(), Error> {
     let no_header_file = !Path::new(CORE_HEADER).is_file();
@@ -49,7 +60,147 @@ fn main() -> Result<(), Error> {
             )
         })?;
 
+    match locate_library()? {
+        Some(dir) => {
+            println!("cargo:rustc-link-search=native={}", dir.display());
+            println!("cargo:rustc-env=TRITONSERVER_RS_LIB_DIR={}", dir.display());
+        }
+        None => println!(
+            "cargo:warning={LIBRARY_FILE_NAME} is not found in {LIB_DIR_ENV}, {HOME_ENV}, pkg-config or {:?}, \
+             relying on the default linker search path",
+            default_lib_dirs()
+        ),
+    }
     println!("cargo:rustc-link-lib=dylib=tritonserver");
 
     Ok(())
 }
+
+/// Find the directory of the Triton library: \
+/// `TRITONSERVER_LIB_DIR`, `TRITONSERVER_HOME/lib`, `pkg-config tritonserver`, the default directories
+/// and, with the `download` feature, the archive downloaded from `TRITONSERVER_DOWNLOAD_URL`.
+fn locate_library() -> Result<Option<PathBuf>, Error> {
+    for env in [LIB_DIR_ENV, HOME_ENV, DOWNLOAD_URL_ENV] {
+        println!("cargo:rerun-if-env-changed={env}");
+    }
+
+    if let Some(dir) = env::var_os(LIB_DIR_ENV) {
+        let dir = PathBuf::from(dir);
+        if !dir.join(LIBRARY_FILE_NAME).is_file() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{LIB_DIR_ENV}={} has no {LIBRARY_FILE_NAME}", dir.display()),
+            ));
+        }
+        return Ok(Some(dir));
+    }
+
+    let probed = env::var_os(HOME_ENV)
+        .map(|home| Path::new(&home).join("lib"))
+        .into_iter()
+        .chain(pkg_config_lib_dirs())
+        .chain(default_lib_dirs())
+        .find(|dir| dir.join(LIBRARY_FILE_NAME).is_file());
+    if probed.is_some() {
+        return Ok(probed);
+    }
+
+    if env::var_os("CARGO_FEATURE_DOWNLOAD").is_some() {
+        return download_library().map(Some);
+    }
+    Ok(None)
+}
+
+/// [DEFAULT_LIB_DIRS] and the multiarch library directory of the target architecture.
+fn default_lib_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<_> = DEFAULT_LIB_DIRS.iter().map(PathBuf::from).collect();
+    if let Ok(arch) = env::var("CARGO_CFG_TARGET_ARCH") {
+        dirs.push(PathBuf::from(format!("/usr/lib/{arch}-linux-gnu")));
+    }
+    dirs
+}
+
+/// Library directories of the `tritonserver` pkg-config package, if pkg-config and the package are installed.
+fn pkg_config_lib_dirs() -> Vec<PathBuf> {
+    Command::new(env::var("PKG_CONFIG").unwrap_or_else(|_| "pkg-config".to_string()))
+        .args(["--libs-only-L", "tritonserver"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .filter_map(|flag| flag.strip_prefix("-L"))
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Download the archive from `TRITONSERVER_DOWNLOAD_URL` with `curl`, unpack it to `OUT_DIR` with `tar`
+/// and return the directory of the library found in it.
+fn download_library() -> Result<PathBuf, Error> {
+    let url = env::var(DOWNLOAD_URL_ENV).map_err(|_| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "Feature `download` requires {DOWNLOAD_URL_ENV} with the URL of the Triton release archive \
+                 matching the bindings API version"
+            ),
+        )
+    })?;
+    let target = PathBuf::from(env::var("OUT_DIR").unwrap()).join("tritonserver");
+    std::fs::create_dir_all(&target)?;
+    let archive = target.join("tritonserver.tar.gz");
+
+    run(Command::new("curl")
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(&archive)
+        .arg(&url))?;
+    run(Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&target))?;
+
+    find_library(&target)?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("Archive {url} has no {LIBRARY_FILE_NAME}"),
+        )
+    })
+}
+
+/// Directory containing the library, searched recursively in `dir`.
+fn find_library(dir: &Path) -> Result<Option<PathBuf>, Error> {
+    if dir.join(LIBRARY_FILE_NAME).is_file() {
+        return Ok(Some(dir.to_path_buf()));
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Some(found) = find_library(&path)? {
+                return Ok(Some(found));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn run(command: &mut Command) -> Result<(), Error> {
+    let status = command.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::Other,
+            format!("{command:?} return error: {status}"),
+        ))
+    }
+}
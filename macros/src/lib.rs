@@ -0,0 +1,289 @@
+//! Derive macros of [tritonserver-rs](https://docs.rs/tritonserver-rs). \
+//! Use them via the crate re-exports (feature "app"), not directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, ExprArray, Fields, LitInt, LitStr,
+    Member, Type,
+};
+
+/// Implement `tritonserver_rs::app::Pipeline` for the struct. \
+/// Check `tritonserver_rs::app` module documentation for the attributes description.
+#[proc_macro_derive(Pipeline, attributes(pipeline))]
+pub fn derive_pipeline(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    pipeline(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Where the string setting of the pipeline is taken from.
+enum Source {
+    Literal(LitStr),
+    Field(Member),
+}
+
+impl Source {
+    fn to_string_tokens(&self) -> TokenStream2 {
+        match self {
+            Source::Literal(literal) => quote!(::std::string::ToString::to_string(#literal)),
+            Source::Field(member) => quote!(::std::string::ToString::to_string(&self.#member)),
+        }
+    }
+}
+
+struct Input {
+    name: LitStr,
+    member: Member,
+    dims: Option<ExprArray>,
+}
+
+#[derive(Default)]
+struct Settings {
+    model: Option<Source>,
+    version: Option<LitInt>,
+    repository: Option<Source>,
+    backends: Option<Source>,
+    output: Option<LitStr>,
+    output_type: Option<Type>,
+    inputs: Vec<Input>,
+}
+
+fn set<T>(slot: &mut Option<T>, value: T, span: proc_macro2::Span, name: &str) -> syn::Result<()> {
+    if slot.replace(value).is_some() {
+        return Err(syn::Error::new(span, format!("duplicate `{name}` setting")));
+    }
+    Ok(())
+}
+
+fn pipeline(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut settings = Settings::default();
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("pipeline"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let span = meta.path.span();
+            if meta.path.is_ident("model") {
+                let value = Source::Literal(meta.value()?.parse()?);
+                set(&mut settings.model, value, span, "model")
+            } else if meta.path.is_ident("version") {
+                set(
+                    &mut settings.version,
+                    meta.value()?.parse()?,
+                    span,
+                    "version",
+                )
+            } else if meta.path.is_ident("repository") {
+                let value = Source::Literal(meta.value()?.parse()?);
+                set(&mut settings.repository, value, span, "repository")
+            } else if meta.path.is_ident("backends") {
+                let value = Source::Literal(meta.value()?.parse()?);
+                set(&mut settings.backends, value, span, "backends")
+            } else if meta.path.is_ident("output") {
+                set(&mut settings.output, meta.value()?.parse()?, span, "output")
+            } else if meta.path.is_ident("output_type") {
+                set(
+                    &mut settings.output_type,
+                    meta.value()?.parse()?,
+                    span,
+                    "output_type",
+                )
+            } else {
+                Err(meta.error("unknown pipeline setting"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "Pipeline can be derived only for structs",
+            ))
+        }
+    };
+    let members: Vec<(Member, &syn::Field)> = match fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| (Member::Named(field.ident.clone().unwrap()), field))
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(index, field)| (Member::Unnamed(index.into()), field))
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    for (member, field) in members {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("pipeline"))
+        {
+            let mut input_name = None;
+            let mut dims = None;
+            attr.parse_nested_meta(|meta| {
+                let span = meta.path.span();
+                let field = || Source::Field(member.clone());
+                if meta.path.is_ident("input") {
+                    set(&mut input_name, meta.value()?.parse()?, span, "input")
+                } else if meta.path.is_ident("dims") {
+                    set(&mut dims, meta.value()?.parse()?, span, "dims")
+                } else if meta.path.is_ident("model") {
+                    set(&mut settings.model, field(), span, "model")
+                } else if meta.path.is_ident("repository") {
+                    set(&mut settings.repository, field(), span, "repository")
+                } else if meta.path.is_ident("backends") {
+                    set(&mut settings.backends, field(), span, "backends")
+                } else {
+                    Err(meta.error("unknown pipeline field setting"))
+                }
+            })?;
+
+            match (input_name, dims) {
+                (Some(name), dims) => settings.inputs.push(Input {
+                    name,
+                    member: member.clone(),
+                    dims,
+                }),
+                (None, Some(dims)) => {
+                    return Err(syn::Error::new(
+                        dims.span(),
+                        "`dims` can be set only for the `input` field",
+                    ))
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    let ident = &input.ident;
+    let missing = |name: &str| {
+        syn::Error::new(
+            ident.span(),
+            format!("pipeline `{name}` is not set: add #[pipeline({name} = \"...\")] to the struct or #[pipeline({name})] to the field"),
+        )
+    };
+    let model = settings
+        .model
+        .as_ref()
+        .ok_or_else(|| missing("model"))?
+        .to_string_tokens();
+    let repository = settings
+        .repository
+        .as_ref()
+        .ok_or_else(|| missing("repository"))?
+        .to_string_tokens();
+    if settings.inputs.is_empty() {
+        return Err(syn::Error::new(
+            ident.span(),
+            "pipeline has no inputs: add #[pipeline(input = \"...\")] to the input field",
+        ));
+    }
+
+    let backends = settings.backends.as_ref().map(|backends| {
+        let backends = backends.to_string_tokens();
+        quote! {
+            fn backends_path(&self) -> ::std::string::String {
+                #backends
+            }
+        }
+    });
+    let version = settings.version.as_ref().map(|version| {
+        quote! {
+            fn model_version(&self) -> i64 {
+                #version
+            }
+        }
+    });
+
+    let add_inputs = settings.inputs.iter().map(|input| {
+        let Input { name, member, dims } = input;
+        match dims {
+            Some(dims) => quote! {
+                request.add_input_with_dims(#name, ::tritonserver_rs::Buffer::from(&self.#member), #dims)?;
+            },
+            None => quote! {
+                request.add_input(#name, ::tritonserver_rs::Buffer::from(&self.#member))?;
+            },
+        }
+    });
+
+    let (output_type, parse_result) = match (&settings.output, &settings.output_type) {
+        (Some(output), Some(output_type)) => (
+            quote!(::std::vec::Vec<#output_type>),
+            quote! {
+                response
+                    .get_output(#output)
+                    .ok_or_else(|| {
+                        ::tritonserver_rs::Error::new(
+                            ::tritonserver_rs::ErrorCode::NotFound,
+                            ::std::format!("Response has no output {}", #output),
+                        )
+                    })?
+                    .to_vec::<#output_type>()
+            },
+        ),
+        (None, None) => (
+            quote!(::tritonserver_rs::Response),
+            quote!(::std::result::Result::Ok(response)),
+        ),
+        (Some(output), None) => {
+            return Err(syn::Error::new(
+                output.span(),
+                "`output_type` must be set along with `output`",
+            ))
+        }
+        (None, Some(output_type)) => {
+            return Err(syn::Error::new(
+                output_type.span(),
+                "`output` must be set along with `output_type`",
+            ))
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let output_type = output_type.into_token_stream();
+
+    Ok(quote! {
+        impl #impl_generics ::tritonserver_rs::app::Pipeline for #ident #ty_generics #where_clause {
+            type Output = #output_type;
+
+            fn model_repo(&self) -> ::std::string::String {
+                #repository
+            }
+
+            #backends
+
+            fn model_name(&self) -> ::std::string::String {
+                #model
+            }
+
+            #version
+
+            fn add_inputs(
+                &mut self,
+                request: &mut ::tritonserver_rs::Request,
+            ) -> ::std::result::Result<(), ::tritonserver_rs::Error> {
+                #(#add_inputs)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn parse_result(
+                &mut self,
+                response: ::tritonserver_rs::Response,
+            ) -> ::std::result::Result<Self::Output, ::tritonserver_rs::Error> {
+                #parse_result
+            }
+        }
+    })
+}
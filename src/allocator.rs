@@ -4,34 +4,116 @@ use std::{
     os::raw::{c_char, c_uint},
     ptr::null_mut,
     sync::{
-        atomic::{AtomicBool, AtomicU32, Ordering},
-        Arc,
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
     },
+    time::SystemTime,
 };
 
 use log::{error, trace};
-use tokio::{runtime::Handle, sync::RwLock};
+use tokio::sync::{watch, RwLock};
 
 use crate::{
     error::{Error, ErrorCode},
     from_char_array,
-    memory::{Buffer, DataType, MemoryType},
-    request::Allocator as AllocTrait,
-    sys,
+    memory::{self, oom, Buffer, DataType, MemoryType, OomEvent},
+    request::{Allocator as AllocTrait, BlockingAllocator},
+    runtime::Runtime,
+    sys, testing,
 };
 
 type Outputs = HashMap<String, Buffer>;
 
+/// Allocator of the output buffers provided by the user.
+pub(crate) enum UserAllocator {
+    Async(Box<dyn AllocTrait>),
+    /// Called right in the Triton callbacks. Check [BlockingAllocator].
+    Blocking(Box<dyn BlockingAllocator>),
+}
+
+enum CustomAllocator {
+    Async(RwLock<Box<dyn AllocTrait>>),
+    Blocking(Mutex<Box<dyn BlockingAllocator>>),
+}
+
+/// Statistics of the output allocations that contradict the previous answer of [AllocTrait::pre_allocation_query]. \
+/// Triton plans the data transfers by the query answers, so each mismatch may cause an extra copy of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QueryMismatch {
+    /// Memory type returned by the last mismatched query.
+    pub queried: MemoryType,
+    /// Memory type of the last mismatched allocation.
+    pub allocated: MemoryType,
+    /// Number of the mismatched allocations.
+    pub count: u64,
+    /// Total byte size of the mismatched allocations.
+    pub byte_size: u64,
+}
+
+/// Query/allocation mismatches of the requests of one server by output name. Check [crate::Server::query_mismatches].
+#[derive(Debug, Default)]
+pub(crate) struct QueryMismatches(Mutex<HashMap<String, QueryMismatch>>);
+
+impl QueryMismatches {
+    pub(crate) fn get(&self) -> HashMap<String, QueryMismatch> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub(crate) fn reset(&self) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    fn record(
+        &self,
+        tensor_name: &str,
+        queried: MemoryType,
+        allocated: MemoryType,
+        byte_size: usize,
+    ) {
+        log::debug!(
+            "Allocator answered {queried:?} on query, but allocated {allocated:?} for output {tensor_name}"
+        );
+        let mut mismatches = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let mismatch = mismatches
+            .entry(tensor_name.to_string())
+            .or_insert(QueryMismatch {
+                queried,
+                allocated,
+                count: 0,
+                byte_size: 0,
+            });
+        mismatch.queried = queried;
+        mismatch.allocated = allocated;
+        mismatch.count += 1;
+        mismatch.byte_size += byte_size as u64;
+    }
+}
+
 pub(crate) struct Inner {
     alloc: *mut sys::TRITONSERVER_ResponseAllocator,
-    pub(crate) output_buffers: RwLock<Outputs>,
+    pub(crate) output_buffers: Mutex<Outputs>,
     alloc_called: AtomicBool,
-    pub(crate) returned_buffers: AtomicU32,
+    /// Number of the buffers returned by Triton with [release].
+    returned_buffers: watch::Sender<u32>,
     /// User is responsible for buffers allocation.
-    custom_allocator: RwLock<Box<dyn AllocTrait>>,
+    custom_allocator: CustomAllocator,
     datatype_hints: HashMap<String, DataType>,
+    /// Host policies by output name.
+    host_policies: HashMap<String, String>,
+    /// Device of the GPU outputs set with [crate::Request::set_device].
+    device: Option<i32>,
+    /// Answers of the allocator on Triton queries by tensor name (None if Triton didn't specify it).
+    query_answers: Mutex<HashMap<Option<String>, MemoryType>>,
+    /// Mismatches of the server the request is inferred on.
+    query_mismatches: Arc<QueryMismatches>,
     /// To run async code in sync C fn
-    runtime: Handle,
+    runtime: Runtime,
 }
 
 /// Response allocator object.
@@ -46,9 +128,12 @@ impl Clone for Allocator {
 
 impl Allocator {
     pub(crate) fn new(
-        custom_allocator: Box<dyn AllocTrait>,
+        custom_allocator: UserAllocator,
         datatype_hints: HashMap<String, DataType>,
-        runtime: Handle,
+        host_policies: HashMap<String, String>,
+        device: Option<i32>,
+        query_mismatches: Arc<QueryMismatches>,
+        runtime: Runtime,
     ) -> Result<Self, Error> {
         let mut ptr = null_mut::<sys::TRITONSERVER_ResponseAllocator>();
         triton_call!(sys::TRITONSERVER_ResponseAllocatorNew(
@@ -60,7 +145,11 @@ impl Allocator {
 
         assert!(!ptr.is_null());
 
-        if custom_allocator.enable_queries() {
+        let enable_queries = match &custom_allocator {
+            UserAllocator::Async(allocator) => allocator.enable_queries(),
+            UserAllocator::Blocking(allocator) => allocator.enable_queries(),
+        };
+        if enable_queries {
             triton_call!(sys::TRITONSERVER_ResponseAllocatorSetQueryFunction(
                 ptr,
                 Some(query)
@@ -69,11 +158,20 @@ impl Allocator {
 
         Ok(Self(Arc::new(Inner {
             alloc: ptr,
-            output_buffers: RwLock::new(HashMap::new()),
+            output_buffers: Mutex::new(HashMap::new()),
             alloc_called: AtomicBool::new(false),
             datatype_hints,
-            returned_buffers: AtomicU32::new(0),
-            custom_allocator: RwLock::new(custom_allocator),
+            host_policies,
+            device,
+            returned_buffers: watch::channel(0).0,
+            custom_allocator: match custom_allocator {
+                UserAllocator::Async(allocator) => CustomAllocator::Async(RwLock::new(allocator)),
+                UserAllocator::Blocking(allocator) => {
+                    CustomAllocator::Blocking(Mutex::new(allocator))
+                }
+            },
+            query_answers: Mutex::new(HashMap::new()),
+            query_mismatches,
             runtime,
         })))
     }
@@ -82,6 +180,23 @@ impl Allocator {
         self.0.alloc_called.load(Ordering::Relaxed)
     }
 
+    /// Take the output buffers returned by Triton.
+    pub(crate) fn take_output_buffers(&self) -> Outputs {
+        self.0
+            .output_buffers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain()
+            .collect()
+    }
+
+    /// Wait until Triton returns `count` buffers.
+    pub(crate) async fn wait_returned(&self, count: u32) {
+        let mut returned = self.0.returned_buffers.subscribe();
+        // Sender is alive while self is, so the wait can't fail.
+        let _ = returned.wait_for(|returned| *returned >= count).await;
+    }
+
     pub(crate) fn get_allocator(&self) -> *mut sys::TRITONSERVER_ResponseAllocator {
         self.0.alloc
     }
@@ -149,19 +264,73 @@ unsafe extern "C" fn alloc(
     let allocator_cloned = allocator.clone();
     let runtime = allocator.0.runtime.clone();
     let output = output_name.clone();
-    let allocation_result = std::thread::spawn(move || {
-        runtime.block_on(async move {
-            allocator_cloned
-                .0
-                .custom_allocator
-                .write()
-                .await
-                .allocate(output, mem_type, byte_size, data_type)
-                .await
-        })
-    })
-    .join()
-    .unwrap();
+    let host_policy = allocator.0.host_policies.get(&output_name).cloned();
+    let mem_type_id = match (allocator.0.device, mem_type) {
+        (Some(device), MemoryType::Gpu) => device,
+        _ => memory_type_id as i32,
+    };
+    let allocation_result = match &allocator.0.custom_allocator {
+        CustomAllocator::Blocking(user_allocator) => {
+            let mut user_allocator = user_allocator
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            allocate_blocking(
+                user_allocator.as_mut(),
+                &output,
+                mem_type,
+                mem_type_id,
+                host_policy.as_deref(),
+                byte_size,
+                data_type,
+            )
+        }
+        CustomAllocator::Async(_) => testing::block_on_callback(runtime, move || async move {
+            let CustomAllocator::Async(custom_allocator) = &allocator_cloned.0.custom_allocator
+            else {
+                unreachable!("allocator kind doesn't change");
+            };
+            let mut custom_allocator = custom_allocator.write().await;
+            let allocator = custom_allocator.as_mut();
+            let device_id = allocator.device_id();
+            let mut result = allocate(
+                allocator,
+                &output,
+                mem_type,
+                mem_type_id,
+                host_policy.as_deref(),
+                byte_size,
+                data_type,
+            )
+            .await;
+
+            if matches!(&result, Err(err) if err.is_out_of_memory()) {
+                let retry = oom::retry_enabled();
+                if retry {
+                    memory::request_trim();
+                    memory::trim_host_heap();
+                    result = allocate(
+                        allocator,
+                        &output,
+                        mem_type,
+                        mem_type_id,
+                        host_policy.as_deref(),
+                        byte_size,
+                        data_type,
+                    )
+                    .await;
+                }
+                oom::report(OomEvent {
+                    tensor_name: output,
+                    byte_size,
+                    memory_type: mem_type,
+                    device_id,
+                    recovered: retry && result.is_ok(),
+                    time: SystemTime::now(),
+                });
+            }
+            result
+        }),
+    };
 
     let users_buffer = match allocation_result {
         Ok(buf) => buf,
@@ -172,23 +341,51 @@ unsafe extern "C" fn alloc(
     };
 
     // Проверки, что буфер подходящий
-    assert!(
-        users_buffer.len >= byte_size,
-        "User allocate smaller buffer ({}b), than required {byte_size} for output {output_name}.",
-        users_buffer.len
-    );
+    if users_buffer.len < byte_size {
+        let err = Error::new(
+            ErrorCode::Internal,
+            format!(
+                "User allocate smaller buffer ({}b), than required {byte_size} for output {output_name}.",
+                users_buffer.len
+            ),
+        );
+        error!("Error in alloc method: {err}");
+        return err.ptr;
+    }
     let mem_type = std::mem::transmute::<u32, MemoryType>(memory_type);
 
-    match (mem_type, users_buffer.memory_type) {
-        (MemoryType::Cpu, MemoryType::Gpu) => panic!("Triton requested to alloc CPU memory for output {output_name} while user provided GPU buffer"),
-        (MemoryType::Gpu, MemoryType::Cpu) => panic!("Triton requested to alloc GPU memory for output {output_name} while user provided CPU buffer"),
-        _ => ()
+    if (mem_type == MemoryType::Gpu) != (users_buffer.memory_type == MemoryType::Gpu) {
+        let err = Error::new(
+            ErrorCode::Internal,
+            format!(
+                "Triton requested to alloc {mem_type:?} memory for output {output_name} while user provided {:?} buffer",
+                users_buffer.memory_type
+            ),
+        );
+        error!("Error in alloc method: {err}");
+        return err.ptr;
     }
 
     let act_mem_type = users_buffer.memory_type;
 
+    let queried = allocator.0.query_answers.lock().ok().and_then(|answers| {
+        answers
+            .get(&Some(output_name.clone()))
+            .or_else(|| answers.get(&None))
+            .copied()
+    });
+    if let Some(queried) = queried.filter(|queried| *queried != act_mem_type) {
+        allocator
+            .0
+            .query_mismatches
+            .record(&output_name, queried, act_mem_type, byte_size);
+    }
+
     *actual_memory_type = act_mem_type as c_uint;
-    *actual_memory_type_id = memory_type_id;
+    *actual_memory_type_id = match act_mem_type {
+        MemoryType::Gpu => users_buffer.device_id as i64,
+        _ => memory_type_id,
+    };
     *buffer = users_buffer.ptr;
 
     *buffer_userp = Box::into_raw(Box::new(ReleaseItems {
@@ -200,6 +397,93 @@ unsafe extern "C" fn alloc(
     null_mut()
 }
 
+/// Allocate the output buffer with the user `allocator` (in the domain of the `host_policy` if it's set).
+async fn allocate(
+    allocator: &mut dyn AllocTrait,
+    tensor_name: &str,
+    memory_type: MemoryType,
+    device_id: i32,
+    host_policy: Option<&str>,
+    byte_size: usize,
+    data_type: DataType,
+) -> Result<Buffer, Error> {
+    let result = match host_policy {
+        Some(host_policy) => {
+            allocator
+                .allocate_with_policy(
+                    tensor_name.to_string(),
+                    memory_type,
+                    device_id,
+                    host_policy.to_string(),
+                    byte_size,
+                    data_type,
+                )
+                .await
+        }
+        None => {
+            allocator
+                .allocate_on(
+                    tensor_name.to_string(),
+                    memory_type,
+                    device_id,
+                    byte_size,
+                    data_type,
+                )
+                .await
+        }
+    };
+    match result {
+        // Size of variable-size outputs may be underestimated by the allocator.
+        Ok(buffer) if buffer.len < byte_size => {
+            allocator
+                .grow(tensor_name.to_string(), buffer, byte_size)
+                .await
+        }
+        result => result,
+    }
+}
+
+/// Allocate the output buffer with the user blocking `allocator`, retrying on OOM as the async allocation does.
+fn allocate_blocking(
+    allocator: &mut dyn BlockingAllocator,
+    tensor_name: &str,
+    memory_type: MemoryType,
+    device_id: i32,
+    host_policy: Option<&str>,
+    byte_size: usize,
+    data_type: DataType,
+) -> Result<Buffer, Error> {
+    let mut allocate = || match host_policy {
+        Some(host_policy) => allocator.allocate_with_policy(
+            tensor_name,
+            memory_type,
+            device_id,
+            host_policy,
+            byte_size,
+            data_type,
+        ),
+        None => allocator.allocate_on(tensor_name, memory_type, device_id, byte_size, data_type),
+    };
+    let mut result = allocate();
+    if matches!(&result, Err(err) if err.is_out_of_memory()) {
+        let retry = oom::retry_enabled();
+        if retry {
+            memory::request_trim();
+            memory::trim_host_heap();
+            result = allocate();
+        }
+        oom::report(OomEvent {
+            tensor_name: tensor_name.to_string(),
+            byte_size,
+            memory_type,
+            device_id: allocator.device_id(),
+            recovered: retry && result.is_ok(),
+            time: SystemTime::now(),
+        });
+    }
+    result
+}
+
 /// Items that flow from alloc fn to release fn.
 struct ReleaseItems {
     allocator: Allocator,
@@ -229,23 +513,44 @@ unsafe extern "C" fn release(
     assert!(byte_size <= allocated_buffer.len);
     assert_eq!(buffer, allocated_buffer.ptr);
 
-    let alloc = allocator.clone();
-    let runtime = allocator.0.runtime.clone();
+    let released = match &allocator.0.custom_allocator {
+        CustomAllocator::Blocking(user_allocator) => user_allocator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .on_release(&allocated_tensor_name, allocated_buffer),
+        CustomAllocator::Async(_) => {
+            let alloc = allocator.clone();
+            let runtime = allocator.0.runtime.clone();
+            let tensor_name = allocated_tensor_name.clone();
+            testing::block_on_callback(runtime, move || async move {
+                let CustomAllocator::Async(custom_allocator) = &alloc.0.custom_allocator else {
+                    unreachable!("allocator kind doesn't change");
+                };
+                custom_allocator
+                    .write()
+                    .await
+                    .on_release(tensor_name, allocated_buffer)
+                    .await
+            })
+        }
+    };
     // Вставляем обратно использованный буфер
-    std::thread::spawn(move || {
-        runtime.block_on(async move {
-            alloc
+    match released {
+        Some(buffer) => {
+            allocator
                 .0
                 .output_buffers
-                .write()
-                .await
-                .insert(allocated_tensor_name, allocated_buffer);
-        })
-    })
-    .join()
-    .unwrap();
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(allocated_tensor_name, buffer);
+        }
+        None => trace!("Buffer of output {allocated_tensor_name} is reclaimed by allocator"),
+    }
 
-    allocator.0.returned_buffers.fetch_add(1, Ordering::Relaxed);
+    allocator
+        .0
+        .returned_buffers
+        .send_modify(|returned| *returned += 1);
     trace!("release is ended");
 
     null_mut()
@@ -268,22 +573,32 @@ unsafe extern "C" fn query(
         Some(alloc) => alloc.clone(),
     };
 
-    let allocator_cloned = allocator.clone();
-    let runtime = allocator.0.runtime.clone();
-    let allocation_result = std::thread::spawn(move || {
-        runtime.block_on(async move {
-            allocator_cloned
-                .0
-                .custom_allocator
-                .write()
-                .await
-                .pre_allocation_query(output_name, byte_size, mem_type)
-                .await
-        })
-    })
-    .join()
-    .unwrap();
+    let tensor_name = output_name.clone();
+    let allocation_result = match &allocator.0.custom_allocator {
+        CustomAllocator::Blocking(user_allocator) => user_allocator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pre_allocation_query(output_name.as_deref(), byte_size, mem_type),
+        CustomAllocator::Async(_) => {
+            let allocator_cloned = allocator.clone();
+            let runtime = allocator.0.runtime.clone();
+            testing::block_on_callback(runtime, move || async move {
+                let CustomAllocator::Async(custom_allocator) = &allocator_cloned.0.custom_allocator
+                else {
+                    unreachable!("allocator kind doesn't change");
+                };
+                custom_allocator
+                    .write()
+                    .await
+                    .pre_allocation_query(output_name, byte_size, mem_type)
+                    .await
+            })
+        }
+    };
 
+    if let Ok(mut answers) = allocator.0.query_answers.lock() {
+        answers.insert(tensor_name, allocation_result);
+    }
     *memory_type = allocation_result as _;
 
     null_mut()
@@ -0,0 +1,127 @@
+//! Scaffolding of the simple "one model, one request" applications (feature "app").
+//!
+//! Implement [Pipeline] (or derive it) and pass it to [run_pipeline]:
+//! the server is started, the request is built and inferred, and the response is parsed.
+//!
+//! [Pipeline] derive macro is configured with the `pipeline` attributes:
+//! | Attribute | Place | Meaning |
+//! |---|---|---|
+//! | `model = "name"` | struct | [Pipeline::model_name] |
+//! | `version = 1` | struct | [Pipeline::model_version], -1 if not set |
+//! | `repository = "path"` | struct | [Pipeline::model_repo] |
+//! | `backends = "path"` | struct | [Pipeline::backends_path], Triton default if not set |
+//! | `output = "name", output_type = f32` | struct | [Pipeline::parse_result] returns the output as `Vec<f32>`. The whole [Response] if not set |
+//! | `model`, `repository`, `backends` | field | the setting is taken from the field (implementing [ToString]) |
+//! | `input = "name"` | field | the field (`AsRef<[T]>` of [Sample](crate::memory::Sample)) is added as the input of the request |
+//! | `dims = [1, 6]` | field | shape of the input, the model metadata shape if not set |
+//!
+//! ```ignore
+//! #[derive(Pipeline)]
+//! #[pipeline(model = "adder", output = "output", output_type = f32)]
+//! struct Adder {
+//!     #[pipeline(repository)]
+//!     models: String,
+//!     #[pipeline(input = "input", dims = [1, 1, 1, 6])]
+//!     values: Vec<f32>,
+//! }
+//!
+//! let sum = run_pipeline(Adder { models: "models/simple_model".into(), values: vec![1.; 6] }).await?;
+//! ```
+
+use std::time::Duration;
+
+pub use tritonserver_rs_macros::Pipeline;
+
+use crate::{
+    options::Options, server::DEFAULT_BACKEND_DIRECTORY, Error, Request, Response, Server,
+};
+
+/// Exit timeout of the server started by [run_pipeline].
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Application running one inference of one model. Check [run_pipeline].
+pub trait Pipeline {
+    /// Result of the application.
+    type Output;
+
+    /// Path to the model repository.
+    fn model_repo(&self) -> String;
+
+    /// Path to the backends directory.
+    fn backends_path(&self) -> String {
+        DEFAULT_BACKEND_DIRECTORY.to_string()
+    }
+
+    /// Name of the model to infer.
+    fn model_name(&self) -> String;
+
+    /// Version of the model to infer. -1 means the server chooses the version based on the model's policy.
+    fn model_version(&self) -> i64 {
+        -1
+    }
+
+    /// Set the additional server options.
+    fn configure(&self, _options: &mut Options) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Add the inputs (and, optionally, the allocator) to the request.
+    /// [crate::request::DefaultAllocator] is set before the call.
+    fn add_inputs(&mut self, request: &mut Request) -> Result<(), Error>;
+
+    /// Get the result of the application from the response.
+    fn parse_result(&mut self, response: Response) -> Result<Self::Output, Error>;
+}
+
+/// Run the `pipeline`: start the server, infer the request of the pipeline, parse the response and stop the server.
+pub async fn run_pipeline<P: Pipeline>(mut pipeline: P) -> Result<P::Output, Error> {
+    #[cfg(feature = "gpu")]
+    crate::init_cuda()?;
+
+    let mut options = Options::new(pipeline.model_repo())?;
+    options
+        .exit_timeout(EXIT_TIMEOUT)?
+        .backend_directory(pipeline.backends_path())?;
+    #[cfg(not(feature = "gpu"))]
+    options.pinned_memory_pool_byte_size(0)?;
+    pipeline.configure(&mut options)?;
+
+    let server = Server::new(options).await?;
+
+    let mut request = server.create_request(pipeline.model_name(), pipeline.model_version())?;
+    request.add_default_allocator();
+    pipeline.add_inputs(&mut request)?;
+
+    let response = request.infer_async()?.await.map_err(|err| err.error)?;
+    let output = pipeline.parse_result(response);
+
+    server.stop()?;
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Pipeline)]
+    #[pipeline(model = "adder", version = 2, output = "output", output_type = f32)]
+    struct Adder {
+        #[pipeline(repository)]
+        models: String,
+        #[pipeline(input = "input", dims = [1, 6])]
+        values: Vec<f32>,
+    }
+
+    #[test]
+    fn derive() {
+        let adder = Adder {
+            models: "models/simple_model".to_string(),
+            values: vec![1.; 6],
+        };
+        assert_eq!(adder.model_name(), "adder");
+        assert_eq!(adder.model_version(), 2);
+        assert_eq!(adder.model_repo(), "models/simple_model");
+        assert_eq!(adder.backends_path(), DEFAULT_BACKEND_DIRECTORY);
+        assert_eq!(adder.values.len(), 6);
+    }
+}
@@ -0,0 +1,325 @@
+//! Profiling of the model inference: an embedded, programmatic counterpart of Triton `perf_analyzer`. \
+//! The model is loaded with the requests by a number of concurrent clients for each concurrency level,
+//! the report contains latency percentiles, throughput and GPU memory usage of each level.
+//!
+//! ```ignore
+//! let report = bench::run(
+//!     &server,
+//!     "detector",
+//!     |_| Ok(vec![("IMAGE".to_string(), Buffer::from(vec![0u8; 640 * 640 * 3]), vec![1, 640, 640, 3])]),
+//!     BenchOptions::default()
+//!         .concurrency(1..=8)
+//!         .duration(Duration::from_secs(30)),
+//! )
+//! .await?;
+//! println!("{report}");
+//! ```
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use futures::future;
+
+pub use crate::request::Inputs;
+use crate::{
+    error::{Error, ErrorCode},
+    Server,
+};
+
+/// Name of the Triton metric of the used GPU memory.
+const GPU_MEMORY_USED: &str = "nv_gpu_memory_used_bytes";
+/// Interval of the GPU memory sampling during the level run.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Options of the profiling run. Check [run].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BenchOptions {
+    /// Version of the model. -1 means the server chooses the version based on the model's policy.
+    pub version: i64,
+    /// Numbers of the concurrent clients, each level is profiled separately.
+    pub concurrency: Vec<usize>,
+    /// Time the load is measured for at each level.
+    pub duration: Duration,
+    /// Time the load runs at each level before the measuring. The requests started in this time are not measured.
+    pub warmup: Duration,
+}
+
+impl Default for BenchOptions {
+    /// Latest version, concurrency 1, 10 seconds of measuring after 1 second of warmup.
+    fn default() -> Self {
+        BenchOptions {
+            version: -1,
+            concurrency: vec![1],
+            duration: Duration::from_secs(10),
+            warmup: Duration::from_secs(1),
+        }
+    }
+}
+
+impl BenchOptions {
+    /// Set the version of the model.
+    pub fn version(mut self, version: i64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the concurrency levels, e.g. `1..=8` or `[1, 2, 4, 8]`.
+    pub fn concurrency<I: IntoIterator<Item = usize>>(mut self, concurrency: I) -> Self {
+        self.concurrency = concurrency.into_iter().collect();
+        self
+    }
+
+    /// Set the measuring time of each level.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Set the warmup time of each level.
+    pub fn warmup(mut self, warmup: Duration) -> Self {
+        self.warmup = warmup;
+        self
+    }
+}
+
+/// Latency distribution of the measured requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Latencies {
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Latencies {
+    /// Distribution of the `samples`. Percentiles are computed with the nearest-rank method.
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Latencies::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Latencies {
+            mean: samples.iter().sum::<Duration>() / samples.len() as u32,
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Result of the profiling of one concurrency level.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelReport {
+    /// Number of the concurrent clients.
+    pub concurrency: usize,
+    /// Number of the measured successful requests.
+    pub requests: usize,
+    /// Number of the measured failed requests.
+    pub errors: usize,
+    /// Time the requests were measured for.
+    pub duration: Duration,
+    /// Successful requests per second.
+    pub throughput: f64,
+    /// Latency of the successful requests.
+    pub latency: Latencies,
+    /// Peak GPU memory used by all the devices in bytes.
+    /// None if the server has no GPU metrics (check [crate::options::Options::gpu_metrics]).
+    pub gpu_memory_used: Option<u64>,
+}
+
+/// Result of [run].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BenchReport {
+    pub model: String,
+    pub version: i64,
+    /// Reports of the concurrency levels in the order of [BenchOptions::concurrency].
+    pub levels: Vec<LevelReport>,
+}
+
+impl BenchReport {
+    /// Get the level with the highest throughput.
+    pub fn best_throughput(&self) -> Option<&LevelReport> {
+        self.levels
+            .iter()
+            .max_by(|a, b| a.throughput.total_cmp(&b.throughput))
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Model {} (version {}):", self.model, self.version)?;
+        for level in &self.levels {
+            write!(
+                f,
+                "  concurrency {}: {:.1} infer/sec, latency p50 {:?}, p95 {:?}, p99 {:?}, max {:?} ({} requests, {} errors",
+                level.concurrency,
+                level.throughput,
+                level.latency.p50,
+                level.latency.p95,
+                level.latency.p99,
+                level.latency.max,
+                level.requests,
+                level.errors,
+            )?;
+            if let Some(memory) = level.gpu_memory_used {
+                write!(f, ", GPU memory {} MiB", memory >> 20)?;
+            }
+            writeln!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Profile the `model` with the `options`. \
+/// `inputs`: generator of the request inputs, takes the index of the request in the level.
+/// An error of the generator stops the profiling, the inference errors are counted in [LevelReport::errors].
+///
+/// **Note**: the clients run in the current task, so the inputs generation time is not hidden by the parallelism.
+/// Keep the generator cheap (e.g. clone the prepared buffers).
+pub async fn run<G>(
+    server: &Server,
+    model: &str,
+    inputs: G,
+    options: BenchOptions,
+) -> Result<BenchReport, Error>
+where
+    G: Fn(usize) -> Result<Inputs, Error>,
+{
+    if options.concurrency.contains(&0) {
+        return Err(Error::new(
+            ErrorCode::InvalidArg,
+            "Concurrency level must be positive",
+        ));
+    }
+
+    let mut report = BenchReport {
+        model: model.to_string(),
+        version: options.version,
+        levels: Vec::with_capacity(options.concurrency.len()),
+    };
+    for &concurrency in &options.concurrency {
+        let level = run_level(server, model, &inputs, concurrency, &options).await?;
+        log::debug!(
+            "Model {model} concurrency {concurrency}: {:.1} infer/sec, p99 {:?}",
+            level.throughput,
+            level.latency.p99
+        );
+        report.levels.push(level);
+    }
+    Ok(report)
+}
+
+async fn run_level<G>(
+    server: &Server,
+    model: &str,
+    inputs: &G,
+    concurrency: usize,
+    options: &BenchOptions,
+) -> Result<LevelReport, Error>
+where
+    G: Fn(usize) -> Result<Inputs, Error>,
+{
+    let measure_start = Instant::now() + options.warmup;
+    let end = measure_start + options.duration;
+    let index = AtomicUsize::new(0);
+
+    let client = || async {
+        let mut latencies = Vec::new();
+        let mut errors = 0;
+        while Instant::now() < end {
+            let inputs = inputs(index.fetch_add(1, Ordering::Relaxed))?;
+            let start = Instant::now();
+            let result = infer(server, model, options.version, inputs).await;
+            if start < measure_start {
+                continue;
+            }
+            match result {
+                Ok(()) => latencies.push(start.elapsed()),
+                Err(err) => {
+                    log::debug!("Inference of model {model} failed: {err}");
+                    errors += 1;
+                }
+            }
+        }
+        Ok::<_, Error>((latencies, errors))
+    };
+    let sampler = async {
+        let mut peak = gpu_memory_used(server);
+        while Instant::now() < end {
+            tokio::time::sleep(SAMPLE_INTERVAL.min(end.saturating_duration_since(Instant::now())))
+                .await;
+            peak = peak.max(gpu_memory_used(server));
+        }
+        peak
+    };
+
+    let (clients, gpu_memory_used) = future::join(
+        future::try_join_all((0..concurrency).map(|_| client())),
+        sampler,
+    )
+    .await;
+    let duration = measure_start.elapsed();
+
+    let mut latencies = Vec::new();
+    let mut errors = 0;
+    for (client_latencies, client_errors) in clients? {
+        latencies.extend(client_latencies);
+        errors += client_errors;
+    }
+    Ok(LevelReport {
+        concurrency,
+        requests: latencies.len(),
+        errors,
+        duration,
+        throughput: latencies.len() as f64 / duration.as_secs_f64(),
+        latency: Latencies::from_samples(latencies),
+        gpu_memory_used,
+    })
+}
+
+async fn infer(server: &Server, model: &str, version: i64, inputs: Inputs) -> Result<(), Error> {
+    let mut request = server.create_request(model, version)?;
+    request.add_default_allocator();
+    for (name, buffer, shape) in inputs {
+        request.add_input_with_dims(name, buffer, shape)?;
+    }
+    request
+        .infer_async()?
+        .await
+        .map(drop)
+        .map_err(|err| err.error)
+}
+
+/// GPU memory used by all the devices. None if the metric is not available.
+fn gpu_memory_used(server: &Server) -> Option<u64> {
+    let metrics = server.metrics().and_then(|metrics| metrics.to_map()).ok()?;
+    metrics
+        .get(GPU_MEMORY_USED)
+        .map(|family| family.sum(&[]) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latencies() {
+        let samples = (1..=200).rev().map(Duration::from_millis).collect();
+        let latencies = Latencies::from_samples(samples);
+        assert_eq!(latencies.p50, Duration::from_millis(100));
+        assert_eq!(latencies.p95, Duration::from_millis(190));
+        assert_eq!(latencies.p99, Duration::from_millis(198));
+        assert_eq!(latencies.max, Duration::from_millis(200));
+        assert_eq!(latencies.mean, Duration::from_micros(100_500));
+
+        let single = Latencies::from_samples(vec![Duration::from_millis(7)]);
+        assert_eq!(single.p50, single.p99);
+        assert_eq!(Latencies::from_samples(Vec::new()), Latencies::default());
+    }
+}
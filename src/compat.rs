@@ -0,0 +1,94 @@
+use std::{collections::HashMap, ffi::CStr, sync::OnceLock};
+
+use crate::{
+    api_version,
+    error::{Error, ErrorCode},
+    TRITONSERVER_API_VERSION_MAJOR, TRITONSERVER_API_VERSION_MINOR,
+};
+
+/// Functions of the Triton C API missing in the older libraries. \
+/// Wrappers calling them check the symbol first and return [ErrorCode::Unsupported]
+/// instead of aborting on the unresolved symbol.
+const GATED: [&CStr; 5] = [
+    c"TRITONSERVER_InferenceTraceContext",
+    c"TRITONSERVER_InferenceTraceSetContext",
+    c"TRITONSERVER_InferenceTraceReportActivity",
+    c"TRITONSERVER_ServerOptionsSetCudaVirtualAddressSize",
+    c"TRITONSERVER_ServerOptionsSetEnablePeerAccess",
+];
+
+/// Availability of the gated functions in the loaded library, resolved once.
+fn available() -> &'static HashMap<&'static CStr, bool> {
+    static AVAILABLE: OnceLock<HashMap<&'static CStr, bool>> = OnceLock::new();
+    AVAILABLE.get_or_init(|| {
+        GATED
+            .iter()
+            .map(|symbol| {
+                let found = !unsafe { libc::dlsym(libc::RTLD_DEFAULT, symbol.as_ptr()) }.is_null();
+                (*symbol, found)
+            })
+            .collect()
+    })
+}
+
+/// Return [ErrorCode::Unsupported] if the gated `symbol` is missing in the loaded Triton library.
+pub(crate) fn require(symbol: &'static CStr) -> Result<(), Error> {
+    if available().get(symbol).copied().unwrap_or(true) {
+        return Ok(());
+    }
+    let (major, minor) = api_version()?;
+    Err(Error::new(
+        ErrorCode::Unsupported,
+        format!(
+            "{} is not available in the Triton library (API version {major}.{minor}), update the library to API version {TRITONSERVER_API_VERSION_MAJOR}.{TRITONSERVER_API_VERSION_MINOR}",
+            symbol.to_string_lossy()
+        ),
+    ))
+}
+
+/// Compatibility of the loaded Triton library with the crate bindings. \
+/// One can get this item using [crate::Server::check_compat].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Compatibility {
+    /// API version of the loaded library.
+    pub library: (u32, u32),
+    /// API version of the bindings the crate is built with.
+    pub bindings: (u32, u32),
+    /// Functions of the bindings missing in the library. The methods calling them return [ErrorCode::Unsupported].
+    pub missing: Vec<&'static str>,
+}
+
+impl Compatibility {
+    /// Compare the loaded library with the bindings. \
+    /// Returns error if the major API versions differ: the library can't be used at all.
+    pub(crate) fn check() -> Result<Self, Error> {
+        let compat = Compatibility {
+            library: api_version()?,
+            bindings: (
+                TRITONSERVER_API_VERSION_MAJOR,
+                TRITONSERVER_API_VERSION_MINOR,
+            ),
+            missing: GATED
+                .iter()
+                .filter(|symbol| !available()[*symbol])
+                .map(|symbol| symbol.to_str().unwrap_or_default())
+                .collect(),
+        };
+        if compat.library.0 != compat.bindings.0 {
+            return Err(Error::new(
+                ErrorCode::Unsupported,
+                format!(
+                    "Triton library API version {}.{} is incompatible with the bindings API version {}.{}: \
+                     the major versions differ. Use the library of the matching Triton release",
+                    compat.library.0, compat.library.1, compat.bindings.0, compat.bindings.1
+                ),
+            ));
+        }
+        Ok(compat)
+    }
+
+    /// Returns true if the library is not older than the bindings, so every method of the crate is available.
+    pub fn is_full(&self) -> bool {
+        self.library.1 >= self.bindings.1 && self.missing.is_empty()
+    }
+}
@@ -1,13 +1,26 @@
-use std::{collections::HashMap, ffi::c_int, sync::Arc};
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    ffi::c_int,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+};
 
 use cuda_driver_sys::{
-    cuCtxCreate_v2, cuCtxDestroy_v2, cuCtxGetApiVersion, cuCtxPopCurrent_v2, cuCtxPushCurrent_v2,
-    cuDeviceGet, cuDeviceGetAttribute, cuDeviceGetName, cuDeviceTotalMem_v2, cuInit, CUcontext,
-    CUdevice, CUdevice_attribute,
+    cuCtxCreate_v2, cuCtxDestroy_v2, cuCtxGetApiVersion, cuCtxGetCurrent, cuCtxGetDevice,
+    cuCtxPopCurrent_v2, cuCtxPushCurrent_v2, cuDeviceGet, cuDeviceGetAttribute, cuDeviceGetName,
+    cuDeviceGetPCIBusId, cuDeviceTotalMem_v2, cuInit, cuStreamCreate, cuStreamDestroy_v2,
+    cuStreamQuery, cuStreamSynchronize, CUcontext, CUdevice, CUdevice_attribute, CUresult,
+    CUstream, CUstream_flags,
 };
 use parking_lot::{Once, RwLock};
 
-use crate::{error::Error, from_char_array};
+use crate::{
+    error::{Error, ErrorCode},
+    from_char_array,
+};
 
 /// Initialize Cuda runtime. Should be called before any Cuda function, perfectly &mdash; on the start of the application.
 pub fn init_cuda() -> Result<(), Error> {
@@ -40,6 +53,74 @@ pub fn get_context(device: i32) -> Result<Arc<Context>, Error> {
     Ok(arc)
 }
 
+/// Device which context is pinned automatically if [set_thread_device] was not called on the thread.
+static DEFAULT_DEVICE: AtomicI32 = AtomicI32::new(0);
+
+thread_local! {
+    static THREAD_DEVICE: Cell<Option<i32>> = const { Cell::new(None) };
+}
+
+/// Cuda context of the device pinned as current for the calling thread. Context is popped on drop. \
+/// Unlike [ContextHandler], owns the context, so can be kept for as long as needed. Check [pin_context].
+pub struct ContextGuard {
+    _context: Arc<Context>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let _ = cuda_call!(cuCtxPopCurrent_v2(std::ptr::null_mut()));
+    }
+}
+
+/// Pin the context of the `device` as current for the calling thread until the returned guard is dropped.
+pub fn pin_context(device: i32) -> Result<ContextGuard, Error> {
+    let context = get_context(device)?;
+    cuda_call!(cuCtxPushCurrent_v2(context.context))?;
+    Ok(ContextGuard { _context: context })
+}
+
+/// Set the device which context [Buffer](crate::Buffer) Cuda operations pin automatically on the calling thread
+/// (if no context is current). None resets the thread to the default device (check [set_default_device]).
+pub fn set_thread_device(device: Option<i32>) {
+    THREAD_DEVICE.with(|thread_device| thread_device.set(device));
+}
+
+/// Set the device which context [Buffer](crate::Buffer) Cuda operations pin automatically
+/// on the threads without [set_thread_device] (if no context is current). Default is 0.
+pub fn set_default_device(device: i32) {
+    DEFAULT_DEVICE.store(device, Ordering::Relaxed);
+}
+
+/// Pin the context of the thread device if no context is current on the calling thread. \
+/// None if the context is already current or the automatic pinning is disabled by the "strict-context" feature.
+pub(crate) fn auto_pin() -> Result<Option<ContextGuard>, Error> {
+    let device = THREAD_DEVICE
+        .with(Cell::get)
+        .unwrap_or_else(|| DEFAULT_DEVICE.load(Ordering::Relaxed));
+    auto_pin_on(device)
+}
+
+/// Pin the context of the `device` if no context is current on the calling thread. Check [auto_pin].
+pub(crate) fn auto_pin_on(device: i32) -> Result<Option<ContextGuard>, Error> {
+    if cfg!(feature = "strict-context") {
+        return Ok(None);
+    }
+    ONCE.call_once(|| init_cuda().unwrap());
+
+    let mut current: CUcontext = std::ptr::null_mut();
+    cuda_call!(cuCtxGetCurrent(&mut current as *mut CUcontext))?;
+    if !current.is_null() {
+        return Ok(None);
+    }
+    pin_context(device).map(Some)
+}
+
+/// Device of the context current on the calling thread.
+pub(crate) fn current_device() -> Result<i32, Error> {
+    let mut device: CUdevice = 0;
+    cuda_call!(cuCtxGetDevice(&mut device as *mut CUdevice)).map(|_| device)
+}
+
 /// Handler of Cuda context that was pushed as current.
 /// On Drop will pop context from current.
 pub struct ContextHandler<'a> {
@@ -97,6 +178,63 @@ impl Drop for Context {
     }
 }
 
+/// Cuda stream. Operations queued to the stream are executed in order, asynchronously to the host. \
+/// Check [Buffer::copy_from_slice_async](crate::Buffer::copy_from_slice_async) and [Buffer::copy_to_async](crate::Buffer::copy_to_async).
+///
+/// **Note**: Stream is bound to the context it was created in.
+/// Methods and drop should be called in sync with this context pinned (check [crate::memory] module level documentation for more info).
+#[derive(Debug)]
+pub struct Stream {
+    stream: CUstream,
+}
+
+unsafe impl Send for Stream {}
+unsafe impl Sync for Stream {}
+
+impl Stream {
+    /// Create the stream that does not synchronize with the default (NULL) stream.
+    pub fn new() -> Result<Self, Error> {
+        let mut stream = Stream {
+            stream: std::ptr::null_mut(),
+        };
+        cuda_call!(cuStreamCreate(
+            &mut stream.stream as *mut CUstream,
+            CUstream_flags::CU_STREAM_NON_BLOCKING as u32
+        ))
+        .map(|_| stream)
+    }
+
+    /// Get the raw Cuda stream, e.g. to launch kernels in it.
+    pub fn as_raw(&self) -> CUstream {
+        self.stream
+    }
+
+    /// Block until all the operations queued to the stream are completed.
+    pub fn synchronize(&self) -> Result<(), Error> {
+        cuda_call!(cuStreamSynchronize(self.stream))
+    }
+
+    /// Check if all the operations queued to the stream are completed without blocking.
+    pub fn is_complete(&self) -> Result<bool, Error> {
+        match unsafe { cuStreamQuery(self.stream) } {
+            CUresult::CUDA_SUCCESS => Ok(true),
+            CUresult::CUDA_ERROR_NOT_READY => Ok(false),
+            res => Err(Error::new(
+                ErrorCode::Internal,
+                format!("Cuda result: {res:?}"),
+            )),
+        }
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        if !self.stream.is_null() {
+            let _ = cuda_call!(cuStreamDestroy_v2(self.stream));
+        }
+    }
+}
+
 /// Cuda representation of the device.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct CuDevice {
@@ -128,6 +266,28 @@ impl CuDevice {
         )
     }
 
+    /// Get PCI bus id of the device in the `domain:bus:device.function` form, e.g. "0000:3B:00.0".
+    pub fn get_pci_bus_id(&self) -> Result<String, Error> {
+        let mut id = vec![0; 32];
+
+        cuda_call!(
+            cuDeviceGetPCIBusId(id.as_mut_ptr() as *mut _, 32, self.device),
+            from_char_array(id.as_mut_ptr())
+        )
+    }
+
+    /// Get NUMA node the device is attached to. None if the node is unknown, e.g. on the single-node system
+    /// or if the system doesn't report it (only Linux sysfs is supported).
+    pub fn get_numa_node(&self) -> Result<Option<u32>, Error> {
+        let bus_id = self.get_pci_bus_id()?.to_lowercase();
+        Ok(
+            std::fs::read_to_string(format!("/sys/bus/pci/devices/{bus_id}/numa_node"))
+                .ok()
+                .and_then(|node| node.trim().parse::<i32>().ok())
+                .and_then(|node| u32::try_from(node).ok()),
+        )
+    }
+
     /// Get total mem of the device.
     pub fn get_total_mem(&self) -> Result<usize, Error> {
         let mut val = 0;
@@ -1,13 +1,53 @@
 use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    env,
     error::Error as ErrorExt,
     ffi::{CStr, CString},
     fmt, io,
     mem::transmute,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::SystemTime,
 };
 
 use crate::sys;
 
 pub(crate) const CSTR_CONVERT_ERROR_PLUG: &str = "INVALID UTF-8 STRING";
+/// Prefix of the messages of the errors caused by the lack of memory. Check [Error::is_out_of_memory].
+pub(crate) const OUT_OF_MEMORY: &str = "OutOfMemory";
+
+/// How many of the last errors are kept for the diagnostics.
+const RECENT_ERRORS_CAPACITY: usize = 32;
+
+static RECORD_RECENT_ERRORS: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref RECENT_ERRORS: Mutex<VecDeque<(SystemTime, String)>> =
+        Mutex::new(VecDeque::with_capacity(RECENT_ERRORS_CAPACITY));
+}
+
+/// Enable or disable keeping the last errors of the crate for [crate::Server::diagnostic_bundle]. Disabled by default. \
+/// While enabled, each error creation formats the error and takes a global lock.
+pub fn record_recent_errors(enable: bool) {
+    RECORD_RECENT_ERRORS.store(enable, Ordering::Relaxed);
+    if !enable {
+        if let Ok(mut errors) = RECENT_ERRORS.lock() {
+            errors.clear();
+        }
+    }
+}
+
+/// Get the last errors created by the crate (oldest first) with the time of their creation.
+/// Empty unless enabled by [record_recent_errors].
+pub(crate) fn recent_errors() -> Vec<(SystemTime, String)> {
+    RECENT_ERRORS
+        .lock()
+        .map(|errors| errors.iter().cloned().collect())
+        .unwrap_or_default()
+}
 
 /// Triton server error codes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -22,10 +62,61 @@ pub enum ErrorCode {
     Alreadyxists = sys::TRITONSERVER_errorcode_enum_TRITONSERVER_ERROR_ALREADY_EXISTS,
 }
 
+/// Where the error happened: the model and the request it concerns and the failed Triton call.
+/// Check [Error::context].
+#[derive(Debug, Clone, Default)]
+pub struct ErrorContext {
+    pub model: Option<String>,
+    /// Version of the model, -1 if the server chooses it.
+    pub model_version: Option<i64>,
+    /// ID of the request (check [crate::Request::set_id]).
+    pub request_id: Option<String>,
+    /// Name of the failed Triton C API function, e.g. `TRITONSERVER_ServerInferAsync`.
+    pub call: Option<&'static str>,
+    /// Backtrace of the error creation. Captured if `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` is set.
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+impl ErrorContext {
+    fn is_empty(&self) -> bool {
+        self.model.is_none() && self.request_id.is_none() && self.call.is_none()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    /// Model, request and call, e.g. `model "resnet" version 1, request "42", in TRITONSERVER_ServerInferAsync`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::with_capacity(3);
+        if let Some(model) = &self.model {
+            parts.push(match self.model_version {
+                Some(version) if version >= 0 => format!("model \"{model}\" version {version}"),
+                _ => format!("model \"{model}\""),
+            });
+        }
+        if let Some(request_id) = self.request_id.as_ref().filter(|id| !id.is_empty()) {
+            parts.push(format!("request \"{request_id}\""));
+        }
+        if let Some(call) = self.call {
+            parts.push(format!("in {call}"));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Name of the function of the `path`, e.g. `TRITONSERVER_ServerInferAsync` of `sys::TRITONSERVER_ServerInferAsync`.
+pub(crate) fn call_name(path: &'static str) -> &'static str {
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
+
 /// Triton server error.
+///
+/// Implements [std::error::Error] and is `Send + Sync + 'static`, so it (as well as [crate::request::InferenceError])
+/// converts to `anyhow::Error` with `?` and gets the context with `anyhow::Context`. \
+/// The errors of the crate calls carry [ErrorContext] shown by [fmt::Display]; [fmt::Debug] also shows the backtrace.
 pub struct Error {
     pub(crate) ptr: *mut sys::TRITONSERVER_Error,
     pub(crate) owned: bool,
+    pub(crate) context: Option<Box<ErrorContext>>,
 }
 
 /// It's protected by the owned, so until no one changes owned it's safe.
@@ -73,6 +164,59 @@ impl Error {
         }
     }
 
+    /// Get the context of the error: the model, the request and the Triton call it comes from.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context.as_deref()
+    }
+
+    /// Get the backtrace of the error creation, if it was captured.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.context.as_ref()?.backtrace.as_deref()
+    }
+
+    /// Set the model the error concerns. Keeps the model already set.
+    pub fn with_model<M: AsRef<str>>(mut self, model: M, version: i64) -> Self {
+        let context = self.context.get_or_insert_with(Default::default);
+        if context.model.is_none() {
+            context.model = Some(model.as_ref().to_string());
+            context.model_version = Some(version);
+        }
+        self
+    }
+
+    /// Set the ID of the request the error concerns. Keeps the ID already set.
+    pub fn with_request_id<I: AsRef<str>>(mut self, request_id: I) -> Self {
+        let context = self.context.get_or_insert_with(Default::default);
+        if context.request_id.is_none() {
+            context.request_id = Some(request_id.as_ref().to_string());
+        }
+        self
+    }
+
+    /// Set the Triton call that failed. `path`: the path of the called function, check [call_name].
+    pub(crate) fn with_call(mut self, path: &'static str) -> Self {
+        self.context.get_or_insert_with(Default::default).call = Some(call_name(path));
+        self
+    }
+
+    /// Check if the error is caused by the lack of memory: failed CPU allocation
+    /// or `CUDA_ERROR_OUT_OF_MEMORY` of the crate CUDA calls.
+    pub fn is_out_of_memory(&self) -> bool {
+        self.message().starts_with(OUT_OF_MEMORY)
+    }
+
+    #[cfg(feature = "gpu")]
+    pub(crate) fn from_cuda(res: cuda_driver_sys::CUresult) -> Self {
+        if res == cuda_driver_sys::CUresult::CUDA_ERROR_OUT_OF_MEMORY {
+            Self::new(
+                ErrorCode::Internal,
+                format!("{OUT_OF_MEMORY}. Cuda result: {res:?}"),
+            )
+        } else {
+            Self::new(ErrorCode::Internal, format!("Cuda result: {res:?}"))
+        }
+    }
+
     #[cfg(not(feature = "gpu"))]
     pub(crate) fn wrong_type(mem_type: crate::memory::MemoryType) -> Self {
         Self::new(
@@ -84,19 +228,59 @@ impl Error {
 
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}: {}", self.name(), self.message())
+        fmt::Display::fmt(self, f)?;
+        if let Some(backtrace) = self.backtrace() {
+            write!(f, "\n\nStack backtrace:\n{backtrace}")?;
+        }
+        Ok(())
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}: {}", self.name(), self.message())
+        write!(f, "{}: {}", self.name(), self.message())?;
+        match self.context() {
+            Some(context) if !context.is_empty() => write!(f, " ({context})"),
+            _ => Ok(()),
+        }
     }
 }
 
+/// Whether the backtraces of the errors are captured. Follows [Backtrace::capture]:
+/// `RUST_LIB_BACKTRACE` takes precedence over `RUST_BACKTRACE`, `0` disables. \
+/// The variables are read once, so the errors created without them set don't touch the environment or the stack.
+fn backtrace_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        env::var("RUST_LIB_BACKTRACE")
+            .or_else(|_| env::var("RUST_BACKTRACE"))
+            .map(|value| value != "0")
+            .unwrap_or(false)
+    })
+}
+
 impl From<*mut sys::TRITONSERVER_Error> for Error {
     fn from(ptr: *mut sys::TRITONSERVER_Error) -> Self {
-        Error { ptr, owned: true }
+        let context = backtrace_enabled().then(|| {
+            Box::new(ErrorContext {
+                backtrace: Some(Arc::new(Backtrace::force_capture())),
+                ..Default::default()
+            })
+        });
+        let error = Error {
+            ptr,
+            owned: true,
+            context,
+        };
+        if RECORD_RECENT_ERRORS.load(Ordering::Relaxed) {
+            if let Ok(mut errors) = RECENT_ERRORS.lock() {
+                if errors.len() == RECENT_ERRORS_CAPACITY {
+                    errors.pop_front();
+                }
+                errors.push_back((SystemTime::now(), error.to_string()));
+            }
+        }
+        error
     }
 }
 
@@ -132,4 +316,56 @@ mod tests {
         assert_eq!(err.code(), ERROR_CODE);
         assert_eq!(err.message(), ERROR_DESCRIPTION);
     }
+
+    #[test]
+    fn context() {
+        assert_eq!(
+            call_name("sys::TRITONSERVER_ServerInferAsync"),
+            "TRITONSERVER_ServerInferAsync"
+        );
+        assert_eq!(
+            call_name("sys :: TRITONSERVER_ErrorNew"),
+            "TRITONSERVER_ErrorNew"
+        );
+        assert_eq!(
+            call_name(stringify!(sys::TRITONSERVER_ServerStop)),
+            "TRITONSERVER_ServerStop"
+        );
+
+        let context = ErrorContext {
+            model: Some("resnet".to_string()),
+            model_version: Some(-1),
+            request_id: Some("42".to_string()),
+            call: Some("TRITONSERVER_ServerInferAsync"),
+            backtrace: None,
+        };
+        assert_eq!(
+            context.to_string(),
+            r#"model "resnet", request "42", in TRITONSERVER_ServerInferAsync"#
+        );
+        assert!(ErrorContext::default().is_empty());
+
+        fn anyhow_compatible<E: ErrorExt + Send + Sync + 'static>() {}
+        anyhow_compatible::<Error>();
+        anyhow_compatible::<crate::request::InferenceError>();
+    }
+
+    #[test]
+    fn recent_errors_opt_in() {
+        const DESCRIPTION: &str = "recorded error";
+
+        let _ = Error::new(ErrorCode::Internal, DESCRIPTION);
+        assert!(!recent_errors()
+            .iter()
+            .any(|(_, err)| err.contains(DESCRIPTION)));
+
+        record_recent_errors(true);
+        let _ = Error::new(ErrorCode::Internal, DESCRIPTION);
+        assert!(recent_errors()
+            .iter()
+            .any(|(_, err)| err.contains(DESCRIPTION)));
+
+        record_recent_errors(false);
+        assert!(recent_errors().is_empty());
+    }
 }
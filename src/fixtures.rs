@@ -0,0 +1,440 @@
+//! Fixtures for the end-to-end tests against the real Triton library (feature "fixtures"). \
+//! [FixtureRepository] writes the tiny Python backend models ([FixtureModel]) to the temporary model repository
+//! and starts the server on it, [Golden] cases check the outputs of the models,
+//! so the pipelines built on the crate can be tested in CI with minimal setup:
+//!
+//! ```ignore
+//! let repository = FixtureRepository::new(&FixtureModel::all())?;
+//! let server = repository.start().await?;
+//!
+//! for (model, report) in repository.verify(&server, Tolerance::exact()).await? {
+//!     assert!(report.passed(), "{model}: {report}");
+//! }
+//! ```
+//!
+//! **Note**: models require the Python backend (with numpy) in the backend directory.
+//! Directory is taken from `TRITON_BACKEND_DIRECTORY` environment variable, [DEFAULT_BACKEND_DIRECTORY] by default.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    error::{Error, ErrorCode},
+    memory::{Buffer, Byte, DataType},
+    options::Options,
+    replay::{self, DiffReport, Tolerance},
+    Request, Response, Server,
+};
+
+/// Backend directory of the Triton docker images.
+pub const DEFAULT_BACKEND_DIRECTORY: &str = crate::server::DEFAULT_BACKEND_DIRECTORY;
+
+/// How long [FixtureRepository::start] waits for the models to be ready.
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+static NEXT_REPOSITORY: AtomicUsize = AtomicUsize::new(0);
+
+/// Tiny models of the fixture repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FixtureModel {
+    /// "identity_fp32": returns FP32 INPUT0 of shape [-1] as OUTPUT0. Supports batching (max batch size 8).
+    Identity,
+    /// "add_sub_int32": returns the sum (OUTPUT0) and the difference (OUTPUT1) of INT32 INPUT0 and INPUT1 of shape [4].
+    AddSub,
+    /// "echo_bytes": returns BYTES INPUT0 of shape [-1] as OUTPUT0.
+    Echo,
+}
+
+impl FixtureModel {
+    /// All the fixture models.
+    pub fn all() -> [FixtureModel; 3] {
+        [
+            FixtureModel::Identity,
+            FixtureModel::AddSub,
+            FixtureModel::Echo,
+        ]
+    }
+
+    /// Name of the model in the repository.
+    pub fn name(self) -> &'static str {
+        match self {
+            FixtureModel::Identity => "identity_fp32",
+            FixtureModel::AddSub => "add_sub_int32",
+            FixtureModel::Echo => "echo_bytes",
+        }
+    }
+
+    fn config(self) -> String {
+        let (batching, tensors) = match self {
+            FixtureModel::Identity => (
+                "max_batch_size: 8\ndynamic_batching { }",
+                r#"input [ { name: "INPUT0" data_type: TYPE_FP32 dims: [ -1 ] } ]
+output [ { name: "OUTPUT0" data_type: TYPE_FP32 dims: [ -1 ] } ]"#,
+            ),
+            FixtureModel::AddSub => (
+                "max_batch_size: 0",
+                r#"input [
+  { name: "INPUT0" data_type: TYPE_INT32 dims: [ 4 ] },
+  { name: "INPUT1" data_type: TYPE_INT32 dims: [ 4 ] }
+]
+output [
+  { name: "OUTPUT0" data_type: TYPE_INT32 dims: [ 4 ] },
+  { name: "OUTPUT1" data_type: TYPE_INT32 dims: [ 4 ] }
+]"#,
+            ),
+            FixtureModel::Echo => (
+                "max_batch_size: 0",
+                r#"input [ { name: "INPUT0" data_type: TYPE_STRING dims: [ -1 ] } ]
+output [ { name: "OUTPUT0" data_type: TYPE_STRING dims: [ -1 ] } ]"#,
+            ),
+        };
+        format!(
+            "name: \"{}\"\nbackend: \"python\"\n{batching}\n{tensors}\ninstance_group [ {{ kind: KIND_CPU count: 1 }} ]\n",
+            self.name()
+        )
+    }
+
+    /// Outputs of the model by its inputs, Python expression.
+    fn execute(self) -> &'static str {
+        match self {
+            FixtureModel::Identity | FixtureModel::Echo => "[(\"OUTPUT0\", inputs[\"INPUT0\"])]",
+            FixtureModel::AddSub => {
+                "[(\"OUTPUT0\", inputs[\"INPUT0\"] + inputs[\"INPUT1\"]), (\"OUTPUT1\", inputs[\"INPUT0\"] - inputs[\"INPUT1\"])]"
+            }
+        }
+    }
+
+    fn model_py(self) -> String {
+        let input_names = match self {
+            FixtureModel::AddSub => "[\"INPUT0\", \"INPUT1\"]",
+            FixtureModel::Identity | FixtureModel::Echo => "[\"INPUT0\"]",
+        };
+        format!(
+            r#"import triton_python_backend_utils as pb_utils
+
+
+class TritonPythonModel:
+    def execute(self, requests):
+        responses = []
+        for request in requests:
+            inputs = {{
+                name: pb_utils.get_input_tensor_by_name(request, name).as_numpy()
+                for name in {input_names}
+            }}
+            outputs = [pb_utils.Tensor(name, value) for name, value in {}]
+            responses.append(pb_utils.InferenceResponse(output_tensors=outputs))
+        return responses
+"#,
+            self.execute()
+        )
+    }
+
+    /// Golden case of the model.
+    pub fn golden(self) -> Golden {
+        match self {
+            FixtureModel::Identity => {
+                let values = [0.5f32, -1., 2.25];
+                Golden {
+                    model: self.name(),
+                    inputs: vec![GoldenTensor::new("INPUT0", &values, vec![1, 3])],
+                    outputs: vec![GoldenTensor::new("OUTPUT0", &values, vec![1, 3])],
+                }
+            }
+            FixtureModel::AddSub => {
+                let (left, right) = ([1i32, 2, 3, 4], [10i32, 20, 30, 40]);
+                let sum = [11i32, 22, 33, 44];
+                let difference = [-9i32, -18, -27, -36];
+                Golden {
+                    model: self.name(),
+                    inputs: vec![
+                        GoldenTensor::new("INPUT0", &left, vec![4]),
+                        GoldenTensor::new("INPUT1", &right, vec![4]),
+                    ],
+                    outputs: vec![
+                        GoldenTensor::new("OUTPUT0", &sum, vec![4]),
+                        GoldenTensor::new("OUTPUT1", &difference, vec![4]),
+                    ],
+                }
+            }
+            FixtureModel::Echo => {
+                let strings = ["triton", "", "rs"];
+                Golden {
+                    model: self.name(),
+                    inputs: vec![GoldenTensor::strings("INPUT0", &strings)],
+                    outputs: vec![GoldenTensor::strings("OUTPUT0", &strings)],
+                }
+            }
+        }
+    }
+}
+
+/// Tensor of the [Golden] case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenTensor {
+    pub name: String,
+    pub data_type: DataType,
+    pub shape: Vec<i64>,
+    /// Elements of the tensor as f64. [DataType::Bytes] tensors are represented byte by byte
+    /// (with the length prefixes of the elements), as they are compared by [crate::replay].
+    pub values: Vec<f64>,
+    bytes: Vec<u8>,
+}
+
+impl GoldenTensor {
+    fn new<T: Copy + Into<f64> + ToBytes>(name: &str, values: &[T], shape: Vec<i64>) -> Self {
+        GoldenTensor {
+            name: name.to_string(),
+            data_type: T::DATA_TYPE,
+            shape,
+            values: values.iter().map(|value| (*value).into()).collect(),
+            bytes: values.iter().flat_map(|value| value.to_le()).collect(),
+        }
+    }
+
+    /// [DataType::Bytes] tensor of the `strings`.
+    fn strings(name: &str, strings: &[&str]) -> Self {
+        let mut bytes = Vec::new();
+        for string in strings {
+            bytes.extend((string.len() as u32).to_le_bytes());
+            bytes.extend(string.as_bytes());
+        }
+        GoldenTensor {
+            name: name.to_string(),
+            data_type: DataType::Bytes,
+            shape: vec![strings.len() as i64],
+            values: bytes.iter().map(|byte| *byte as f64).collect(),
+            bytes,
+        }
+    }
+
+    /// CPU buffer with the content of the tensor.
+    pub fn buffer(&self) -> Buffer {
+        let bytes = self
+            .bytes
+            .iter()
+            .map(|byte| Byte(*byte))
+            .collect::<Vec<_>>();
+        let mut buffer = Buffer::from(bytes);
+        buffer.data_type = self.data_type;
+        buffer
+    }
+}
+
+/// Little endian representation of the golden tensors elements.
+trait ToBytes {
+    const DATA_TYPE: DataType;
+    fn to_le(self) -> Vec<u8>;
+}
+
+impl ToBytes for f32 {
+    const DATA_TYPE: DataType = DataType::Fp32;
+    fn to_le(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+}
+
+impl ToBytes for i32 {
+    const DATA_TYPE: DataType = DataType::Int32;
+    fn to_le(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+}
+
+/// Inputs of the model and the outputs it must produce on them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Golden {
+    pub model: &'static str,
+    pub inputs: Vec<GoldenTensor>,
+    pub outputs: Vec<GoldenTensor>,
+}
+
+impl Golden {
+    /// Create the request to the model with the inputs of the case and the default allocator.
+    pub fn request<'s>(&self, server: &'s Server) -> Result<Request<'s>, Error> {
+        let mut request = server.create_request(self.model, -1)?;
+        request.add_default_allocator();
+        for input in &self.inputs {
+            request.add_input_with_dims(&input.name, input.buffer(), input.shape.clone())?;
+        }
+        Ok(request)
+    }
+
+    /// Compare the outputs of the `response` with the golden ones.
+    /// Outputs of the response that are not in the case are ignored.
+    pub fn check(&self, response: &Response, tolerance: Tolerance) -> Result<DiffReport, Error> {
+        let mut report = DiffReport {
+            requests: 1,
+            ..Default::default()
+        };
+        for expected in &self.outputs {
+            let diff = report.outputs.entry(expected.name.clone()).or_default();
+            match response.get_output(&expected.name) {
+                None => diff.missing += 1,
+                Some(output)
+                    if output.shape != expected.shape
+                        || output.data_type() != expected.data_type =>
+                {
+                    diff.mismatched_shapes += 1
+                }
+                Some(output) => {
+                    diff.add_values(&expected.values, &replay::values(output)?, tolerance)
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Infer the case on the `server` and compare the outputs with the golden ones.
+    pub async fn run(&self, server: &Server, tolerance: Tolerance) -> Result<DiffReport, Error> {
+        let response = self
+            .request(server)?
+            .infer_async()?
+            .await
+            .map_err(|err| err.error)?;
+        self.check(&response, tolerance)
+    }
+}
+
+/// Temporary model repository with the fixture models. Removed on drop.
+#[derive(Debug)]
+pub struct FixtureRepository {
+    path: PathBuf,
+    models: Vec<FixtureModel>,
+}
+
+impl FixtureRepository {
+    /// Write the `models` to the new directory in the system temporary directory.
+    pub fn new(models: &[FixtureModel]) -> Result<Self, Error> {
+        let path = std::env::temp_dir().join(format!(
+            "tritonserver-rs-fixtures-{}-{}",
+            std::process::id(),
+            NEXT_REPOSITORY.fetch_add(1, Ordering::Relaxed)
+        ));
+        Self::create_in(path, models)
+    }
+
+    /// Write the `models` to the `path` directory. The directory is created if it doesn't exist
+    /// and removed when the repository is dropped.
+    pub fn create_in<P: AsRef<Path>>(path: P, models: &[FixtureModel]) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let mut models = models.to_vec();
+        models.sort();
+        models.dedup();
+
+        for model in &models {
+            let dir = path.join(model.name());
+            write(&dir.join("config.pbtxt"), &model.config())?;
+            write(&dir.join("1").join("model.py"), &model.model_py())?;
+        }
+        Ok(FixtureRepository { path, models })
+    }
+
+    /// Path of the repository.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Models of the repository.
+    pub fn models(&self) -> &[FixtureModel] {
+        &self.models
+    }
+
+    /// Server options with the repository and the backend directory
+    /// (`TRITON_BACKEND_DIRECTORY` environment variable or [DEFAULT_BACKEND_DIRECTORY]).
+    pub fn options(&self) -> Result<Options, Error> {
+        let backends = std::env::var("TRITON_BACKEND_DIRECTORY")
+            .unwrap_or_else(|_| DEFAULT_BACKEND_DIRECTORY.to_string());
+        let mut options = Options::new(&self.path)?;
+        options
+            .backend_directory(backends)?
+            .exit_timeout(Duration::from_secs(5))?;
+        Ok(options)
+    }
+
+    /// Start the server on the repository and wait until all the models are ready.
+    pub async fn start(&self) -> Result<Server, Error> {
+        let server = Server::new(self.options()?).await?;
+        for model in &self.models {
+            server
+                .wait_for_model_ready(model.name(), -1, READY_TIMEOUT)
+                .await?;
+        }
+        Ok(server)
+    }
+
+    /// Run the [Golden] cases of all the models of the repository on the `server`.
+    pub async fn verify(
+        &self,
+        server: &Server,
+        tolerance: Tolerance,
+    ) -> Result<BTreeMap<&'static str, DiffReport>, Error> {
+        let mut reports = BTreeMap::new();
+        for model in &self.models {
+            let report = model.golden().run(server, tolerance).await.map_err(|err| {
+                Error::new(
+                    ErrorCode::Internal,
+                    format!("Golden case of {} failed: {err}", model.name()),
+                )
+            })?;
+            reports.insert(model.name(), report);
+        }
+        Ok(reports)
+    }
+}
+
+/// Write the `contents` to the `path`, creating the parent directories.
+fn write(path: &Path, contents: &str) -> Result<(), Error> {
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, contents))
+        .map_err(|err| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't write {}: {err}", path.display()),
+            )
+        })
+}
+
+impl Drop for FixtureRepository {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.path) {
+            log::warn!(
+                "Failed to remove fixture repository {}: {err}",
+                self.path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repository() {
+        let repository =
+            FixtureRepository::new(&[FixtureModel::Echo, FixtureModel::AddSub, FixtureModel::Echo])
+                .unwrap();
+        let path = repository.path().to_path_buf();
+        assert_eq!(
+            repository.models(),
+            [FixtureModel::AddSub, FixtureModel::Echo]
+        );
+
+        let config = fs::read_to_string(path.join("add_sub_int32/config.pbtxt")).unwrap();
+        assert!(config.contains("name: \"add_sub_int32\""));
+        assert!(path.join("echo_bytes/1/model.py").exists());
+
+        let golden = FixtureModel::Echo.golden();
+        assert_eq!(golden.inputs[0].buffer().size(), 4 * 3 + 8);
+        assert_eq!(golden.outputs[0].values.len(), 20);
+
+        drop(repository);
+        assert!(!path.exists());
+    }
+}
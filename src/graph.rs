@@ -0,0 +1,557 @@
+//! Execution graph of the inference results postprocessing.
+//!
+//! Postprocessing is declared as a set of stages (crop, NMS, softmax, decode, ...).
+//! Each stage consumes named buffers and produces new named buffers, and is placed either on CPU or on GPU. \
+//! [Graph::run] executes stages in dependency order, moves buffers between host and device only when
+//! the placement of the consuming stage requires it, and reuses released buffers for the following transfers and allocations.
+//! ```ignore
+//! let mut graph = Graph::new();
+//! graph
+//!     .stage("softmax", Placement::Gpu(0), ["logits"], ["probs"], |ctx, inputs| {
+//!         let probs = ctx.alloc(inputs[0].size() / 4, DataType::Fp32)?;
+//!         // Launch the kernel...
+//!         ctx.recycle(inputs);
+//!         Ok(vec![probs])
+//!     })
+//!     .stage("decode", Placement::Cpu, ["probs"], ["labels"], decode);
+//!
+//! let outputs = graph.run(response.return_buffers().await?)?;
+//! ```
+//!
+//! **Note**: [Graph::run] is blocking and pins cuda context for GPU stages and transfers,
+//! so in asynchronous context it should be called via [tokio::task::spawn_blocking].
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::{Error, ErrorCode},
+    memory::{self, Buffer, DataType, MemoryType},
+};
+
+/// Maximum count of the released buffers of the same kind kept for the reuse.
+const MAX_POOLED: usize = 4;
+
+/// Where the stage is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Placement {
+    /// Stage works with host memory (Cpu or Pinned buffers).
+    Cpu,
+    /// Stage works with Gpu buffers of the device with the specified id.
+    /// Stage is executed with the cuda context of this device pinned.
+    Gpu(i32),
+}
+
+impl Placement {
+    /// Memory type of the buffers the stage operates on.
+    pub fn memory_type(self) -> MemoryType {
+        match self {
+            Placement::Cpu => MemoryType::Cpu,
+            Placement::Gpu(_) => MemoryType::Gpu,
+        }
+    }
+
+    /// Can the stage use the `buffer` without the transfer.
+    fn accepts(self, buffer: &Buffer) -> bool {
+        match self {
+            Placement::Cpu => buffer.memory_type != MemoryType::Gpu,
+            Placement::Gpu(device) => {
+                buffer.memory_type == MemoryType::Gpu && buffer.device_id == device
+            }
+        }
+    }
+
+    fn device(self) -> i32 {
+        match self {
+            Placement::Cpu => 0,
+            Placement::Gpu(device) => device,
+        }
+    }
+}
+
+/// Key of the pooled buffers: memory type, device, data type and byte size.
+type PoolKey = (MemoryType, i32, DataType, usize);
+
+/// Released buffers kept for the reuse.
+#[derive(Debug)]
+struct BufferPool {
+    buffers: HashMap<PoolKey, Vec<Buffer>>,
+    /// Trim generation the pooled buffers belong to. Check [crate::Server::trim_memory].
+    generation: u64,
+    /// Source of the trim generation: [memory::trim_generation] (replaced in the tests).
+    trim_generation: fn() -> u64,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        BufferPool {
+            buffers: HashMap::new(),
+            generation: memory::trim_generation(),
+            trim_generation: memory::trim_generation,
+        }
+    }
+}
+
+impl BufferPool {
+    /// Release pooled buffers if memory trim was requested since the last use.
+    fn sync_trim(&mut self) {
+        let generation = (self.trim_generation)();
+        if self.generation != generation {
+            self.clear();
+            self.generation = generation;
+        }
+    }
+
+    /// Free the pooled buffers. Pinned and Gpu buffers are freed with the cuda context of their device pinned.
+    #[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+    fn clear(&mut self) {
+        for ((memory_type, device, ..), buffers) in self.buffers.drain() {
+            #[cfg(feature = "gpu")]
+            let _context = match memory_type {
+                MemoryType::Cpu => None,
+                MemoryType::Pinned | MemoryType::Gpu => crate::context::pin_context(device)
+                    .inspect_err(|err| {
+                        log::warn!("Can't pin cuda context of device {device} to free pooled buffers: {err}")
+                    })
+                    .ok(),
+            };
+            drop(buffers);
+        }
+    }
+
+    fn take(
+        &mut self,
+        memory_type: MemoryType,
+        device: i32,
+        data_type: DataType,
+        size: usize,
+    ) -> Option<Buffer> {
+        self.sync_trim();
+        self.buffers
+            .get_mut(&(memory_type, device, data_type, size))?
+            .pop()
+    }
+
+    fn put(&mut self, buffer: Buffer) {
+        self.sync_trim();
+        let pooled = self
+            .buffers
+            .entry((
+                buffer.memory_type,
+                buffer.device_id,
+                buffer.data_type,
+                buffer.len,
+            ))
+            .or_default();
+        if pooled.len() < MAX_POOLED {
+            pooled.push(buffer);
+        }
+    }
+
+    /// Get a buffer of `count` elements of `data_type` in `memory_type` memory of the `device`.
+    /// Buffer released earlier is reused if possible.
+    fn alloc(
+        &mut self,
+        memory_type: MemoryType,
+        device: i32,
+        count: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        if let Some(buffer) = self.take(
+            memory_type,
+            device,
+            data_type,
+            count * data_type.size() as usize,
+        ) {
+            return Ok(buffer);
+        }
+        alloc_on(memory_type, device, count, data_type)
+    }
+
+    /// Copy `source` into the buffer of `memory_type` on the `device`. Target buffer is taken from the pool if possible.
+    fn copy(
+        &mut self,
+        source: &Buffer,
+        memory_type: MemoryType,
+        device: i32,
+    ) -> Result<Buffer, Error> {
+        let mut target = self.alloc(
+            memory_type,
+            device,
+            source.len / source.data_type.size() as usize,
+            source.data_type,
+        )?;
+
+        if source.memory_type == MemoryType::Gpu {
+            #[cfg(feature = "gpu")]
+            run_in_context_sync!(
+                source.device_id,
+                target.copy_from_cuda_array(0, unsafe { source.get_cuda_array() })
+            )?;
+        } else {
+            target.copy_from_slice(0, source.bytes())?;
+        }
+        Ok(target)
+    }
+}
+
+/// Allocate the buffer with the cuda context of the `device` pinned, unless it's the Cpu one.
+#[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+fn alloc_on(
+    memory_type: MemoryType,
+    device: i32,
+    count: usize,
+    data_type: DataType,
+) -> Result<Buffer, Error> {
+    match memory_type {
+        MemoryType::Cpu => Buffer::alloc_with_data_type(count, memory_type, data_type),
+        MemoryType::Pinned | MemoryType::Gpu => run_in_context_sync!(
+            device,
+            Buffer::alloc_with_data_type(count, memory_type, data_type)
+        ),
+    }
+}
+
+/// Context of the stage execution.
+pub struct StageContext<'a> {
+    placement: Placement,
+    pool: &'a mut BufferPool,
+}
+
+impl StageContext<'_> {
+    /// Placement of the executing stage.
+    pub fn placement(&self) -> Placement {
+        self.placement
+    }
+
+    /// Get a buffer of `count` elements of `data_type` located according to the stage placement. \
+    /// Buffer released earlier is reused if possible, so its content is unspecified.
+    pub fn alloc(&mut self, count: usize, data_type: DataType) -> Result<Buffer, Error> {
+        self.pool.alloc(
+            self.placement.memory_type(),
+            self.placement.device(),
+            count,
+            data_type,
+        )
+    }
+
+    /// Return buffers that are no longer needed, so they can be reused by the following stages and runs.
+    pub fn recycle<B: IntoIterator<Item = Buffer>>(&mut self, buffers: B) {
+        for buffer in buffers {
+            self.pool.put(buffer);
+        }
+    }
+}
+
+type StageFn = dyn FnMut(&mut StageContext<'_>, Vec<Buffer>) -> Result<Vec<Buffer>, Error> + Send;
+
+struct Stage {
+    name: String,
+    placement: Placement,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    func: Box<StageFn>,
+}
+
+/// Postprocessing execution graph. Check [module](crate::graph) level documentation for more info.
+#[derive(Default)]
+pub struct Graph {
+    stages: Vec<Stage>,
+    pool: BufferPool,
+}
+
+impl Graph {
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a stage to the graph. \
+    /// `name`: name of the stage (used in errors). \
+    /// `placement`: where the stage is executed. Input buffers are passed to the stage located accordingly. \
+    /// `inputs`: names of the buffers consumed by the stage. Each of them is either the graph input or the output of another stage. \
+    /// `outputs`: names of the buffers produced by the stage. \
+    /// `func`: stage itself. Receives input buffers in the order of `inputs` and must return buffers in the order of `outputs`.
+    pub fn stage<N, I, O, F>(
+        &mut self,
+        name: N,
+        placement: Placement,
+        inputs: I,
+        outputs: O,
+        func: F,
+    ) -> &mut Self
+    where
+        N: AsRef<str>,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        O: IntoIterator,
+        O::Item: AsRef<str>,
+        F: FnMut(&mut StageContext<'_>, Vec<Buffer>) -> Result<Vec<Buffer>, Error> + Send + 'static,
+    {
+        self.stages.push(Stage {
+            name: name.as_ref().to_string(),
+            placement,
+            inputs: inputs.into_iter().map(|i| i.as_ref().to_string()).collect(),
+            outputs: outputs
+                .into_iter()
+                .map(|o| o.as_ref().to_string())
+                .collect(),
+            func: Box::new(func),
+        });
+        self
+    }
+
+    /// Execution order of the stages if the graph is run with `inputs` names provided.
+    pub fn schedule<I>(&self, inputs: I) -> Result<Vec<&str>, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let order = self.order(inputs.into_iter().map(|i| i.as_ref().to_string()).collect())?;
+        Ok(order
+            .into_iter()
+            .map(|idx| self.stages[idx].name.as_str())
+            .collect())
+    }
+
+    /// Run all the stages of the graph over `inputs`. \
+    /// Returns all the buffers that were not consumed by any stage (outputs of the final stages and unused inputs).
+    pub fn run(
+        &mut self,
+        inputs: HashMap<String, Buffer>,
+    ) -> Result<HashMap<String, Buffer>, Error> {
+        let order = self.order(inputs.keys().cloned().collect())?;
+
+        let mut consumers = HashMap::<&str, usize>::new();
+        for stage in &self.stages {
+            for input in &stage.inputs {
+                *consumers.entry(input.as_str()).or_default() += 1;
+            }
+        }
+        let mut consumers: HashMap<String, usize> = consumers
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+
+        let mut values = inputs;
+        for idx in order {
+            let Stage {
+                name,
+                placement,
+                inputs,
+                outputs,
+                func,
+            } = &mut self.stages[idx];
+            let placement = *placement;
+            let pool = &mut self.pool;
+
+            let produced = run_in_placement(placement, || {
+                let mut stage_inputs = Vec::with_capacity(inputs.len());
+                for input in inputs.iter() {
+                    let left = consumers.get_mut(input).expect("consumers are counted");
+                    *left -= 1;
+
+                    let buffer = if *left == 0 {
+                        values
+                            .remove(input)
+                            .expect("scheduled stage has all inputs")
+                    } else {
+                        let buffer = &values[input];
+                        // Buffer will be used by another stage, so copy is passed.
+                        if placement.accepts(buffer) {
+                            pool.copy(buffer, buffer.memory_type, buffer.device_id)?
+                        } else {
+                            pool.copy(buffer, placement.memory_type(), placement.device())?
+                        }
+                    };
+
+                    let buffer = if placement.accepts(&buffer) {
+                        buffer
+                    } else {
+                        let moved =
+                            pool.copy(&buffer, placement.memory_type(), placement.device())?;
+                        pool.put(buffer);
+                        moved
+                    };
+                    stage_inputs.push(buffer);
+                }
+
+                func(&mut StageContext { placement, pool }, stage_inputs)
+            })?;
+
+            if produced.len() != outputs.len() {
+                return Err(Error::new(
+                    ErrorCode::Internal,
+                    format!(
+                        "Stage {name} produced {} buffers, while {} outputs are declared: {outputs:?}",
+                        produced.len(),
+                        outputs.len()
+                    ),
+                ));
+            }
+            values.extend(outputs.iter().cloned().zip(produced));
+        }
+
+        Ok(values)
+    }
+
+    /// Release the buffers kept for the reuse. \
+    /// Pooled Pinned and Gpu buffers are freed with the cuda context of their device pinned.
+    pub fn trim(&mut self) -> Result<(), Error> {
+        self.pool.clear();
+        Ok(())
+    }
+
+    /// Order stages so that each stage goes after the producers of its inputs.
+    fn order(&self, available: HashSet<String>) -> Result<Vec<usize>, Error> {
+        let mut producers = HashMap::new();
+        for (idx, stage) in self.stages.iter().enumerate() {
+            for output in &stage.outputs {
+                if available.contains(output) {
+                    return Err(Error::new(
+                        ErrorCode::InvalidArg,
+                        format!(
+                            "Output {output} of stage {} is also the graph input",
+                            stage.name
+                        ),
+                    ));
+                }
+                if let Some(other) = producers.insert(output.as_str(), idx) {
+                    return Err(Error::new(
+                        ErrorCode::InvalidArg,
+                        format!(
+                            "Output {output} is produced by both stages {} and {}",
+                            self.stages[other].name, stage.name
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let mut dependencies = vec![HashSet::new(); self.stages.len()];
+        for (idx, stage) in self.stages.iter().enumerate() {
+            let missing: Vec<_> = stage
+                .inputs
+                .iter()
+                .filter(|i| !available.contains(*i) && !producers.contains_key(i.as_str()))
+                .collect();
+            if !missing.is_empty() {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Stage {} can't be scheduled: inputs {missing:?} are neither graph inputs nor produced by other stages",
+                        stage.name
+                    ),
+                ));
+            }
+            dependencies[idx].extend(
+                stage
+                    .inputs
+                    .iter()
+                    .filter_map(|i| producers.get(i.as_str()).copied()),
+            );
+        }
+
+        topological_order(&dependencies).map_err(|idx| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Stage {} can't be scheduled: its inputs are produced in a cycle",
+                    self.stages[idx].name
+                ),
+            )
+        })
+    }
+}
+
+/// Order the nodes so that each node goes after its `dependencies` (indices of the other nodes). \
+/// Of the ready nodes the one added first goes first. \
+/// Returns the first node that can't be ordered if the dependencies form a cycle.
+pub(crate) fn topological_order(dependencies: &[HashSet<usize>]) -> Result<Vec<usize>, usize> {
+    let mut order = Vec::with_capacity(dependencies.len());
+    let mut done = HashSet::new();
+    let mut pending: Vec<usize> = (0..dependencies.len()).collect();
+    while !pending.is_empty() {
+        let pos = pending
+            .iter()
+            .position(|idx| dependencies[*idx].is_subset(&done))
+            .ok_or(pending[0])?;
+        let idx = pending.remove(pos);
+        done.insert(idx);
+        order.push(idx);
+    }
+    Ok(order)
+}
+
+/// Run `f` with the cuda context of the device pinned for the Gpu placement. Cpu stages don't touch cuda.
+fn run_in_placement<T, F: FnOnce() -> Result<T, Error>>(
+    placement: Placement,
+    f: F,
+) -> Result<T, Error> {
+    match placement {
+        Placement::Cpu => f(),
+        #[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+        Placement::Gpu(device) => run_in_context_sync!(device, f()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn cpu_graph() {
+        let mut graph = Graph::new();
+        graph
+            .stage(
+                "scale",
+                Placement::Cpu,
+                ["sum"],
+                ["scaled"],
+                |ctx, inputs| {
+                    let count = inputs[0].size() / DataType::Fp32.size() as usize;
+                    let values: &[f32] = inputs[0].as_ref();
+                    let scaled: Vec<f32> = values[..count].iter().map(|v| v * 10.).collect();
+                    ctx.recycle(inputs);
+                    Ok(vec![Buffer::from(scaled)])
+                },
+            )
+            .stage("sum", Placement::Cpu, ["a", "b"], ["sum"], |ctx, inputs| {
+                let mut sum = ctx.alloc(2, DataType::Fp32)?;
+                let (a, b): (&[f32], &[f32]) = (inputs[0].as_ref(), inputs[1].as_ref());
+                let out: &mut [f32] = sum.as_mut();
+                out[0] = a[0] + b[0];
+                out[1] = a[1] + b[1];
+                Ok(vec![sum])
+            });
+
+        assert_eq!(graph.schedule(["a", "b"]).unwrap(), ["sum", "scale"]);
+
+        let inputs = HashMap::from([
+            ("a".to_string(), Buffer::from([1f32, 2.])),
+            ("b".to_string(), Buffer::from([3f32, 4.])),
+        ]);
+        let outputs = graph.run(inputs).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(AsRef::<[f32]>::as_ref(&outputs["scaled"])[..2], [40., 60.]);
+    }
+
+    #[test]
+    fn pool_trim() {
+        // The pool gets the trim generation of its own, not racing with the tests requesting the trim.
+        static GENERATION: AtomicU64 = AtomicU64::new(0);
+        let mut pool = BufferPool {
+            generation: 0,
+            trim_generation: || GENERATION.load(Ordering::Acquire),
+            ..Default::default()
+        };
+
+        pool.put(Buffer::from([1f32, 2.]));
+        assert!(pool.take(MemoryType::Cpu, 0, DataType::Fp32, 8).is_some());
+
+        pool.put(Buffer::from([1f32, 2.]));
+        GENERATION.fetch_add(1, Ordering::AcqRel);
+        assert!(pool.take(MemoryType::Cpu, 0, DataType::Fp32, 8).is_none());
+    }
+}
@@ -128,16 +128,33 @@
 
 #![allow(clippy::bad_bit_mask)]
 
+// Paths generated by the derive macros are resolved in the crate tests.
+#[cfg(all(test, feature = "app"))]
+extern crate self as tritonserver_rs;
+
 /// Macros to run some Cuda operations in context.
 #[macro_use]
 pub mod macros;
 
 pub(crate) mod allocator;
+#[cfg(feature = "app")]
+pub mod app;
+/// Profiling of the model inference: latency percentiles, throughput and GPU memory under load.
+pub mod bench;
+/// Compatibility of the loaded Triton library with the crate bindings.
+pub mod compat;
 #[cfg(feature = "gpu")]
 /// Cuda context for managing device execution.
 pub mod context;
 /// Error types for Tritonserver-rs.
 pub mod error;
+#[cfg(feature = "fixtures")]
+/// Fixture models and golden cases for the end-to-end tests.
+pub mod fixtures;
+/// Execution graph of the inference results postprocessing.
+pub mod graph;
+/// Triton server logs in the log facade.
+pub mod logging;
 /// Memory management utilities for model inference.
 pub mod memory;
 /// Metadata message serialization/deserialization.
@@ -148,10 +165,21 @@ pub mod metrics;
 pub mod options;
 /// Model inference requests and server parameters.
 pub mod parameter;
+/// Client side pipeline of the models feeding outputs of one model to the inputs of the next.
+pub mod pipeline;
+/// Common postprocessing of the inference results: softmax, top-k, non-maximum suppression.
+pub mod postprocess;
+/// Recording of the inference inputs and comparison of the models on them.
+pub mod replay;
+pub mod repository;
 /// Request builder and utilities for Triton server inference.
 pub mod request;
 /// Response handling and parsing from Triton server.
 pub mod response;
+/// Executors of the crate async code.
+pub mod runtime;
+/// Client side queue of the requests with priorities and concurrency limits.
+pub mod scheduler;
 /// Server initialization and lifecycle management.
 pub mod server;
 pub(crate) mod sys {
@@ -165,14 +193,16 @@ pub(crate) mod sys {
     )]
     include!(concat!(env!("OUT_DIR"), "/tritonserver.rs"));
 }
+/// Deterministic mode of the crate for integration tests.
+pub mod testing;
 pub mod trace;
 
 pub use crate::{
-    error::{Error, ErrorCode},
+    error::{Error, ErrorCode, ErrorContext},
     memory::{Buffer, MemoryType},
-    request::{Allocator, Request},
+    request::{Allocator, BlockingAllocator, Request},
     response::Response,
-    server::Server,
+    server::{Server, ServerHandle},
     sys::{TRITONSERVER_API_VERSION_MAJOR, TRITONSERVER_API_VERSION_MINOR},
 };
 #[cfg(feature = "gpu")]
@@ -196,6 +226,31 @@ pub fn api_version() -> Result<(u32, u32), Error> {
     )
 }
 
+/// Check that the Triton library is compatible with the bindings the crate is built with:
+/// the major API versions must match and the library minor version must not be older. \
+/// Called before the server creation, the error names the library directory found at build time (if any).
+pub fn check_api_version() -> Result<(), Error> {
+    let (major, minor) = api_version()?;
+    if api_compatible((major, minor)) {
+        return Ok(());
+    }
+    let linked = option_env!("TRITONSERVER_RS_LIB_DIR")
+        .map(|dir| format!(" (built against the library in {dir})"))
+        .unwrap_or_default();
+    Err(Error::new(
+        ErrorCode::Unsupported,
+        format!(
+            "Triton library API version {major}.{minor} is incompatible with the bindings API version \
+             {TRITONSERVER_API_VERSION_MAJOR}.{TRITONSERVER_API_VERSION_MINOR}{linked}. \
+             Use the library of the matching Triton release (set TRITONSERVER_LIB_DIR at build time and LD_LIBRARY_PATH at runtime)"
+        ),
+    ))
+}
+
+fn api_compatible((major, minor): (u32, u32)) -> bool {
+    major == TRITONSERVER_API_VERSION_MAJOR && minor >= TRITONSERVER_API_VERSION_MINOR
+}
+
 pub(crate) fn to_cstring<S: AsRef<str>>(value: S) -> Result<CString, Error> {
     CString::new(value.as_ref().as_bytes())
         .map_err(|err| Error::new(ErrorCode::InvalidArg, format!("{}", err)))
@@ -212,6 +267,24 @@ pub(crate) fn path_to_cstring<P: AsRef<Path>>(value: P) -> Result<CString, Error
         })
 }
 
+/// Path of the loaded Triton shared library.
+pub(crate) fn library_path() -> Option<std::path::PathBuf> {
+    let mut info = std::mem::MaybeUninit::<libc::Dl_info>::zeroed();
+    let found = unsafe {
+        libc::dladdr(
+            sys::TRITONSERVER_ApiVersion as *const libc::c_void,
+            info.as_mut_ptr(),
+        )
+    };
+    let info = unsafe { info.assume_init() };
+    if found == 0 || info.dli_fname.is_null() {
+        return None;
+    }
+    Some(std::path::PathBuf::from(std::ffi::OsStr::from_bytes(
+        unsafe { CStr::from_ptr(info.dli_fname) }.to_bytes(),
+    )))
+}
+
 pub(crate) fn from_char_array(value: *const c_char) -> String {
     assert!(!value.is_null());
     unsafe { CStr::from_ptr(value) }
@@ -231,4 +304,18 @@ mod tests {
         assert_eq!(major, TRITONSERVER_API_VERSION_MAJOR);
         assert_eq!(minor, TRITONSERVER_API_VERSION_MINOR);
     }
+
+    #[test]
+    fn api_compatibility() {
+        let bindings = (
+            TRITONSERVER_API_VERSION_MAJOR,
+            TRITONSERVER_API_VERSION_MINOR,
+        );
+        assert!(api_compatible(bindings));
+        assert!(api_compatible((bindings.0, bindings.1 + 1)));
+        assert!(!api_compatible((bindings.0 + 1, bindings.1)));
+        if bindings.1 > 0 {
+            assert!(!api_compatible((bindings.0, bindings.1 - 1)));
+        }
+    }
 }
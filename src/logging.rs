@@ -0,0 +1,284 @@
+//! Triton server logs in the [log] facade.
+//!
+//! By default Triton writes its logs to the console or to the log file ([crate::options::Options::log_file]). \
+//! With [crate::options::Options::log_to_facade] the logs are captured and re-emitted as [log] records of [LOG_TARGET] target
+//! (with the level, file and line of the Triton message), so the application and the server logs share one pipeline and format.
+//! Use the `tracing-log` bridge to receive them as `tracing` events.
+//!
+//! [log_message] and [crate::triton_log] macro write the application messages to the Triton log.
+
+use std::{
+    borrow::Cow,
+    ffi::CString,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{ffi::OsStrExt, fs::OpenOptionsExt},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread::JoinHandle,
+};
+
+use crate::{
+    error::{Error, ErrorCode},
+    sys, to_cstring,
+};
+
+/// Target of the records of the Triton logs.
+pub const LOG_TARGET: &str = "tritonserver";
+
+/// Line written to the log pipe to stop the forwarding thread. Triton messages never start with NUL.
+const STOP_LINE: &str = "\0tritonserver-rs: stop log forwarding";
+
+/// Triton log levels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum LogLevel {
+    Info = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_INFO,
+    Warn = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_WARN,
+    Error = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_ERROR,
+    Verbose = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_VERBOSE,
+}
+
+impl From<LogLevel> for log::Level {
+    /// Verbose messages are mapped to [log::Level::Debug].
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Verbose => log::Level::Debug,
+        }
+    }
+}
+
+/// Check if the Triton logging of `level` is enabled.
+pub fn is_enabled(level: LogLevel) -> bool {
+    unsafe { sys::TRITONSERVER_LogIsEnabled(level as _) }
+}
+
+/// Write the `message` to the Triton log: the console or the file set with [crate::options::Options::log_file],
+/// so the application entries are consolidated with the server ones. Also exported as `server::log_message`. \
+/// `file` and `line`: location of the message shown in the log. Check [crate::triton_log] macro that fills them in.
+pub fn log_message<F: AsRef<str>, M: AsRef<str>>(
+    level: LogLevel,
+    file: F,
+    line: u32,
+    message: M,
+) -> Result<(), Error> {
+    let file = to_cstring(file)?;
+    let message = to_cstring(message)?;
+    triton_call!(sys::TRITONSERVER_LogMessage(
+        level as _,
+        file.as_ptr(),
+        line as _,
+        message.as_ptr()
+    ))
+}
+
+/// Parsed header line of the Triton log.
+#[derive(Debug, PartialEq, Eq)]
+struct Message<'a> {
+    level: LogLevel,
+    location: Option<(&'a str, u32)>,
+    text: Cow<'a, str>,
+}
+
+/// Parse the first line of the Triton log message of any [crate::options::LogFormat]:
+///
+/// `I0520 20:03:25.829575 3355 model_lifecycle.cc:441] "AsyncLoad() 'simple'"` \
+/// `2024-05-20T20:03:26Z I 3415 model_lifecycle.cc:441] "AsyncLoad() 'simple'"`
+///
+/// Returns None if the line is not the first line of the message (continuation of the multiline message).
+fn parse_line(line: &str) -> Option<Message<'_>> {
+    let (header, text) = line.split_once("] ")?;
+    let tokens: Vec<_> = header.split_whitespace().collect();
+    let [first, second, _, location] = tokens[..] else {
+        return None;
+    };
+    let level = if first.ends_with('Z') { second } else { first };
+    let level = match level.chars().next()? {
+        'I' => LogLevel::Info,
+        'W' => LogLevel::Warn,
+        'E' => LogLevel::Error,
+        'V' => LogLevel::Verbose,
+        _ => return None,
+    };
+    let location = location
+        .rsplit_once(':')
+        .and_then(|(file, line)| Some((file, line.parse().ok()?)));
+
+    // Triton escapes the messages as JSON strings by default.
+    let text = match text.trim_end() {
+        quoted if quoted.len() >= 2 && quoted.starts_with('"') && quoted.ends_with('"') => {
+            serde_json::from_str::<String>(quoted)
+                .map(Cow::Owned)
+                .unwrap_or(Cow::Borrowed(quoted))
+        }
+        text => Cow::Borrowed(text),
+    };
+    Some(Message {
+        level,
+        location,
+        text,
+    })
+}
+
+fn emit(level: LogLevel, location: Option<(&str, u32)>, text: &str) {
+    let level = log::Level::from(level);
+    if level > log::max_level() {
+        return;
+    }
+    log::logger().log(
+        &log::Record::builder()
+            .args(format_args!("{text}"))
+            .level(level)
+            .target(LOG_TARGET)
+            .file(location.map(|(file, _)| file))
+            .line(location.map(|(_, line)| line))
+            .build(),
+    );
+}
+
+/// Re-emit the log lines of `reader` as the [log] records until [STOP_LINE] or the end of the input.
+fn forward<R: BufRead>(reader: R) {
+    let mut level = LogLevel::Info;
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line == STOP_LINE {
+            break;
+        }
+        match parse_line(&line) {
+            Some(message) => {
+                level = message.level;
+                emit(message.level, message.location, &message.text);
+            }
+            // Continuation lines (e.g. tables of the loaded models) have the level of the message.
+            None if !line.trim().is_empty() => emit(level, None, &line),
+            None => {}
+        }
+    }
+}
+
+/// Pipe the Triton logs are written to and the thread forwarding them to the [log] facade. \
+/// Created on the server start, stops the thread and removes the pipe on drop.
+#[derive(Debug)]
+pub(crate) struct LogRedirect {
+    path: PathBuf,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LogRedirect {
+    /// Path of the pipe to set as the log file.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LogRedirect {
+    fn drop(&mut self) {
+        // The thread keeps the pipe open for writing too, so the non-blocking open succeeds while it runs.
+        let stopped = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)
+            .and_then(|mut pipe| writeln!(pipe, "{STOP_LINE}"));
+        match (stopped, self.thread.take()) {
+            (Ok(()), Some(thread)) => {
+                let _ = thread.join();
+            }
+            (Err(err), Some(_)) => {
+                log::warn!("Can't stop log forwarding of {:?}: {err}", self.path)
+            }
+            (_, None) => {}
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Create the pipe the Triton logs are written to and spawn the thread forwarding them to the [log] facade.
+pub(crate) fn redirect() -> Result<LogRedirect, Error> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "tritonserver-rs-log-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| Error::new(ErrorCode::InvalidArg, err.to_string()))?;
+    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        return Err(Error::new(
+            ErrorCode::Internal,
+            format!(
+                "Can't create log pipe {path:?}: {}",
+                io::Error::last_os_error()
+            ),
+        ));
+    }
+    let mut redirect = LogRedirect { path, thread: None };
+
+    // Opened for writing too: the open doesn't wait for the server
+    // and the reads don't end when the server reopens the log.
+    let pipe = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&redirect.path)
+        .map_err(|err| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't open log pipe {:?}: {err}", redirect.path),
+            )
+        })?;
+    let thread = std::thread::Builder::new()
+        .name("triton-log".into())
+        .spawn(move || forward(BufReader::new(pipe)))
+        .map_err(|err| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't spawn log thread: {err}"),
+            )
+        })?;
+    redirect.thread = Some(thread);
+    Ok(redirect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let message = parse_line(
+            r#"I0520 20:03:25.829575 3355 model_lifecycle.cc:441] "AsyncLoad() 'simple'""#,
+        )
+        .unwrap();
+        assert_eq!(message.level, LogLevel::Info);
+        assert_eq!(message.location, Some(("model_lifecycle.cc", 441)));
+        assert_eq!(message.text, "AsyncLoad() 'simple'");
+
+        let message =
+            parse_line(r#"2024-05-20T20:03:26Z W 3415 server.cc:307] "Timeout \"30s\"""#).unwrap();
+        assert_eq!(message.level, LogLevel::Warn);
+        assert_eq!(message.text, r#"Timeout "30s""#);
+
+        assert_eq!(parse_line("| simple | 1       | READY  |"), None);
+    }
+
+    #[test]
+    fn redirect_cleanup() {
+        let redirect = redirect().unwrap();
+        let path = redirect.path().to_path_buf();
+        assert!(path.exists());
+
+        let mut server_log = OpenOptions::new().write(true).open(&path).unwrap();
+        writeln!(
+            server_log,
+            r#"I0520 20:03:25.829575 3355 server.cc:1] "started""#
+        )
+        .unwrap();
+
+        drop(redirect);
+        assert!(!path.exists());
+    }
+}
@@ -6,10 +6,7 @@ macro_rules! cuda_call {
         let res = unsafe { $expr };
 
         if res != cuda_driver_sys::CUresult::CUDA_SUCCESS {
-            Err($crate::error::Error::new(
-                $crate::error::ErrorCode::Internal,
-                format!("Cuda result: {:?}", res),
-            ))
+            Err($crate::error::Error::from_cuda(res))
         } else {
             std::result::Result::<_, $crate::error::Error>::Ok(())
         }
@@ -19,41 +16,44 @@ macro_rules! cuda_call {
         let res = unsafe { $expr };
 
         if res != cuda_driver_sys::CUresult::CUDA_SUCCESS {
-            Err($crate::error::Error::new(
-                $crate::error::ErrorCode::Internal,
-                format!("Cuda result: {:?}", res),
-            ))
+            Err($crate::error::Error::from_cuda(res))
         } else {
             std::result::Result::<_, $crate::error::Error>::Ok($val)
         }
     }};
 }
 
-/// Run triton method and get the Result<(), tritonserver_rs::Error> instead of cuda_driver_sys::CUresult.
+/// Run triton method and get the Result<(), tritonserver_rs::Error> instead of cuda_driver_sys::CUresult. \
+/// The error records the name of the called function (not its arguments), check [crate::error::ErrorContext::call].
 macro_rules! triton_call {
-    ($expr: expr) => {{
+    ($($path: ident)::+ ($($arg: expr),* $(,)?)) => {{
         #[allow(clippy::macro_metavars_in_unsafe)]
-        let res = unsafe { $expr };
+        let res = unsafe { $($path)::+($($arg),*) };
 
         if res.is_null() {
             std::result::Result::<(), $crate::error::Error>::Ok(())
         } else {
-            std::result::Result::<(), $crate::error::Error>::Err(res.into())
+            std::result::Result::<(), $crate::error::Error>::Err(
+                $crate::error::Error::from(res).with_call(stringify!($($path)::+)),
+            )
         }
     }};
-    ($expr: expr, $val: expr) => {{
+    ($($path: ident)::+ ($($arg: expr),* $(,)?), $val: expr) => {{
         #[allow(clippy::macro_metavars_in_unsafe)]
-        let res = unsafe { $expr };
+        let res = unsafe { $($path)::+($($arg),*) };
 
         if res.is_null() {
             std::result::Result::<_, $crate::error::Error>::Ok($val)
         } else {
-            std::result::Result::<_, $crate::error::Error>::Err(res.into())
+            std::result::Result::<_, $crate::error::Error>::Err(
+                $crate::error::Error::from(res).with_call(stringify!($($path)::+)),
+            )
         }
     }};
 }
 
 /// Run cuda code (which should be run in sync + cuda context pinned) in asynchronous context.
+/// The code is run on the tokio blocking thread pool, or on the current thread outside of the tokio runtime.
 ///
 /// First argument is an id of device to run function on; second is the code to run. All the variables will be moved.
 ///
@@ -63,13 +63,12 @@ macro_rules! run_in_context {
     ($val: expr, $expr: expr) => {{
         #[cfg(feature = "gpu")]
         {
-            tokio::task::spawn_blocking(move || {
+            $crate::runtime::run_blocking(move || {
                 let ctx = $crate::get_context($val)?;
                 let _handle = ctx.make_current()?;
                 $expr
             })
             .await
-            .expect("tokio failed to join thread")
         }
         #[cfg(not(feature = "gpu"))]
         $expr
@@ -94,3 +93,16 @@ macro_rules! run_in_context_sync {
         $expr
     }};
 }
+
+/// Write the formatted message to the Triton log (check [crate::logging::log_message]) with the location of the macro call.
+///
+/// First argument is the [crate::logging::LogLevel]; the others are the format arguments.
+/// ```ignore
+/// triton_log!(LogLevel::Info, "request {id} is rejected by the queue")?;
+/// ```
+#[macro_export]
+macro_rules! triton_log {
+    ($level: expr, $($arg: tt)+) => {
+        $crate::logging::log_message($level, file!(), line!(), format!($($arg)+))
+    };
+}
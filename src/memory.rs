@@ -2,32 +2,95 @@
 //!
 //! **NOTE**: some functions that uses CUDA must be run in synchronous context +
 //! [crate::context::Context] must me pushed as current. \
-//! To simplify satisfaction of this requirement, those methods can be run with [crate::run_in_context] or [crate::run_in_context_sync] macro.
+//! If no context is current, [Buffer] methods pin the context of the thread device themselves
+//! (check [crate::context::set_thread_device]), unless the "strict-context" feature is enabled. \
+//! To pin the context explicitly (e.g. to avoid the pinning on each call), those methods can be run with
+//! [crate::run_in_context] or [crate::run_in_context_sync] macro or with [crate::context::pin_context] guard.
 //! ```
 //! run_in_context!(0, Buffer::alloc<f32>(10, MemoryType::Gpu))
 //! ```
 
+#[cfg(feature = "ndarray")]
+mod array;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod attributes;
+mod dlpack;
+#[cfg(feature = "image")]
+mod image;
+/// Transforms between the channels-first and the channels-last layouts of the tensors.
+pub mod layout;
+mod npy;
+pub(crate) mod oom;
+mod precision;
+mod quantize;
+mod shm;
+#[cfg(feature = "gpu")]
+mod staging;
+#[cfg(feature = "gpu")]
+mod stream;
+mod view;
+
+#[cfg(feature = "image")]
+pub use self::image::{ChannelOrder, Normalization};
+pub use attributes::BufferAttributes;
+pub(crate) use attributes::TritonBufferAttributes;
+pub use dlpack::{DLDataType, DLDevice, DLManagedTensor, DLTensor, DL_CPU, DL_CUDA, DL_CUDA_HOST};
+pub use oom::{clear_oom_handler, set_oom_handler, set_oom_retry, OomEvent};
+pub(crate) use precision::bytes_to_f32;
+pub use precision::HalfSample;
+pub(crate) use quantize::dequantize_bytes;
+pub use quantize::{QuantizationParams, QuantizedSample};
+pub use shm::CUDA_IPC_HANDLE_SIZE;
+#[cfg(feature = "gpu")]
+pub use staging::{StagingPool, DEFAULT_STAGING_CHUNK};
+pub use view::BufferView;
+
 use core::slice;
 use std::{
     ffi::CStr,
-    fmt::Debug,
+    fmt::{self, Debug},
     intrinsics::copy_nonoverlapping,
     mem::{size_of_val, transmute},
     ops::{Bound, RangeBounds},
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 #[cfg(feature = "gpu")]
 use cuda_driver_sys::{
-    cuMemAllocHost_v2, cuMemAlloc_v2, cuMemFreeHost, cuMemFree_v2, cuMemcpyDtoD_v2,
-    cuMemcpyDtoH_v2, cuMemcpyHtoD_v2, CUdeviceptr,
+    cuMemAllocHost_v2, cuMemAlloc_v2, cuMemFreeHost, cuMemFree_v2, cuMemHostRegister_v2,
+    cuMemHostUnregister, cuMemcpyDtoD_v2, cuMemcpyDtoH_v2, cuMemcpyHtoD_v2, cuMemsetD8_v2,
+    CUdeviceptr,
 };
 use libc::{c_void, calloc, free};
 
 use crate::{
-    error::{Error, ErrorCode, CSTR_CONVERT_ERROR_PLUG},
+    error::{Error, ErrorCode, CSTR_CONVERT_ERROR_PLUG, OUT_OF_MEMORY},
     sys, to_cstring,
 };
 
+/// Generation of the memory trim. Buffer pools of the crate release cached buffers when it changes.
+static TRIM_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Current generation of the memory trim.
+pub(crate) fn trim_generation() -> u64 {
+    TRIM_GENERATION.load(Ordering::Acquire)
+}
+
+/// Make all the buffer pools of the crate release their cached buffers on the next use.
+pub(crate) fn request_trim() {
+    TRIM_GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Return the freed host heap memory to the system (glibc `malloc_trim`, no-op on other platforms).
+pub(crate) fn trim_host_heap() {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    {
+        let released = unsafe { libc::malloc_trim(0) };
+        log::debug!("malloc_trim released memory: {}", released != 0);
+    }
+}
+
 macro_rules! impl_sample {
     ($type:ty, $data:expr) => {
         impl private::Sealed for $type {}
@@ -97,6 +160,28 @@ impl DataType {
             .unwrap_or(CSTR_CONVERT_ERROR_PLUG)
     }
 
+    /// Get the model config (config.pbtxt) representation of a data type, e.g. "TYPE_FP32". \
+    /// Note that [DataType::Bytes] is named "TYPE_STRING" in model configs.
+    pub fn as_config_str(self) -> &'static str {
+        match self {
+            DataType::Invalid => "TYPE_INVALID",
+            DataType::Bool => "TYPE_BOOL",
+            DataType::Uint8 => "TYPE_UINT8",
+            DataType::Uint16 => "TYPE_UINT16",
+            DataType::Uint32 => "TYPE_UINT32",
+            DataType::Uint64 => "TYPE_UINT64",
+            DataType::Int8 => "TYPE_INT8",
+            DataType::Int16 => "TYPE_INT16",
+            DataType::Int32 => "TYPE_INT32",
+            DataType::Int64 => "TYPE_INT64",
+            DataType::Fp16 => "TYPE_FP16",
+            DataType::Fp32 => "TYPE_FP32",
+            DataType::Fp64 => "TYPE_FP64",
+            DataType::Bytes => "TYPE_STRING",
+            DataType::Bf16 => "TYPE_BF16",
+        }
+    }
+
     /// Get the size of a Triton datatype in bytes. For [DataType::Bytes] the size of one element is returned.
     pub fn size(self) -> u32 {
         if self == Self::Bytes {
@@ -109,18 +194,34 @@ impl DataType {
 
 impl TryFrom<&str> for DataType {
     type Error = Error;
-    /// Get the Triton datatype corresponding to a string representation of a datatype.
+    /// Get the Triton datatype corresponding to a string representation of a datatype. \
+    /// Both Triton ("FP32", "BYTES") and model config ("TYPE_FP32", "TYPE_STRING") names are accepted.
     fn try_from(name: &str) -> Result<Self, Self::Error> {
-        let name = to_cstring(name)?;
-        let data_type = unsafe { sys::TRITONSERVER_StringToDataType(name.as_ptr()) };
+        let triton_name = match name.strip_prefix("TYPE_") {
+            Some("STRING") => "BYTES",
+            Some(stripped) => stripped,
+            None => name,
+        };
+        let c_name = to_cstring(triton_name)?;
+        let data_type = unsafe { sys::TRITONSERVER_StringToDataType(c_name.as_ptr()) };
         if data_type != sys::TRITONSERVER_datatype_enum_TRITONSERVER_TYPE_INVALID {
             Ok(unsafe { transmute::<u32, crate::memory::DataType>(data_type) })
         } else {
-            Err(Error::new(ErrorCode::InvalidArg, ""))
+            Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("Unknown data type: {name}"),
+            ))
         }
     }
 }
 
+impl fmt::Display for DataType {
+    /// Writes the model config (config.pbtxt) name of the data type.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_config_str())
+    }
+}
+
 /// Types of memory recognized by TRITONSERVER.
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[repr(u32)]
@@ -140,6 +241,29 @@ impl MemoryType {
     }
 }
 
+impl TryFrom<&str> for MemoryType {
+    type Error = Error;
+    /// Get the memory type corresponding to a string representation of it ("CPU", "CPU_PINNED" or "GPU"). Case insensitive.
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name.to_ascii_uppercase().as_str() {
+            "CPU" => Ok(MemoryType::Cpu),
+            "CPU_PINNED" => Ok(MemoryType::Pinned),
+            "GPU" => Ok(MemoryType::Gpu),
+            _ => Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("Unknown memory type: {name}"),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MemoryType {
+    /// Writes the Triton name of the memory type.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Representation of GPU based cuda array.
 ///
 /// Does not delete array on drop.
@@ -164,8 +288,21 @@ pub struct Buffer {
     pub(crate) len: usize,
     pub(crate) data_type: DataType,
     pub(crate) memory_type: MemoryType,
+    /// Device of the GPU or Pinned memory (the device of the context it was allocated in). 0 for CPU memory.
+    pub(crate) device_id: i32,
     /// Should we execute the Drop or not.
     pub(crate) owned: bool,
+    /// Custom release of the data. If None, data is freed according to the memory type.
+    pub(crate) deleter: Option<Deleter>,
+}
+
+/// Custom release function of the [Buffer] data. Gets the data pointer.
+pub(crate) struct Deleter(pub(crate) Box<dyn FnOnce(*mut c_void) + Send + Sync>);
+
+impl Debug for Deleter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Deleter")
+    }
 }
 
 unsafe impl Send for Buffer {}
@@ -201,6 +338,35 @@ impl Buffer {
         Self::alloc_with_data_type(count, memory_type, T::DATA_TYPE)
     }
 
+    /// Allocate new buffer of requested memory type on the `device` (with the context of the `device` pinned). \
+    /// Check [Buffer::alloc].
+    pub fn alloc_on<T: Sample>(
+        device: i32,
+        count: usize,
+        memory_type: MemoryType,
+    ) -> Result<Self, Error> {
+        Self::alloc_with_data_type_on(device, count, memory_type, T::DATA_TYPE)
+    }
+
+    /// Allocate new buffer of requested memory type and data type on the `device`
+    /// (with the context of the `device` pinned). \
+    /// Check [Buffer::alloc_with_data_type].
+    pub fn alloc_with_data_type_on(
+        device: i32,
+        count: usize,
+        memory_type: MemoryType,
+        data_type: DataType,
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "gpu")]
+        let _context = match memory_type {
+            MemoryType::Cpu => None,
+            MemoryType::Pinned | MemoryType::Gpu => Some(crate::context::pin_context(device)?),
+        };
+        #[cfg(not(feature = "gpu"))]
+        let _ = device;
+        Self::alloc_with_data_type(count, memory_type, data_type)
+    }
+
     /// Allocate new buffer of requested memory type.\
     /// `count`: size of buffer in `T` units (i.e. 128 chunks of f32 (that has byte size 512) should be allocated with `count=128`).\
     /// `memory_type`: Cpu/Pinned/Gpu.\
@@ -214,6 +380,16 @@ impl Buffer {
     ) -> Result<Self, Error> {
         let data_type_size = data_type.size() as usize;
         let size = count * data_type_size;
+        #[cfg(feature = "gpu")]
+        let (_context, device_id) = match memory_type {
+            MemoryType::Cpu => (None, 0),
+            MemoryType::Pinned | MemoryType::Gpu => {
+                let context = crate::context::auto_pin()?;
+                (context, crate::context::current_device()?)
+            }
+        };
+        #[cfg(not(feature = "gpu"))]
+        let device_id = 0;
 
         let ptr = match memory_type {
             MemoryType::Cpu => Ok::<_, Error>(unsafe { calloc(count as _, data_type_size) }),
@@ -240,19 +416,134 @@ impl Buffer {
         }?;
 
         if ptr.is_null() {
+            return Err(Error::new(
+                ErrorCode::Internal,
+                format!("{OUT_OF_MEMORY}. {memory_type:?}"),
+            ));
+        }
+
+        let buffer = Buffer {
+            ptr,
+            len: size,
+            data_type,
+            memory_type,
+            device_id,
+            owned: true,
+            deleter: None,
+        };
+
+        if let Some(seed) = crate::testing::allocation_seed() {
+            match memory_type {
+                MemoryType::Cpu | MemoryType::Pinned => {
+                    let bytes = unsafe { std::slice::from_raw_parts_mut(ptr as *mut u8, size) };
+                    crate::testing::fill_seeded(bytes, seed);
+                }
+                MemoryType::Gpu => {
+                    #[cfg(feature = "gpu")]
+                    {
+                        let mut bytes = vec![0u8; size];
+                        crate::testing::fill_seeded(&mut bytes, seed);
+                        cuda_call!(cuMemcpyHtoD_v2(
+                            ptr as CUdeviceptr,
+                            bytes.as_ptr() as *const c_void,
+                            size
+                        ))?;
+                    }
+                }
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// Allocate `count` samples of `data_type` in the pinned memory placed on the NUMA `node`:
+    /// the pages are bound to the node and then page-locked for all the cuda contexts. \
+    /// `device_id` is the device the buffer is reported for.
+    ///
+    /// **Note**: Should be called in sync with cuda context pinned (check module level documentation for more info).
+    #[cfg(all(feature = "gpu", target_os = "linux"))]
+    pub(crate) fn alloc_pinned_on_node(
+        count: usize,
+        data_type: DataType,
+        device_id: i32,
+        node: u32,
+    ) -> Result<Self, Error> {
+        const MPOL_BIND: libc::c_int = 2;
+        const CU_MEMHOSTREGISTER_PORTABLE: libc::c_uint = 1;
+        const MASK_BITS: usize = libc::c_ulong::BITS as usize;
+
+        let size = count * data_type.size() as usize;
+        // Anonymous mapping can't be empty.
+        let map_len = size.max(1);
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::new(
+                ErrorCode::Internal,
+                format!(
+                    "{OUT_OF_MEMORY}. Pinned: {}",
+                    std::io::Error::last_os_error()
+                ),
+            ));
+        }
+
+        let mut nodes = vec![0 as libc::c_ulong; node as usize / MASK_BITS + 1];
+        nodes[node as usize / MASK_BITS] |= 1 << (node as usize % MASK_BITS);
+        // The kernel reads `maxnode - 1` bits of the mask.
+        let bound = unsafe {
+            libc::syscall(
+                libc::SYS_mbind,
+                ptr,
+                map_len,
+                MPOL_BIND,
+                nodes.as_ptr(),
+                nodes.len() * MASK_BITS + 1,
+                0,
+            )
+        };
+        let registered = if bound != 0 {
             Err(Error::new(
                 ErrorCode::Internal,
-                format!("OutOfMemory. {memory_type:?}"),
+                format!(
+                    "Can't bind pinned memory to NUMA node {node}: {}",
+                    std::io::Error::last_os_error()
+                ),
             ))
         } else {
-            Ok(Buffer {
+            cuda_call!(cuMemHostRegister_v2(
                 ptr,
-                len: size,
-                data_type,
-                memory_type,
-                owned: true,
-            })
+                map_len,
+                CU_MEMHOSTREGISTER_PORTABLE
+            ))
+        };
+        if let Err(err) = registered {
+            unsafe { libc::munmap(ptr, map_len) };
+            return Err(err);
         }
+
+        // Anonymous mappings are zeroed.
+        Ok(Buffer {
+            ptr,
+            len: size,
+            data_type,
+            memory_type: MemoryType::Pinned,
+            device_id,
+            owned: true,
+            deleter: Some(Deleter(Box::new(move |ptr| {
+                let _context = crate::context::auto_pin_on(device_id).ok().flatten();
+                unsafe {
+                    cuMemHostUnregister(ptr);
+                    libc::munmap(ptr, map_len);
+                }
+            }))),
+        })
     }
 
     /// Create CPU buffer of data type `T::DARA_TYPE` from `slice` of T.
@@ -269,7 +560,29 @@ impl Buffer {
             len: size_of_val(slice),
             data_type: T::DATA_TYPE,
             memory_type: MemoryType::Cpu,
+            device_id: 0,
             owned: true,
+            deleter: None,
+        }
+    }
+
+    /// Create CPU buffer of data type `T::DATA_TYPE` pointing on the `slice` data without copying it. \
+    /// The data is not freed on drop of the buffer.
+    ///
+    /// # Safety
+    /// `slice` must outlive the returned buffer and all its uses. In particular, if the buffer is passed to the request as input,
+    /// the slice must be alive until Triton releases the input: add the slice with
+    /// [BorrowedInputs::add_input_borrowed](crate::request::BorrowedInputs::add_input_borrowed) to keep it borrowed until then. \
+    /// The data must not be mutated via the returned buffer (e.g. via [Buffer::bytes_mut]).
+    pub unsafe fn from_borrowed_slice<T: Sample>(slice: &[T]) -> Self {
+        Buffer {
+            ptr: slice.as_ptr() as *mut _,
+            len: size_of_val(slice),
+            data_type: T::DATA_TYPE,
+            memory_type: MemoryType::Cpu,
+            device_id: 0,
+            owned: false,
+            deleter: None,
         }
     }
 }
@@ -287,7 +600,9 @@ impl From<CudaArray> for Buffer {
             len: value.len,
             data_type: DataType::Uint8,
             memory_type: MemoryType::Gpu,
+            device_id: crate::context::current_device().unwrap_or(0),
             owned: true,
+            deleter: None,
         }
     }
 }
@@ -319,6 +634,19 @@ impl Buffer {
         self.data_type
     }
 
+    /// Get the device of the GPU or Pinned memory. 0 for CPU buffers.
+    pub fn device_id(&self) -> i32 {
+        self.device_id
+    }
+
+    /// Memory type id of the buffer for Triton: device id for GPU memory, 0 otherwise.
+    pub(crate) fn memory_type_id(&self) -> i64 {
+        match self.memory_type {
+            MemoryType::Gpu => self.device_id as i64,
+            MemoryType::Cpu | MemoryType::Pinned => 0,
+        }
+    }
+
     /// Get byte size of data.
     pub fn size(&self) -> usize {
         self.len
@@ -366,6 +694,8 @@ impl Buffer {
                 copy_nonoverlapping(slice.as_ptr(), self.ptr.byte_add(offset) as _, slice.len());
             },
             MemoryType::Gpu => {
+                #[cfg(feature = "gpu")]
+                let _context = crate::context::auto_pin_on(self.device_id)?;
                 #[cfg(feature = "gpu")]
                 cuda_call!(cuMemcpyHtoD_v2(
                     self.ptr as CUdeviceptr + offset as CUdeviceptr,
@@ -377,6 +707,15 @@ impl Buffer {
         Ok(())
     }
 
+    /// Write `source` elements to the buffer starting from the element `offset` for any memory type of the buffer. \
+    /// Returns error if `T` doesn't match the buffer data type or `source` doesn't fit into the buffer.
+    ///
+    /// **Note**: If self.memory_type is Gpu, should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn write_at<T: Sample>(&mut self, offset: usize, source: &[T]) -> Result<(), Error> {
+        self.check_sample::<T>("write_at")?;
+        self.copy_from_slice(offset * std::mem::size_of::<T>(), source)
+    }
+
     /// Copy `source` content to self from the `offset` position.\
     /// Returns error if offset + source.len > self.size().
     ///
@@ -387,6 +726,7 @@ impl Buffer {
     #[cfg(feature = "gpu")]
     pub fn copy_from_cuda_array(&mut self, offset: usize, source: CudaArray) -> Result<(), Error> {
         let CudaArray { ptr, len } = source;
+        let _context = crate::context::auto_pin()?;
 
         if len + offset > self.len {
             return Err(Error::new(
@@ -441,6 +781,38 @@ impl Buffer {
         self.into_mem_type(MemoryType::Gpu)
     }
 
+    /// Copy this Buffer content to the new buffer of the same memory type with at least `byte_size` bytes
+    /// (rounded up to whole samples). The tail after the copied content is filled with zeros.
+    ///
+    /// **Note**: If self.memory_type() is not Cpu, method should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub(crate) fn zero_padded(&self, byte_size: usize) -> Result<Self, Error> {
+        self.check_mem_type_feature()?;
+
+        let sample_count = byte_size
+            .max(self.len)
+            .div_ceil(self.data_type.size() as usize);
+        let mut res = Buffer::alloc_with_data_type(sample_count, self.memory_type, self.data_type)?;
+
+        match self.memory_type {
+            // Cpu buffers are allocated with calloc and are already zeroed, unless the allocations are seeded.
+            MemoryType::Cpu if crate::testing::allocation_seed().is_none() => {
+                res.copy_from_slice(0, self.bytes())?
+            }
+            MemoryType::Cpu | MemoryType::Pinned => {
+                res.bytes_mut().fill(0);
+                res.copy_from_slice(0, self.bytes())?;
+            }
+            MemoryType::Gpu => {
+                #[cfg(feature = "gpu")]
+                {
+                    cuda_call!(cuMemsetD8_v2(res.ptr as CUdeviceptr, 0, res.len))?;
+                    res.copy_from_cuda_array(0, unsafe { self.get_cuda_array() })?;
+                }
+            }
+        }
+        Ok(res)
+    }
+
     fn into_mem_type(self, mem_type: MemoryType) -> Result<Self, Error> {
         self.check_mem_type_feature()?;
 
@@ -461,6 +833,21 @@ impl Buffer {
     }
 }
 
+/// Start and end (exclusive) of the `range` in the sequence of `len` elements.
+pub(crate) fn range_bounds<Range: RangeBounds<usize>>(range: &Range, len: usize) -> (usize, usize) {
+    let left = match range.start_bound() {
+        Bound::Unbounded => 0,
+        Bound::Included(pos) => *pos,
+        Bound::Excluded(pos) => *pos + 1,
+    };
+    let right = match range.end_bound() {
+        Bound::Unbounded => len,
+        Bound::Included(pos) => *pos + 1,
+        Bound::Excluded(pos) => *pos,
+    };
+    (left, right)
+}
+
 /// Obtaining buffer content section.
 impl Buffer {
     /// Get buffer content as bytes.
@@ -495,16 +882,7 @@ impl Buffer {
     ) -> Result<Vec<u8>, Error> {
         self.check_mem_type_feature()?;
 
-        let left = match range.start_bound() {
-            Bound::Unbounded => 0,
-            Bound::Included(pos) => *pos,
-            Bound::Excluded(pos) => *pos + 1,
-        };
-        let right = match range.end_bound() {
-            Bound::Unbounded => self.len,
-            Bound::Included(pos) => *pos + 1,
-            Bound::Excluded(pos) => *pos,
-        };
+        let (left, right) = range_bounds(&range, self.len);
 
         if right > self.len {
             return Err(Error::new(
@@ -521,17 +899,36 @@ impl Buffer {
         } else {
             let mut res = Vec::with_capacity(right - left);
             #[cfg(feature = "gpu")]
+            let _context = crate::context::auto_pin_on(self.device_id)?;
+            #[cfg(feature = "gpu")]
             cuda_call!(cuMemcpyDtoH_v2(
                 res.as_mut_ptr() as _,
                 self.ptr as CUdeviceptr + left as CUdeviceptr,
                 right - left
             ))?;
 
-            unsafe { res.set_len(self.len) };
+            unsafe { res.set_len(right - left) };
             Ok(res)
         }
     }
 
+    /// Read `len` elements of `T` starting from the element `offset` for any memory type of the buffer. \
+    /// Returns error if `T` doesn't match the buffer data type or the range is out of the buffer.
+    ///
+    /// **Note**: If self.memory_type is Gpu, should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn read_at<T: Sample>(&self, offset: usize, len: usize) -> Result<Vec<T>, Error> {
+        self.check_sample::<T>("read_at")?;
+        let sample_size = std::mem::size_of::<T>();
+        let bytes = self.get_owned_slice(offset * sample_size..(offset + len) * sample_size)?;
+
+        let mut res = Vec::<T>::with_capacity(len);
+        unsafe {
+            copy_nonoverlapping(bytes.as_ptr(), res.as_mut_ptr() as *mut u8, bytes.len());
+            res.set_len(len);
+        }
+        Ok(res)
+    }
+
     /// Get content of the GPU based buffer.
     /// # Panics
     /// Panics if self.memory_type != Gpu.
@@ -551,6 +948,20 @@ impl Buffer {
         }
     }
 
+    fn check_sample<T: Sample>(&self, method: &str) -> Result<(), Error> {
+        if T::DATA_TYPE != self.data_type {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "{method} error: buffer data_type {:?} != requested data_type {:?}",
+                    self.data_type,
+                    T::DATA_TYPE
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     fn check_mem_type_feature(&self) -> Result<(), Error> {
         #[cfg(not(feature = "gpu"))]
         if self.memory_type != MemoryType::Cpu {
@@ -611,6 +1022,17 @@ impl<T: Sample> AsMut<[T]> for Buffer {
 impl Drop for Buffer {
     fn drop(&mut self) {
         if self.owned && !self.ptr.is_null() {
+            if let Some(Deleter(deleter)) = self.deleter.take() {
+                deleter(self.ptr);
+                return;
+            }
+            #[cfg(feature = "gpu")]
+            let _context = match self.memory_type {
+                MemoryType::Cpu => None,
+                MemoryType::Pinned | MemoryType::Gpu => {
+                    crate::context::auto_pin_on(self.device_id).ok().flatten()
+                }
+            };
             unsafe {
                 match self.memory_type {
                     MemoryType::Cpu => {
@@ -629,3 +1051,40 @@ impl Drop for Buffer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_names() {
+        for data_type in [
+            DataType::Fp32,
+            DataType::Bytes,
+            DataType::Bf16,
+            DataType::Bool,
+        ] {
+            assert_eq!(
+                DataType::try_from(data_type.to_string().as_str()).unwrap(),
+                data_type
+            );
+        }
+        assert_eq!(DataType::Bytes.to_string(), "TYPE_STRING");
+        assert_eq!(DataType::try_from("TYPE_STRING").unwrap(), DataType::Bytes);
+        assert_eq!(DataType::try_from("FP16").unwrap(), DataType::Fp16);
+
+        assert_eq!(
+            MemoryType::try_from("cpu_pinned").unwrap(),
+            MemoryType::Pinned
+        );
+        assert_eq!(MemoryType::try_from("GPU").unwrap(), MemoryType::Gpu);
+    }
+
+    #[test]
+    fn typed_access() {
+        let mut buffer = Buffer::from([0f32; 6]);
+        buffer.write_at(2, &[1.5f32, 2.5]).unwrap();
+        assert_eq!(buffer.read_at::<f32>(1, 3).unwrap(), [0., 1.5, 2.5]);
+        assert!(buffer.read_at::<f32>(6, 0).unwrap().is_empty());
+    }
+}
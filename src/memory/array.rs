@@ -0,0 +1,107 @@
+//! Conversions between buffers and [ndarray] arrays.
+
+use std::{mem::size_of, ptr::copy_nonoverlapping};
+
+use ndarray::{Array, ArrayD, Dimension, IxDyn};
+
+use super::{Buffer, DataType, MemoryType, Sample};
+use crate::error::{Error, ErrorCode};
+
+impl<T: Sample, D: Dimension> TryFrom<Array<T, D>> for Buffer {
+    type Error = Error;
+
+    /// Create CPU buffer of data type `T::DATA_TYPE` from the `array` elements in the logical (row-major) order. \
+    /// Use [crate::Request::add_input_array] to pass the array shape to the request as well.
+    ///
+    /// Arrays of [DataType::Bytes] are not supported, since the shape of the BYTES tensor is the number of strings, not bytes.
+    fn try_from(array: Array<T, D>) -> Result<Self, Self::Error> {
+        if T::DATA_TYPE == DataType::Bytes {
+            return Err(Error::new(
+                ErrorCode::Unsupported,
+                "Buffer of BYTES can't be created from the array",
+            ));
+        }
+
+        if let Some(slice) = array.as_slice() {
+            return Ok(Buffer::from(slice));
+        }
+        Ok(Buffer::from(array.iter().copied().collect::<Vec<_>>()))
+    }
+}
+
+impl Buffer {
+    /// Copy the buffer content to the array of `shape`. \
+    /// `shape`: shape of the array. Product of the dims must match the number of buffer elements.
+    ///
+    /// Returns error if `T` does not match the buffer data type.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn to_ndarray<T: Sample>(&self, shape: &[i64]) -> Result<ArrayD<T>, Error> {
+        if T::DATA_TYPE != self.data_type || self.data_type == DataType::Bytes {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Buffer of {:?} can't be converted to the array of {:?}",
+                    self.data_type,
+                    T::DATA_TYPE
+                ),
+            ));
+        }
+
+        let count = self.len / size_of::<T>();
+        let dims = shape
+            .iter()
+            .map(|dim| usize::try_from(*dim))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|dims| dims.iter().product::<usize>() == count)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("shape {shape:?} mismatches buffer of {count} elements"),
+                )
+            })?;
+
+        let owned;
+        let bytes = if self.memory_type == MemoryType::Gpu {
+            owned = self.get_owned_slice(..)?;
+            owned.as_slice()
+        } else {
+            self.bytes()
+        };
+
+        let mut data = Vec::<T>::with_capacity(count);
+        // Bytes may be not aligned to T (owned slice of Gpu buffer), so copy them as bytes.
+        unsafe {
+            copy_nonoverlapping(
+                bytes.as_ptr(),
+                data.as_mut_ptr() as *mut u8,
+                count * size_of::<T>(),
+            );
+            data.set_len(count);
+        }
+
+        ArrayD::from_shape_vec(IxDyn(&dims), data)
+            .map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr2;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let array = arr2(&[[1f32, 2., 3.], [4., 5., 6.]]);
+        // Transposed array is not in the standard layout.
+        let buffer = Buffer::try_from(array.t().to_owned()).unwrap();
+        assert_eq!(buffer.data_type(), DataType::Fp32);
+
+        let back = buffer.to_ndarray::<f32>(&[3, 2]).unwrap();
+        assert_eq!(back, array.t().into_dyn());
+        assert!(buffer.to_ndarray::<f32>(&[4, 2]).is_err());
+        assert!(buffer.to_ndarray::<i32>(&[3, 2]).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+//! Conversions between buffers and [arrow_array] arrays for the tabular models (FIL, XGBoost, ...).
+
+use std::sync::Arc;
+
+use arrow_array::{
+    cast::AsArray,
+    types::{
+        Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+        UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+    },
+    Array, ArrayRef, BinaryArray, BooleanArray, FixedSizeListArray, PrimitiveArray,
+};
+use arrow_schema::{DataType as ArrowType, Field};
+
+use super::{Buffer, Byte, DataType};
+use crate::error::{Error, ErrorCode};
+
+/// Convert arrow primitive `array` of the arrow type to the buffer.
+macro_rules! primitive_buffer {
+    ($array:expr, $($arrow:ident => $ty:ty),+ $(,)?) => {
+        match $array.data_type() {
+            $(ArrowType::$arrow => Some(Buffer::from($array.as_primitive::<$ty>().values())),)+
+            _ => None,
+        }
+    };
+}
+
+/// Convert the buffer of the data type to the arrow primitive array.
+macro_rules! primitive_array {
+    ($buffer:expr, $($data_type:ident => $ty:ty),+ $(,)?) => {
+        match $buffer.data_type {
+            $(DataType::$data_type => {
+                let values = $buffer.read_at(0, $buffer.len / DataType::$data_type.size() as usize)?;
+                Arc::new(PrimitiveArray::<$ty>::from_iter_values(values)) as ArrayRef
+            })+
+            DataType::Bool => {
+                let values = $buffer.read_at::<bool>(0, $buffer.len)?;
+                Arc::new(BooleanArray::from(values)) as ArrayRef
+            }
+            DataType::Bytes => {
+                let bytes = $buffer.get_owned_slice(..)?;
+                Arc::new(BinaryArray::from_iter_values(split_bytes(&bytes)?)) as ArrayRef
+            }
+            DataType::Bf16 | DataType::Invalid => {
+                return Err(Error::new(
+                    ErrorCode::Unsupported,
+                    format!("{:?} buffer can't be converted to the arrow array", $buffer.data_type),
+                ))
+            }
+        }
+    };
+}
+
+/// Split the serialized BYTES tensor (4-byte length before each element) into the elements.
+fn split_bytes(mut bytes: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    let mut elements = Vec::new();
+    while !bytes.is_empty() {
+        let len = bytes
+            .get(..4)
+            .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+            .filter(|len| bytes.len() >= 4 + len)
+            .ok_or_else(|| Error::new(ErrorCode::InvalidArg, "BYTES buffer is malformed"))?;
+        elements.push(&bytes[4..4 + len]);
+        bytes = &bytes[4 + len..];
+    }
+    Ok(elements)
+}
+
+impl Buffer {
+    /// Copy the arrow `array` (e.g. the column of the `RecordBatch`) to the CPU buffer.
+    /// Returns the buffer and the shape of the tensor: `[array.len()]`,
+    /// or `[array.len(), size]` for `FixedSizeList` arrays of primitive values (e.g. the rows of features). \
+    /// Utf8 and Binary arrays are converted to the [DataType::Bytes] buffers.
+    ///
+    /// Arrays with nulls are not supported.
+    pub fn from_arrow(array: &dyn Array) -> Result<(Buffer, Vec<i64>), Error> {
+        if array.null_count() != 0 {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("{} nulls in the arrow array", array.null_count()),
+            ));
+        }
+
+        if let ArrowType::FixedSizeList(_, size) = array.data_type() {
+            let list = array.as_fixed_size_list();
+            let values = list.values().slice(
+                list.value_offset(0) as usize,
+                list.len() * list.value_length() as usize,
+            );
+            let (buffer, _) = Buffer::from_arrow(values.as_ref())?;
+            if buffer.data_type == DataType::Bytes {
+                return Err(Error::new(
+                    ErrorCode::Unsupported,
+                    "FixedSizeList arrays of strings are not supported",
+                ));
+            }
+            return Ok((buffer, vec![list.len() as i64, *size as i64]));
+        }
+
+        let shape = vec![array.len() as i64];
+        let buffer = primitive_buffer!(array,
+            UInt8 => UInt8Type,
+            UInt16 => UInt16Type,
+            UInt32 => UInt32Type,
+            UInt64 => UInt64Type,
+            Int8 => Int8Type,
+            Int16 => Int16Type,
+            Int32 => Int32Type,
+            Int64 => Int64Type,
+            Float16 => Float16Type,
+            Float32 => Float32Type,
+            Float64 => Float64Type,
+        );
+        if let Some(buffer) = buffer {
+            return Ok((buffer, shape));
+        }
+
+        let elements: Vec<&[u8]> = match array.data_type() {
+            ArrowType::Boolean => {
+                let values: Vec<bool> = array.as_boolean().values().iter().collect();
+                return Ok((Buffer::from(values), shape));
+            }
+            ArrowType::Utf8 => array
+                .as_string::<i32>()
+                .iter()
+                .flatten()
+                .map(str::as_bytes)
+                .collect(),
+            ArrowType::LargeUtf8 => array
+                .as_string::<i64>()
+                .iter()
+                .flatten()
+                .map(str::as_bytes)
+                .collect(),
+            ArrowType::Binary => array.as_binary::<i32>().iter().flatten().collect(),
+            ArrowType::LargeBinary => array.as_binary::<i64>().iter().flatten().collect(),
+            data_type => {
+                return Err(Error::new(
+                    ErrorCode::Unsupported,
+                    format!("arrow array of {data_type} can't be converted to the buffer"),
+                ))
+            }
+        };
+        let mut bytes = Vec::with_capacity(elements.iter().map(|element| element.len() + 4).sum());
+        for element in elements {
+            bytes.extend((element.len() as u32).to_le_bytes().map(Byte));
+            bytes.extend(element.iter().map(|byte| Byte(*byte)));
+        }
+        Ok((Buffer::from(bytes), shape))
+    }
+
+    /// Copy the buffer content to the arrow array. \
+    /// `shape`: shape of the tensor. Tensors of rank 0 and 1 are converted to the flat arrays,
+    /// tensors of the higher rank are converted to the `FixedSizeList` arrays of `shape[0]` rows
+    /// with the other dims flattened. \
+    /// [DataType::Bytes] buffers are converted to the Binary arrays.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn to_arrow(&self, shape: &[i64]) -> Result<ArrayRef, Error> {
+        let values = primitive_array!(self,
+            Uint8 => UInt8Type,
+            Uint16 => UInt16Type,
+            Uint32 => UInt32Type,
+            Uint64 => UInt64Type,
+            Int8 => Int8Type,
+            Int16 => Int16Type,
+            Int32 => Int32Type,
+            Int64 => Int64Type,
+            Fp16 => Float16Type,
+            Fp32 => Float32Type,
+            Fp64 => Float64Type,
+        );
+
+        let count = values.len();
+        if shape.iter().product::<i64>() as usize != count || shape.iter().any(|dim| *dim < 0) {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("shape {shape:?} mismatches buffer of {count} elements"),
+            ));
+        }
+        if shape.len() <= 1 {
+            return Ok(values);
+        }
+
+        let size: i64 = shape[1..].iter().product();
+        let field = Arc::new(Field::new("item", values.data_type().clone(), false));
+        FixedSizeListArray::try_new(field, size as i32, values, None)
+            .map(|array| Arc::new(array) as ArrayRef)
+            .map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Float32Array, StringArray};
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let array = Float32Array::from(vec![1., 2., 3., 4., 5., 6.]);
+        let (buffer, shape) = Buffer::from_arrow(&array).unwrap();
+        assert_eq!(buffer.data_type(), DataType::Fp32);
+        assert_eq!(shape, [6]);
+
+        let rows = buffer.to_arrow(&[2, 3]).unwrap();
+        assert!(matches!(rows.data_type(), ArrowType::FixedSizeList(_, 3)));
+        let (buffer, shape) = Buffer::from_arrow(rows.as_ref()).unwrap();
+        assert_eq!(shape, [2, 3]);
+        assert_eq!(
+            buffer.to_arrow(&[6]).unwrap().as_ref(),
+            &array as &dyn Array
+        );
+
+        let strings = StringArray::from(vec!["triton", "", "rs"]);
+        let (buffer, shape) = Buffer::from_arrow(&strings).unwrap();
+        assert_eq!(buffer.data_type(), DataType::Bytes);
+        assert_eq!(shape, [3]);
+        let binary = buffer.to_arrow(&shape).unwrap();
+        assert_eq!(binary.as_binary::<i32>().value(0), b"triton");
+        assert_eq!(binary.as_binary::<i32>().value(1), b"");
+    }
+}
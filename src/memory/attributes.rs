@@ -0,0 +1,88 @@
+use std::ptr::null_mut;
+
+use super::{MemoryType, CUDA_IPC_HANDLE_SIZE};
+use crate::{error::Error, sys};
+
+/// Attributes of the input buffer passed to Triton with [crate::Request::add_input_with_attributes].
+/// Attributes that are not set are taken from the buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BufferAttributes {
+    /// Id of the memory (device id for [MemoryType::Gpu]).
+    pub memory_type_id: Option<i64>,
+    /// Number of the buffer bytes passed to Triton. Must not exceed the buffer size.
+    pub byte_size: Option<usize>,
+    /// CUDA IPC handle of the buffer allocation (check [crate::Buffer::cuda_ipc_handle]),
+    /// so the backends running in other processes (e.g. python backend) can open the buffer without copying.
+    pub cuda_ipc_handle: Option<[u8; CUDA_IPC_HANDLE_SIZE]>,
+}
+
+impl BufferAttributes {
+    /// Set the id of the memory.
+    pub fn memory_type_id(mut self, memory_type_id: i64) -> Self {
+        self.memory_type_id = Some(memory_type_id);
+        self
+    }
+
+    /// Set the number of the buffer bytes passed to Triton.
+    pub fn byte_size(mut self, byte_size: usize) -> Self {
+        self.byte_size = Some(byte_size);
+        self
+    }
+
+    /// Set the CUDA IPC handle of the buffer allocation.
+    pub fn cuda_ipc_handle(mut self, handle: [u8; CUDA_IPC_HANDLE_SIZE]) -> Self {
+        self.cuda_ipc_handle = Some(handle);
+        self
+    }
+}
+
+/// Triton buffer attributes object. Deleted on drop.
+pub(crate) struct TritonBufferAttributes(pub(crate) *mut sys::TRITONSERVER_BufferAttributes);
+
+impl TritonBufferAttributes {
+    pub(crate) fn new() -> Result<Self, Error> {
+        let mut ptr = null_mut::<sys::TRITONSERVER_BufferAttributes>();
+        triton_call!(sys::TRITONSERVER_BufferAttributesNew(&mut ptr as *mut _))?;
+        assert!(!ptr.is_null());
+        Ok(TritonBufferAttributes(ptr))
+    }
+
+    pub(crate) fn set_memory_type(&self, memory_type: MemoryType) -> Result<(), Error> {
+        triton_call!(sys::TRITONSERVER_BufferAttributesSetMemoryType(
+            self.0,
+            memory_type as _
+        ))
+    }
+
+    pub(crate) fn set_memory_type_id(&self, memory_type_id: i64) -> Result<(), Error> {
+        triton_call!(sys::TRITONSERVER_BufferAttributesSetMemoryTypeId(
+            self.0,
+            memory_type_id
+        ))
+    }
+
+    pub(crate) fn set_byte_size(&self, byte_size: usize) -> Result<(), Error> {
+        triton_call!(sys::TRITONSERVER_BufferAttributesSetByteSize(
+            self.0, byte_size
+        ))
+    }
+
+    /// Triton keeps the pointer to the `handle`, so it must outlive the usage of the attributes.
+    pub(crate) fn set_cuda_ipc_handle(
+        &self,
+        handle: &mut [u8; CUDA_IPC_HANDLE_SIZE],
+    ) -> Result<(), Error> {
+        triton_call!(sys::TRITONSERVER_BufferAttributesSetCudaIpcHandle(
+            self.0,
+            handle.as_mut_ptr() as _
+        ))
+    }
+}
+
+impl Drop for TritonBufferAttributes {
+    fn drop(&mut self) {
+        unsafe {
+            sys::TRITONSERVER_BufferAttributesDelete(self.0);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Zero-copy exchange of the buffers with the other frameworks (PyTorch, CuPy, onnxruntime, ...) in DLPack format.
+//!
+//! Format description: <https://dmlc.github.io/dlpack/latest/c_api.html>.
+
+use std::{ffi::c_void, ptr::null_mut};
+
+use super::{Buffer, DataType, Deleter, MemoryType};
+use crate::error::{Error, ErrorCode};
+
+/// `kDLCPU` device type.
+pub const DL_CPU: i32 = 1;
+/// `kDLCUDA` device type.
+pub const DL_CUDA: i32 = 2;
+/// `kDLCUDAHost` device type (page-locked host memory).
+pub const DL_CUDA_HOST: i32 = 3;
+
+/// `DLDevice`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DLDevice {
+    pub device_type: i32,
+    pub device_id: i32,
+}
+
+/// `DLDataType`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DLDataType {
+    pub code: u8,
+    pub bits: u8,
+    pub lanes: u16,
+}
+
+/// `DLTensor`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DLTensor {
+    pub data: *mut c_void,
+    pub device: DLDevice,
+    pub ndim: i32,
+    pub dtype: DLDataType,
+    pub shape: *mut i64,
+    /// Strides in elements. Null means compact row-major tensor.
+    pub strides: *mut i64,
+    pub byte_offset: u64,
+}
+
+/// `DLManagedTensor`: the tensor with the deleter of its producer.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DLManagedTensor {
+    pub dl_tensor: DLTensor,
+    pub manager_ctx: *mut c_void,
+    pub deleter: Option<unsafe extern "C" fn(*mut DLManagedTensor)>,
+}
+
+const DL_INT: u8 = 0;
+const DL_UINT: u8 = 1;
+const DL_FLOAT: u8 = 2;
+const DL_BFLOAT: u8 = 4;
+const DL_BOOL: u8 = 6;
+
+fn to_dl_data_type(data_type: DataType) -> Option<DLDataType> {
+    let code = match data_type {
+        DataType::Bool => DL_BOOL,
+        DataType::Uint8 | DataType::Uint16 | DataType::Uint32 | DataType::Uint64 => DL_UINT,
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => DL_INT,
+        DataType::Fp16 | DataType::Fp32 | DataType::Fp64 => DL_FLOAT,
+        DataType::Bf16 => DL_BFLOAT,
+        DataType::Bytes | DataType::Invalid => return None,
+    };
+    Some(DLDataType {
+        code,
+        bits: (data_type.size() * 8) as u8,
+        lanes: 1,
+    })
+}
+
+fn from_dl_data_type(dtype: DLDataType) -> Option<DataType> {
+    Some(match (dtype.code, dtype.bits, dtype.lanes) {
+        (DL_BOOL, 8, 1) => DataType::Bool,
+        (DL_UINT, 8, 1) => DataType::Uint8,
+        (DL_UINT, 16, 1) => DataType::Uint16,
+        (DL_UINT, 32, 1) => DataType::Uint32,
+        (DL_UINT, 64, 1) => DataType::Uint64,
+        (DL_INT, 8, 1) => DataType::Int8,
+        (DL_INT, 16, 1) => DataType::Int16,
+        (DL_INT, 32, 1) => DataType::Int32,
+        (DL_INT, 64, 1) => DataType::Int64,
+        (DL_FLOAT, 16, 1) => DataType::Fp16,
+        (DL_FLOAT, 32, 1) => DataType::Fp32,
+        (DL_FLOAT, 64, 1) => DataType::Fp64,
+        (DL_BFLOAT, 16, 1) => DataType::Bf16,
+        _ => return None,
+    })
+}
+
+/// Buffer and shape exported by [Buffer::to_dlpack], owned by the tensor.
+struct Exported {
+    tensor: DLManagedTensor,
+    _buffer: Buffer,
+    _shape: Vec<i64>,
+}
+
+unsafe extern "C" fn delete_exported(tensor: *mut DLManagedTensor) {
+    if let Some(tensor) = tensor.as_ref() {
+        drop(Box::from_raw(tensor.manager_ctx as *mut Exported));
+    }
+}
+
+/// Release the imported `tensor` with its producer deleter.
+unsafe fn release(tensor: *mut DLManagedTensor) {
+    if let Some(deleter) = tensor.as_ref().and_then(|tensor| tensor.deleter) {
+        deleter(tensor);
+    }
+}
+
+impl Buffer {
+    /// Take the DLPack `tensor` as the buffer without copying. Returns the buffer and the shape of the tensor. \
+    /// The tensor is released with its deleter when the buffer is dropped (or right away, if error is returned).
+    ///
+    /// Only compact row-major tensors of CPU, CUDA and CUDA host memory with one lane are supported.
+    ///
+    /// # Safety
+    /// `tensor` must be a valid `DLManagedTensor` that is not used by the caller after the call.
+    pub unsafe fn from_dlpack(tensor: *mut DLManagedTensor) -> Result<(Buffer, Vec<i64>), Error> {
+        let managed = tensor
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorCode::InvalidArg, "DLPack tensor is null"))?;
+        let dl_tensor = &managed.dl_tensor;
+        let shape = if dl_tensor.ndim == 0 {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(dl_tensor.shape, dl_tensor.ndim as usize).to_vec()
+        };
+
+        let checked = || -> Result<(DataType, MemoryType), String> {
+            let data_type = from_dl_data_type(dl_tensor.dtype)
+                .ok_or_else(|| format!("unsupported data type {:?}", dl_tensor.dtype))?;
+            let memory_type = match dl_tensor.device.device_type {
+                DL_CPU => MemoryType::Cpu,
+                DL_CUDA => MemoryType::Gpu,
+                DL_CUDA_HOST => MemoryType::Pinned,
+                device_type => return Err(format!("unsupported device type {device_type}")),
+            };
+            if !dl_tensor.strides.is_null() && dl_tensor.ndim > 0 {
+                let strides =
+                    std::slice::from_raw_parts(dl_tensor.strides, dl_tensor.ndim as usize);
+                let mut expected = 1;
+                for (dim, stride) in shape.iter().zip(strides).rev() {
+                    if *dim != 1 && *stride != expected {
+                        return Err(format!(
+                            "tensor of shape {shape:?} with strides {strides:?} is not compact row-major"
+                        ));
+                    }
+                    expected *= dim;
+                }
+            }
+            if shape.iter().any(|dim| *dim < 0) {
+                return Err(format!("invalid shape {shape:?}"));
+            }
+            Ok((data_type, memory_type))
+        };
+        let (data_type, memory_type) = match checked() {
+            Ok(checked) => checked,
+            Err(message) => {
+                release(tensor);
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Can't import DLPack tensor: {message}"),
+                ));
+            }
+        };
+
+        let len = shape.iter().product::<i64>() as usize * data_type.size() as usize;
+        // Raw pointer is not Send, the tensor is released only once by the buffer.
+        let tensor_addr = tensor as usize;
+        let buffer = Buffer {
+            ptr: dl_tensor.data.byte_add(dl_tensor.byte_offset as usize),
+            len,
+            data_type,
+            memory_type,
+            device_id: match memory_type {
+                MemoryType::Cpu => 0,
+                MemoryType::Gpu | MemoryType::Pinned => dl_tensor.device.device_id,
+            },
+            owned: true,
+            deleter: Some(Deleter(Box::new(move |_| {
+                release(tensor_addr as *mut DLManagedTensor)
+            }))),
+        };
+        Ok((buffer, shape))
+    }
+
+    /// Give the buffer away as the DLPack tensor without copying. \
+    /// `shape`: shape of the tensor. If None, the tensor is one-dimensional.
+    ///
+    /// The buffer is dropped when the consumer calls the tensor deleter. \
+    /// [DataType::Bytes] buffers are not supported, since DLPack has no matching type.
+    pub fn to_dlpack(self, shape: Option<&[i64]>) -> Result<*mut DLManagedTensor, Error> {
+        let dtype = to_dl_data_type(self.data_type).ok_or_else(|| {
+            Error::new(
+                ErrorCode::Unsupported,
+                format!("{:?} buffer can't be exported to DLPack", self.data_type),
+            )
+        })?;
+        let count = self.len / self.data_type.size() as usize;
+        let mut shape = match shape {
+            None => vec![count as i64],
+            Some(shape) => {
+                if shape.iter().any(|dim| *dim < 0)
+                    || shape.iter().product::<i64>() as usize != count
+                {
+                    return Err(Error::new(
+                        ErrorCode::InvalidArg,
+                        format!("Shape {shape:?} mismatches buffer of {count} elements"),
+                    ));
+                }
+                shape.to_vec()
+            }
+        };
+        let device = match self.memory_type {
+            MemoryType::Cpu => DLDevice {
+                device_type: DL_CPU,
+                device_id: 0,
+            },
+            MemoryType::Pinned => DLDevice {
+                device_type: DL_CUDA_HOST,
+                device_id: self.device_id,
+            },
+            MemoryType::Gpu => DLDevice {
+                device_type: DL_CUDA,
+                device_id: self.device_id,
+            },
+        };
+
+        let exported = Box::into_raw(Box::new(Exported {
+            tensor: DLManagedTensor {
+                dl_tensor: DLTensor {
+                    data: self.ptr,
+                    device,
+                    ndim: shape.len() as i32,
+                    dtype,
+                    shape: shape.as_mut_ptr(),
+                    strides: null_mut(),
+                    byte_offset: 0,
+                },
+                manager_ctx: null_mut(),
+                deleter: Some(delete_exported),
+            },
+            _buffer: self,
+            _shape: shape,
+        }));
+        unsafe {
+            (*exported).tensor.manager_ctx = exported as *mut c_void;
+            Ok(&mut (*exported).tensor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let buffer = Buffer::from([1i32, 2, 3, 4, 5, 6]);
+        let tensor = buffer.to_dlpack(Some(&[2, 3])).unwrap();
+        unsafe {
+            assert_eq!((*tensor).dl_tensor.dtype.code, DL_INT);
+            assert_eq!((*tensor).dl_tensor.dtype.bits, 32);
+        }
+
+        let (buffer, shape) = unsafe { Buffer::from_dlpack(tensor) }.unwrap();
+        assert_eq!(shape, [2, 3]);
+        assert_eq!(buffer.data_type(), DataType::Int32);
+        assert_eq!(buffer.read_at::<i32>(0, 6).unwrap(), [1, 2, 3, 4, 5, 6]);
+    }
+}
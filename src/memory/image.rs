@@ -0,0 +1,143 @@
+//! Conversion of [image] images to the input buffers of vision models.
+
+use image::{imageops::FilterType, DynamicImage};
+
+use super::Buffer;
+
+/// Layout of the image channels in the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelOrder {
+    /// Height, width, channels: pixels are stored one by one (RGBRGB...).
+    Hwc,
+    /// Channels, height, width: planes of the channels are stored one by one (RR...GG...BB...).
+    Chw,
+}
+
+impl ChannelOrder {
+    /// Dims of the RGB image of `width` x `height` in this layout.
+    pub fn dims(self, width: u32, height: u32) -> [i64; 3] {
+        match self {
+            ChannelOrder::Hwc => [height as i64, width as i64, 3],
+            ChannelOrder::Chw => [3, height as i64, width as i64],
+        }
+    }
+}
+
+/// Normalization of the pixel values: `(value * scale - mean[channel]) / std[channel]`,
+/// where value is in range [0, 255].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Normalization {
+    pub scale: f32,
+    /// Mean of R, G, B channels.
+    pub mean: [f32; 3],
+    /// Standard deviation of R, G, B channels.
+    pub std: [f32; 3],
+}
+
+impl Normalization {
+    /// Scale values to range [0, 1].
+    pub fn unit() -> Self {
+        Normalization {
+            scale: 1. / 255.,
+            mean: [0.; 3],
+            std: [1.; 3],
+        }
+    }
+
+    /// Scale values to range [0, 1] and normalize them with ImageNet mean and std.
+    pub fn imagenet() -> Self {
+        Normalization {
+            scale: 1. / 255.,
+            mean: [0.485, 0.456, 0.406],
+            std: [0.229, 0.224, 0.225],
+        }
+    }
+
+    fn apply(&self, value: u8, channel: usize) -> f32 {
+        (value as f32 * self.scale - self.mean[channel]) / self.std[channel]
+    }
+}
+
+impl Buffer {
+    /// Create CPU FP32 buffer from the RGB pixels of the `image`. \
+    /// `layout`: layout of the channels in the buffer (check [ChannelOrder::dims] for the dims of the input). \
+    /// `normalize`: normalization of the pixel values. If None, values are left in range [0, 255].
+    ///
+    /// Alpha channel is dropped, grayscale images are converted to RGB.
+    pub fn from_image(
+        image: &DynamicImage,
+        layout: ChannelOrder,
+        normalize: Option<Normalization>,
+    ) -> Self {
+        let rgb = image.to_rgb8();
+        let (width, height) = (rgb.width() as usize, rgb.height() as usize);
+        let normalize = |value: u8, channel: usize| match &normalize {
+            Some(normalization) => normalization.apply(value, channel),
+            None => value as f32,
+        };
+
+        let pixels = rgb.as_raw();
+        let mut data = vec![0f32; pixels.len()];
+        match layout {
+            ChannelOrder::Hwc => {
+                for (idx, value) in pixels.iter().enumerate() {
+                    data[idx] = normalize(*value, idx % 3);
+                }
+            }
+            ChannelOrder::Chw => {
+                let plane = width * height;
+                for (idx, value) in pixels.iter().enumerate() {
+                    let channel = idx % 3;
+                    data[channel * plane + idx / 3] = normalize(*value, channel);
+                }
+            }
+        }
+        Buffer::from(data)
+    }
+
+    /// Resize the `image` to `width` x `height` (ignoring the aspect ratio) and create the buffer from it. \
+    /// Check [Buffer::from_image] for more info.
+    pub fn from_image_resized(
+        image: &DynamicImage,
+        width: u32,
+        height: u32,
+        layout: ChannelOrder,
+        normalize: Option<Normalization>,
+    ) -> Self {
+        if image.width() == width && image.height() == height {
+            return Self::from_image(image, layout, normalize);
+        }
+        let resized = image.resize_exact(width, height, FilterType::Triangle);
+        Self::from_image(&resized, layout, normalize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    #[test]
+    fn layouts() {
+        let mut image = RgbImage::new(2, 1);
+        image.put_pixel(0, 0, Rgb([0, 51, 102]));
+        image.put_pixel(1, 0, Rgb([153, 204, 255]));
+        let image = DynamicImage::ImageRgb8(image);
+
+        let hwc = Buffer::from_image(&image, ChannelOrder::Hwc, None);
+        assert_eq!(
+            AsRef::<[f32]>::as_ref(&hwc)[..6],
+            [0., 51., 102., 153., 204., 255.]
+        );
+
+        let normalization = Normalization {
+            scale: 1.,
+            mean: [0.; 3],
+            std: [51.; 3],
+        };
+        let chw = Buffer::from_image(&image, ChannelOrder::Chw, Some(normalization));
+        assert_eq!(AsRef::<[f32]>::as_ref(&chw)[..6], [0., 3., 1., 4., 2., 5.]);
+        assert_eq!(ChannelOrder::Chw.dims(2, 1), [3, 1, 2]);
+    }
+}
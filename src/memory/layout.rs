@@ -0,0 +1,456 @@
+//! Transforms between the channels-first and the channels-last layouts of the image tensors.
+//!
+//! ```ignore
+//! let image = Buffer::from(rgb_hwc_f32);
+//! let (chw, dims) = layout::transpose(&image, Layout::Hwc, Layout::Chw, [h, w, 3])?;
+//! request.add_input_with_dims("images", chw, dims)?;
+//! ```
+
+#[cfg(feature = "gpu")]
+use std::{collections::HashMap, ffi::c_void, sync::Mutex};
+
+#[cfg(feature = "gpu")]
+use cuda_driver_sys::{
+    cuCtxGetCurrent, cuLaunchKernel, cuModuleGetFunction, cuModuleLoadData, cuStreamSynchronize,
+    CUcontext, CUdeviceptr, CUfunction, CUmodule,
+};
+
+use super::{Buffer, DataType, MemoryType};
+use crate::error::{Error, ErrorCode};
+
+/// Side of the square tile of the CPU transpose: the tile of the source and the target fits the L1 cache.
+const TILE: usize = 32;
+
+/// Threads per block of the GPU transpose kernel.
+#[cfg(feature = "gpu")]
+const BLOCK: u32 = 256;
+
+/// Sample sizes the GPU transpose kernel is built for.
+#[cfg(feature = "gpu")]
+const KERNEL_SAMPLE_SIZES: [usize; 4] = [1, 2, 4, 8];
+
+/// PTX of the GPU transpose kernel of one sample size: `SIZE`, `TYPE` and `REG` are replaced. \
+/// Each thread writes one sample of the target image `ctaid.y`: sample `t` of the target is `t / rows` row
+/// and `t % rows` column of the source.
+#[cfg(feature = "gpu")]
+const TRANSPOSE_PTX: &str = r#"
+.visible .entry transpose_SIZE(
+    .param .u64 transpose_SIZE_source,
+    .param .u64 transpose_SIZE_target,
+    .param .u32 transpose_SIZE_rows,
+    .param .u32 transpose_SIZE_cols
+)
+{
+    .reg .pred %p;
+    .reg .b32 %r<12>;
+    .reg .b64 %rd<6>;
+    .reg .REG %v;
+
+    ld.param.u64 %rd1, [transpose_SIZE_source];
+    ld.param.u64 %rd2, [transpose_SIZE_target];
+    ld.param.u32 %r1, [transpose_SIZE_rows];
+    ld.param.u32 %r2, [transpose_SIZE_cols];
+    mov.u32 %r3, %ctaid.x;
+    mov.u32 %r4, %ntid.x;
+    mov.u32 %r5, %tid.x;
+    mad.lo.u32 %r6, %r3, %r4, %r5;
+    mul.lo.u32 %r7, %r1, %r2;
+    setp.ge.u32 %p, %r6, %r7;
+    @%p bra DONE;
+    rem.u32 %r8, %r6, %r1;
+    div.u32 %r9, %r6, %r1;
+    mad.lo.u32 %r10, %r8, %r2, %r9;
+    mov.u32 %r11, %ctaid.y;
+    mul.wide.u32 %rd3, %r11, %r7;
+    cvt.u64.u32 %rd4, %r10;
+    add.u64 %rd4, %rd4, %rd3;
+    mad.lo.u64 %rd4, %rd4, SIZE, %rd1;
+    cvt.u64.u32 %rd5, %r6;
+    add.u64 %rd5, %rd5, %rd3;
+    mad.lo.u64 %rd5, %rd5, SIZE, %rd2;
+    ld.global.TYPE %v, [%rd4];
+    st.global.TYPE [%rd5], %v;
+DONE:
+    ret;
+}
+"#;
+
+/// Transpose kernels of each of [KERNEL_SAMPLE_SIZES], loaded once per Cuda context.
+#[cfg(feature = "gpu")]
+#[derive(Clone, Copy)]
+struct TransposeKernels([CUfunction; KERNEL_SAMPLE_SIZES.len()]);
+
+#[cfg(feature = "gpu")]
+unsafe impl Send for TransposeKernels {}
+
+#[cfg(feature = "gpu")]
+lazy_static::lazy_static! {
+    /// Kernels keyed by the address of the Cuda context they are loaded to.
+    static ref TRANSPOSE_KERNELS: Mutex<HashMap<usize, TransposeKernels>> = Mutex::new(HashMap::new());
+}
+
+#[cfg(feature = "gpu")]
+impl TransposeKernels {
+    /// Get the kernels of the current Cuda context, loading them on the first call.
+    fn current() -> Result<Self, Error> {
+        let mut context: CUcontext = std::ptr::null_mut();
+        cuda_call!(cuCtxGetCurrent(&mut context as *mut CUcontext))?;
+        let mut kernels = TRANSPOSE_KERNELS
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if let Some(loaded) = kernels.get(&(context as usize)) {
+            return Ok(*loaded);
+        }
+
+        let mut ptx = String::from(".version 6.0\n.target sm_50\n.address_size 64\n");
+        for size in KERNEL_SAMPLE_SIZES {
+            let (ty, reg) = match size {
+                1 => ("u8", "b16"),
+                2 => ("u16", "b16"),
+                4 => ("u32", "b32"),
+                _ => ("u64", "b64"),
+            };
+            ptx += &TRANSPOSE_PTX
+                .replace("SIZE", &size.to_string())
+                .replace("TYPE", ty)
+                .replace("REG", reg);
+        }
+        let ptx = std::ffi::CString::new(ptx).expect("PTX has no NUL bytes");
+
+        let mut module: CUmodule = std::ptr::null_mut();
+        cuda_call!(cuModuleLoadData(
+            &mut module as *mut CUmodule,
+            ptx.as_ptr() as *const c_void
+        ))?;
+        let mut functions = [std::ptr::null_mut(); KERNEL_SAMPLE_SIZES.len()];
+        for (function, size) in functions.iter_mut().zip(KERNEL_SAMPLE_SIZES) {
+            let name = std::ffi::CString::new(format!("transpose_{size}"))
+                .expect("kernel name has no NUL bytes");
+            cuda_call!(cuModuleGetFunction(
+                function as *mut CUfunction,
+                module,
+                name.as_ptr()
+            ))?;
+        }
+        // The module stays loaded for the lifetime of the context.
+        let loaded = TransposeKernels(functions);
+        kernels.insert(context as usize, loaded);
+        Ok(loaded)
+    }
+
+    fn get(&self, sample_size: usize) -> Option<CUfunction> {
+        KERNEL_SAMPLE_SIZES
+            .iter()
+            .position(|size| *size == sample_size)
+            .map(|idx| self.0[idx])
+    }
+}
+
+/// Order of the dimensions of the image tensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Batch, channels, height, width.
+    Nchw,
+    /// Batch, height, width, channels.
+    Nhwc,
+    /// Channels, height, width.
+    Chw,
+    /// Height, width, channels.
+    Hwc,
+}
+
+impl Layout {
+    /// Number of the dimensions.
+    pub fn rank(self) -> usize {
+        match self {
+            Layout::Nchw | Layout::Nhwc => 4,
+            Layout::Chw | Layout::Hwc => 3,
+        }
+    }
+
+    fn channels_first(self) -> bool {
+        matches!(self, Layout::Nchw | Layout::Chw)
+    }
+
+    /// Get the `dims` of the tensor of this layout permuted to the layout `to`. \
+    /// Returns error if the layouts or the dims have different ranks.
+    pub fn permute_dims<D: AsRef<[i64]>>(self, to: Layout, dims: D) -> Result<Vec<i64>, Error> {
+        let dims = dims.as_ref();
+        if self.rank() != to.rank() || dims.len() != self.rank() {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("Can't permute dims {dims:?} from {self:?} to {to:?}: ranks differ"),
+            ));
+        }
+        let mut permuted = dims.to_vec();
+        let spatial = dims.len() - 3;
+        match (self.channels_first(), to.channels_first()) {
+            (true, false) => permuted[spatial..].rotate_left(1),
+            (false, true) => permuted[spatial..].rotate_right(1),
+            _ => (),
+        }
+        Ok(permuted)
+    }
+}
+
+/// Copy the `buffer` with `dims` of the layout `from` to the new buffer of the layout `to`. \
+/// Returns the new buffer (of the same memory type, data type and device) and its dims.
+///
+/// Host buffers (Cpu and Pinned) are transposed by tiles fitting the cache.
+/// Gpu buffers are transposed on the device by the kernel (one launch per up to 65535 images),
+/// without the round trip through the host memory.
+///
+/// Returns error if the ranks of the layouts or the dims differ, the dims don't match the buffer size
+/// or the data type is [DataType::Bytes]. Returns [ErrorCode::Unsupported] for Gpu buffers without the "gpu" feature.
+///
+/// **Note**: If buffer.memory_type is not Cpu, should be called in sync with cuda context pinned (check module level documentation for more info).
+pub fn transpose<D: AsRef<[i64]>>(
+    buffer: &Buffer,
+    from: Layout,
+    to: Layout,
+    dims: D,
+) -> Result<(Buffer, Vec<i64>), Error> {
+    let dims = dims.as_ref();
+    let permuted = from.permute_dims(to, dims)?;
+    if buffer.data_type == DataType::Bytes {
+        return Err(Error::new(
+            ErrorCode::InvalidArg,
+            "Buffer of Bytes can't be transposed",
+        ));
+    }
+    let sample_size = buffer.data_type.size() as usize;
+    let count = dims.iter().try_fold(1usize, |count, dim| {
+        usize::try_from(*dim).ok().map(|dim| count * dim)
+    });
+    if count.map(|count| count * sample_size) != Some(buffer.len) {
+        return Err(Error::new(
+            ErrorCode::InvalidArg,
+            format!(
+                "Dims {dims:?} of {:?} don't match the buffer size {}",
+                buffer.data_type, buffer.len
+            ),
+        ));
+    }
+    let count = count.unwrap_or_default();
+    #[cfg(not(feature = "gpu"))]
+    if buffer.memory_type == MemoryType::Gpu {
+        return Err(Error::new(
+            ErrorCode::Unsupported,
+            "Gpu buffers can't be transposed without the \"gpu\" feature",
+        ));
+    }
+    if from.channels_first() == to.channels_first() || count == 0 {
+        return Ok((buffer.try_clone()?, permuted));
+    }
+
+    let mut res = Buffer::alloc_with_data_type_on(
+        buffer.device_id,
+        count,
+        buffer.memory_type,
+        buffer.data_type,
+    )?;
+
+    // Each image is the matrix of `rows` x `cols` elements, transposed to `cols` x `rows`.
+    let spatial = dims.len() - 3;
+    let channels = if from.channels_first() {
+        dims[spatial]
+    } else {
+        dims[dims.len() - 1]
+    } as usize;
+    let pixels = count / dims[..spatial].iter().product::<i64>() as usize / channels;
+    let (rows, cols) = if from.channels_first() {
+        (channels, pixels)
+    } else {
+        (pixels, channels)
+    };
+    let matrix = Matrix {
+        rows,
+        cols,
+        sample_size,
+    };
+
+    if buffer.memory_type == MemoryType::Gpu {
+        #[cfg(feature = "gpu")]
+        {
+            let _context = crate::context::auto_pin_on(buffer.device_id)?;
+            matrix.transpose_gpu(
+                buffer.ptr as CUdeviceptr,
+                res.ptr as CUdeviceptr,
+                count / (rows * cols),
+            )?;
+        }
+    } else {
+        let source = buffer.bytes();
+        let target = res.bytes_mut();
+        for (source, target) in source
+            .chunks_exact(matrix.bytes())
+            .zip(target.chunks_exact_mut(matrix.bytes()))
+        {
+            matrix.transpose_cpu(source, target);
+        }
+    }
+    Ok((res, permuted))
+}
+
+/// Row-major matrix of one image.
+struct Matrix {
+    rows: usize,
+    cols: usize,
+    sample_size: usize,
+}
+
+impl Matrix {
+    fn bytes(&self) -> usize {
+        self.rows * self.cols * self.sample_size
+    }
+
+    fn transpose_cpu(&self, source: &[u8], target: &mut [u8]) {
+        match self.sample_size {
+            1 => self.transpose_typed::<u8>(source, target),
+            2 => self.transpose_typed::<u16>(source, target),
+            4 => self.transpose_typed::<u32>(source, target),
+            8 => self.transpose_typed::<u64>(source, target),
+            _ => self.transpose_bytes(source, target),
+        }
+    }
+
+    /// Copy the elements one by one, for the unaligned data.
+    fn transpose_bytes(&self, source: &[u8], target: &mut [u8]) {
+        let size = self.sample_size;
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let from = (row * self.cols + col) * size;
+                let to = (col * self.rows + row) * size;
+                target[to..to + size].copy_from_slice(&source[from..from + size]);
+            }
+        }
+    }
+
+    fn transpose_typed<T: Copy>(&self, source: &[u8], target: &mut [u8]) {
+        // Safety: any bits are valid unsigned integers.
+        let (([], source, []), ([], target, [])) = (unsafe { source.align_to::<T>() }, unsafe {
+            target.align_to_mut::<T>()
+        }) else {
+            return self.transpose_bytes(source, target);
+        };
+
+        for row_tile in (0..self.rows).step_by(TILE) {
+            for col_tile in (0..self.cols).step_by(TILE) {
+                for row in row_tile..(row_tile + TILE).min(self.rows) {
+                    for col in col_tile..(col_tile + TILE).min(self.cols) {
+                        target[col * self.rows + row] = source[row * self.cols + col];
+                    }
+                }
+            }
+        }
+    }
+
+    /// Transpose `images` matrices placed one after another with the kernel of the current Cuda context.
+    #[cfg(feature = "gpu")]
+    fn transpose_gpu(
+        &self,
+        source: CUdeviceptr,
+        target: CUdeviceptr,
+        images: usize,
+    ) -> Result<(), Error> {
+        /// Maximum y dimension of the grid.
+        const MAX_IMAGES: usize = 65535;
+
+        let kernel = TransposeKernels::current()?
+            .get(self.sample_size)
+            .expect("data types have the kernel sample sizes");
+        let (Ok(mut rows), Ok(mut cols)) = (u32::try_from(self.rows), u32::try_from(self.cols))
+        else {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("Image of {}x{} samples is too large", self.rows, self.cols),
+            ));
+        };
+        let Some(samples) = rows.checked_mul(cols) else {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("Image of {rows}x{cols} samples is too large"),
+            ));
+        };
+
+        for first in (0..images).step_by(MAX_IMAGES) {
+            let offset = (first * self.bytes()) as CUdeviceptr;
+            let (mut source, mut target) = (source + offset, target + offset);
+            let mut params = [
+                &mut source as *mut CUdeviceptr as *mut c_void,
+                &mut target as *mut CUdeviceptr as *mut c_void,
+                &mut rows as *mut u32 as *mut c_void,
+                &mut cols as *mut u32 as *mut c_void,
+            ];
+            cuda_call!(cuLaunchKernel(
+                kernel,
+                samples.div_ceil(BLOCK),
+                (images - first).min(MAX_IMAGES) as u32,
+                1,
+                BLOCK,
+                1,
+                1,
+                0,
+                std::ptr::null_mut(),
+                params.as_mut_ptr(),
+                std::ptr::null_mut(),
+            ))?;
+        }
+        // The result is ready on return, as with the other copies of the buffers.
+        cuda_call!(cuStreamSynchronize(std::ptr::null_mut()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hwc_to_chw() {
+        // 2x3 image of 2 channels.
+        let hwc: Vec<f32> = (0..12).map(|v| v as f32).collect();
+        let (chw, dims) =
+            transpose(&Buffer::from(&hwc), Layout::Hwc, Layout::Chw, [2, 3, 2]).unwrap();
+        assert_eq!(dims, [2, 2, 3]);
+        assert_eq!(
+            chw.read_at::<f32>(0, 12).unwrap(),
+            [0., 2., 4., 6., 8., 10., 1., 3., 5., 7., 9., 11.]
+        );
+
+        let (back, dims) = transpose(&chw, Layout::Chw, Layout::Hwc, dims).unwrap();
+        assert_eq!(dims, [2, 3, 2]);
+        assert_eq!(back.read_at::<f32>(0, 12).unwrap(), hwc);
+
+        let nchw: Vec<u8> = (0..2 * 3 * 40 * 50).map(|v| v as u8).collect();
+        let (nhwc, dims) = transpose(
+            &Buffer::from(&nchw),
+            Layout::Nchw,
+            Layout::Nhwc,
+            [2, 3, 40, 50],
+        )
+        .unwrap();
+        assert_eq!(dims, [2, 40, 50, 3]);
+        let nhwc = nhwc.bytes();
+        // Image 1, channel 2, row 7, column 9.
+        assert_eq!(
+            nhwc[((40 + 7) * 50 + 9) * 3 + 2],
+            nchw[((3 + 2) * 40 + 7) * 50 + 9]
+        );
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    #[test]
+    fn gpu_unsupported() {
+        let gpu = Buffer {
+            ptr: std::ptr::null_mut(),
+            len: 12 * 4,
+            data_type: DataType::Fp32,
+            memory_type: MemoryType::Gpu,
+            device_id: 0,
+            owned: false,
+            deleter: None,
+        };
+        let err = transpose(&gpu, Layout::Hwc, Layout::Chw, [2, 3, 2]).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Unsupported);
+    }
+}
@@ -0,0 +1,277 @@
+//! Reading and writing buffers in NumPy `.npy` format.
+//!
+//! Format description: <https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html>.
+
+use std::{fs, path::Path};
+
+use super::{Buffer, DataType, MemoryType};
+use crate::error::{Error, ErrorCode};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+/// Total header length (magic + version + header len + header) should be divisible by it.
+const HEADER_ALIGN: usize = 64;
+/// Byte order char of the native byte order, the buffers are saved and loaded in.
+const NATIVE: &str = if cfg!(target_endian = "little") {
+    "<"
+} else {
+    ">"
+};
+
+fn descr(data_type: DataType) -> Option<String> {
+    let kind = match data_type {
+        DataType::Bool => return Some("|b1".to_string()),
+        DataType::Uint8 => return Some("|u1".to_string()),
+        DataType::Int8 => return Some("|i1".to_string()),
+        DataType::Uint16 => "u2",
+        DataType::Uint32 => "u4",
+        DataType::Uint64 => "u8",
+        DataType::Int16 => "i2",
+        DataType::Int32 => "i4",
+        DataType::Int64 => "i8",
+        DataType::Fp16 => "f2",
+        DataType::Fp32 => "f4",
+        DataType::Fp64 => "f8",
+        DataType::Invalid | DataType::Bytes | DataType::Bf16 => return None,
+    };
+    Some(format!("{NATIVE}{kind}"))
+}
+
+fn data_type(descr: &str) -> Option<DataType> {
+    if descr.len() < 2 || !descr.is_char_boundary(1) {
+        return None;
+    }
+    let (byte_order, kind) = descr.split_at(1);
+    // Single byte types can be written with any byte order char.
+    let single_byte = matches!(kind, "b1" | "u1" | "i1");
+    let native = matches!(byte_order, "=" | "|") || byte_order == NATIVE || single_byte;
+    if !native {
+        return None;
+    }
+    let data_type = match kind {
+        "b1" => DataType::Bool,
+        "u1" => DataType::Uint8,
+        "u2" => DataType::Uint16,
+        "u4" => DataType::Uint32,
+        "u8" => DataType::Uint64,
+        "i1" => DataType::Int8,
+        "i2" => DataType::Int16,
+        "i4" => DataType::Int32,
+        "i8" => DataType::Int64,
+        "f2" => DataType::Fp16,
+        "f4" => DataType::Fp32,
+        "f8" => DataType::Fp64,
+        _ => return None,
+    };
+    Some(data_type)
+}
+
+/// Number of the elements of the array of `shape`. None if a dim is negative or the number overflows.
+fn element_count(shape: &[i64]) -> Option<usize> {
+    shape.iter().try_fold(1usize, |count, dim| {
+        count.checked_mul(usize::try_from(*dim).ok()?)
+    })
+}
+
+fn invalid<S: AsRef<str>>(path: &Path, message: S) -> Error {
+    Error::new(
+        ErrorCode::InvalidArg,
+        format!("{}: {}", path.display(), message.as_ref()),
+    )
+}
+
+/// Get the value of `key` from the header dict (the text after `'key':` up to the end of the value).
+fn header_value<'h>(header: &'h str, key: &str) -> Option<&'h str> {
+    let start = header.find(&format!("'{key}'"))? + key.len() + 2;
+    let value = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = match value.chars().next()? {
+        '(' => value.find(')')? + 1,
+        '\'' => value[1..].find('\'')? + 2,
+        _ => value.find([',', '}']).unwrap_or(value.len()),
+    };
+    Some(value[..end].trim())
+}
+
+impl Buffer {
+    /// Save the buffer content to the `path` in NumPy `.npy` format in the native byte order. \
+    /// `shape`: shape of the saved array. If None, the array is saved as one-dimensional.
+    ///
+    /// [DataType::Bytes] and [DataType::Bf16] buffers are not supported, since NumPy has no matching type.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn save_npy<P: AsRef<Path>>(&self, path: P, shape: Option<&[i64]>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let descr = descr(self.data_type).ok_or_else(|| {
+            invalid(
+                path,
+                format!("{:?} can't be saved in .npy format", self.data_type),
+            )
+        })?;
+
+        let count = self.len / self.data_type.size() as usize;
+        let shape = match shape {
+            None => vec![count as i64],
+            Some(shape) => {
+                if element_count(shape) != Some(count) {
+                    return Err(invalid(
+                        path,
+                        format!("shape {shape:?} mismatches buffer of {count} elements"),
+                    ));
+                }
+                shape.to_vec()
+            }
+        };
+        let shape = match shape.as_slice() {
+            [dim] => format!("({dim},)"),
+            dims => format!(
+                "({})",
+                dims.iter()
+                    .map(i64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+
+        let mut header =
+            format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+        // magic + version (2) + header len (2) + header + '\n'
+        let unpadded = MAGIC.len() + 4 + header.len() + 1;
+        header.push_str(&" ".repeat(unpadded.next_multiple_of(HEADER_ALIGN) - unpadded));
+        header.push('\n');
+
+        let data = if self.memory_type == MemoryType::Gpu {
+            self.get_owned_slice(..)?
+        } else {
+            self.bytes().to_vec()
+        };
+
+        let mut file = Vec::with_capacity(MAGIC.len() + 4 + header.len() + data.len());
+        file.extend_from_slice(MAGIC);
+        file.extend_from_slice(&[1, 0]);
+        file.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        file.extend_from_slice(header.as_bytes());
+        file.extend_from_slice(&data);
+
+        fs::write(path, file).map_err(|err| invalid(path, err.to_string()))
+    }
+
+    /// Load the array from the `path` in NumPy `.npy` format into the CPU buffer. \
+    /// Returns the buffer and the shape of the array.
+    ///
+    /// Only C ordered arrays of numeric and bool types in the native byte order are supported.
+    pub fn load_npy<P: AsRef<Path>>(path: P) -> Result<(Buffer, Vec<i64>), Error> {
+        let path = path.as_ref();
+        let file = fs::read(path).map_err(|err| invalid(path, err.to_string()))?;
+
+        if file.len() < MAGIC.len() + 4 || !file.starts_with(MAGIC) {
+            return Err(invalid(path, "not a .npy file"));
+        }
+        let major_version = file[MAGIC.len()];
+        let (header_start, header_len) = match major_version {
+            1 => (
+                MAGIC.len() + 4,
+                u16::from_le_bytes([file[MAGIC.len() + 2], file[MAGIC.len() + 3]]) as usize,
+            ),
+            2 | 3 if file.len() >= MAGIC.len() + 6 => {
+                let mut len = [0; 4];
+                len.copy_from_slice(&file[MAGIC.len() + 2..MAGIC.len() + 6]);
+                (MAGIC.len() + 6, u32::from_le_bytes(len) as usize)
+            }
+            _ => {
+                return Err(invalid(
+                    path,
+                    format!("unsupported .npy version {major_version}"),
+                ))
+            }
+        };
+
+        let header = file
+            .get(header_start..header_start + header_len)
+            .and_then(|header| std::str::from_utf8(header).ok())
+            .ok_or_else(|| invalid(path, "invalid .npy header"))?;
+
+        let descr = header_value(header, "descr")
+            .map(|descr| descr.trim_matches('\''))
+            .ok_or_else(|| invalid(path, "no 'descr' in .npy header"))?;
+        let data_type = data_type(descr)
+            .ok_or_else(|| invalid(path, format!("unsupported .npy dtype {descr}")))?;
+
+        if header_value(header, "fortran_order") != Some("False") {
+            return Err(invalid(path, "only C ordered .npy arrays are supported"));
+        }
+
+        let shape = header_value(header, "shape")
+            .and_then(|shape| shape.strip_prefix('('))
+            .and_then(|shape| shape.strip_suffix(')'))
+            .ok_or_else(|| invalid(path, "no 'shape' in .npy header"))?;
+        let shape = shape
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| dim.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| invalid(path, format!("invalid .npy shape: {err}")))?;
+
+        let data = &file[header_start + header_len..];
+        let (count, byte_size) = element_count(&shape)
+            .and_then(|count| Some((count, count.checked_mul(data_type.size() as usize)?)))
+            .ok_or_else(|| invalid(path, format!("invalid .npy shape {shape:?}")))?;
+        if data.len() < byte_size {
+            return Err(invalid(
+                path,
+                format!(
+                    "expected {byte_size} bytes of data for shape {shape:?}, found {}",
+                    data.len()
+                ),
+            ));
+        }
+
+        let mut buffer = Buffer::alloc_with_data_type(count, MemoryType::Cpu, data_type)?;
+        buffer.copy_from_slice(0, &data[..byte_size])?;
+        Ok((buffer, shape))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npy_round_trip() {
+        let path = std::env::temp_dir().join(format!("tritonserver_rs_{}.npy", std::process::id()));
+        let data = [1f32, 2., 3., 4., 5., 6.];
+
+        Buffer::from(data).save_npy(&path, Some(&[2, 3])).unwrap();
+        let file = fs::read(&path).unwrap();
+        assert_eq!((file.len() - data.len() * 4) % HEADER_ALIGN, 0);
+
+        let (buffer, shape) = Buffer::load_npy(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(shape, [2, 3]);
+        assert_eq!(buffer.data_type(), DataType::Fp32);
+        assert_eq!(buffer.bytes(), Buffer::from(data).bytes());
+    }
+
+    #[test]
+    fn npy_header() {
+        let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (4,), }";
+        assert_eq!(header_value(header, "descr"), Some("'<i8'"));
+        assert_eq!(header_value(header, "fortran_order"), Some("False"));
+        assert_eq!(header_value(header, "shape"), Some("(4,)"));
+        assert_eq!(data_type("|u1"), Some(DataType::Uint8));
+        assert_eq!(data_type(">u1"), Some(DataType::Uint8));
+        let foreign = if cfg!(target_endian = "little") {
+            ">f4"
+        } else {
+            "<f4"
+        };
+        assert_eq!(data_type(foreign), None);
+        let native = descr(DataType::Fp32).unwrap();
+        assert_eq!(data_type(&native), Some(DataType::Fp32));
+
+        assert_eq!(element_count(&[2, 3]), Some(6));
+        assert_eq!(element_count(&[]), Some(1));
+        assert_eq!(element_count(&[-1, 3]), None);
+        assert_eq!(element_count(&[i64::MAX, i64::MAX]), None);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::SystemTime,
+};
+
+use super::MemoryType;
+
+type Handler = Arc<dyn Fn(&OomEvent) + Send + Sync>;
+
+static RETRY: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+}
+
+/// Failed output allocation caused by the lack of memory. Check [set_oom_handler].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OomEvent {
+    /// Name of the output the allocation was requested for.
+    pub tensor_name: String,
+    /// Requested byte size.
+    pub byte_size: usize,
+    /// Requested memory type.
+    pub memory_type: MemoryType,
+    /// Device of the allocator, if it's set (check [crate::request::Allocator::device_id]).
+    pub device_id: Option<i32>,
+    /// The allocation succeeded on retry (check [set_oom_retry]).
+    pub recovered: bool,
+    pub time: SystemTime,
+}
+
+/// Set the handler called on each output allocation that failed with the out of memory error
+/// ([crate::Error::is_out_of_memory]), e.g. to send capacity alerts. Replaces the previous handler. \
+/// The handler is called from the Triton allocation callback, so it should be fast and must not block on the inference.
+pub fn set_oom_handler<F: Fn(&OomEvent) + Send + Sync + 'static>(handler: F) {
+    if let Ok(mut current) = HANDLER.write() {
+        *current = Some(Arc::new(handler));
+    }
+}
+
+/// Remove the handler set by [set_oom_handler].
+pub fn clear_oom_handler() {
+    if let Ok(mut current) = HANDLER.write() {
+        *current = None;
+    }
+}
+
+/// Retry the output allocation once if it failed with the out of memory error. Disabled by default. \
+/// Before the retry, the crate memory is trimmed as by [crate::Server::trim_memory] with `backends` set.
+///
+/// **Note**: buffer pools of [crate::graph::Graph] release their buffers only on the next use of the pool,
+/// so the retry succeeds only if the memory was freed by someone else meanwhile.
+pub fn set_oom_retry(retry: bool) {
+    RETRY.store(retry, Ordering::Relaxed);
+}
+
+pub(crate) fn retry_enabled() -> bool {
+    RETRY.load(Ordering::Relaxed)
+}
+
+/// Log the event and pass it to the handler.
+pub(crate) fn report(event: OomEvent) {
+    log::warn!(
+        "Out of memory allocating {} bytes of {:?} on device {:?} for output {}, recovered: {}",
+        event.byte_size,
+        event.memory_type,
+        event.device_id,
+        event.tensor_name,
+        event.recovered
+    );
+    let handler = HANDLER.read().ok().and_then(|handler| handler.clone());
+    if let Some(handler) = handler {
+        handler(&event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn handler() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        set_oom_handler(|event| {
+            assert_eq!(event.tensor_name, "logits");
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        });
+        let event = OomEvent {
+            tensor_name: "logits".to_string(),
+            byte_size: 1 << 30,
+            memory_type: MemoryType::Gpu,
+            device_id: Some(1),
+            recovered: false,
+            time: SystemTime::now(),
+        };
+        report(event.clone());
+        clear_oom_handler();
+        report(event);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,126 @@
+//! Conversions between f32 data and the half precision (Fp16/Bf16) tensors.
+
+use half::{bf16, f16, slice::HalfFloatSliceExt};
+
+use super::{Buffer, DataType, MemoryType, Sample};
+use crate::error::{Error, ErrorCode};
+
+/// Half precision [Sample]: [half::f16] or [half::bf16]. \
+/// The slice conversions use the SIMD instructions where available (check [half::slice::HalfFloatSliceExt]).
+pub trait HalfSample: Sample + Copy + Default {
+    /// Convert `src` to the half precision `dst` of the same length.
+    fn from_f32_slice(dst: &mut [Self], src: &[f32]);
+    /// Convert the half precision `src` to `dst` of the same length.
+    fn to_f32_slice(src: &[Self], dst: &mut [f32]);
+    /// Convert the bits of the half precision value to f32.
+    fn bits_to_f32(bits: u16) -> f32;
+}
+
+macro_rules! impl_half_sample {
+    ($type:ty) => {
+        impl HalfSample for $type {
+            fn from_f32_slice(dst: &mut [Self], src: &[f32]) {
+                dst.convert_from_f32_slice(src);
+            }
+
+            fn to_f32_slice(src: &[Self], dst: &mut [f32]) {
+                src.convert_to_f32_slice(dst);
+            }
+
+            fn bits_to_f32(bits: u16) -> f32 {
+                <$type>::from_bits(bits).to_f32()
+            }
+        }
+    };
+}
+
+impl_half_sample!(f16);
+impl_half_sample!(bf16);
+
+/// Convert the host `bytes` of the half precision tensor to f32.
+fn half_bytes_to_f32<T: HalfSample>(bytes: &[u8]) -> Vec<f32> {
+    let count = bytes.len() / size_of::<T>();
+    let mut res = vec![0f32; count];
+    // Safety: any bits are valid f16/bf16.
+    match unsafe { bytes.align_to::<T>() } {
+        ([], values, []) => T::to_f32_slice(values, &mut res),
+        // Unaligned data is converted element by element.
+        _ => {
+            for (value, chunk) in res.iter_mut().zip(bytes.chunks_exact(2)) {
+                *value = T::bits_to_f32(u16::from_ne_bytes([chunk[0], chunk[1]]));
+            }
+        }
+    }
+    res
+}
+
+/// Convert the host `bytes` of the tensor of `data_type` to f32. \
+/// None if the data type is not one of Fp32, Fp16 or Bf16.
+pub(crate) fn bytes_to_f32(data_type: DataType, bytes: &[u8]) -> Option<Vec<f32>> {
+    match data_type {
+        DataType::Fp16 => Some(half_bytes_to_f32::<f16>(bytes)),
+        DataType::Bf16 => Some(half_bytes_to_f32::<bf16>(bytes)),
+        DataType::Fp32 => Some(
+            bytes
+                .chunks_exact(4)
+                .map(|v| f32::from_ne_bytes([v[0], v[1], v[2], v[3]]))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+impl Buffer {
+    /// Create CPU buffer of data type `T::DATA_TYPE` (Fp16 or Bf16) from the f32 `data`, converting each value. \
+    /// E.g. `Buffer::from_f32_as::<half::f16, _>(&image)` for the model with FP16 input.
+    pub fn from_f32_as<T: HalfSample, S: AsRef<[f32]>>(data: S) -> Self {
+        let data = data.as_ref();
+        let mut converted = vec![T::default(); data.len()];
+        T::from_f32_slice(&mut converted, data);
+        Buffer::from(converted)
+    }
+
+    /// Copy the buffer of Fp32, Fp16 or Bf16 data type to the vector of f32, converting each value. \
+    /// Returns error for the other data types.
+    ///
+    /// **Note**: If self.memory_type is Gpu, should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn to_f32_vec(&self) -> Result<Vec<f32>, Error> {
+        let bytes = if self.memory_type == MemoryType::Gpu {
+            self.get_owned_slice(..)?
+        } else {
+            self.check_mem_type_feature()?;
+            self.bytes().to_vec()
+        };
+        bytes_to_f32(self.data_type, &bytes).ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("Buffer of {:?} can't be converted to f32", self.data_type),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_conversions() {
+        let values = [0.5f32, -1.25, 3.0, 1024.0];
+        let buffer = Buffer::from_f32_as::<f16, _>(values);
+        assert_eq!(buffer.size(), 8);
+        assert_eq!(buffer.to_f32_vec().unwrap(), values);
+
+        let bf16_bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| bf16::from_f32(*v).to_ne_bytes())
+            .collect();
+        assert_eq!(bytes_to_f32(DataType::Bf16, &bf16_bytes).unwrap(), values);
+        // Possibly unaligned data.
+        assert_eq!(
+            half_bytes_to_f32::<bf16>(&[0, bf16_bytes[0], bf16_bytes[1]][1..]),
+            [0.5]
+        );
+        assert!(bytes_to_f32(DataType::Int32, &[]).is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! Quantization of the f32 data for the INT8 models and dequantization of their outputs.
+
+use super::{Buffer, DataType, MemoryType, Sample};
+use crate::{
+    error::{Error, ErrorCode},
+    response::Quantization,
+};
+
+/// Quantized [Sample]: i8 (e.g. TensorRT INT8 engines) or u8.
+pub trait QuantizedSample: Sample {
+    /// Saturate the rounded value to the sample.
+    fn saturate(value: f32) -> Self;
+    /// Value of the sample.
+    fn to_i32(self) -> i32;
+}
+
+impl QuantizedSample for i8 {
+    fn saturate(value: f32) -> Self {
+        value.clamp(i8::MIN as f32, i8::MAX as f32) as i8
+    }
+
+    fn to_i32(self) -> i32 {
+        self as i32
+    }
+}
+
+impl QuantizedSample for u8 {
+    fn saturate(value: f32) -> Self {
+        value.clamp(u8::MIN as f32, u8::MAX as f32) as u8
+    }
+
+    fn to_i32(self) -> i32 {
+        self as i32
+    }
+}
+
+/// Per-tensor or per-channel quantization parameters: `real value = (quantized value - zero_point) * scale`,
+/// where the scale and the zero point are the ones of the channel of the value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizationParams {
+    scales: Vec<f32>,
+    zero_points: Vec<i32>,
+    /// Number of the consecutive values of one channel.
+    channel_stride: usize,
+}
+
+impl QuantizationParams {
+    /// Same parameters for all the values of the tensor.
+    pub fn per_tensor(scale: f32, zero_point: i32) -> Self {
+        QuantizationParams {
+            scales: vec![scale],
+            zero_points: vec![zero_point],
+            channel_stride: 1,
+        }
+    }
+
+    /// Parameters of each channel along the `axis` of the tensor of `shape` (e.g. axis 0 for the weights,
+    /// axis 1 for NCHW activations). \
+    /// `zero_points` contain one value for all the channels or one value per channel. \
+    /// Returns error if the number of the parameters doesn't match the channels count.
+    pub fn per_channel(
+        scales: Vec<f32>,
+        zero_points: Vec<i32>,
+        shape: &[i64],
+        axis: usize,
+    ) -> Result<Self, Error> {
+        let channels = shape.get(axis).copied().ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("Quantization axis {axis} is out of shape {shape:?}"),
+            )
+        })?;
+        if channels <= 0
+            || scales.len() as i64 != channels
+            || (zero_points.len() != 1 && zero_points.len() != scales.len())
+        {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "{} scales and {} zero points don't match {channels} channels of the axis {axis} of shape {shape:?}",
+                    scales.len(),
+                    zero_points.len()
+                ),
+            ));
+        }
+        Ok(QuantizationParams {
+            scales,
+            zero_points,
+            channel_stride: shape[axis + 1..].iter().product::<i64>().max(1) as usize,
+        })
+    }
+
+    /// Scale and zero point of the value with the flat `index`.
+    fn of(&self, index: usize) -> (f32, i32) {
+        let channel = (index / self.channel_stride) % self.scales.len();
+        let zero_point = self.zero_points[channel.min(self.zero_points.len() - 1)];
+        (self.scales[channel], zero_point)
+    }
+
+    fn quantize<T: QuantizedSample>(&self, data: &[f32]) -> Vec<T> {
+        data.iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let (scale, zero_point) = self.of(index);
+                T::saturate((value / scale).round() + zero_point as f32)
+            })
+            .collect()
+    }
+
+    fn dequantize<T: QuantizedSample>(&self, data: impl Iterator<Item = T>) -> Vec<f32> {
+        data.enumerate()
+            .map(|(index, value)| {
+                let (scale, zero_point) = self.of(index);
+                (value.to_i32() - zero_point) as f32 * scale
+            })
+            .collect()
+    }
+}
+
+impl From<Quantization> for QuantizationParams {
+    fn from(value: Quantization) -> Self {
+        QuantizationParams::per_tensor(value.scale, value.zero_point)
+    }
+}
+
+/// Dequantize the host `bytes` of the tensor of `data_type` (Int8 or Uint8). None for the other data types.
+pub(crate) fn dequantize_bytes(
+    params: &QuantizationParams,
+    data_type: DataType,
+    bytes: &[u8],
+) -> Option<Vec<f32>> {
+    match data_type {
+        DataType::Int8 => Some(params.dequantize(bytes.iter().map(|v| *v as i8))),
+        DataType::Uint8 => Some(params.dequantize(bytes.iter().copied())),
+        _ => None,
+    }
+}
+
+impl Buffer {
+    /// Create CPU buffer of data type `T::DATA_TYPE` (Int8 or Uint8) quantizing the f32 `data`:
+    /// `quantized value = round(value / scale) + zero_point`, saturated to `T`.
+    pub fn quantize_from_f32<T: QuantizedSample, S: AsRef<[f32]>>(
+        data: S,
+        params: &QuantizationParams,
+    ) -> Self {
+        Buffer::from(params.quantize::<T>(data.as_ref()))
+    }
+
+    /// Dequantize the buffer of Int8 or Uint8 data type to the vector of f32. \
+    /// Returns error for the other data types.
+    ///
+    /// **Note**: If self.memory_type is Gpu, should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn dequantize_to_f32(&self, params: &QuantizationParams) -> Result<Vec<f32>, Error> {
+        let bytes = if self.memory_type == MemoryType::Gpu {
+            self.get_owned_slice(..)?
+        } else {
+            self.check_mem_type_feature()?;
+            self.bytes().to_vec()
+        };
+        dequantize_bytes(params, self.data_type, &bytes).ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("Buffer of {:?} can't be dequantized", self.data_type),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantization() {
+        let params = QuantizationParams::per_tensor(0.5, -128);
+        let quantized = params.quantize::<i8>(&[0.0, 1.0, -1000.0, 1000.0]);
+        assert_eq!(quantized, [-128, -126, -128, 127]);
+        assert_eq!(params.dequantize([-128i8, -126].into_iter()), [0.0, 1.0]);
+
+        let params =
+            QuantizationParams::per_channel(vec![1.0, 0.1], vec![0], &[2, 2, 2], 1).unwrap();
+        let data = [1.0, 2.0, 0.1, 0.2, 3.0, 4.0, 0.3, 0.4];
+        let quantized = params.quantize::<u8>(&data);
+        assert_eq!(quantized, [1, 2, 1, 2, 3, 4, 3, 4]);
+        let dequantized = dequantize_bytes(&params, DataType::Uint8, &quantized).unwrap();
+        for (value, expected) in dequantized.iter().zip(data) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+}
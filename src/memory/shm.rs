@@ -0,0 +1,266 @@
+//! Buffers backed by memory shared with other processes: POSIX shared memory and CUDA IPC.
+
+use std::{io, ptr::null_mut};
+
+#[cfg(feature = "gpu")]
+use cuda_driver_sys::{
+    cuIpcCloseMemHandle, cuIpcGetMemHandle, cuIpcOpenMemHandle, CUdeviceptr, CUipcMemHandle,
+    CUipcMem_flags,
+};
+use libc::c_void;
+
+use super::{Buffer, DataType, Deleter, MemoryType};
+use crate::{
+    error::{Error, ErrorCode},
+    to_cstring,
+};
+
+/// Size of the CUDA IPC memory handle in bytes.
+pub const CUDA_IPC_HANDLE_SIZE: usize = 64;
+
+fn check_len(len: usize, data_type: DataType) -> Result<(), Error> {
+    if len == 0 || !len.is_multiple_of(data_type.size() as usize) {
+        return Err(Error::new(
+            ErrorCode::InvalidArg,
+            format!(
+                "shared buffer len {len} must be a positive multiple of {data_type:?} size {}",
+                data_type.size()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn os_error<N: AsRef<str>>(name: N, call: &str) -> Error {
+    Error::new(
+        ErrorCode::Internal,
+        format!(
+            "{call} of shared memory {} failed: {}",
+            name.as_ref(),
+            io::Error::last_os_error()
+        ),
+    )
+}
+
+/// Map the region of the POSIX shared memory segment, check [Buffer::from_shm].
+unsafe fn map_shm<N: AsRef<str>>(
+    name: N,
+    offset: usize,
+    len: usize,
+    data_type: DataType,
+    writable: bool,
+) -> Result<Buffer, Error> {
+    check_len(len, data_type)?;
+    let end = offset.checked_add(len).ok_or_else(|| {
+        Error::new(
+            ErrorCode::InvalidArg,
+            format!("shared buffer offset {offset} + len {len} overflows"),
+        )
+    })?;
+    let c_name = to_cstring(&name)?;
+
+    let (flags, protection) = if writable {
+        (libc::O_RDWR, libc::PROT_READ | libc::PROT_WRITE)
+    } else {
+        (libc::O_RDONLY, libc::PROT_READ)
+    };
+    let fd = libc::shm_open(c_name.as_ptr(), flags, 0);
+    if fd < 0 {
+        return Err(os_error(name, "shm_open"));
+    }
+
+    let mut stat = std::mem::zeroed::<libc::stat>();
+    if libc::fstat(fd, &mut stat) != 0 {
+        libc::close(fd);
+        return Err(os_error(name, "fstat"));
+    }
+    if (stat.st_size as usize) < end {
+        libc::close(fd);
+        return Err(Error::new(
+            ErrorCode::InvalidArg,
+            format!(
+                "shared memory {} of size {} is less than offset {offset} + len {len}",
+                name.as_ref(),
+                stat.st_size
+            ),
+        ));
+    }
+
+    // mmap offset must be a multiple of the page size.
+    let page_size = libc::sysconf(libc::_SC_PAGESIZE) as usize;
+    let map_offset = offset - offset % page_size;
+    let map_len = len + offset % page_size;
+    let base = libc::mmap(
+        null_mut(),
+        map_len,
+        protection,
+        libc::MAP_SHARED,
+        fd,
+        map_offset as libc::off_t,
+    );
+    libc::close(fd);
+    if base == libc::MAP_FAILED {
+        return Err(os_error(name, "mmap"));
+    }
+
+    let base = base as usize;
+    Ok(Buffer {
+        ptr: (base + offset % page_size) as *mut c_void,
+        len,
+        data_type,
+        memory_type: MemoryType::Cpu,
+        device_id: 0,
+        owned: true,
+        deleter: Some(Deleter(Box::new(move |_| {
+            libc::munmap(base as *mut c_void, map_len);
+        }))),
+    })
+}
+
+impl Buffer {
+    /// Create CPU buffer mapped to the POSIX shared memory segment (see `shm_open(3)`). \
+    /// `name`: name of the segment, e.g. "/input_0". \
+    /// `offset`: offset of the buffer data in the segment in bytes. \
+    /// `len`: size of the buffer data in bytes. \
+    /// `data_type`: data type of the buffer elements.
+    ///
+    /// The segment is opened for reading and writing, use [Buffer::from_shm_read_only] for the read-only segments.
+    /// The segment is unmapped when the buffer is dropped. The segment itself is not removed.
+    ///
+    /// # Safety
+    /// The region is shared with other processes, so the caller must ensure that
+    /// it is not modified or truncated by them while the buffer (or the request using it) is alive.
+    pub unsafe fn from_shm<N: AsRef<str>>(
+        name: N,
+        offset: usize,
+        len: usize,
+        data_type: DataType,
+    ) -> Result<Self, Error> {
+        map_shm(name, offset, len, data_type, true)
+    }
+
+    /// Create CPU buffer mapped read-only to the POSIX shared memory segment (see `shm_open(3)`),
+    /// e.g. to the segment the process has no write permission for. \
+    /// Arguments are the same as of [Buffer::from_shm].
+    ///
+    /// The segment is unmapped when the buffer is dropped. The segment itself is not removed.
+    ///
+    /// # Safety
+    /// Same as of [Buffer::from_shm]. Besides, the mapping can't be written, so the buffer must be used
+    /// only as the request input and must not be modified (e.g. with [Buffer::copy_from_slice]
+    /// or as the output buffer): the write crashes the process.
+    pub unsafe fn from_shm_read_only<N: AsRef<str>>(
+        name: N,
+        offset: usize,
+        len: usize,
+        data_type: DataType,
+    ) -> Result<Self, Error> {
+        map_shm(name, offset, len, data_type, false)
+    }
+
+    /// Create GPU buffer from the CUDA IPC memory handle exported by another process (`cuIpcGetMemHandle`). \
+    /// `handle`: bytes of the handle. \
+    /// `offset`: offset of the buffer data in the exported allocation in bytes. \
+    /// `len`: size of the buffer data in bytes. \
+    /// `data_type`: data type of the buffer elements.
+    ///
+    /// The handle is closed when the buffer is dropped.
+    ///
+    /// **Note**: Method should be called in sync with cuda context pinned (check module level documentation for more info).
+    /// Buffer must be dropped in the same context.
+    ///
+    /// # Safety
+    /// The memory is shared with another process, so the caller must ensure that
+    /// it is not modified or freed by it while the buffer (or the request using it) is alive.
+    /// `offset + len` must not exceed the size of the exported allocation.
+    #[cfg(feature = "gpu")]
+    pub unsafe fn from_cuda_ipc_handle(
+        handle: [u8; CUDA_IPC_HANDLE_SIZE],
+        offset: usize,
+        len: usize,
+        data_type: DataType,
+    ) -> Result<Self, Error> {
+        check_len(len, data_type)?;
+
+        let handle = CUipcMemHandle {
+            reserved: handle.map(|byte| byte as _),
+        };
+        let mut base: CUdeviceptr = 0;
+        cuda_call!(cuIpcOpenMemHandle(
+            &mut base,
+            handle,
+            CUipcMem_flags::CU_IPC_MEM_LAZY_ENABLE_PEER_ACCESS as u32
+        ))?;
+
+        Ok(Buffer {
+            ptr: (base as usize + offset) as *mut c_void,
+            len,
+            data_type,
+            memory_type: MemoryType::Gpu,
+            device_id: crate::context::current_device()?,
+            owned: true,
+            deleter: Some(Deleter(Box::new(move |_| {
+                if let Err(err) = cuda_call!(cuIpcCloseMemHandle(base)) {
+                    log::error!("Error closing CUDA IPC memory handle: {err}");
+                }
+            }))),
+        })
+    }
+
+    /// Export the CUDA IPC memory handle of the GPU buffer,
+    /// that can be opened in another process with [Buffer::from_cuda_ipc_handle] (with zero offset) or `cuIpcOpenMemHandle`.
+    ///
+    /// Buffer must be allocated by [Buffer::alloc] or [Buffer::alloc_with_data_type]
+    /// and must outlive the usage of the handle in the other process.
+    ///
+    /// **Note**: Method should be called in sync with cuda context pinned (check module level documentation for more info).
+    #[cfg(feature = "gpu")]
+    pub fn cuda_ipc_handle(&self) -> Result<[u8; CUDA_IPC_HANDLE_SIZE], Error> {
+        if self.memory_type != MemoryType::Gpu {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "CUDA IPC handle can be exported only for Gpu buffer, got {:?}",
+                    self.memory_type
+                ),
+            ));
+        }
+
+        let mut handle = CUipcMemHandle {
+            reserved: [0; CUDA_IPC_HANDLE_SIZE],
+        };
+        cuda_call!(cuIpcGetMemHandle(&mut handle, self.ptr as CUdeviceptr))?;
+        Ok(handle.reserved.map(|byte| byte as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shm_mapping() {
+        let name = format!("/tritonserver_rs_{}", std::process::id());
+        let c_name = to_cstring(name.as_str()).unwrap();
+        unsafe {
+            let fd = libc::shm_open(c_name.as_ptr(), libc::O_RDWR | libc::O_CREAT, 0o600);
+            assert!(fd >= 0);
+            assert_eq!(libc::ftruncate(fd, 16), 0);
+            libc::close(fd);
+
+            let mut writable = Buffer::from_shm(&name, 4, 8, DataType::Uint8).unwrap();
+            writable
+                .copy_from_slice(0, [1u8, 2, 3, 4, 5, 6, 7, 8])
+                .unwrap();
+            let read_only = Buffer::from_shm_read_only(&name, 8, 4, DataType::Uint8).unwrap();
+            assert_eq!(read_only.bytes(), [5, 6, 7, 8]);
+
+            let overflow = Buffer::from_shm_read_only(&name, usize::MAX, 4, DataType::Uint8);
+            assert_eq!(overflow.unwrap_err().code(), ErrorCode::InvalidArg);
+            let too_long = Buffer::from_shm_read_only(&name, 8, 16, DataType::Uint8);
+            assert_eq!(too_long.unwrap_err().code(), ErrorCode::InvalidArg);
+
+            libc::shm_unlink(c_name.as_ptr());
+        }
+    }
+}
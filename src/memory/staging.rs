@@ -0,0 +1,243 @@
+//! Host-device copies of the buffers staged through the reusable page-locked memory.
+
+use std::{
+    collections::HashMap,
+    ptr::copy_nonoverlapping,
+    sync::{Mutex, PoisonError},
+};
+
+use cuda_driver_sys::{cuMemcpyDtoHAsync_v2, cuMemcpyHtoDAsync_v2, CUdeviceptr};
+
+use super::{trim_generation, Buffer, MemoryType};
+use crate::{context::Stream, error::Error};
+
+/// Default size of the [StagingPool] chunk: 8MB.
+pub const DEFAULT_STAGING_CHUNK: usize = 8 << 20;
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_POOL: StagingPool = StagingPool::new(DEFAULT_STAGING_CHUNK, 4);
+}
+
+#[derive(Debug, Default)]
+struct Chunks {
+    /// Pinned chunks by the device of the context they were allocated in.
+    free: HashMap<i32, Vec<Buffer>>,
+    /// Trim generation the pooled chunks belong to. Check [crate::Server::trim_memory].
+    generation: u64,
+}
+
+/// Pool of the page-locked (Pinned) memory chunks used to copy the pageable CPU buffers to GPU and back. \
+/// Copies from the pageable memory are synchronous and slow, so the pool copies the buffer by chunks:
+/// while one chunk is transferred to (from) the device, the next one is filled on the host.
+///
+/// Check [Buffer::into_gpu_via_pool] and [Buffer::into_cpu_via_pool] that use the [StagingPool::global] pool.
+///
+/// **Note**: methods should be called in sync with cuda context pinned (check module level documentation for more info).
+#[derive(Debug)]
+pub struct StagingPool {
+    chunk_size: usize,
+    /// Maximum number of the chunks kept for the reuse per device.
+    max_pooled: usize,
+    chunks: Mutex<Chunks>,
+}
+
+/// Chunk in use with its stream.
+struct Slot {
+    chunk: Buffer,
+    stream: Stream,
+    /// Range of the buffer transferred through the chunk now.
+    pending: Option<(usize, usize)>,
+}
+
+impl StagingPool {
+    /// Create the pool of `chunk_size` bytes chunks, keeping up to `max_pooled` of them for the reuse.
+    pub fn new(chunk_size: usize, max_pooled: usize) -> Self {
+        StagingPool {
+            chunk_size: chunk_size.max(1),
+            max_pooled,
+            chunks: Mutex::new(Chunks::default()),
+        }
+    }
+
+    /// Pool used by [Buffer::into_gpu_via_pool] and [Buffer::into_cpu_via_pool]:
+    /// chunks of [DEFAULT_STAGING_CHUNK] bytes, up to 4 kept per device.
+    pub fn global() -> &'static StagingPool {
+        &GLOBAL_POOL
+    }
+
+    /// Size of the chunk in bytes.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Release all the pooled chunks.
+    pub fn clear(&self) {
+        self.lock().free.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Chunks> {
+        let mut chunks = self.chunks.lock().unwrap_or_else(PoisonError::into_inner);
+        let generation = trim_generation();
+        if chunks.generation != generation {
+            chunks.free.clear();
+            chunks.generation = generation;
+        }
+        chunks
+    }
+
+    fn take(&self, device: i32) -> Result<Slot, Error> {
+        let pooled = self.lock().free.get_mut(&device).and_then(Vec::pop);
+        let chunk = match pooled {
+            Some(chunk) => chunk,
+            None => Buffer::alloc::<u8>(self.chunk_size, MemoryType::Pinned)?,
+        };
+        Ok(Slot {
+            chunk,
+            stream: Stream::new()?,
+            pending: None,
+        })
+    }
+
+    fn put(&self, device: i32, slots: Vec<Slot>) {
+        let mut chunks = self.lock();
+        let free = chunks.free.entry(device).or_default();
+        for slot in slots {
+            if free.len() < self.max_pooled {
+                free.push(slot.chunk);
+            }
+        }
+    }
+
+    /// Take two chunks of the current device, run `f` with them and return them to the pool.
+    fn with_slots<R, F>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut [Slot; 2]) -> Result<R, Error>,
+    {
+        let _context = crate::context::auto_pin()?;
+        let device = crate::context::current_device()?;
+
+        let mut slots = [self.take(device)?, self.take(device)?];
+        let result = f(&mut slots);
+        // Chunks must not be reused while the copies are in progress.
+        let synchronized = slots.iter().all(|slot| slot.stream.synchronize().is_ok());
+        if synchronized {
+            self.put(device, slots.into());
+        }
+        result
+    }
+
+    /// Copy `source` (of any memory type) to the new GPU buffer through the pool chunks.
+    pub fn copy_to_gpu(&self, source: &Buffer) -> Result<Buffer, Error> {
+        source.check_mem_type_feature()?;
+        let mut target = Buffer::alloc_with_data_type(
+            source.len / source.data_type.size() as usize,
+            MemoryType::Gpu,
+            source.data_type,
+        )?;
+        if source.memory_type != MemoryType::Cpu {
+            if source.memory_type == MemoryType::Gpu {
+                target.copy_from_cuda_array(0, unsafe { source.get_cuda_array() })?;
+            } else {
+                target.copy_from_slice(0, source.bytes())?;
+            }
+            return Ok(target);
+        }
+
+        self.with_slots(|slots| {
+            for (index, offset) in (0..source.len).step_by(self.chunk_size).enumerate() {
+                let slot = &mut slots[index % 2];
+                let len = self.chunk_size.min(source.len - offset);
+                // Wait until the previous copy from the chunk is completed.
+                slot.stream.synchronize()?;
+                unsafe {
+                    copy_nonoverlapping(
+                        source.ptr.byte_add(offset) as *const u8,
+                        slot.chunk.ptr as *mut u8,
+                        len,
+                    )
+                };
+                cuda_call!(cuMemcpyHtoDAsync_v2(
+                    target.ptr as CUdeviceptr + offset as CUdeviceptr,
+                    slot.chunk.ptr,
+                    len,
+                    slot.stream.as_raw()
+                ))?;
+            }
+            Ok(())
+        })?;
+        Ok(target)
+    }
+
+    /// Copy `source` (of any memory type) to the new CPU buffer through the pool chunks.
+    pub fn copy_to_cpu(&self, source: &Buffer) -> Result<Buffer, Error> {
+        source.check_mem_type_feature()?;
+        let mut target = Buffer::alloc_with_data_type(
+            source.len / source.data_type.size() as usize,
+            MemoryType::Cpu,
+            source.data_type,
+        )?;
+        if source.memory_type != MemoryType::Gpu {
+            target.copy_from_slice(0, source.bytes())?;
+            return Ok(target);
+        }
+
+        let drain = |slot: &mut Slot, target: &mut Buffer| -> Result<(), Error> {
+            if let Some((offset, len)) = slot.pending.take() {
+                slot.stream.synchronize()?;
+                unsafe {
+                    copy_nonoverlapping(
+                        slot.chunk.ptr as *const u8,
+                        target.ptr.byte_add(offset) as *mut u8,
+                        len,
+                    )
+                };
+            }
+            Ok(())
+        };
+
+        self.with_slots(|slots| {
+            for (index, offset) in (0..source.len).step_by(self.chunk_size).enumerate() {
+                let slot = &mut slots[index % 2];
+                let len = self.chunk_size.min(source.len - offset);
+                // Copy out the previous range of the chunk before overwriting it.
+                drain(slot, &mut target)?;
+                cuda_call!(cuMemcpyDtoHAsync_v2(
+                    slot.chunk.ptr,
+                    source.ptr as CUdeviceptr + offset as CUdeviceptr,
+                    len,
+                    slot.stream.as_raw()
+                ))?;
+                slot.pending = Some((offset, len));
+            }
+            for slot in slots.iter_mut() {
+                drain(slot, &mut target)?;
+            }
+            Ok(())
+        })?;
+        Ok(target)
+    }
+}
+
+impl Buffer {
+    /// Move the buffer to GPU copying it through the page-locked chunks of [StagingPool::global]. \
+    /// Faster than the direct copy for the large CPU buffers. GPU buffers are returned as is.
+    ///
+    /// **Note**: Method should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn into_gpu_via_pool(self) -> Result<Buffer, Error> {
+        if self.memory_type == MemoryType::Gpu {
+            return Ok(self);
+        }
+        StagingPool::global().copy_to_gpu(&self)
+    }
+
+    /// Move the buffer to CPU copying it through the page-locked chunks of [StagingPool::global]. \
+    /// Faster than the direct copy for the large GPU buffers. CPU buffers are returned as is.
+    ///
+    /// **Note**: Method should be called in sync with cuda context pinned (check module level documentation for more info).
+    pub fn into_cpu_via_pool(self) -> Result<Buffer, Error> {
+        if self.memory_type == MemoryType::Cpu {
+            return Ok(self);
+        }
+        StagingPool::global().copy_to_cpu(&self)
+    }
+}
@@ -0,0 +1,124 @@
+//! Copies of the buffers queued to the Cuda stream.
+
+use std::mem::size_of_val;
+
+use cuda_driver_sys::{
+    cuMemcpyAsync, cuMemcpyDtoDAsync_v2, cuMemcpyDtoHAsync_v2, cuMemcpyHtoDAsync_v2, CUdeviceptr,
+};
+
+use super::{Buffer, MemoryType, Sample};
+use crate::{
+    context::Stream,
+    error::{Error, ErrorCode},
+};
+
+impl Buffer {
+    /// Queue the copy of `source` content to self from the `offset` position to the `stream`. \
+    /// Returns error if offset + size_of_val(source) > self.size().
+    ///
+    /// `offset`: offset (in bytes) from the beginning of the Buffer to location to copy `source` to. \
+    /// `source`: slice of Samples. \
+    /// `stream`: stream to queue the copy to.
+    ///
+    /// The copy is truly asynchronous only if `source` is page-locked (Pinned) memory.
+    ///
+    /// **Note**: Method should be called in sync with cuda context pinned (check module level documentation for more info).
+    ///
+    /// # Safety
+    /// `source` and self must not be dropped or modified until the copy is completed (check [Stream::synchronize]).
+    pub unsafe fn copy_from_slice_async<S: AsRef<[T]>, T: Sample>(
+        &mut self,
+        offset: usize,
+        source: S,
+        stream: &Stream,
+    ) -> Result<(), Error> {
+        let slice = source.as_ref();
+        let byte_size = size_of_val(slice);
+
+        if self.len < byte_size + offset {
+            return Err(Error::new(
+                ErrorCode::Internal,
+                format!(
+                    "copy_from_slice_async error: size mismatch! (required {}, buffer len {})",
+                    byte_size + offset,
+                    self.len
+                ),
+            ));
+        }
+
+        match self.memory_type {
+            MemoryType::Cpu | MemoryType::Pinned => cuda_call!(cuMemcpyAsync(
+                self.ptr.byte_add(offset) as CUdeviceptr,
+                slice.as_ptr() as CUdeviceptr,
+                byte_size,
+                stream.as_raw()
+            )),
+            MemoryType::Gpu => cuda_call!(cuMemcpyHtoDAsync_v2(
+                self.ptr as CUdeviceptr + offset as CUdeviceptr,
+                slice.as_ptr() as _,
+                byte_size,
+                stream.as_raw()
+            )),
+        }
+    }
+
+    /// Queue the copy of self content to `target` from the `offset` position to the `stream`. \
+    /// Returns error if offset + self.size() > target.size().
+    ///
+    /// `target`: buffer to copy to. Can be of any memory type. \
+    /// `offset`: offset (in bytes) from the beginning of the `target` to location to copy self to. \
+    /// `stream`: stream to queue the copy to.
+    ///
+    /// The copy is truly asynchronous only if both buffers are Gpu or Pinned.
+    ///
+    /// **Note**: Method should be called in sync with cuda context pinned (check module level documentation for more info).
+    ///
+    /// # Safety
+    /// Self and `target` must not be dropped or modified until the copy is completed (check [Stream::synchronize]).
+    pub unsafe fn copy_to_async(
+        &self,
+        target: &mut Buffer,
+        offset: usize,
+        stream: &Stream,
+    ) -> Result<(), Error> {
+        if self.len + offset > target.len {
+            return Err(Error::new(
+                ErrorCode::Internal,
+                format!(
+                    "copy_to_async error: size mismatch (target len {}, required {})",
+                    target.len,
+                    self.len + offset
+                ),
+            ));
+        }
+
+        let dst = target.ptr.byte_add(offset);
+        let stream = stream.as_raw();
+        match (self.memory_type, target.memory_type) {
+            (MemoryType::Gpu, MemoryType::Gpu) => cuda_call!(cuMemcpyDtoDAsync_v2(
+                dst as CUdeviceptr,
+                self.ptr as CUdeviceptr,
+                self.len,
+                stream
+            )),
+            (MemoryType::Gpu, _) => cuda_call!(cuMemcpyDtoHAsync_v2(
+                dst,
+                self.ptr as CUdeviceptr,
+                self.len,
+                stream
+            )),
+            (_, MemoryType::Gpu) => cuda_call!(cuMemcpyHtoDAsync_v2(
+                dst as CUdeviceptr,
+                self.ptr,
+                self.len,
+                stream
+            )),
+            _ => cuda_call!(cuMemcpyAsync(
+                dst as CUdeviceptr,
+                self.ptr as CUdeviceptr,
+                self.len,
+                stream
+            )),
+        }
+    }
+}
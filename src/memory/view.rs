@@ -0,0 +1,170 @@
+//! Views of the buffer parts without copying.
+
+use std::{fmt::Debug, ops::RangeBounds, slice, sync::Arc};
+
+use super::{range_bounds, Buffer, DataType, Deleter, MemoryType};
+use crate::error::{Error, ErrorCode};
+
+/// Non-owning view of the part of the [Buffer]. \
+/// One can obtain this item using [Buffer::slice].
+///
+/// The view keeps the parent buffer alive, so the parts of one buffer
+/// (e.g. the frames of the batched video) can be passed to the requests as separate inputs:
+/// ```ignore
+/// let video = Arc::new(video);
+/// let frame_size = video.size() / frames;
+/// for frame in 0..frames {
+///     let view = video.slice(frame * frame_size..(frame + 1) * frame_size)?;
+///     let mut request = server.create_request("detector", -1)?;
+///     request.add_input("IMAGE", unsafe { view.into_buffer() })?;
+/// }
+/// ```
+/// The view points to the same memory as the parent, so the GPU views stay on the parent device.
+#[derive(Debug, Clone)]
+pub struct BufferView {
+    parent: Arc<Buffer>,
+    /// Byte offset from the beginning of the parent.
+    offset: usize,
+    /// Byte size.
+    len: usize,
+}
+
+impl Buffer {
+    /// Get the view of the bytes `range` of the buffer without copying. \
+    /// Returns error if the range is out of the buffer or its bounds are not aligned to the data type size.
+    pub fn slice<Range: RangeBounds<usize> + Debug>(
+        self: &Arc<Self>,
+        range: Range,
+    ) -> Result<BufferView, Error> {
+        BufferView {
+            parent: self.clone(),
+            offset: 0,
+            len: self.len,
+        }
+        .slice(range)
+    }
+}
+
+impl BufferView {
+    /// Get the view of the bytes `range` of this view (relative to its beginning). \
+    /// Returns error if the range is out of the view or its bounds are not aligned to the data type size.
+    pub fn slice<Range: RangeBounds<usize> + Debug>(&self, range: Range) -> Result<Self, Error> {
+        let (left, right) = range_bounds(&range, self.len);
+        if left > right || right > self.len {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("slice invalid range: {range:?}, view len is: {}", self.len),
+            ));
+        }
+        let sample_size = self.parent.data_type.size() as usize;
+        if !left.is_multiple_of(sample_size) || !right.is_multiple_of(sample_size) {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "slice range {range:?} is not aligned to the size {sample_size} of the data type {:?}",
+                    self.parent.data_type
+                ),
+            ));
+        }
+
+        Ok(BufferView {
+            parent: self.parent.clone(),
+            offset: self.offset + left,
+            len: right - left,
+        })
+    }
+
+    /// Get the buffer the view points to.
+    pub fn parent(&self) -> &Arc<Buffer> {
+        &self.parent
+    }
+
+    /// Get byte offset of the view from the beginning of the parent buffer.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Get byte size of the view.
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    /// True if the view is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get memory type of the parent buffer.
+    pub fn memory_type(&self) -> MemoryType {
+        self.parent.memory_type
+    }
+
+    /// Get data type of the parent buffer.
+    pub fn data_type(&self) -> DataType {
+        self.parent.data_type
+    }
+
+    /// Get the device of the parent buffer. Check [Buffer::device_id].
+    pub fn device_id(&self) -> i32 {
+        self.parent.device_id
+    }
+
+    /// Get content of the view as bytes.
+    ///
+    /// Will return nothing if self.memory_type == Gpu. Use [BufferView::get_owned_slice] instead.
+    pub fn bytes(&self) -> &[u8] {
+        if self.parent.memory_type == MemoryType::Gpu {
+            log::warn!("Use bytes() on Gpu BufferView. empty slice will be returned");
+            return &[];
+        }
+
+        unsafe { slice::from_raw_parts((self.parent.ptr as *const u8).add(self.offset), self.len) }
+    }
+
+    /// Get content of the view as host located bytes. Check [Buffer::get_owned_slice].
+    pub fn get_owned_slice(&self) -> Result<Vec<u8>, Error> {
+        self.parent
+            .get_owned_slice(self.offset..self.offset + self.len)
+    }
+
+    /// Convert the view to the buffer pointing to the same memory, e.g. to pass it to the request as input. \
+    /// The parent buffer is kept alive until the returned buffer is dropped.
+    ///
+    /// # Safety
+    /// The data must not be mutated via the returned buffer (e.g. via [Buffer::bytes_mut]):
+    /// the memory is shared with the parent buffer and the other views of it.
+    pub unsafe fn into_buffer(self) -> Buffer {
+        let parent = self.parent;
+        Buffer {
+            ptr: (parent.ptr as *mut u8).add(self.offset) as *mut _,
+            len: self.len,
+            data_type: parent.data_type,
+            memory_type: parent.memory_type,
+            device_id: parent.device_id,
+            owned: true,
+            // The data is freed with the parent.
+            deleter: Some(Deleter(Box::new(move |_| drop(parent)))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Byte;
+
+    #[test]
+    fn views() {
+        let buffer = Arc::new(Buffer::from((0..12).map(Byte).collect::<Vec<_>>()));
+        let frame = buffer.slice(4..8).unwrap();
+        assert_eq!(frame.bytes(), [4, 5, 6, 7]);
+        let nested = frame.slice(1..=2).unwrap();
+        assert_eq!((nested.offset(), nested.bytes()), (5, &[5u8, 6][..]));
+
+        let owned = unsafe { nested.into_buffer() };
+        assert_eq!(owned.bytes(), [5, 6]);
+        assert_eq!(Arc::strong_count(&buffer), 3);
+        drop((frame, owned));
+        assert_eq!(Arc::strong_count(&buffer), 1);
+    }
+}
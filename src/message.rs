@@ -1,11 +1,24 @@
-use std::ptr::null;
+use std::{os::raw::c_char, ptr::null};
 
 use serde::{
     de::{Error as _, Unexpected},
     Deserialize, Deserializer,
 };
 
-use crate::{error::Error, memory::DataType, server::State, sys};
+use crate::{
+    error::{Error, ErrorCode},
+    memory::DataType,
+    server::State,
+    sys,
+};
+
+mod config;
+mod statistics;
+
+pub use config::{
+    DynamicBatching, InstanceGroupConfig, ModelConfig, ModelConfigBuilder, TensorConfig,
+};
+pub use statistics::{BatchStatistics, DurationStatistics, InferenceStatistics, ModelStatistics};
 
 /// Representation of any configuration json message that server can send.
 #[derive(Debug)]
@@ -14,10 +27,7 @@ pub(crate) struct Message(pub(crate) *mut sys::TRITONSERVER_Message);
 impl Message {
     /// Get the serialized message in JSON format.
     pub(crate) fn to_json(&self) -> Result<&[u8], Error> {
-        #[cfg(target_arch = "x86_64")]
-        let mut ptr = null::<i8>();
-        #[cfg(target_arch = "aarch64")]
-        let mut ptr = null::<u8>();
+        let mut ptr = null::<c_char>();
         let mut size: usize = 0;
 
         triton_call!(sys::TRITONSERVER_MessageSerializeToJson(
@@ -51,6 +61,114 @@ pub struct Index {
     pub version: i64,
 }
 
+/// Lifecycle state of the model version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum VersionState {
+    Unknown,
+    Ready,
+    Unavailable,
+    Loading,
+    Unloading,
+}
+
+/// Status of the model version in the model repository.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct VersionStatus {
+    pub name: String,
+    /// None if the model was never loaded.
+    #[serde(default, deserialize_with = "de_opt_version")]
+    pub version: Option<i64>,
+    /// None if the model was never loaded.
+    #[serde(default)]
+    pub state: Option<VersionState>,
+    /// Reason of the state (for example, the error of the load).
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Version policy of the model, specified in the model configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VersionPolicy {
+    /// Only the latest `num_versions` versions of the model are served.
+    Latest { num_versions: u32 },
+    /// All versions of the model are served.
+    All,
+    /// Only the listed versions of the model are served.
+    Specific { versions: Vec<i64> },
+}
+
+impl Default for VersionPolicy {
+    /// Triton serves only the latest version of the model if no policy is specified.
+    fn default() -> Self {
+        VersionPolicy::Latest { num_versions: 1 }
+    }
+}
+
+impl VersionPolicy {
+    /// Parse version policy from the model configuration (as returned by [Server::model_config](crate::Server::model_config)). \
+    /// If the configuration has no "version_policy" field, [VersionPolicy::default] is returned.
+    pub fn from_config(config: &serde_json::Value) -> Result<Self, Error> {
+        match config.get("version_policy") {
+            None | Some(serde_json::Value::Null) => Ok(VersionPolicy::default()),
+            Some(policy) => VersionPolicy::from_json(policy),
+        }
+    }
+
+    /// Parse version policy from the json value of the "version_policy" field of the model configuration.
+    pub fn from_json(policy: &serde_json::Value) -> Result<Self, Error> {
+        let invalid = || {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("Can't parse model version_policy: {policy}"),
+            )
+        };
+
+        if let Some(latest) = policy.get("latest") {
+            let num_versions = match latest.get("num_versions") {
+                None => 1,
+                Some(num) => json_as_i64(num)
+                    .and_then(|num| u32::try_from(num).ok())
+                    .ok_or_else(invalid)?,
+            };
+            Ok(VersionPolicy::Latest { num_versions })
+        } else if policy.get("all").is_some() {
+            Ok(VersionPolicy::All)
+        } else if let Some(specific) = policy.get("specific") {
+            let versions = specific
+                .get("versions")
+                .and_then(|versions| versions.as_array())
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|version| json_as_i64(version).ok_or_else(invalid))
+                .collect::<Result<_, _>>()?;
+            Ok(VersionPolicy::Specific { versions })
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// Convert the policy to the json value of the "version_policy" field of the model configuration.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            VersionPolicy::Latest { num_versions } => {
+                serde_json::json!({"latest": {"num_versions": num_versions}})
+            }
+            VersionPolicy::All => serde_json::json!({"all": {}}),
+            VersionPolicy::Specific { versions } => {
+                serde_json::json!({"specific": {"versions": versions}})
+            }
+        }
+    }
+}
+
+/// Triton serializes 64-bit integers of the model config as strings.
+pub(crate) fn json_as_i64(value: &serde_json::Value) -> Option<i64> {
+    value
+        .as_i64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
 /// Model's metadata.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
 pub struct Model {
@@ -97,6 +215,13 @@ where
     })
 }
 
+fn de_opt_version<'de, D>(de: D) -> Result<Option<i64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    de_version(de).map(Some)
+}
+
 fn de_state<'de, D>(de: D) -> Result<State, D::Error>
 where
     D: Deserializer<'de>,
@@ -109,3 +234,31 @@ where
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_policy() {
+        let config = serde_json::json!({"name": "model"});
+        assert_eq!(
+            VersionPolicy::from_config(&config).unwrap(),
+            VersionPolicy::Latest { num_versions: 1 }
+        );
+
+        let config = serde_json::json!({"version_policy": {"specific": {"versions": ["1", 3]}}});
+        assert_eq!(
+            VersionPolicy::from_config(&config).unwrap(),
+            VersionPolicy::Specific {
+                versions: vec![1, 3]
+            }
+        );
+
+        let config = serde_json::json!({"version_policy": VersionPolicy::All.to_json()});
+        assert_eq!(
+            VersionPolicy::from_config(&config).unwrap(),
+            VersionPolicy::All
+        );
+    }
+}
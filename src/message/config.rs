@@ -0,0 +1,304 @@
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use super::{json_as_i64, VersionPolicy};
+use crate::{
+    error::{Error, ErrorCode},
+    memory::DataType,
+    options::InstanceGroupKind,
+};
+
+mod builder;
+
+pub use builder::ModelConfigBuilder;
+
+/// Typed model configuration (config.pbtxt). \
+/// Fields that are not described by this struct are kept in `other`,
+/// so [ModelConfig::from_json] followed by [ModelConfig::to_json] doesn't lose any setting of the model.
+///
+/// Can be obtained with [Server::typed_model_config](crate::Server::typed_model_config)
+/// and passed to [Parameter::from_config](crate::parameter::Parameter::from_config) after the modification.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelConfig {
+    pub name: String,
+    pub platform: String,
+    pub backend: String,
+    pub max_batch_size: i32,
+    /// None if the configuration has no policy ([VersionPolicy::default] is used by Triton).
+    #[serde(with = "version_policy", skip_serializing_if = "Option::is_none")]
+    pub version_policy: Option<VersionPolicy>,
+    pub input: Vec<TensorConfig>,
+    pub output: Vec<TensorConfig>,
+    pub instance_group: Vec<InstanceGroupConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dynamic_batching: Option<DynamicBatching>,
+    /// Optimization settings of the model (execution accelerators, cuda graphs, etc.) as is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimization: Option<Value>,
+    /// Other fields of the configuration.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// Input or output of the model configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TensorConfig {
+    pub name: String,
+    #[serde(with = "data_type")]
+    pub data_type: DataType,
+    /// Shape of the tensor without the batch dimension. -1 marks the dynamic dimension.
+    #[serde(with = "int64_vec")]
+    pub dims: Vec<i64>,
+    /// Other fields of the tensor (reshape, format, optional, etc.).
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Default for TensorConfig {
+    fn default() -> Self {
+        TensorConfig {
+            name: String::new(),
+            data_type: DataType::Invalid,
+            dims: Vec::new(),
+            other: Map::new(),
+        }
+    }
+}
+
+/// Instance group of the model configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceGroupConfig {
+    pub name: String,
+    #[serde(with = "kind")]
+    pub kind: InstanceGroupKind,
+    /// Number of the instances of the model on each device.
+    pub count: i32,
+    /// Devices of the instances. Empty means all available GPUs.
+    pub gpus: Vec<i32>,
+    /// Other fields of the instance group.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Default for InstanceGroupConfig {
+    fn default() -> Self {
+        InstanceGroupConfig {
+            name: String::new(),
+            kind: InstanceGroupKind::Auto,
+            count: 1,
+            gpus: Vec::new(),
+            other: Map::new(),
+        }
+    }
+}
+
+/// Dynamic batching settings of the model configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DynamicBatching {
+    pub preferred_batch_size: Vec<i32>,
+    #[serde(with = "int64")]
+    pub max_queue_delay_microseconds: i64,
+    pub preserve_ordering: bool,
+    /// Other fields of the dynamic batching (priority levels, queue policy, etc.).
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl ModelConfig {
+    /// Parse the model configuration, as returned by [Server::model_config](crate::Server::model_config)
+    /// or [load_config_as_json](crate::parameter::load_config_as_json).
+    pub fn from_json(config: &Value) -> Result<Self, Error> {
+        ModelConfig::deserialize(config).map_err(|err| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("Can't parse model config: {err}"),
+            )
+        })
+    }
+
+    /// Convert the configuration to the json value accepted by Triton.
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_else(|err| {
+            log::warn!("Error serializing model config: {err}");
+            Value::Null
+        })
+    }
+
+    /// Returns true if the response cache is enabled for the model. Check [ModelConfigBuilder::response_cache].
+    pub fn response_cache_enabled(&self) -> bool {
+        self.other
+            .get("response_cache")
+            .and_then(|cache| cache.get("enable"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    /// Get the input of the configuration by name.
+    pub fn input<N: AsRef<str>>(&self, name: N) -> Option<&TensorConfig> {
+        self.input.iter().find(|input| input.name == name.as_ref())
+    }
+
+    /// Get the output of the configuration by name.
+    pub fn output<N: AsRef<str>>(&self, name: N) -> Option<&TensorConfig> {
+        self.output
+            .iter()
+            .find(|output| output.name == name.as_ref())
+    }
+}
+
+impl From<ModelConfig> for Value {
+    fn from(config: ModelConfig) -> Self {
+        config.to_json()
+    }
+}
+
+impl From<&ModelConfig> for Value {
+    fn from(config: &ModelConfig) -> Self {
+        config.to_json()
+    }
+}
+
+mod version_policy {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        policy: &Option<VersionPolicy>,
+        ser: S,
+    ) -> Result<S::Ok, S::Error> {
+        policy.as_ref().map(VersionPolicy::to_json).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        de: D,
+    ) -> Result<Option<VersionPolicy>, D::Error> {
+        match Value::deserialize(de)? {
+            Value::Null => Ok(None),
+            policy => VersionPolicy::from_json(&policy)
+                .map(Some)
+                .map_err(|err| D::Error::custom(err.message())),
+        }
+    }
+}
+
+mod data_type {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(data_type: &DataType, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_str(data_type.as_config_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<DataType, D::Error> {
+        let name = String::deserialize(de)?;
+        DataType::try_from(name.as_str()).map_err(|err| D::Error::custom(err.message()))
+    }
+}
+
+mod kind {
+    use super::*;
+
+    const KINDS: [(InstanceGroupKind, &str); 4] = [
+        (InstanceGroupKind::Auto, "KIND_AUTO"),
+        (InstanceGroupKind::Cpu, "KIND_CPU"),
+        (InstanceGroupKind::Gpu, "KIND_GPU"),
+        (InstanceGroupKind::Model, "KIND_MODEL"),
+    ];
+
+    pub fn serialize<S: Serializer>(kind: &InstanceGroupKind, ser: S) -> Result<S::Ok, S::Error> {
+        let (_, name) = KINDS.iter().find(|(k, _)| k == kind).unwrap_or(&KINDS[0]);
+        ser.serialize_str(name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<InstanceGroupKind, D::Error> {
+        let name = String::deserialize(de)?;
+        KINDS
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(kind, _)| *kind)
+            .ok_or_else(|| {
+                D::Error::unknown_variant(
+                    &name,
+                    &["KIND_AUTO", "KIND_CPU", "KIND_GPU", "KIND_MODEL"],
+                )
+            })
+    }
+}
+
+/// Triton serializes 64-bit integers as strings, both strings and numbers are accepted.
+mod int64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &i64, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_i64(*value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<i64, D::Error> {
+        let value = Value::deserialize(de)?;
+        json_as_i64(&value).ok_or_else(|| D::Error::custom(format!("{value} is not an i64")))
+    }
+}
+
+mod int64_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[i64], ser: S) -> Result<S::Ok, S::Error> {
+        values.serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<i64>, D::Error> {
+        Vec::<Value>::deserialize(de)?
+            .iter()
+            .map(|value| {
+                json_as_i64(value).ok_or_else(|| D::Error::custom(format!("{value} is not an i64")))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let json = serde_json::json!({
+            "name": "detector",
+            "platform": "onnxruntime_onnx",
+            "max_batch_size": 8,
+            "version_policy": {"specific": {"versions": ["2"]}},
+            "input": [{"name": "images", "data_type": "TYPE_FP32", "dims": ["3", "-1", "-1"], "optional": false}],
+            "output": [{"name": "labels", "data_type": "TYPE_STRING", "dims": [1]}],
+            "instance_group": [{"name": "detector_0", "kind": "KIND_GPU", "count": 2, "gpus": [0]}],
+            "dynamic_batching": {"preferred_batch_size": [4, 8], "max_queue_delay_microseconds": "100"},
+            "sequence_batching": null,
+        });
+
+        let mut config = ModelConfig::from_json(&json).unwrap();
+        assert_eq!(
+            config.version_policy,
+            Some(VersionPolicy::Specific { versions: vec![2] })
+        );
+        let images = config.input("images").unwrap();
+        assert_eq!(images.data_type, DataType::Fp32);
+        assert_eq!(images.dims, [3, -1, -1]);
+        assert_eq!(config.output("labels").unwrap().data_type, DataType::Bytes);
+        assert_eq!(config.instance_group[0].kind, InstanceGroupKind::Gpu);
+        assert_eq!(
+            config
+                .dynamic_batching
+                .as_ref()
+                .unwrap()
+                .max_queue_delay_microseconds,
+            100
+        );
+        assert!(config.other.contains_key("sequence_batching"));
+
+        config.max_batch_size = 16;
+        let reparsed = ModelConfig::from_json(&config.to_json()).unwrap();
+        assert_eq!(reparsed, config);
+        assert_eq!(reparsed.to_json()["input"][0]["optional"], false);
+    }
+}
@@ -0,0 +1,252 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use super::{InstanceGroupConfig, ModelConfig, TensorConfig};
+use crate::{
+    memory::DataType, message::VersionPolicy, options::InstanceGroupKind, parameter::Parameter,
+    Error,
+};
+
+/// Builder of the [ModelConfig]. \
+/// Can start from scratch ([ModelConfigBuilder::new]) or patch the existing config
+/// ([ModelConfigBuilder::from_config], e.g. the one returned by [Server::typed_model_config](crate::Server::typed_model_config)).
+/// The result can be passed to [Server::load_model_with_parametrs](crate::Server::load_model_with_parametrs)
+/// as [ModelConfigBuilder::into_parameter]:
+/// ```ignore
+/// let config = ModelConfigBuilder::from_config(server.typed_model_config("detector", -1)?)
+///     .max_batch_size(16)
+///     .gpu_instances(2, [1])
+///     .dynamic_batching([8, 16], Duration::from_micros(500))
+///     .into_parameter()?;
+/// server.load_model_with_parametrs("detector", [config])?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ModelConfigBuilder {
+    config: ModelConfig,
+}
+
+impl ModelConfigBuilder {
+    /// Start the config of the model `name` from scratch.
+    pub fn new<N: AsRef<str>>(name: N) -> Self {
+        ModelConfigBuilder {
+            config: ModelConfig {
+                name: name.as_ref().to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Patch the existing `config`.
+    pub fn from_config(config: ModelConfig) -> Self {
+        ModelConfigBuilder { config }
+    }
+
+    /// Set the platform of the model, e.g. "tensorrt_plan" or "onnxruntime_onnx".
+    pub fn platform<P: AsRef<str>>(mut self, platform: P) -> Self {
+        self.config.platform = platform.as_ref().to_string();
+        self
+    }
+
+    /// Set the backend of the model, e.g. "python" or "onnxruntime".
+    pub fn backend<B: AsRef<str>>(mut self, backend: B) -> Self {
+        self.config.backend = backend.as_ref().to_string();
+        self
+    }
+
+    /// Set the max batch size of the model. 0 means the model doesn't support batching.
+    pub fn max_batch_size(mut self, max_batch_size: i32) -> Self {
+        self.config.max_batch_size = max_batch_size;
+        self
+    }
+
+    /// Set the version policy of the model.
+    pub fn version_policy(mut self, policy: VersionPolicy) -> Self {
+        self.config.version_policy = Some(policy);
+        self
+    }
+
+    /// Add the input to the config or replace the input with the same name. \
+    /// `dims`: shape without the batch dimension, -1 marks the dynamic dimension.
+    pub fn input<N: AsRef<str>, D: AsRef<[i64]>>(
+        mut self,
+        name: N,
+        data_type: DataType,
+        dims: D,
+    ) -> Self {
+        upsert_tensor(&mut self.config.input, name, data_type, dims);
+        self
+    }
+
+    /// Add the output to the config or replace the output with the same name. \
+    /// `dims`: shape without the batch dimension, -1 marks the dynamic dimension.
+    pub fn output<N: AsRef<str>, D: AsRef<[i64]>>(
+        mut self,
+        name: N,
+        data_type: DataType,
+        dims: D,
+    ) -> Self {
+        upsert_tensor(&mut self.config.output, name, data_type, dims);
+        self
+    }
+
+    /// Add the instance group to the config.
+    pub fn instance_group(mut self, group: InstanceGroupConfig) -> Self {
+        self.config.instance_group.push(group);
+        self
+    }
+
+    /// Add the group of `count` instances on each CPU.
+    pub fn cpu_instances(self, count: i32) -> Self {
+        self.instance_group(InstanceGroupConfig {
+            kind: InstanceGroupKind::Cpu,
+            count,
+            ..Default::default()
+        })
+    }
+
+    /// Add the group of `count` instances on each of the `gpus`. Empty `gpus` means all available GPUs.
+    pub fn gpu_instances<G: AsRef<[i32]>>(self, count: i32, gpus: G) -> Self {
+        self.instance_group(InstanceGroupConfig {
+            kind: InstanceGroupKind::Gpu,
+            count,
+            gpus: gpus.as_ref().to_vec(),
+            ..Default::default()
+        })
+    }
+
+    /// Remove all the instance groups of the config, e.g. before adding new ones to the patched config.
+    pub fn clear_instance_groups(mut self) -> Self {
+        self.config.instance_group.clear();
+        self
+    }
+
+    /// Pin all the instance groups of the config to the `gpus`. Groups of other kinds become GPU groups.
+    pub fn pin_gpus<G: AsRef<[i32]>>(mut self, gpus: G) -> Self {
+        for group in &mut self.config.instance_group {
+            group.kind = InstanceGroupKind::Gpu;
+            group.gpus = gpus.as_ref().to_vec();
+        }
+        self
+    }
+
+    /// Enable the dynamic batching. \
+    /// `preferred_batch_size`: batch sizes the batcher tries to create, may be empty. \
+    /// `max_queue_delay`: max time the request can wait in the queue for the batch to be formed.
+    pub fn dynamic_batching<P: AsRef<[i32]>>(
+        mut self,
+        preferred_batch_size: P,
+        max_queue_delay: Duration,
+    ) -> Self {
+        let batching = self
+            .config
+            .dynamic_batching
+            .get_or_insert_with(Default::default);
+        batching.preferred_batch_size = preferred_batch_size.as_ref().to_vec();
+        batching.max_queue_delay_microseconds = max_queue_delay.as_micros() as i64;
+        self
+    }
+
+    /// Disable the dynamic batching.
+    pub fn no_dynamic_batching(mut self) -> Self {
+        self.config.dynamic_batching = None;
+        self
+    }
+
+    /// Enable or disable the response cache of the model (the server cache is set with [crate::options::Options::response_cache_config]). \
+    /// Latency-sensitive requests skip the enabled cache with [crate::Request::disable_response_cache].
+    pub fn response_cache(mut self, enable: bool) -> Self {
+        self.config
+            .other
+            .insert("response_cache".to_string(), json!({ "enable": enable }));
+        self
+    }
+
+    /// Set the string parameter of the model ("parameters" field of the config) passed to the backend.
+    pub fn parameter<K: AsRef<str>, V: AsRef<str>>(mut self, key: K, value: V) -> Self {
+        let parameters = self
+            .config
+            .other
+            .entry("parameters")
+            .or_insert_with(|| json!({}));
+        if !parameters.is_object() {
+            *parameters = json!({});
+        }
+        parameters[key.as_ref()] = json!({"string_value": value.as_ref()});
+        self
+    }
+
+    /// Get the built config.
+    pub fn build(self) -> ModelConfig {
+        self.config
+    }
+
+    /// Get the "config" [Parameter] of the built config. Check [Parameter::from_config] for more info.
+    pub fn into_parameter(self) -> Result<Parameter, Error> {
+        Parameter::from_config(&self.config)
+    }
+}
+
+impl From<ModelConfig> for ModelConfigBuilder {
+    fn from(config: ModelConfig) -> Self {
+        ModelConfigBuilder::from_config(config)
+    }
+}
+
+fn upsert_tensor<N: AsRef<str>, D: AsRef<[i64]>>(
+    tensors: &mut Vec<TensorConfig>,
+    name: N,
+    data_type: DataType,
+    dims: D,
+) {
+    let tensor = match tensors.iter_mut().find(|t| t.name == name.as_ref()) {
+        Some(tensor) => tensor,
+        None => {
+            tensors.push(TensorConfig {
+                name: name.as_ref().to_string(),
+                ..Default::default()
+            });
+            tensors.last_mut().expect("tensor is pushed")
+        }
+    };
+    tensor.data_type = data_type;
+    tensor.dims = dims.as_ref().to_vec();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch() {
+        let base = ModelConfigBuilder::new("detector")
+            .platform("onnxruntime_onnx")
+            .input("images", DataType::Fp32, [3, -1, -1])
+            .cpu_instances(1)
+            .build();
+
+        let config = ModelConfigBuilder::from_config(base)
+            .max_batch_size(8)
+            .input("images", DataType::Fp16, [3, 640, 640])
+            .pin_gpus([1])
+            .dynamic_batching([4, 8], Duration::from_micros(200))
+            .parameter("threads", "4")
+            .response_cache(true)
+            .build();
+        assert!(config.response_cache_enabled());
+
+        assert_eq!(config.input.len(), 1);
+        assert_eq!(config.input[0].data_type, DataType::Fp16);
+        assert_eq!(config.instance_group[0].kind, InstanceGroupKind::Gpu);
+        assert_eq!(config.instance_group[0].gpus, [1]);
+
+        let json = config.to_json();
+        assert_eq!(json["max_batch_size"], 8);
+        assert_eq!(
+            json["dynamic_batching"]["max_queue_delay_microseconds"],
+            200
+        );
+        assert_eq!(json["instance_group"][0]["kind"], "KIND_GPU");
+        assert_eq!(json["parameters"]["threads"]["string_value"], "4");
+    }
+}
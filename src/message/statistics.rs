@@ -0,0 +1,257 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::de_version;
+use crate::error::{Error, ErrorCode};
+
+/// Statistics of the model version, as returned by [Server::typed_model_statistics](crate::Server::typed_model_statistics). \
+/// All the counters are accumulated since the model version was loaded,
+/// use [ModelStatistics::since] to get the statistics of a time window.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default)]
+pub struct ModelStatistics {
+    pub name: String,
+    #[serde(deserialize_with = "de_version")]
+    pub version: i64,
+    /// Time of the last inference in milliseconds since the epoch. 0 if there was no inference.
+    pub last_inference: u64,
+    /// Number of the inferences (including the ones of the batches). Doesn't include cache hits.
+    pub inference_count: u64,
+    /// Number of the model executions (a batch is executed once). Doesn't include cache hits.
+    pub execution_count: u64,
+    pub inference_stats: InferenceStatistics,
+    /// Statistics of the executions by batch size.
+    pub batch_stats: Vec<BatchStatistics>,
+}
+
+/// Number of the events and their total duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default)]
+pub struct DurationStatistics {
+    pub count: u64,
+    /// Total duration in nanoseconds.
+    pub ns: u64,
+}
+
+/// Statistics of the inference requests of the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default)]
+pub struct InferenceStatistics {
+    /// Successful requests, duration is from the request arrival to the response.
+    pub success: DurationStatistics,
+    /// Failed requests.
+    pub fail: DurationStatistics,
+    /// Time the requests waited in the scheduling queue.
+    pub queue: DurationStatistics,
+    /// Time of the inputs preparation.
+    pub compute_input: DurationStatistics,
+    /// Time of the model execution.
+    pub compute_infer: DurationStatistics,
+    /// Time of the outputs extraction.
+    pub compute_output: DurationStatistics,
+    /// Requests served from the response cache.
+    pub cache_hit: DurationStatistics,
+    /// Requests missed the response cache, duration includes the cache lookup and insertion.
+    pub cache_miss: DurationStatistics,
+}
+
+/// Statistics of the model executions with the same batch size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default)]
+pub struct BatchStatistics {
+    pub batch_size: u64,
+    pub compute_input: DurationStatistics,
+    pub compute_infer: DurationStatistics,
+    pub compute_output: DurationStatistics,
+}
+
+impl DurationStatistics {
+    /// Total duration of the events.
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.ns)
+    }
+
+    /// Average duration of the event. None if there were no events.
+    pub fn average(&self) -> Option<Duration> {
+        (self.count != 0).then(|| Duration::from_nanos(self.ns / self.count))
+    }
+
+    /// Statistics of the events happened since the `earlier` statistics were taken.
+    pub fn since(&self, earlier: &DurationStatistics) -> DurationStatistics {
+        DurationStatistics {
+            count: self.count.saturating_sub(earlier.count),
+            ns: self.ns.saturating_sub(earlier.ns),
+        }
+    }
+}
+
+impl InferenceStatistics {
+    /// Statistics of the requests happened since the `earlier` statistics were taken.
+    pub fn since(&self, earlier: &InferenceStatistics) -> InferenceStatistics {
+        InferenceStatistics {
+            success: self.success.since(&earlier.success),
+            fail: self.fail.since(&earlier.fail),
+            queue: self.queue.since(&earlier.queue),
+            compute_input: self.compute_input.since(&earlier.compute_input),
+            compute_infer: self.compute_infer.since(&earlier.compute_infer),
+            compute_output: self.compute_output.since(&earlier.compute_output),
+            cache_hit: self.cache_hit.since(&earlier.cache_hit),
+            cache_miss: self.cache_miss.since(&earlier.cache_miss),
+        }
+    }
+}
+
+impl ModelStatistics {
+    /// Parse the statistics of the model versions, as returned by [Server::model_statistics](crate::Server::model_statistics).
+    pub fn from_json(statistics: &Value) -> Result<Vec<Self>, Error> {
+        #[derive(Deserialize)]
+        struct Statistics {
+            #[serde(default)]
+            model_stats: Vec<ModelStatistics>,
+        }
+
+        Statistics::deserialize(statistics)
+            .map(|statistics| statistics.model_stats)
+            .map_err(|err| {
+                Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Can't parse model statistics: {err}"),
+                )
+            })
+    }
+
+    /// Time of the last inference. None if there was no inference.
+    pub fn last_inference_time(&self) -> Option<SystemTime> {
+        (self.last_inference != 0).then(|| UNIX_EPOCH + Duration::from_millis(self.last_inference))
+    }
+
+    /// Number of the successful requests.
+    pub fn success_count(&self) -> u64 {
+        self.inference_stats.success.count
+    }
+
+    /// Number of the failed requests.
+    pub fn failure_count(&self) -> u64 {
+        self.inference_stats.fail.count
+    }
+
+    /// Average time the request waits in the scheduling queue. None if there were no requests.
+    pub fn avg_queue_latency(&self) -> Option<Duration> {
+        self.inference_stats.queue.average()
+    }
+
+    /// Average time of the request computation (inputs preparation, execution and outputs extraction).
+    /// None if there were no computed requests.
+    pub fn avg_compute_latency(&self) -> Option<Duration> {
+        let stats = &self.inference_stats;
+        let count = stats.compute_infer.count;
+        let ns = stats.compute_input.ns + stats.compute_infer.ns + stats.compute_output.ns;
+        (count != 0).then(|| Duration::from_nanos(ns / count))
+    }
+
+    /// Average time from the request arrival to the successful response. None if there were no successful requests.
+    pub fn avg_request_latency(&self) -> Option<Duration> {
+        self.inference_stats.success.average()
+    }
+
+    /// Average number of the requests in the executed batch. None if there were no executions.
+    pub fn avg_batch_size(&self) -> Option<f64> {
+        (self.execution_count != 0)
+            .then(|| self.inference_count as f64 / self.execution_count as f64)
+    }
+
+    /// Fraction of the requests served from the response cache. None if the cache was never looked up.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let hits = self.inference_stats.cache_hit.count;
+        let total = hits + self.inference_stats.cache_miss.count;
+        (total != 0).then(|| hits as f64 / total as f64)
+    }
+
+    /// Statistics of the executions with the batch size `batch_size`.
+    pub fn batch(&self, batch_size: u64) -> Option<&BatchStatistics> {
+        self.batch_stats
+            .iter()
+            .find(|stats| stats.batch_size == batch_size)
+    }
+
+    /// Statistics of the requests happened since the `earlier` statistics of the same model version were taken. \
+    /// Useful to get the latencies and throughput of the last time window, e.g. for the autoscaling.
+    pub fn since(&self, earlier: &ModelStatistics) -> ModelStatistics {
+        let batch_stats = self
+            .batch_stats
+            .iter()
+            .map(|stats| match earlier.batch(stats.batch_size) {
+                Some(earlier) => BatchStatistics {
+                    batch_size: stats.batch_size,
+                    compute_input: stats.compute_input.since(&earlier.compute_input),
+                    compute_infer: stats.compute_infer.since(&earlier.compute_infer),
+                    compute_output: stats.compute_output.since(&earlier.compute_output),
+                },
+                None => *stats,
+            })
+            .collect();
+
+        ModelStatistics {
+            name: self.name.clone(),
+            version: self.version,
+            last_inference: self.last_inference,
+            inference_count: self.inference_count.saturating_sub(earlier.inference_count),
+            execution_count: self.execution_count.saturating_sub(earlier.execution_count),
+            inference_stats: self.inference_stats.since(&earlier.inference_stats),
+            batch_stats,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let json = serde_json::json!({"model_stats": [{
+            "name": "detector",
+            "version": "2",
+            "last_inference": 1700000000000u64,
+            "inference_count": 12,
+            "execution_count": 4,
+            "inference_stats": {
+                "success": {"count": 12, "ns": 120000},
+                "fail": {"count": 1, "ns": 500},
+                "queue": {"count": 12, "ns": 24000},
+                "compute_input": {"count": 4, "ns": 4000},
+                "compute_infer": {"count": 4, "ns": 32000},
+                "compute_output": {"count": 4, "ns": 4000},
+                "cache_hit": {"count": 0, "ns": 0},
+                "cache_miss": {"count": 0, "ns": 0}
+            },
+            "batch_stats": [{
+                "batch_size": 3,
+                "compute_input": {"count": 4, "ns": 4000},
+                "compute_infer": {"count": 4, "ns": 32000},
+                "compute_output": {"count": 4, "ns": 4000}
+            }],
+            "memory_usage": []
+        }]});
+
+        let stats = ModelStatistics::from_json(&json).unwrap();
+        let stats = &stats[0];
+        assert_eq!(stats.version, 2);
+        assert_eq!(stats.failure_count(), 1);
+        assert_eq!(stats.avg_queue_latency(), Some(Duration::from_micros(2)));
+        assert_eq!(stats.avg_compute_latency(), Some(Duration::from_micros(10)));
+        assert_eq!(stats.avg_batch_size(), Some(3.));
+        assert_eq!(stats.cache_hit_ratio(), None);
+        assert_eq!(stats.batch(3).unwrap().compute_infer.count, 4);
+
+        let window = stats.since(&ModelStatistics {
+            inference_count: 6,
+            execution_count: 2,
+            ..stats.clone()
+        });
+        assert_eq!(window.inference_count, 6);
+        assert_eq!(window.avg_queue_latency(), None);
+    }
+}
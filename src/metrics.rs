@@ -1,6 +1,14 @@
-use std::ptr::null;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    os::raw::c_char,
+    ptr::null,
+    time::{Duration, SystemTime},
+};
 
-use crate::{sys, Error};
+use crate::{
+    error::{Error, ErrorCode},
+    sys,
+};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(u32)]
@@ -18,10 +26,7 @@ pub struct Metrics(pub(crate) *mut sys::TRITONSERVER_Metrics);
 impl Metrics {
     /// Get a buffer containing the metrics in the specified format.
     pub fn formatted(&self, format: Format) -> Result<&[u8], Error> {
-        #[cfg(target_arch = "x86_64")]
-        let mut ptr = null::<i8>();
-        #[cfg(target_arch = "aarch64")]
-        let mut ptr = null::<u8>();
+        let mut ptr = null::<c_char>();
         let mut size: usize = 0;
 
         triton_call!(sys::TRITONSERVER_MetricsFormatted(
@@ -34,6 +39,390 @@ impl Metrics {
         assert!(!ptr.is_null());
         Ok(unsafe { std::slice::from_raw_parts(ptr as *const u8, size) })
     }
+
+    /// Get the metrics in the specified format as an owned string. \
+    /// Unlike [Metrics::formatted], the result doesn't borrow the metrics object, so it can be kept across await points.
+    pub fn formatted_owned(&self, format: Format) -> Result<String, Error> {
+        String::from_utf8(self.formatted(format)?.to_vec())
+            .map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))
+    }
+
+    /// Get the metrics parsed into metric families by their names. \
+    /// For example, `map["nv_inference_request_failure"]` contains the failed requests count per model and version.
+    pub fn to_map(&self) -> Result<HashMap<String, MetricFamily>, Error> {
+        let text = std::str::from_utf8(self.formatted(Format::Prometheus)?)
+            .map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))?;
+        parse_prometheus(text)
+    }
+}
+
+/// Type of the metric family.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+    #[default]
+    Untyped,
+}
+
+/// Single sample of the metric family.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Metric {
+    /// Name of the sample. Same as the family name except for histograms and summaries
+    /// (`_bucket`, `_sum` and `_count` suffixes).
+    pub name: String,
+    /// Labels of the sample, e.g. `model` and `version`.
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+    /// Timestamp in milliseconds, if present.
+    pub timestamp: Option<i64>,
+}
+
+impl Metric {
+    /// Get the value of the label `name`.
+    pub fn label<N: AsRef<str>>(&self, name: N) -> Option<&str> {
+        self.labels.get(name.as_ref()).map(String::as_str)
+    }
+}
+
+/// Metric family: all the samples of one metric.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MetricFamily {
+    pub name: String,
+    pub help: String,
+    pub metric_type: MetricType,
+    pub metrics: Vec<Metric>,
+}
+
+impl MetricFamily {
+    /// Get the samples having all the `labels` with specified values. \
+    /// `labels`: pairs of label name and value, e.g. `[("model", "resnet")]`.
+    pub fn filter<'a>(&'a self, labels: &'a [(&str, &str)]) -> impl Iterator<Item = &'a Metric> {
+        self.metrics.iter().filter(move |metric| {
+            labels
+                .iter()
+                .all(|(name, value)| metric.label(name) == Some(*value))
+        })
+    }
+
+    /// Get the sum of values of the samples having all the `labels` with specified values.
+    pub fn sum(&self, labels: &[(&str, &str)]) -> f64 {
+        self.filter(labels).map(|metric| metric.value).sum()
+    }
+}
+
+/// Per-model inference counters of Triton. Check [crate::Server::model_metrics].
+///
+/// Durations are cumulative over all the requests (or executions) counted,
+/// e.g. the mean queue time is `queue_duration / success`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ModelMetrics {
+    /// Number of the successful inference requests, all batch sizes.
+    pub success: u64,
+    /// Number of the failed inference requests, all batch sizes and reasons.
+    pub failure: u64,
+    /// Number of the inferences performed (a batch of `n` counts as `n`).
+    pub inference_count: u64,
+    /// Number of the model executions (a batch counts as one).
+    pub execution_count: u64,
+    /// Cumulative end-to-end duration of the requests.
+    pub request_duration: Duration,
+    /// Cumulative time the requests waited in the scheduling queue.
+    pub queue_duration: Duration,
+    /// Cumulative time the model spent on processing the inputs.
+    pub compute_input_duration: Duration,
+    /// Cumulative time the model spent on the inference.
+    pub compute_infer_duration: Duration,
+    /// Cumulative time the model spent on processing the outputs.
+    pub compute_output_duration: Duration,
+}
+
+impl ModelMetrics {
+    /// Collect the counters of the `model` from the metric families (check [Metrics::to_map]). \
+    /// `version`: version of the model. None means the sum over all the versions. \
+    /// Missing families (e.g. Triton started with the metrics disabled) leave the counters zero.
+    pub fn from_families<M: AsRef<str>>(
+        families: &HashMap<String, MetricFamily>,
+        model: M,
+        version: Option<i64>,
+    ) -> Self {
+        let version = version.map(|version| version.to_string());
+        let mut labels = vec![("model", model.as_ref())];
+        if let Some(version) = &version {
+            labels.push(("version", version));
+        }
+        let sum = |name: &str| families.get(name).map_or(0., |family| family.sum(&labels));
+        let count = |name: &str| sum(name) as u64;
+        let duration = |name: &str| Duration::from_micros(sum(name) as u64);
+
+        ModelMetrics {
+            success: count("nv_inference_request_success"),
+            failure: count("nv_inference_request_failure"),
+            inference_count: count("nv_inference_count"),
+            execution_count: count("nv_inference_exec_count"),
+            request_duration: duration("nv_inference_request_duration_us"),
+            queue_duration: duration("nv_inference_queue_duration_us"),
+            compute_input_duration: duration("nv_inference_compute_input_duration_us"),
+            compute_infer_duration: duration("nv_inference_compute_infer_duration_us"),
+            compute_output_duration: duration("nv_inference_compute_output_duration_us"),
+        }
+    }
+}
+
+/// Statistics of the model in the [ServerStats] snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelStats {
+    /// Cumulative counters of the model summed over its versions.
+    pub metrics: ModelMetrics,
+    /// Number of the requests waiting in the scheduling queue.
+    pub pending_requests: u64,
+    /// Successful and failed requests per second since the previous snapshot.
+    pub request_rate: f64,
+    /// Inferences per second since the previous snapshot (a batch of `n` counts as `n`).
+    pub inference_rate: f64,
+    /// Mean queue time of the requests finished since the previous snapshot.
+    pub mean_queue_time: Duration,
+}
+
+/// Statistics of the GPU in the [ServerStats] snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GpuStats {
+    /// Utilization in the range 0..=1.
+    pub utilization: f64,
+    /// Used memory in bytes.
+    pub memory_used: u64,
+    /// Total memory in bytes.
+    pub memory_total: u64,
+    /// Power usage in watts.
+    pub power_usage: f64,
+}
+
+/// Typed snapshot of the server metrics. Check [crate::Server::spawn_stats_sampler].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerStats {
+    /// Time of the snapshot. None for the initial value of the sampler.
+    pub sampled_at: Option<SystemTime>,
+    /// Statistics of the models by name.
+    pub models: HashMap<String, ModelStats>,
+    /// Statistics of the GPUs by UUID.
+    pub gpus: HashMap<String, GpuStats>,
+}
+
+impl ServerStats {
+    /// Build the snapshot from the metric families (check [Metrics::to_map]) sampled at `sampled_at`. \
+    /// The rates are computed against the `previous` snapshot; they are zero without it.
+    pub fn from_families(
+        families: &HashMap<String, MetricFamily>,
+        sampled_at: SystemTime,
+        previous: Option<&ServerStats>,
+    ) -> Self {
+        let label_values = |family: &str, label: &str| {
+            families
+                .get(family)
+                .into_iter()
+                .flat_map(|family| &family.metrics)
+                .filter_map(|metric| metric.label(label).map(str::to_string))
+                .collect::<BTreeSet<_>>()
+        };
+        let elapsed = previous
+            .and_then(|previous| sampled_at.duration_since(previous.sampled_at?).ok())
+            .filter(|elapsed| !elapsed.is_zero());
+
+        let models = label_values("nv_inference_request_success", "model")
+            .into_iter()
+            .map(|model| {
+                let metrics = ModelMetrics::from_families(families, &model, None);
+                let pending_requests = families
+                    .get("nv_inference_pending_request_count")
+                    .map_or(0., |family| family.sum(&[("model", &model)]))
+                    as u64;
+                let mut stats = ModelStats {
+                    metrics,
+                    pending_requests,
+                    ..Default::default()
+                };
+                let before = previous.and_then(|previous| previous.models.get(&model));
+                if let (Some(before), Some(elapsed)) = (before, elapsed) {
+                    let before = before.metrics;
+                    let requests = (metrics.success + metrics.failure)
+                        .saturating_sub(before.success + before.failure);
+                    stats.request_rate = requests as f64 / elapsed.as_secs_f64();
+                    stats.inference_rate = metrics
+                        .inference_count
+                        .saturating_sub(before.inference_count)
+                        as f64
+                        / elapsed.as_secs_f64();
+                    let finished = metrics.success.saturating_sub(before.success);
+                    if finished > 0 {
+                        stats.mean_queue_time =
+                            metrics.queue_duration.saturating_sub(before.queue_duration)
+                                / finished as u32;
+                    }
+                }
+                (model, stats)
+            })
+            .collect();
+
+        let gpus = label_values("nv_gpu_memory_total_bytes", "gpu_uuid")
+            .into_iter()
+            .map(|gpu| {
+                let value = |name: &str| {
+                    families
+                        .get(name)
+                        .map_or(0., |family| family.sum(&[("gpu_uuid", &gpu)]))
+                };
+                let stats = GpuStats {
+                    utilization: value("nv_gpu_utilization"),
+                    memory_used: value("nv_gpu_memory_used_bytes") as u64,
+                    memory_total: value("nv_gpu_memory_total_bytes") as u64,
+                    power_usage: value("nv_gpu_power_usage"),
+                };
+                (gpu, stats)
+            })
+            .collect();
+
+        ServerStats {
+            sampled_at: Some(sampled_at),
+            models,
+            gpus,
+        }
+    }
+}
+
+fn parse_error<S: AsRef<str>>(line: &str, message: S) -> Error {
+    Error::new(
+        ErrorCode::Internal,
+        format!("Can't parse metrics line \"{line}\": {}", message.as_ref()),
+    )
+}
+
+/// Parse text in Prometheus format into metric families by their names.
+pub fn parse_prometheus(text: &str) -> Result<HashMap<String, MetricFamily>, Error> {
+    let mut families = HashMap::<String, MetricFamily>::new();
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut parts = comment.trim_start().splitn(3, ' ');
+            let (kind, name, rest) = match (parts.next(), parts.next()) {
+                (Some(kind), Some(name)) => (kind, name, parts.next().unwrap_or("")),
+                _ => continue,
+            };
+            let family = families
+                .entry(name.to_string())
+                .or_insert_with(|| MetricFamily {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+            match kind {
+                "HELP" => family.help = unescape(rest),
+                "TYPE" => {
+                    family.metric_type = match rest.trim() {
+                        "counter" => MetricType::Counter,
+                        "gauge" => MetricType::Gauge,
+                        "histogram" => MetricType::Histogram,
+                        "summary" => MetricType::Summary,
+                        _ => MetricType::Untyped,
+                    }
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        let metric = parse_sample(line)?;
+        let family_name = ["_bucket", "_sum", "_count"]
+            .iter()
+            .filter_map(|suffix| metric.name.strip_suffix(suffix))
+            .find(|base| {
+                families.get(*base).is_some_and(|family| {
+                    matches!(
+                        family.metric_type,
+                        MetricType::Histogram | MetricType::Summary
+                    )
+                })
+            })
+            .unwrap_or(&metric.name)
+            .to_string();
+        families
+            .entry(family_name.clone())
+            .or_insert_with(|| MetricFamily {
+                name: family_name,
+                ..Default::default()
+            })
+            .metrics
+            .push(metric);
+    }
+
+    Ok(families)
+}
+
+fn parse_sample(line: &str) -> Result<Metric, Error> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .ok_or_else(|| parse_error(line, "no value"))?;
+    let name = line[..name_end].to_string();
+    let mut rest = &line[name_end..];
+
+    let mut labels = BTreeMap::new();
+    if let Some(label_text) = rest.strip_prefix('{') {
+        rest = label_text;
+        loop {
+            rest = rest.trim_start_matches([' ', ',']);
+            if let Some(after) = rest.strip_prefix('}') {
+                rest = after;
+                break;
+            }
+            let (label, after) = rest
+                .split_once('=')
+                .ok_or_else(|| parse_error(line, "invalid label"))?;
+            let after = after
+                .trim_start()
+                .strip_prefix('"')
+                .ok_or_else(|| parse_error(line, "label value is not quoted"))?;
+
+            let mut value = String::new();
+            let mut chars = after.char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, c)) => value.push(c),
+                        None => return Err(parse_error(line, "unterminated label value")),
+                    },
+                    Some((_, c)) => value.push(c),
+                    None => return Err(parse_error(line, "unterminated label value")),
+                }
+            };
+            labels.insert(label.trim().to_string(), value);
+            rest = &after[end + 1..];
+        }
+    }
+
+    let mut parts = rest.split_whitespace();
+    let value = parts
+        .next()
+        .ok_or_else(|| parse_error(line, "no value"))?
+        .parse::<f64>()
+        .map_err(|err| parse_error(line, err.to_string()))?;
+    let timestamp = parts
+        .next()
+        .map(|timestamp| timestamp.parse::<i64>())
+        .transpose()
+        .map_err(|err| parse_error(line, err.to_string()))?;
+
+    Ok(Metric {
+        name,
+        labels,
+        value,
+        timestamp,
+    })
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", "\n").replace("\\\\", "\\")
 }
 
 impl Drop for Metrics {
@@ -45,3 +434,104 @@ impl Drop for Metrics {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text = r#"
+# HELP nv_inference_request_success Number of successful inference requests, all batch sizes
+# TYPE nv_inference_request_success counter
+nv_inference_request_success{model="simple",version="1"} 12
+nv_inference_request_success{model="other",version="2"} 3
+# HELP nv_inference_request_failure Number of failed inference requests, all batch sizes
+# TYPE nv_inference_request_failure counter
+nv_inference_request_failure{model="simple",reason="OTHER",version="1"} 2
+# TYPE nv_latency histogram
+nv_latency_bucket{model="simple",le="+Inf"} 4
+nv_latency_sum{model="simple"} 1.5e3
+nv_latency_count{model="simple"} 4
+nv_unknown{path="a\"b"} NaN 1700000000
+"#;
+        let map = parse_prometheus(text).unwrap();
+
+        let success = &map["nv_inference_request_success"];
+        assert_eq!(success.metric_type, MetricType::Counter);
+        assert_eq!(success.metrics.len(), 2);
+        assert_eq!(success.sum(&[("model", "simple")]), 12.);
+        assert_eq!(map["nv_inference_request_failure"].sum(&[]), 2.);
+
+        let latency = &map["nv_latency"];
+        assert_eq!(latency.metrics.len(), 3);
+        assert_eq!(latency.metrics[0].label("le"), Some("+Inf"));
+        assert_eq!(latency.metrics[1].value, 1500.);
+
+        let unknown = &map["nv_unknown"].metrics[0];
+        assert_eq!(unknown.label("path"), Some("a\"b"));
+        assert!(unknown.value.is_nan());
+        assert_eq!(unknown.timestamp, Some(1700000000));
+    }
+
+    #[test]
+    fn model_metrics() {
+        let text = r#"
+nv_inference_request_success{model="simple",version="1"} 12
+nv_inference_request_success{model="simple",version="2"} 3
+nv_inference_request_failure{model="simple",reason="OTHER",version="1"} 2
+nv_inference_request_failure{model="simple",reason="BACKEND",version="1"} 1
+nv_inference_queue_duration_us{model="simple",version="1"} 1500
+nv_inference_queue_duration_us{model="other",version="1"} 700
+"#;
+        let map = parse_prometheus(text).unwrap();
+
+        let all = ModelMetrics::from_families(&map, "simple", None);
+        assert_eq!(all.success, 15);
+        assert_eq!(all.failure, 3);
+        assert_eq!(all.queue_duration, Duration::from_micros(1500));
+        assert_eq!(all.compute_infer_duration, Duration::ZERO);
+
+        let second = ModelMetrics::from_families(&map, "simple", Some(2));
+        assert_eq!(second.success, 3);
+        assert_eq!(second.failure, 0);
+        assert_eq!(
+            ModelMetrics::from_families(&map, "missing", None),
+            ModelMetrics::default()
+        );
+    }
+
+    #[test]
+    fn server_stats() {
+        let sample = |success: u32, queue_us: u32| {
+            parse_prometheus(&format!(
+                r#"
+nv_inference_request_success{{model="simple",version="1"}} {success}
+nv_inference_count{{model="simple",version="1"}} {}
+nv_inference_queue_duration_us{{model="simple",version="1"}} {queue_us}
+nv_inference_pending_request_count{{model="simple",version="1"}} 3
+nv_gpu_utilization{{gpu_uuid="GPU-0"}} 0.5
+nv_gpu_memory_total_bytes{{gpu_uuid="GPU-0"}} 1000
+nv_gpu_memory_used_bytes{{gpu_uuid="GPU-0"}} 250
+"#,
+                success * 2
+            ))
+            .unwrap()
+        };
+        let start = SystemTime::UNIX_EPOCH;
+        let first = ServerStats::from_families(&sample(10, 1000), start, None);
+        assert_eq!(first.models["simple"].pending_requests, 3);
+        assert_eq!(first.models["simple"].request_rate, 0.);
+        assert_eq!(first.gpus["GPU-0"].memory_used, 250);
+
+        let second = ServerStats::from_families(
+            &sample(30, 5000),
+            start + Duration::from_secs(2),
+            Some(&first),
+        );
+        let simple = second.models["simple"];
+        assert_eq!(simple.request_rate, 10.);
+        assert_eq!(simple.inference_rate, 20.);
+        assert_eq!(simple.mean_queue_time, Duration::from_micros(200));
+    }
+}
@@ -1,11 +1,21 @@
-use std::{ffi::CString, os::unix::prelude::OsStrExt, path::Path, ptr::null_mut, time::Duration};
+mod cli;
+mod config;
+pub use config::{OptionsConfig, RateLimiterResource};
+
+use std::{
+    borrow::Cow, ffi::CString, fmt, os::unix::prelude::OsStrExt, path::Path, ptr::null_mut,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    compat,
     error::{Error, ErrorCode},
     path_to_cstring, sys, to_cstring,
 };
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u32)]
 /// Triton server control model modes.
 pub enum Control {
@@ -23,7 +33,7 @@ pub enum Control {
 }
 
 /// Triton server rate limit modes.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum Limit {
     // The rate limiting is turned off and the inference gets executed whenever an instance is available.
@@ -40,7 +50,7 @@ pub enum Limit {
 /// how the timestamp for a log entry is represented.
 /// Messages are serialized according to JSON\n encoding rules by default.
 /// This behavior can be disabled by setting the environment variable TRITON_SERVER_ESCAPE_LOG_MESSAGES to "0".
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum LogFormat {
     /// `<level><month><day><hour>:<min>:<sec>.<usec> <pid> <file>:<line>] <msg>`
@@ -77,37 +87,116 @@ pub enum InstanceGroupKind {
     Model = sys::TRITONSERVER_instancegroupkind_enum_TRITONSERVER_INSTANCEGROUPKIND_MODEL,
 }
 
-/// Triton server creation options.
-#[derive(Debug)]
-pub struct Options(pub(crate) *mut sys::TRITONSERVER_ServerOptions);
+/// Exit timeout Triton uses if [Options::exit_timeout] is not set.
+const DEFAULT_EXIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Triton server creation options. \
+/// The options that were set are recorded: check [Options::settings] and [Options::config]. \
+/// Options can be created from the configuration file with [Options::from_config]
+/// and are serialized as the [OptionsConfig] with the secrets redacted (check [OptionsConfig::redacted]).
+pub struct Options {
+    pub(crate) ptr: *mut sys::TRITONSERVER_ServerOptions,
+    /// Options that were set, in order of setting.
+    settings: Vec<(&'static str, String)>,
+    /// Options that were set, as the config.
+    config: OptionsConfig,
+    /// Defer the server start. Check [Options::lazy_startup].
+    pub(crate) lazy_startup: bool,
+    /// Used by [crate::Server::shutdown] to wait for the in-flight inferences.
+    pub(crate) exit_timeout: Duration,
+    /// Redirect the logs on the server start. Check [Options::log_to_facade].
+    pub(crate) log_to_facade: bool,
+}
 
 impl Options {
     /// Create a new server options object. \
-    /// The path must be the full absolute path to the model repository. \
-    /// This function can be called multiple times with different paths to set multiple model repositories. \
+    /// The path must be the full absolute path to the model repository
+    /// or the URI of the remote repository (check [crate::repository] module). \
+    /// Use [Options::model_repository] to set multiple model repositories. \
     /// Note that if a model is not unique across all model repositories at any time, the model will not be available.
     pub fn new<P: AsRef<Path>>(repository: P) -> Result<Self, Error> {
-        let path = path_to_cstring(repository)?;
+        let path = repository_to_cstring(repository)?;
         let mut this = null_mut::<sys::TRITONSERVER_ServerOptions>();
 
         triton_call!(sys::TRITONSERVER_ServerOptionsNew(&mut this as *mut _))?;
 
         assert!(!this.is_null());
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetModelRepositoryPath(
-                this,
-                path.as_bytes().as_ptr() as *const _,
-            ),
-            Self(this)
+            sys::TRITONSERVER_ServerOptionsSetModelRepositoryPath(this, path.as_ptr()),
+            Self {
+                ptr: this,
+                settings: vec![("model_repository", path.to_string_lossy().into_owned())],
+                config: OptionsConfig {
+                    model_repository: vec![path.to_string_lossy().into_owned()],
+                    ..Default::default()
+                },
+                lazy_startup: false,
+                exit_timeout: DEFAULT_EXIT_TIMEOUT,
+                log_to_facade: false,
+            }
+        )
+    }
+
+    /// Add the model repository to a server options. \
+    /// `repository`: the full absolute path to the model repository or the URI of the remote repository (check [crate::repository] module).
+    pub fn model_repository<P: AsRef<Path>>(&mut self, repository: P) -> Result<&mut Self, Error> {
+        let path = repository_to_cstring(repository)?;
+        triton_call!(
+            sys::TRITONSERVER_ServerOptionsSetModelRepositoryPath(self.ptr, path.as_ptr()),
+            self.record("model_repository", path.to_string_lossy(), |config| {
+                config
+                    .model_repository
+                    .push(path.to_string_lossy().into_owned())
+            })
         )
     }
 
+    /// Get the options that were set, in order of setting, as (option name, value) pairs. \
+    /// Option name is the name of the [Options] method that set it. Options that can be set multiple times
+    /// (for example, `startup_model` or `backend_config`) have a pair for each call. \
+    /// The values are not redacted, so they may hold secrets (e.g. `backend_config` credentials);
+    /// [Debug](fmt::Debug) of the options shows them redacted.
+    pub fn settings(&self) -> &[(&'static str, String)] {
+        &self.settings
+    }
+
+    /// Get the options that were set as the config: [Options::from_config] creates the same options from it. \
+    /// The values are not redacted, unlike the [serialized](Serialize) options.
+    pub fn config(&self) -> &OptionsConfig {
+        &self.config
+    }
+
+    /// Record the set option: `value` to the [Options::settings] and `update` to the [Options::config].
+    fn record<V, U>(&mut self, option: &'static str, value: V, update: U) -> &mut Self
+    where
+        V: ToString,
+        U: FnOnce(&mut OptionsConfig),
+    {
+        self.settings.push((option, value.to_string()));
+        update(&mut self.config);
+        self
+    }
+
+    /// Defer the start of Triton server created with these options
+    /// until the first call that needs it (e.g. [crate::Server::create_request]) or [crate::Server::ensure_started]. \
+    /// It cuts the startup time of the tools that may finish without inference. Default is false.
+    ///
+    /// **Note**: with lazy startup the errors of the server creation are returned from the call that started the server.
+    pub fn lazy_startup(&mut self, lazy: bool) -> &mut Self {
+        self.lazy_startup = lazy;
+        self.record("lazy_startup", lazy, |config| {
+            config.lazy_startup = Some(lazy)
+        })
+    }
+
     /// Set the textual ID for the server in a server options. The ID is a name that identifies the server.
     pub fn server_id<I: AsRef<str>>(&mut self, id: I) -> Result<&mut Self, Error> {
         let id = to_cstring(id)?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetServerId(self.0, id.as_ptr()),
-            self
+            sys::TRITONSERVER_ServerOptionsSetServerId(self.ptr, id.as_ptr()),
+            self.record("server_id", id.to_string_lossy(), |config| {
+                config.server_id = Some(id.to_string_lossy().into_owned())
+            })
         )
     }
 
@@ -118,8 +207,12 @@ impl Options {
     pub fn startup_model<S: AsRef<str>>(&mut self, model: S) -> Result<&mut Self, Error> {
         let model = to_cstring(model)?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetStartupModel(self.0, model.as_ptr()),
-            self
+            sys::TRITONSERVER_ServerOptionsSetStartupModel(self.ptr, model.as_ptr()),
+            self.record("startup_model", model.to_string_lossy(), |config| {
+                config
+                    .startup_model
+                    .push(model.to_string_lossy().into_owned())
+            })
         )
     }
 
@@ -136,16 +229,20 @@ impl Options {
     /// The corresponding model control APIs must be called to load / unload a model in the model repository.
     pub fn model_control_mode(&mut self, mode: Control) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetModelControlMode(self.0, mode as _),
-            self
+            sys::TRITONSERVER_ServerOptionsSetModelControlMode(self.ptr, mode as _),
+            self.record("model_control_mode", format!("{mode:?}"), |config| {
+                config.model_control_mode = Some(mode)
+            })
         )
     }
 
     /// Enable or disable strict model configuration handling in a server options.
     pub fn strict_model_config(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetStrictModelConfig(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetStrictModelConfig(self.ptr, enable),
+            self.record("strict_model_config", enable, |config| {
+                config.strict_model_config = Some(enable)
+            })
         )
     }
 
@@ -156,8 +253,10 @@ impl Options {
     pub fn model_config_name<C: AsRef<str>>(&mut self, config_name: C) -> Result<&mut Self, Error> {
         let name = to_cstring(config_name)?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetModelConfigName(self.0, name.as_ptr(),),
-            self
+            sys::TRITONSERVER_ServerOptionsSetModelConfigName(self.ptr, name.as_ptr(),),
+            self.record("model_config_name", name.to_string_lossy(), |config| {
+                config.model_config_name = Some(name.to_string_lossy().into_owned())
+            })
         )
     }
 
@@ -171,8 +270,10 @@ impl Options {
     /// By default, execution count is used to determine the priorities.
     pub fn rate_limiter_mode(&mut self, mode: Limit) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetRateLimiterMode(self.0, mode as _),
-            self
+            sys::TRITONSERVER_ServerOptionsSetRateLimiterMode(self.ptr, mode as _),
+            self.record("rate_limiter_mode", format!("{mode:?}"), |config| {
+                config.rate_limiter_mode = Some(mode)
+            })
         )
     }
 
@@ -194,12 +295,22 @@ impl Options {
         let name = to_cstring(name)?;
         triton_call!(
             sys::TRITONSERVER_ServerOptionsAddRateLimiterResource(
-                self.0,
+                self.ptr,
                 name.as_ptr(),
                 count as usize,
                 device,
             ),
-            self
+            self.record(
+                "add_rate_limiter_resource",
+                format!("{}:{count}:{device}", name.to_string_lossy()),
+                |config| {
+                    config.rate_limiter_resource.push(RateLimiterResource {
+                        name: name.to_string_lossy().into_owned(),
+                        count,
+                        device,
+                    })
+                }
+            )
         )
     }
 
@@ -208,8 +319,10 @@ impl Options {
     /// `size`: The pinned memory pool byte size.
     pub fn pinned_memory_pool_byte_size(&mut self, size: u64) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetPinnedMemoryPoolByteSize(self.0, size),
-            self
+            sys::TRITONSERVER_ServerOptionsSetPinnedMemoryPoolByteSize(self.ptr, size),
+            self.record("pinned_memory_pool_byte_size", size, |config| {
+                config.pinned_memory_pool_byte_size = Some(size)
+            })
         )
     }
 
@@ -223,8 +336,14 @@ impl Options {
         size: u64,
     ) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetCudaMemoryPoolByteSize(self.0, device, size),
-            self
+            sys::TRITONSERVER_ServerOptionsSetCudaMemoryPoolByteSize(self.ptr, device, size),
+            self.record(
+                "cuda_memory_pool_byte_size",
+                format!("{device}:{size}"),
+                |config| {
+                    config.cuda_memory_pool_byte_size.insert(device, size);
+                }
+            )
         )
     }
 
@@ -238,9 +357,16 @@ impl Options {
         device: i32,
         size: usize,
     ) -> Result<&mut Self, Error> {
+        compat::require(c"TRITONSERVER_ServerOptionsSetCudaVirtualAddressSize")?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetCudaVirtualAddressSize(self.0, device, size),
-            self
+            sys::TRITONSERVER_ServerOptionsSetCudaVirtualAddressSize(self.ptr, device, size),
+            self.record(
+                "cuda_virtual_address_size",
+                format!("{device}:{size}"),
+                |config| {
+                    config.cuda_virtual_address_size.insert(device, size);
+                }
+            )
         )
     }
 
@@ -251,8 +377,10 @@ impl Options {
     #[deprecated]
     pub fn response_cache_byte_size(&mut self, size: u64) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetResponseCacheByteSize(self.0, size),
-            self
+            sys::TRITONSERVER_ServerOptionsSetResponseCacheByteSize(self.ptr, size),
+            self.record("response_cache_byte_size", size, |config| {
+                config.response_cache_byte_size = Some(size)
+            })
         )
     }
 
@@ -266,8 +394,15 @@ impl Options {
     ) -> Result<&mut Self, Error> {
         let cache_dir = path_to_cstring(cache_dir)?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetCacheDirectory(self.0, cache_dir.as_ptr()),
-            self
+            sys::TRITONSERVER_ServerOptionsSetCacheDirectory(self.ptr, cache_dir.as_ptr()),
+            self.record(
+                "response_cache_directory",
+                cache_dir.to_string_lossy(),
+                |config| {
+                    config.response_cache_directory =
+                        Some(cache_dir.to_string_lossy().into_owned().into())
+                }
+            )
         )
     }
 
@@ -301,11 +436,26 @@ impl Options {
         let config_json = to_cstring(config_json)?;
         triton_call!(
             sys::TRITONSERVER_ServerOptionsSetCacheConfig(
-                self.0,
+                self.ptr,
                 name.as_ptr(),
                 config_json.as_ptr()
             ),
-            self
+            self.record(
+                "response_cache_config",
+                format!(
+                    "{},{}",
+                    name.to_string_lossy(),
+                    config_json.to_string_lossy()
+                ),
+                |config| {
+                    let config_json = config_json.to_string_lossy();
+                    let value = serde_json::from_str(&config_json)
+                        .unwrap_or_else(|_| serde_json::Value::String(config_json.into_owned()));
+                    config
+                        .response_cache_config
+                        .insert(name.to_string_lossy().into_owned(), value);
+                }
+            )
         )
     }
 
@@ -316,40 +466,54 @@ impl Options {
         capability: f64,
     ) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetMinSupportedComputeCapability(self.0, capability),
-            self
+            sys::TRITONSERVER_ServerOptionsSetMinSupportedComputeCapability(self.ptr, capability),
+            self.record("min_supported_compute_capability", capability, |config| {
+                config.min_supported_compute_capability = Some(capability)
+            })
         )
     }
 
     /// Enable or disable exit-on-error. True to enable exiting on initialization error, false to continue.
     pub fn exit_on_error(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetExitOnError(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetExitOnError(self.ptr, enable),
+            self.record("exit_on_error", enable, |config| {
+                config.exit_on_error = Some(enable)
+            })
         )
     }
 
     /// Enable or disable strict readiness handling.
     pub fn strict_readiness(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetStrictReadiness(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetStrictReadiness(self.ptr, enable),
+            self.record("strict_readiness", enable, |config| {
+                config.strict_readiness = Some(enable)
+            })
         )
     }
 
     /// Set the exit timeout.
     pub fn exit_timeout(&mut self, timeout: Duration) -> Result<&mut Self, Error> {
-        triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetExitTimeout(self.0, timeout.as_secs().max(1) as _),
-            self
+        triton_call!(sys::TRITONSERVER_ServerOptionsSetExitTimeout(
+            self.ptr,
+            timeout.as_secs().max(1) as _
+        ))?;
+        self.exit_timeout = timeout;
+        Ok(
+            self.record("exit_timeout", format!("{timeout:?}"), |config| {
+                config.exit_timeout_secs = Some(timeout.as_secs().max(1))
+            }),
         )
     }
 
     /// Set the number of threads used in buffer manager.
     pub fn buffer_manager_thread_count(&mut self, thread: usize) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetBufferManagerThreadCount(self.0, thread as _),
-            self
+            sys::TRITONSERVER_ServerOptionsSetBufferManagerThreadCount(self.ptr, thread as _),
+            self.record("buffer_manager_thread_count", thread, |config| {
+                config.buffer_manager_thread_count = Some(thread)
+            })
         )
     }
 
@@ -358,8 +522,10 @@ impl Options {
     /// `thread_count` The number of threads.
     pub fn model_load_thread_count(&mut self, thread_count: usize) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetModelLoadThreadCount(self.0, thread_count as _),
-            self
+            sys::TRITONSERVER_ServerOptionsSetModelLoadThreadCount(self.ptr, thread_count as _),
+            self.record("model_load_thread_count", thread_count, |config| {
+                config.model_load_thread_count = Some(thread_count)
+            })
         )
     }
 
@@ -368,8 +534,10 @@ impl Options {
     /// `retry_count` The number of retry.
     pub fn model_retry_count(&mut self, retry_count: usize) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetModelLoadRetryCount(self.0, retry_count as _),
-            self
+            sys::TRITONSERVER_ServerOptionsSetModelLoadRetryCount(self.ptr, retry_count as _),
+            self.record("model_retry_count", retry_count, |config| {
+                config.model_retry_count = Some(retry_count)
+            })
         )
     }
 
@@ -379,9 +547,12 @@ impl Options {
     ///
     /// `enable_peer_access` Whether to enable peer access or not.
     pub fn peer_access(&mut self, enable_peer_access: bool) -> Result<&mut Self, Error> {
+        compat::require(c"TRITONSERVER_ServerOptionsSetEnablePeerAccess")?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetEnablePeerAccess(self.0, enable_peer_access),
-            self
+            sys::TRITONSERVER_ServerOptionsSetEnablePeerAccess(self.ptr, enable_peer_access),
+            self.record("peer_access", enable_peer_access, |config| {
+                config.peer_access = Some(enable_peer_access)
+            })
         )
     }
 
@@ -390,8 +561,10 @@ impl Options {
     /// `enable_namespace` Whether to enable model namespacing or not.
     pub fn model_namespacing(&mut self, enable_namespace: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetModelNamespacing(self.0, enable_namespace),
-            self
+            sys::TRITONSERVER_ServerOptionsSetModelNamespacing(self.ptr, enable_namespace),
+            self.record("model_namespacing", enable_namespace, |config| {
+                config.model_namespacing = Some(enable_namespace)
+            })
         )
     }
 
@@ -402,55 +575,81 @@ impl Options {
     pub fn log_file<P: AsRef<str>>(&mut self, log_file: P) -> Result<&mut Self, Error> {
         let log_file = to_cstring(log_file)?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetLogFile(self.0, log_file.as_ptr()),
-            self
+            sys::TRITONSERVER_ServerOptionsSetLogFile(self.ptr, log_file.as_ptr()),
+            self.record("log_file", log_file.to_string_lossy(), |config| {
+                config.log_file = Some(log_file.to_string_lossy().into_owned())
+            })
         )
     }
 
+    /// Redirect the server logs to the [log] facade instead of the console or the log file
+    /// (check [crate::logging] module). Overrides [Options::log_file] and [Options::log_format]. \
+    /// Levels of the logs are still enabled with [Options::log_info], [Options::log_verbose], etc. \
+    /// The pipe the logs are read from and the forwarding thread are created when the server starts
+    /// and are removed when it is shut down or dropped.
+    pub fn log_to_facade(&mut self) -> Result<&mut Self, Error> {
+        self.log_to_facade = true;
+        Ok(self.record("log_to_facade", true, |config| {
+            config.log_to_facade = Some(true)
+        }))
+    }
+
     /// Enable or disable info level logging.
     pub fn log_info(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetLogInfo(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetLogInfo(self.ptr, enable),
+            self.record("log_info", enable, |config| {
+                config.log_info = Some(enable)
+            })
         )
     }
 
     /// Enable or disable warning level logging.
     pub fn log_warn(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetLogWarn(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetLogWarn(self.ptr, enable),
+            self.record("log_warn", enable, |config| {
+                config.log_warn = Some(enable)
+            })
         )
     }
 
     /// Enable or disable error level logging.
     pub fn log_error(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetLogError(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetLogError(self.ptr, enable),
+            self.record("log_error", enable, |config| {
+                config.log_error = Some(enable)
+            })
         )
     }
 
     pub fn log_format(&mut self, log_format: LogFormat) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetLogFormat(self.0, log_format as _),
-            self
+            sys::TRITONSERVER_ServerOptionsSetLogFormat(self.ptr, log_format as _),
+            self.record("log_format", format!("{log_format:?}"), |config| {
+                config.log_format = Some(log_format)
+            })
         )
     }
 
     /// Set verbose logging level. Level zero disables verbose logging.
     pub fn log_verbose(&mut self, level: i32) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetLogVerbose(self.0, level),
-            self
+            sys::TRITONSERVER_ServerOptionsSetLogVerbose(self.ptr, level),
+            self.record("log_verbose", level, |config| {
+                config.log_verbose = Some(level)
+            })
         )
     }
 
     /// Enable or disable metrics collection in a server options.
     pub fn metrics(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetMetrics(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetMetrics(self.ptr, enable),
+            self.record("metrics", enable, |config| {
+                config.metrics = Some(enable)
+            })
         )
     }
 
@@ -458,8 +657,10 @@ impl Options {
     /// GPU metrics are collected if both this option and [Options::metrics] are set.
     pub fn gpu_metrics(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetGpuMetrics(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetGpuMetrics(self.ptr, enable),
+            self.record("gpu_metrics", enable, |config| {
+                config.gpu_metrics = Some(enable)
+            })
         )
     }
 
@@ -468,8 +669,10 @@ impl Options {
     /// True to enable CPU metrics, false to disable.
     pub fn cpu_metrics(&mut self, enable: bool) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetCpuMetrics(self.0, enable),
-            self
+            sys::TRITONSERVER_ServerOptionsSetCpuMetrics(self.ptr, enable),
+            self.record("cpu_metrics", enable, |config| {
+                config.cpu_metrics = Some(enable)
+            })
         )
     }
 
@@ -478,13 +681,41 @@ impl Options {
     pub fn metrics_interval(&mut self, interval: Duration) -> Result<&mut Self, Error> {
         triton_call!(
             sys::TRITONSERVER_ServerOptionsSetMetricsInterval(
-                self.0,
+                self.ptr,
                 interval.as_millis().max(1) as _,
             ),
-            self
+            self.record("metrics_interval", format!("{interval:?}"), |config| {
+                config.metrics_interval_ms = Some(interval.as_millis().max(1) as u64)
+            })
         )
     }
 
+    /// Enable or disable the per-model latency counters (`nv_inference_*_duration_us`). Default is true. \
+    /// Shortcut of the global [Options::metrics_config] `counter_latencies` setting.
+    pub fn metrics_counter_latencies(&mut self, enable: bool) -> Result<&mut Self, Error> {
+        self.metrics_config("", "counter_latencies", enable.to_string())
+    }
+
+    /// Enable or disable the per-model latency summaries (`nv_inference_*_summary_us`). Default is false. \
+    /// Shortcut of the global [Options::metrics_config] `summary_latencies` setting.
+    pub fn metrics_summary_latencies(&mut self, enable: bool) -> Result<&mut Self, Error> {
+        self.metrics_config("", "summary_latencies", enable.to_string())
+    }
+
+    /// Set the quantiles of the latency summaries. \
+    /// `quantiles`: pairs of the quantile and its allowed error, e.g. `[(0.5, 0.05), (0.99, 0.001)]`.
+    pub fn metrics_summary_quantiles(
+        &mut self,
+        quantiles: &[(f64, f64)],
+    ) -> Result<&mut Self, Error> {
+        let quantiles = quantiles
+            .iter()
+            .map(|(quantile, error)| format!("{quantile}:{error}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.metrics_config("", "summary_quantiles", quantiles)
+    }
+
     /// Set the directory containing backend shared libraries. \
     /// This directory is searched last after the version and model directory
     /// in the model repository when looking for the backend shared library for a model. \
@@ -499,8 +730,10 @@ impl Options {
                     .map_err(|err| Error::new(ErrorCode::InvalidArg, err.to_string()))
             })?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetBackendDirectory(self.0, path.as_ptr()),
-            self
+            sys::TRITONSERVER_ServerOptionsSetBackendDirectory(self.ptr, path.as_ptr()),
+            self.record("backend_directory", path.to_string_lossy(), |config| {
+                config.backend_directory = Some(path.to_string_lossy().into_owned().into())
+            })
         )
     }
 
@@ -511,8 +744,10 @@ impl Options {
         let path = CString::new(path.as_ref().as_os_str().as_bytes())
             .map_err(|err| Error::new(ErrorCode::InvalidArg, format!("{}", err)))?;
         triton_call!(
-            sys::TRITONSERVER_ServerOptionsSetRepoAgentDirectory(self.0, path.as_ptr()),
-            self
+            sys::TRITONSERVER_ServerOptionsSetRepoAgentDirectory(self.ptr, path.as_ptr()),
+            self.record("repo_agent_directory", path.to_string_lossy(), |config| {
+                config.repo_agent_directory = Some(path.to_string_lossy().into_owned().into())
+            })
         )
     }
 
@@ -534,9 +769,17 @@ impl Options {
     ) -> Result<&mut Self, Error> {
         triton_call!(
             sys::TRITONSERVER_ServerOptionsSetModelLoadDeviceLimit(
-                self.0, kind as _, device, fraction
+                self.ptr, kind as _, device, fraction
             ),
-            self
+            self.record(
+                "model_load_device_limit",
+                format!("{kind:?}:{device}:{fraction}"),
+                |config| {
+                    if kind == InstanceGroupKind::Gpu {
+                        config.model_load_gpu_limit.insert(device, fraction);
+                    }
+                }
+            )
         )
     }
 
@@ -561,12 +804,30 @@ impl Options {
 
         triton_call!(
             sys::TRITONSERVER_ServerOptionsSetBackendConfig(
-                self.0,
+                self.ptr,
                 name.as_ptr(),
                 setting.as_ptr(),
                 value.as_ptr(),
             ),
-            self
+            self.record(
+                "backend_config",
+                format!(
+                    "{},{},{}",
+                    name.to_string_lossy(),
+                    setting.to_string_lossy(),
+                    value.to_string_lossy()
+                ),
+                |config| {
+                    config
+                        .backend_config
+                        .entry(name.to_string_lossy().into_owned())
+                        .or_default()
+                        .insert(
+                            setting.to_string_lossy().into_owned(),
+                            value.to_string_lossy().into_owned(),
+                        );
+                }
+            )
         )
     }
 
@@ -591,12 +852,30 @@ impl Options {
 
         triton_call!(
             sys::TRITONSERVER_ServerOptionsSetHostPolicy(
-                self.0,
+                self.ptr,
                 name.as_ptr(),
                 setting.as_ptr(),
                 value.as_ptr(),
             ),
-            self
+            self.record(
+                "host_policy",
+                format!(
+                    "{},{},{}",
+                    name.to_string_lossy(),
+                    setting.to_string_lossy(),
+                    value.to_string_lossy()
+                ),
+                |config| {
+                    config
+                        .host_policy
+                        .entry(name.to_string_lossy().into_owned())
+                        .or_default()
+                        .insert(
+                            setting.to_string_lossy().into_owned(),
+                            value.to_string_lossy().into_owned(),
+                        );
+                }
+            )
         )
     }
 
@@ -622,24 +901,146 @@ impl Options {
 
         triton_call!(
             sys::TRITONSERVER_ServerOptionsSetMetricsConfig(
-                self.0,
+                self.ptr,
                 name.as_ptr(),
                 setting.as_ptr(),
                 value.as_ptr()
             ),
-            self
+            self.record(
+                "metrics_config",
+                format!(
+                    "{},{},{}",
+                    name.to_string_lossy(),
+                    setting.to_string_lossy(),
+                    value.to_string_lossy()
+                ),
+                |config| {
+                    config
+                        .metrics_config
+                        .entry(name.to_string_lossy().into_owned())
+                        .or_default()
+                        .insert(
+                            setting.to_string_lossy().into_owned(),
+                            value.to_string_lossy().into_owned(),
+                        );
+                }
+            )
         )
     }
 }
 
 unsafe impl Send for Options {}
 
+impl fmt::Debug for Options {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let settings: Vec<_> = self
+            .settings
+            .iter()
+            .map(|(option, value)| (*option, redacted_setting(option, value)))
+            .collect();
+        f.debug_struct("Options")
+            .field("settings", &settings)
+            .field("lazy_startup", &self.lazy_startup)
+            .field("exit_timeout", &self.exit_timeout)
+            .finish()
+    }
+}
+
 impl Drop for Options {
     fn drop(&mut self) {
-        if !self.0.is_null() {
+        if !self.ptr.is_null() {
             unsafe {
-                let _ = sys::TRITONSERVER_ServerOptionsDelete(self.0);
+                let _ = sys::TRITONSERVER_ServerOptionsDelete(self.ptr);
             }
         }
     }
 }
+
+/// Value shown instead of the redacted settings.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Options which values never hold secrets.
+const PUBLIC_SETTINGS: &[&str] = &[
+    "model_repository",
+    "lazy_startup",
+    "server_id",
+    "startup_model",
+    "model_control_mode",
+    "strict_model_config",
+    "model_config_name",
+    "rate_limiter_mode",
+    "add_rate_limiter_resource",
+    "pinned_memory_pool_byte_size",
+    "cuda_memory_pool_byte_size",
+    "cuda_virtual_address_size",
+    "response_cache_byte_size",
+    "response_cache_directory",
+    "min_supported_compute_capability",
+    "exit_on_error",
+    "strict_readiness",
+    "exit_timeout",
+    "buffer_manager_thread_count",
+    "model_load_thread_count",
+    "model_retry_count",
+    "peer_access",
+    "model_namespacing",
+    "log_file",
+    "log_to_facade",
+    "log_info",
+    "log_warn",
+    "log_error",
+    "log_format",
+    "log_verbose",
+    "metrics",
+    "gpu_metrics",
+    "cpu_metrics",
+    "metrics_interval",
+    "metrics_config",
+    "backend_directory",
+    "repo_agent_directory",
+    "model_load_device_limit",
+];
+
+/// Get the recorded `value` of the `option` (check [Options::settings]) safe to be logged or attached to the bug report. \
+/// Backend configs, host policies and cache configs often hold credentials (e.g. of the cloud storages),
+/// so only the names of the backend (policy, cache) and the setting are kept. Values of the unknown options are redacted.
+pub(crate) fn redacted_setting<'a>(option: &str, value: &'a str) -> Cow<'a, str> {
+    if PUBLIC_SETTINGS.contains(&option) {
+        return Cow::Borrowed(value);
+    }
+    let names = match option {
+        "backend_config" | "host_policy" => 2,
+        "response_cache_config" => 1,
+        _ => 0,
+    };
+    let mut redacted: Vec<&str> = value.splitn(names + 1, ',').take(names).collect();
+    redacted.push(REDACTED);
+    Cow::Owned(redacted.join(","))
+}
+
+/// Remote repository URIs are passed to Triton as is, local paths are canonicalized.
+fn repository_to_cstring<P: AsRef<Path>>(repository: P) -> Result<CString, Error> {
+    match repository.as_ref().to_str() {
+        Some(uri) if crate::repository::is_remote(uri) => to_cstring(uri),
+        _ => path_to_cstring(repository),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redaction() {
+        assert_eq!(redacted_setting("log_verbose", "1"), "1");
+        assert_eq!(
+            redacted_setting("backend_config", "python,aws_secret_access_key,abc,def"),
+            "python,aws_secret_access_key,<redacted>"
+        );
+        assert_eq!(
+            redacted_setting("response_cache_config", r#"redis,{"password":"abc"}"#),
+            "redis,<redacted>"
+        );
+        assert_eq!(redacted_setting("future_option", "secret"), "<redacted>");
+    }
+}
@@ -0,0 +1,333 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde_json::Value;
+
+use super::{Control, Limit, LogFormat, Options, OptionsConfig, RateLimiterResource};
+use crate::error::{Error, ErrorCode};
+
+/// Prefixes of the flags of the `tritonserver` endpoints (HTTP, GRPC, Sagemaker, Vertex AI, metrics port and tracing).
+/// The server embedded in the application has no endpoints of its own, so these flags are ignored.
+const FRONTEND_FLAGS: &[&str] = &[
+    "http-",
+    "grpc-",
+    "allow-http",
+    "allow-grpc",
+    "allow-sagemaker",
+    "allow-vertex-ai",
+    "sagemaker-",
+    "vertex-ai-",
+    "metrics-address",
+    "metrics-port",
+    "reuse-http-port",
+    "reuse-grpc-port",
+    "trace-",
+];
+
+impl Options {
+    /// Create the options from the command-line flags of the stock `tritonserver` binary, e.g.
+    /// `--model-repository=/models --model-control-mode explicit --load-model resnet`. \
+    /// Check [OptionsConfig::from_cli_args] for the supported flags.
+    /// ```ignore
+    /// let args: Vec<String> = std::env::args().skip(1).collect();
+    /// let server = Server::new(Options::from_cli_args(&args)?).await?;
+    /// ```
+    pub fn from_cli_args<S: AsRef<str>>(args: &[S]) -> Result<Self, Error> {
+        Options::from_config(&OptionsConfig::from_cli_args(args)?)
+    }
+}
+
+impl OptionsConfig {
+    /// Parse the command-line flags of the stock `tritonserver` binary. `args` must not contain the program name. \
+    /// Values are given either as `--flag=value` or as `--flag value`; the flags can be repeated where `tritonserver` allows it.
+    ///
+    /// Supported flags:
+    /// - `--model-repository` (`--model-store`), `--id`, `--model-control-mode`, `--load-model`, `--strict-model-config`,
+    ///   `--model-config-name`, `--strict-readiness`, `--exit-on-error`, `--exit-timeout-secs`, `--model-namespacing`;
+    /// - `--rate-limit`, `--rate-limit-resource=<name>:<count>[:<device>]`;
+    /// - `--pinned-memory-pool-byte-size`, `--cuda-memory-pool-byte-size=<device>:<size>`,
+    ///   `--cuda-virtual-address-size=<device>:<size>`, `--model-load-gpu-limit=<device>:<fraction>`,
+    ///   `--min-supported-compute-capability`, `--enable-peer-access`;
+    /// - `--cache-config=<cache>,<setting>=<value>`, `--cache-directory`;
+    /// - `--buffer-manager-thread-count`, `--model-load-thread-count`, `--model-load-retry-count`;
+    /// - `--log-file`, `--log-info`, `--log-warning`, `--log-error`, `--log-format`, `--log-verbose`;
+    /// - `--allow-metrics`, `--allow-gpu-metrics`, `--allow-cpu-metrics`, `--metrics-interval-ms`,
+    ///   `--metrics-config=[<group>,]<setting>=<value>`;
+    /// - `--backend-directory`, `--repoagent-directory`, `--backend-config=[<backend>,]<setting>=<value>`,
+    ///   `--host-policy=<policy>,<setting>=<value>`.
+    ///
+    /// Flags of the endpoints (e.g. `--http-port`, `--allow-grpc`, `--trace-config`) are ignored with a warning:
+    /// the embedded server has no endpoints. \
+    /// Returns error on the unknown flag, the missing value or the value that can't be parsed.
+    pub fn from_cli_args<S: AsRef<str>>(args: &[S]) -> Result<Self, Error> {
+        let mut config = OptionsConfig::default();
+        let mut args = args.iter().map(AsRef::as_ref).peekable();
+
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Unexpected argument {arg}, expected --<flag>"),
+                ));
+            };
+            let (flag, inline) = match flag.split_once('=') {
+                Some((flag, value)) => (flag, Some(value)),
+                None => (flag, None),
+            };
+            if FRONTEND_FLAGS.iter().any(|prefix| flag.starts_with(prefix)) {
+                // Skip the value given as the next argument.
+                if inline.is_none() && args.peek().is_some_and(|value| !value.starts_with("--")) {
+                    args.next();
+                }
+                log::warn!("Flag --{flag} of the server endpoints is ignored");
+                continue;
+            }
+            let Some(value) = inline.or_else(|| args.next()) else {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Flag --{flag} has no value"),
+                ));
+            };
+            let flag = Flag { name: flag, value };
+
+            match flag.name {
+                "model-repository" | "model-store" => config.model_repository.push(value.into()),
+                "id" => config.server_id = Some(value.into()),
+                "model-control-mode" => {
+                    config.model_control_mode = Some(match value {
+                        "none" => Control::None,
+                        "poll" => Control::Poll,
+                        "explicit" => Control::Explicit,
+                        _ => return Err(flag.invalid("none, poll or explicit")),
+                    })
+                }
+                "load-model" => config.startup_model.push(value.into()),
+                "strict-model-config" => config.strict_model_config = Some(flag.parse_bool()?),
+                "model-config-name" => config.model_config_name = Some(value.into()),
+                "strict-readiness" => config.strict_readiness = Some(flag.parse_bool()?),
+                "exit-on-error" => config.exit_on_error = Some(flag.parse_bool()?),
+                "exit-timeout-secs" => config.exit_timeout_secs = Some(flag.parse()?),
+                "model-namespacing" => config.model_namespacing = Some(flag.parse_bool()?),
+
+                "rate-limit" => {
+                    config.rate_limiter_mode = Some(match value {
+                        "off" => Limit::Off,
+                        "execution_count" => Limit::ExecCount,
+                        _ => return Err(flag.invalid("off or execution_count")),
+                    })
+                }
+                "rate-limit-resource" => {
+                    let parts: Vec<&str> = value.split(':').collect();
+                    let (name, count, device) = match parts.as_slice() {
+                        [name, count] => (*name, *count, "-1"),
+                        [name, count, device] => (*name, *count, *device),
+                        _ => return Err(flag.invalid("<name>:<count>[:<device>]")),
+                    };
+                    config.rate_limiter_resource.push(RateLimiterResource {
+                        name: name.to_string(),
+                        count: flag.parse_part(count)?,
+                        device: flag.parse_part(device)?,
+                    });
+                }
+
+                "pinned-memory-pool-byte-size" => {
+                    config.pinned_memory_pool_byte_size = Some(flag.parse()?)
+                }
+                "cuda-memory-pool-byte-size" => {
+                    let (device, size) = flag.parse_device_value()?;
+                    config.cuda_memory_pool_byte_size.insert(device, size);
+                }
+                "cuda-virtual-address-size" => {
+                    let (device, size) = flag.parse_device_value()?;
+                    config.cuda_virtual_address_size.insert(device, size);
+                }
+                "model-load-gpu-limit" => {
+                    let (device, fraction) = flag.parse_device_value()?;
+                    config.model_load_gpu_limit.insert(device, fraction);
+                }
+                "min-supported-compute-capability" => {
+                    config.min_supported_compute_capability = Some(flag.parse()?)
+                }
+                "enable-peer-access" => config.peer_access = Some(flag.parse_bool()?),
+
+                "cache-config" => {
+                    let (cache, setting, value) = flag.parse_group_setting(false)?;
+                    let cache = config
+                        .response_cache_config
+                        .entry(cache.to_string())
+                        .or_insert_with(|| Value::Object(Default::default()));
+                    if let Value::Object(settings) = cache {
+                        settings.insert(setting.to_string(), Value::String(value.to_string()));
+                    }
+                }
+                "cache-directory" => config.response_cache_directory = Some(value.into()),
+
+                "buffer-manager-thread-count" => {
+                    config.buffer_manager_thread_count = Some(flag.parse()?)
+                }
+                "model-load-thread-count" => config.model_load_thread_count = Some(flag.parse()?),
+                "model-load-retry-count" => config.model_retry_count = Some(flag.parse()?),
+
+                "log-file" => config.log_file = Some(value.into()),
+                "log-info" => config.log_info = Some(flag.parse_bool()?),
+                "log-warning" => config.log_warn = Some(flag.parse_bool()?),
+                "log-error" => config.log_error = Some(flag.parse_bool()?),
+                "log-format" => {
+                    config.log_format = Some(match value {
+                        "default" => LogFormat::Default,
+                        "ISO8601" => LogFormat::Iso8601,
+                        _ => return Err(flag.invalid("default or ISO8601")),
+                    })
+                }
+                "log-verbose" => config.log_verbose = Some(flag.parse()?),
+
+                "allow-metrics" => config.metrics = Some(flag.parse_bool()?),
+                "allow-gpu-metrics" => config.gpu_metrics = Some(flag.parse_bool()?),
+                "allow-cpu-metrics" => config.cpu_metrics = Some(flag.parse_bool()?),
+                "metrics-interval-ms" => config.metrics_interval_ms = Some(flag.parse()?),
+                "metrics-config" => {
+                    let (group, setting, value) = flag.parse_group_setting(true)?;
+                    insert_setting(&mut config.metrics_config, group, setting, value);
+                }
+
+                "backend-directory" => config.backend_directory = Some(value.into()),
+                "repoagent-directory" => config.repo_agent_directory = Some(value.into()),
+                "backend-config" => {
+                    let (backend, setting, value) = flag.parse_group_setting(true)?;
+                    insert_setting(&mut config.backend_config, backend, setting, value);
+                }
+                "host-policy" => {
+                    let (policy, setting, value) = flag.parse_group_setting(false)?;
+                    insert_setting(&mut config.host_policy, policy, setting, value);
+                }
+
+                _ => {
+                    return Err(Error::new(
+                        ErrorCode::InvalidArg,
+                        format!("Unknown flag --{}", flag.name),
+                    ))
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn insert_setting(
+    settings: &mut BTreeMap<String, BTreeMap<String, String>>,
+    group: &str,
+    setting: &str,
+    value: &str,
+) {
+    settings
+        .entry(group.to_string())
+        .or_default()
+        .insert(setting.to_string(), value.to_string());
+}
+
+/// Flag with its value.
+struct Flag<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+impl<'a> Flag<'a> {
+    fn invalid(&self, expected: &str) -> Error {
+        Error::new(
+            ErrorCode::InvalidArg,
+            format!(
+                "Invalid value {} of the flag --{}, expected {expected}",
+                self.value, self.name
+            ),
+        )
+    }
+
+    fn parse<T: FromStr>(&self) -> Result<T, Error> {
+        self.parse_part(self.value)
+    }
+
+    fn parse_part<T: FromStr>(&self, part: &str) -> Result<T, Error> {
+        part.parse()
+            .map_err(|_| self.invalid(std::any::type_name::<T>()))
+    }
+
+    /// `tritonserver` accepts true, on, 1 and false, off, 0.
+    fn parse_bool(&self) -> Result<bool, Error> {
+        match self.value.to_lowercase().as_str() {
+            "true" | "on" | "1" => Ok(true),
+            "false" | "off" | "0" => Ok(false),
+            _ => Err(self.invalid("true or false")),
+        }
+    }
+
+    /// `<device>:<value>`.
+    fn parse_device_value<T: FromStr>(&self) -> Result<(i32, T), Error> {
+        let Some((device, value)) = self.value.split_once(':') else {
+            return Err(self.invalid("<device>:<value>"));
+        };
+        Ok((self.parse_part(device)?, self.parse_part(value)?))
+    }
+
+    /// `<group>,<setting>=<value>`. If `global` is allowed, the group can be omitted: it is "" then.
+    fn parse_group_setting(&self, global: bool) -> Result<(&'a str, &'a str, &'a str), Error> {
+        let (group, setting) = match self.value.split_once(',') {
+            Some((group, setting)) => (group, setting),
+            None if global => ("", self.value),
+            None => return Err(self.invalid("<group>,<setting>=<value>")),
+        };
+        let Some((setting, value)) = setting.split_once('=') else {
+            return Err(self.invalid("<group>,<setting>=<value>"));
+        };
+        Ok((group, setting, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tritonserver_flags() {
+        let config = OptionsConfig::from_cli_args(&[
+            "--model-repository=/models",
+            "--model-store",
+            "s3://bucket/models",
+            "--model-control-mode=explicit",
+            "--load-model",
+            "resnet",
+            "--strict-model-config=false",
+            "--log-verbose=1",
+            "--rate-limit-resource=R1:4",
+            "--cuda-memory-pool-byte-size=0:268435456",
+            "--backend-config=onnxruntime,enable-global-threadpool=1",
+            "--backend-config=backend-directory=/opt/backends",
+            "--cache-config=local,size=1048576",
+            "--http-port",
+            "8000",
+            "--allow-grpc=false",
+            "--exit-timeout-secs",
+            "10",
+        ])
+        .unwrap();
+
+        assert_eq!(config.model_repository, ["/models", "s3://bucket/models"]);
+        assert_eq!(config.model_control_mode, Some(Control::Explicit));
+        assert_eq!(config.startup_model, ["resnet"]);
+        assert_eq!(config.strict_model_config, Some(false));
+        assert_eq!(config.log_verbose, Some(1));
+        assert_eq!(config.rate_limiter_resource[0].device, -1);
+        assert_eq!(config.cuda_memory_pool_byte_size[&0], 1 << 28);
+        assert_eq!(
+            config.backend_config["onnxruntime"]["enable-global-threadpool"],
+            "1"
+        );
+        assert_eq!(
+            config.backend_config[""]["backend-directory"],
+            "/opt/backends"
+        );
+        assert_eq!(
+            config.response_cache_config["local"],
+            serde_json::json!({ "size": "1048576" })
+        );
+        assert_eq!(config.exit_timeout_secs, Some(10));
+    }
+}
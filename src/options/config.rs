@@ -0,0 +1,344 @@
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use super::{Control, InstanceGroupKind, Limit, LogFormat, Options, REDACTED};
+use crate::error::{Error, ErrorCode};
+
+/// Rate limiter resource of [OptionsConfig]. Check [Options::add_rate_limiter_resource].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RateLimiterResource {
+    pub name: String,
+    pub count: u64,
+    /// -1 means the resource is available on every device.
+    #[serde(default = "every_device")]
+    pub device: i32,
+}
+
+fn every_device() -> i32 {
+    -1
+}
+
+/// Server options in the form of the configuration file. Check [Options::from_config]. \
+/// Each field corresponds to the method of [Options] of the same name; unset fields keep the Triton defaults.
+/// The maps are keyed by the device id or by the backend (policy, metrics group) name and the setting name.
+/// ```ignore
+/// let config: OptionsConfig = serde_json::from_str(r#"{
+///     "model_repository": ["/models"],
+///     "model_control_mode": "Explicit",
+///     "startup_model": ["resnet"],
+///     "exit_timeout_secs": 10,
+///     "backend_config": { "onnxruntime": { "enable-global-threadpool": "1" } }
+/// }"#)?;
+/// let options = Options::from_config(&config)?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptionsConfig {
+    /// Model repositories: local paths or remote URIs. At least one is required.
+    pub model_repository: Vec<String>,
+    pub server_id: Option<String>,
+    pub startup_model: Vec<String>,
+    pub model_control_mode: Option<Control>,
+    pub strict_model_config: Option<bool>,
+    pub model_config_name: Option<String>,
+    pub rate_limiter_mode: Option<Limit>,
+    pub rate_limiter_resource: Vec<RateLimiterResource>,
+    pub pinned_memory_pool_byte_size: Option<u64>,
+    /// Deprecated, check [Options::response_cache_byte_size].
+    pub response_cache_byte_size: Option<u64>,
+    pub cuda_memory_pool_byte_size: BTreeMap<i32, u64>,
+    pub cuda_virtual_address_size: BTreeMap<i32, usize>,
+    pub response_cache_directory: Option<PathBuf>,
+    /// Configs of the caches by the cache name. Check [Options::response_cache_config]. \
+    /// The config is either the JSON object or the string with the JSON.
+    pub response_cache_config: BTreeMap<String, Value>,
+    pub min_supported_compute_capability: Option<f64>,
+    pub exit_on_error: Option<bool>,
+    pub strict_readiness: Option<bool>,
+    /// Check [Options::exit_timeout].
+    pub exit_timeout_secs: Option<u64>,
+    pub buffer_manager_thread_count: Option<usize>,
+    pub model_load_thread_count: Option<usize>,
+    pub model_retry_count: Option<usize>,
+    pub peer_access: Option<bool>,
+    pub model_namespacing: Option<bool>,
+    pub lazy_startup: Option<bool>,
+    pub log_file: Option<String>,
+    /// Check [Options::log_to_facade].
+    pub log_to_facade: Option<bool>,
+    pub log_info: Option<bool>,
+    pub log_warn: Option<bool>,
+    pub log_error: Option<bool>,
+    pub log_format: Option<LogFormat>,
+    pub log_verbose: Option<i32>,
+    pub metrics: Option<bool>,
+    pub gpu_metrics: Option<bool>,
+    pub cpu_metrics: Option<bool>,
+    /// Check [Options::metrics_interval].
+    pub metrics_interval_ms: Option<u64>,
+    pub backend_directory: Option<PathBuf>,
+    pub repo_agent_directory: Option<PathBuf>,
+    /// Memory usage limits of the GPUs by the device id. Check [Options::model_load_device_limit]
+    /// (Triton supports only the GPU limits).
+    pub model_load_gpu_limit: BTreeMap<i32, f64>,
+    pub backend_config: BTreeMap<String, BTreeMap<String, String>>,
+    pub host_policy: BTreeMap<String, BTreeMap<String, String>>,
+    /// Settings of the metrics groups, "" is the global group.
+    pub metrics_config: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Options {
+    /// Create the options from the `config`, e.g. deserialized from the configuration file
+    /// with `serde_json::from_str` or `serde_yaml::from_str`. \
+    /// Returns error if the config has no model repository or any of the settings is rejected.
+    pub fn from_config(config: &OptionsConfig) -> Result<Self, Error> {
+        let [repository, repositories @ ..] = config.model_repository.as_slice() else {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                "Options config has no model_repository",
+            ));
+        };
+        let mut options = Options::new(repository)?;
+        for repository in repositories {
+            options.model_repository(repository)?;
+        }
+
+        if let Some(id) = &config.server_id {
+            options.server_id(id)?;
+        }
+        for model in &config.startup_model {
+            options.startup_model(model)?;
+        }
+        if let Some(mode) = config.model_control_mode {
+            options.model_control_mode(mode)?;
+        }
+        if let Some(enable) = config.strict_model_config {
+            options.strict_model_config(enable)?;
+        }
+        if let Some(name) = &config.model_config_name {
+            options.model_config_name(name)?;
+        }
+        if let Some(mode) = config.rate_limiter_mode {
+            options.rate_limiter_mode(mode)?;
+        }
+        for resource in &config.rate_limiter_resource {
+            options.add_rate_limiter_resource(&resource.name, resource.count, resource.device)?;
+        }
+        if let Some(size) = config.pinned_memory_pool_byte_size {
+            options.pinned_memory_pool_byte_size(size)?;
+        }
+        if let Some(size) = config.response_cache_byte_size {
+            #[allow(deprecated)]
+            options.response_cache_byte_size(size)?;
+        }
+        for (device, size) in &config.cuda_memory_pool_byte_size {
+            options.cuda_memory_pool_byte_size(*device, *size)?;
+        }
+        for (device, size) in &config.cuda_virtual_address_size {
+            options.cuda_virtual_address_size(*device, *size)?;
+        }
+        if let Some(dir) = &config.response_cache_directory {
+            options.response_cache_directory(dir)?;
+        }
+        for (name, cache_config) in &config.response_cache_config {
+            match cache_config {
+                Value::String(json) => options.response_cache_config(name, json)?,
+                json => options.response_cache_config(name, json.to_string())?,
+            };
+        }
+        if let Some(capability) = config.min_supported_compute_capability {
+            options.min_supported_compute_capability(capability)?;
+        }
+        if let Some(enable) = config.exit_on_error {
+            options.exit_on_error(enable)?;
+        }
+        if let Some(enable) = config.strict_readiness {
+            options.strict_readiness(enable)?;
+        }
+        if let Some(secs) = config.exit_timeout_secs {
+            options.exit_timeout(Duration::from_secs(secs))?;
+        }
+        if let Some(count) = config.buffer_manager_thread_count {
+            options.buffer_manager_thread_count(count)?;
+        }
+        if let Some(count) = config.model_load_thread_count {
+            options.model_load_thread_count(count)?;
+        }
+        if let Some(count) = config.model_retry_count {
+            options.model_retry_count(count)?;
+        }
+        if let Some(enable) = config.peer_access {
+            options.peer_access(enable)?;
+        }
+        if let Some(enable) = config.model_namespacing {
+            options.model_namespacing(enable)?;
+        }
+        if let Some(lazy) = config.lazy_startup {
+            options.lazy_startup(lazy);
+        }
+
+        if let Some(file) = &config.log_file {
+            options.log_file(file)?;
+        }
+        if config.log_to_facade == Some(true) {
+            options.log_to_facade()?;
+        }
+        if let Some(enable) = config.log_info {
+            options.log_info(enable)?;
+        }
+        if let Some(enable) = config.log_warn {
+            options.log_warn(enable)?;
+        }
+        if let Some(enable) = config.log_error {
+            options.log_error(enable)?;
+        }
+        if let Some(format) = config.log_format {
+            options.log_format(format)?;
+        }
+        if let Some(level) = config.log_verbose {
+            options.log_verbose(level)?;
+        }
+
+        if let Some(enable) = config.metrics {
+            options.metrics(enable)?;
+        }
+        if let Some(enable) = config.gpu_metrics {
+            options.gpu_metrics(enable)?;
+        }
+        if let Some(enable) = config.cpu_metrics {
+            options.cpu_metrics(enable)?;
+        }
+        if let Some(interval) = config.metrics_interval_ms {
+            options.metrics_interval(Duration::from_millis(interval))?;
+        }
+
+        if let Some(dir) = &config.backend_directory {
+            options.backend_directory(dir)?;
+        }
+        if let Some(dir) = &config.repo_agent_directory {
+            options.repo_agent_directory(dir)?;
+        }
+        for (device, fraction) in &config.model_load_gpu_limit {
+            options.model_load_device_limit(InstanceGroupKind::Gpu, *device, *fraction)?;
+        }
+        for (backend, settings) in &config.backend_config {
+            for (setting, value) in settings {
+                options.backend_config(backend, setting, value)?;
+            }
+        }
+        for (policy, settings) in &config.host_policy {
+            for (setting, value) in settings {
+                options.host_policy(policy, setting, value)?;
+            }
+        }
+        for (group, settings) in &config.metrics_config {
+            for (setting, value) in settings {
+                options.metrics_config(group, setting, value)?;
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl OptionsConfig {
+    /// Get the config with the values of the backend configs, host policies and cache configs replaced with `"<redacted>"`:
+    /// they often hold credentials (e.g. of the cloud storages). The backend (policy, cache) and the setting names are kept.
+    pub fn redacted(&self) -> OptionsConfig {
+        let redact = |settings: &BTreeMap<String, BTreeMap<String, String>>| {
+            settings
+                .iter()
+                .map(|(name, settings)| {
+                    let settings = settings
+                        .keys()
+                        .map(|setting| (setting.clone(), REDACTED.to_string()))
+                        .collect();
+                    (name.clone(), settings)
+                })
+                .collect()
+        };
+        OptionsConfig {
+            backend_config: redact(&self.backend_config),
+            host_policy: redact(&self.host_policy),
+            response_cache_config: self
+                .response_cache_config
+                .keys()
+                .map(|name| (name.clone(), Value::String(REDACTED.to_string())))
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// The options are serialized as the [redacted](OptionsConfig::redacted) [config](Options::config), e.g. to log the effective configuration.
+/// The result can be loaded with [Options::from_config], provided the redacted settings are filled back.
+impl Serialize for Options {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.config.redacted().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize() {
+        let config: OptionsConfig = serde_json::from_str(
+            r#"{
+                "model_repository": ["/models", "s3://bucket/models"],
+                "model_control_mode": "Explicit",
+                "rate_limiter_resource": [{ "name": "R1", "count": 4 }],
+                "cuda_memory_pool_byte_size": { "0": 268435456 },
+                "backend_config": { "onnxruntime": { "enable-global-threadpool": "1" } },
+                "exit_timeout_secs": 10
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.model_repository.len(), 2);
+        assert_eq!(config.model_control_mode, Some(Control::Explicit));
+        assert_eq!(config.rate_limiter_resource[0].device, -1);
+        assert_eq!(config.cuda_memory_pool_byte_size[&0], 1 << 28);
+        assert_eq!(config.log_format, None);
+
+        assert!(serde_json::from_str::<OptionsConfig>(r#"{ "model_repo": [] }"#).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = OptionsConfig {
+            model_repository: vec!["/models".to_string()],
+            model_control_mode: Some(Control::Poll),
+            strict_model_config: Some(false),
+            exit_timeout_secs: Some(10),
+            metrics_interval_ms: Some(500),
+            response_cache_byte_size: Some(1 << 20),
+            cuda_memory_pool_byte_size: BTreeMap::from([(0, 1 << 28)]),
+            response_cache_config: BTreeMap::from([(
+                "redis".to_string(),
+                serde_json::json!({ "host": "localhost", "password": "secret" }),
+            )]),
+            backend_config: BTreeMap::from([(
+                "python".to_string(),
+                BTreeMap::from([("aws_secret_access_key".to_string(), "secret".to_string())]),
+            )]),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<OptionsConfig>(&json).unwrap(),
+            config
+        );
+
+        let redacted = serde_json::to_string(&config.redacted()).unwrap();
+        assert!(!redacted.contains("secret\""));
+        let redacted: OptionsConfig = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(
+            redacted.backend_config["python"]["aws_secret_access_key"],
+            REDACTED
+        );
+        assert_eq!(redacted.exit_timeout_secs, Some(10));
+    }
+}
@@ -1,6 +1,30 @@
-use std::{fs::File, path::Path, ptr::null_mut};
+use std::{
+    collections::HashMap, fs::File, path::Path, ptr::null_mut, sync::RwLock, time::Duration,
+};
 
-use crate::{error::Error, sys, to_cstring};
+use crate::{
+    error::{Error, ErrorCode},
+    message::{ModelConfig, VersionPolicy},
+    sys, to_cstring,
+};
+
+/// Parameter names that are handled by Triton itself and must be set via typed [RequestParameter] variants.
+const RESERVED_REQUEST_PARAMETERS: [&str; 6] = [
+    "priority",
+    "timeout",
+    "sequence_id",
+    "sequence_start",
+    "sequence_end",
+    RESPONSE_CACHE_PARAMETER,
+];
+
+/// Bool request parameter switching the response cache for the request. Check [Request::disable_response_cache](crate::Request::disable_response_cache).
+pub(crate) const RESPONSE_CACHE_PARAMETER: &str = "triton_enable_cache";
+
+lazy_static::lazy_static! {
+    static ref REGISTERED_REQUEST_PARAMETERS: RwLock<HashMap<String, TritonParameterType>> =
+        RwLock::new(HashMap::new());
+}
 
 /// Types of parameters recognized by TRITONSERVER.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -23,6 +47,120 @@ pub enum ParameterContent {
     Bytes(Vec<u8>),
 }
 
+impl ParameterContent {
+    /// Get the Triton type of the content.
+    pub fn parameter_type(&self) -> TritonParameterType {
+        match self {
+            ParameterContent::String(_) => TritonParameterType::String,
+            ParameterContent::Int(_) => TritonParameterType::Int,
+            ParameterContent::Bool(_) => TritonParameterType::Bool,
+            ParameterContent::Double(_) => TritonParameterType::Double,
+            ParameterContent::Bytes(_) => TritonParameterType::Bytes,
+        }
+    }
+}
+
+/// Parameter of the inference request. Check [Request::set_parameter](crate::Request::set_parameter).
+///
+/// Well-known parameters have typed variants, so they can't be misspelled or set with the wrong type. \
+/// Backend-specific parameters are passed as [RequestParameter::Custom].
+/// Their expected types can be registered with [register_request_parameter] to be validated before the request is sent.
+#[derive(Debug, Clone)]
+pub enum RequestParameter {
+    /// Priority of the request. 0 means the model's default priority.
+    Priority(u32),
+    /// Timeout of the request. Zero means no timeout.
+    Timeout(Duration),
+    /// Backend-specific parameter.
+    Custom {
+        name: String,
+        content: ParameterContent,
+    },
+}
+
+impl RequestParameter {
+    /// Create backend-specific parameter.
+    pub fn custom<N: AsRef<str>>(name: N, content: ParameterContent) -> Self {
+        RequestParameter::Custom {
+            name: name.as_ref().to_string(),
+            content,
+        }
+    }
+
+    /// Check the parameter: custom parameters must not use the names of the typed ones
+    /// and must have the registered type, if any.
+    pub fn validate(&self) -> Result<(), Error> {
+        let (name, content) = match self {
+            RequestParameter::Custom { name, content } => (name, content),
+            _ => return Ok(()),
+        };
+
+        if name.is_empty() {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                "Request parameter name is empty",
+            ));
+        }
+        if RESERVED_REQUEST_PARAMETERS.contains(&name.as_str()) {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("Request parameter \"{name}\" is handled by Triton, use the typed RequestParameter variant or Request setter"),
+            ));
+        }
+
+        let registered = REGISTERED_REQUEST_PARAMETERS
+            .read()
+            .ok()
+            .and_then(|registered| registered.get(name).copied());
+        match registered {
+            Some(expected) if expected != content.parameter_type() => Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Request parameter \"{name}\" must be {expected:?}, got {:?}",
+                    content.parameter_type()
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<Parameter> for RequestParameter {
+    fn from(parameter: Parameter) -> Self {
+        RequestParameter::Custom {
+            name: parameter.name.clone(),
+            content: parameter.content.clone(),
+        }
+    }
+}
+
+/// Encode `bytes` with the standard base64 alphabet and padding.
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk.iter().enumerate().fold(0u32, |triple, (i, &byte)| {
+            triple | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Register the expected type of the backend-specific request parameter `name`. \
+/// [RequestParameter::Custom] with this name and other type will be rejected by [Request::set_parameter](crate::Request::set_parameter).
+pub fn register_request_parameter<N: AsRef<str>>(name: N, parameter_type: TritonParameterType) {
+    if let Ok(mut registered) = REGISTERED_REQUEST_PARAMETERS.write() {
+        registered.insert(name.as_ref().to_string(), parameter_type);
+    }
+}
+
 /// Parameter of the [Server](crate::Server) or [Response](crate::Response).
 #[derive(Debug)]
 pub struct Parameter {
@@ -86,17 +224,31 @@ impl Parameter {
     }
 
     /// Create String Parameter of model config with exact version of the model. \
-    /// `config`: model config.pbtxt as json value.
+    /// `config`: model config.pbtxt as json value or [ModelConfig].
     /// Check [load_config_as_json] to permutate .pbtxt config to json value. \
     /// If [Options::model_control_mode](crate::options::Options::model_control_mode) set as EXPLICIT and the result of this method is passed to [crate::Server::load_model_with_parametrs],
     /// the server will load only that exact model and only that exact version of it.
-    pub fn from_config_with_exact_version(
-        mut config: serde_json::Value,
+    pub fn from_config_with_exact_version<C: Into<serde_json::Value>>(
+        config: C,
         version: i64,
     ) -> Result<Self, Error> {
-        config["version_policy"] = serde_json::json!({"specific": { "versions": [version]}});
+        let mut config = config.into();
+        config["version_policy"] = VersionPolicy::Specific {
+            versions: vec![version],
+        }
+        .to_json();
         Parameter::new("config", ParameterContent::String(config.to_string()))
     }
+
+    /// Create String Parameter of the typed model config. \
+    /// If [Options::model_control_mode](crate::options::Options::model_control_mode) set as EXPLICIT and the result of this method is passed to [crate::Server::load_model_with_parametrs],
+    /// the server will load the model with this config instead of the config.pbtxt from the repository.
+    pub fn from_config(config: &ModelConfig) -> Result<Self, Error> {
+        Parameter::new(
+            "config",
+            ParameterContent::String(config.to_json().to_string()),
+        )
+    }
 }
 
 impl Clone for Parameter {
@@ -198,3 +350,21 @@ fn test_config_to_json() {
         json_cfg
     );
 }
+
+#[test]
+fn test_base64_encode() {
+    assert_eq!(base64_encode(b""), "");
+    assert_eq!(base64_encode(b"f"), "Zg==");
+    assert_eq!(base64_encode(b"fo"), "Zm8=");
+    assert_eq!(base64_encode(b"foo"), "Zm9v");
+    assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    assert_eq!(base64_encode(&[0xff, 0xfe]), "//4=");
+}
+
+#[test]
+fn test_reserved_request_parameters() {
+    let cache = RequestParameter::custom(RESPONSE_CACHE_PARAMETER, ParameterContent::Bool(false));
+    assert!(cache.validate().is_err());
+    let custom = RequestParameter::custom("threads", ParameterContent::Int(4));
+    assert!(custom.validate().is_ok());
+}
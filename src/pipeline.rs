@@ -0,0 +1,337 @@
+//! Client side pipeline of the models: outputs of one model's request feed the inputs of the next one.
+//!
+//! Useful when the server side ensemble can't be configured (e.g. the models are loaded from different repositories
+//! or the pipeline is chosen at runtime). \
+//! The output buffers are passed to the consuming stages as they are, without copying:
+//! with [Stage::outputs_on] set to [MemoryType::Gpu] the data stays on the device between the models.
+//! ```ignore
+//! let pipeline = ModelPipeline::new()
+//!     .stage("preproc")
+//!     .stage("yolov8")
+//!     .link("out0", "images");
+//!
+//! let inputs = HashMap::from([("preproc".to_string(), HashMap::from([("raw".to_string(), image)]))]);
+//! let outputs = pipeline.run(&server, inputs).await?;
+//! let detections = &outputs["yolov8"]["output0"];
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    error::{Error, ErrorCode},
+    graph,
+    memory::{Buffer, MemoryType},
+    request::DefaultAllocator,
+    Server,
+};
+
+/// Buffers of the pipeline keyed by the stage name and then by the input (output) name.
+pub type StageBuffers = HashMap<String, HashMap<String, Buffer>>;
+
+/// Model of the [ModelPipeline].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    name: String,
+    model: String,
+    version: i64,
+    memory_type: MemoryType,
+    device: Option<i32>,
+}
+
+impl Stage {
+    /// Stage inferring the latest version of the `model`. The stage is named after the model.
+    pub fn new<M: AsRef<str>>(model: M) -> Self {
+        Stage {
+            name: model.as_ref().to_string(),
+            model: model.as_ref().to_string(),
+            version: -1,
+            memory_type: MemoryType::Gpu,
+            device: None,
+        }
+    }
+
+    /// Set the name of the stage, e.g. to use the same model twice. Default is the model name.
+    pub fn name<N: AsRef<str>>(mut self, name: N) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+
+    /// Set the version of the model. Default is -1 (server chooses the version).
+    pub fn version(mut self, version: i64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the preferred memory type of the stage outputs. Default is [MemoryType::Gpu],
+    /// so the linked outputs are passed to the next model without leaving the device. \
+    /// If the allocation fails, the memory type requested by Triton is used (check [DefaultAllocator::prefer]).
+    pub fn outputs_on(mut self, memory_type: MemoryType) -> Self {
+        self.memory_type = memory_type;
+        self
+    }
+
+    /// Set the device of the stage request and its GPU outputs. Check [crate::Request::set_device].
+    pub fn device(mut self, device_id: i32) -> Self {
+        self.device = Some(device_id);
+        self
+    }
+}
+
+/// Output of one stage consumed as the input of another.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Link {
+    from: String,
+    output: String,
+    to: String,
+    input: String,
+}
+
+/// DAG of the models executed on the client side. Check [module](crate::pipeline) level documentation for more info.
+#[derive(Debug, Clone, Default)]
+pub struct ModelPipeline {
+    stages: Vec<Stage>,
+    links: Vec<Link>,
+}
+
+impl ModelPipeline {
+    /// Create an empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the stage inferring the latest version of the `model`. Check [ModelPipeline::add_stage].
+    pub fn stage<M: AsRef<str>>(self, model: M) -> Self {
+        self.add_stage(Stage::new(model))
+    }
+
+    /// Add the `stage` to the pipeline.
+    pub fn add_stage(mut self, stage: Stage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Feed the `output` of the stage added before the last one to the `input` of the last added stage. \
+    /// Does nothing if the pipeline has less than two stages. Use [ModelPipeline::link_stages] for the other links.
+    pub fn link<O: AsRef<str>, I: AsRef<str>>(self, output: O, input: I) -> Self {
+        let [.., from, to] = self.stages.as_slice() else {
+            log::warn!(
+                "ModelPipeline::link({}, {}) needs two stages to link",
+                output.as_ref(),
+                input.as_ref()
+            );
+            return self;
+        };
+        let (from, to) = (from.name.clone(), to.name.clone());
+        self.link_stages(from, output, to, input)
+    }
+
+    /// Feed the `output` of the stage `from` to the `input` of the stage `to`. \
+    /// One output can be linked to several inputs: the consumers share the buffer without copying.
+    pub fn link_stages<F, O, T, I>(mut self, from: F, output: O, to: T, input: I) -> Self
+    where
+        F: AsRef<str>,
+        O: AsRef<str>,
+        T: AsRef<str>,
+        I: AsRef<str>,
+    {
+        self.links.push(Link {
+            from: from.as_ref().to_string(),
+            output: output.as_ref().to_string(),
+            to: to.as_ref().to_string(),
+            input: input.as_ref().to_string(),
+        });
+        self
+    }
+
+    /// Execution order of the stage names. \
+    /// Returns error if the links refer to the unknown stages, the stage names are not unique,
+    /// an input is linked twice or the links form a cycle.
+    pub fn schedule(&self) -> Result<Vec<&str>, Error> {
+        Ok(self
+            .order()?
+            .into_iter()
+            .map(|idx| self.stages[idx].name.as_str())
+            .collect())
+    }
+
+    /// Run the stages in dependency order. \
+    /// `inputs`: inputs of the stages which are not linked to other stages' outputs, keyed by the stage name.
+    /// They are added with the shape from the model configuration (check [crate::Request::add_input]). \
+    /// Linked inputs get the buffer and the shape of the producing output.
+    ///
+    /// Returns outputs not consumed by any stage, keyed by the stage name.
+    /// Returns error of the first failed stage; the outputs of the finished stages are dropped.
+    pub async fn run(
+        &self,
+        server: &Server,
+        mut inputs: StageBuffers,
+    ) -> Result<StageBuffers, Error> {
+        let order = self.order()?;
+
+        // Outputs are kept with their shape until all the consumers are run.
+        let mut produced = HashMap::<(&str, &str), (Arc<Buffer>, Vec<i64>)>::new();
+        let mut consumers = HashMap::<(&str, &str), usize>::new();
+        for link in &self.links {
+            *consumers
+                .entry((link.from.as_str(), link.output.as_str()))
+                .or_default() += 1;
+        }
+        let mut results = StageBuffers::new();
+
+        for idx in order {
+            let stage = &self.stages[idx];
+            let mut request = server.create_request(&stage.model, stage.version)?;
+            if let Some(device) = stage.device {
+                request.set_device(device)?;
+            }
+            request.add_allocator(Box::new(DefaultAllocator::prefer(stage.memory_type)));
+
+            for (input, buffer) in inputs.remove(&stage.name).unwrap_or_default() {
+                request.add_input(input, buffer)?;
+            }
+            for link in self.links.iter().filter(|link| link.to == stage.name) {
+                let key = (link.from.as_str(), link.output.as_str());
+                let left = consumers.get_mut(&key).expect("consumers are counted");
+                *left -= 1;
+                let (buffer, shape) = if *left == 0 {
+                    produced.remove(&key)
+                } else {
+                    produced.get(&key).cloned()
+                }
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorCode::NotFound,
+                        format!(
+                            "Stage {} has no output {} linked to input {} of stage {}",
+                            link.from, link.output, link.input, stage.name
+                        ),
+                    )
+                })?;
+
+                let buffer = match Arc::try_unwrap(buffer) {
+                    Ok(buffer) => buffer,
+                    // Safety: Triton doesn't mutate the inputs, so the consumers can share the memory.
+                    Err(shared) => unsafe { shared.slice(..)?.into_buffer() },
+                };
+                request.add_input_with_dims(&link.input, buffer, shape)?;
+            }
+
+            let response = request.infer_async()?.await.map_err(|err| err.error)?;
+            let shapes: HashMap<String, Vec<i64>> = response
+                .get_outputs()
+                .iter()
+                .map(|output| (output.name.clone(), output.shape.clone()))
+                .collect();
+
+            let mut outputs = response.return_buffers().await?;
+            for (output, shape) in shapes {
+                let Some(buffer) = outputs.remove(&output) else {
+                    continue;
+                };
+                let link = self
+                    .links
+                    .iter()
+                    .find(|link| link.from == stage.name && link.output == output);
+                match link {
+                    Some(link) => {
+                        produced.insert(
+                            (link.from.as_str(), link.output.as_str()),
+                            (Arc::new(buffer), shape),
+                        );
+                    }
+                    None => {
+                        results
+                            .entry(stage.name.clone())
+                            .or_default()
+                            .insert(output, buffer);
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Order stages so that each stage goes after the producers of its linked inputs.
+    fn order(&self) -> Result<Vec<usize>, Error> {
+        let mut indices = HashMap::with_capacity(self.stages.len());
+        for (idx, stage) in self.stages.iter().enumerate() {
+            if indices.insert(stage.name.as_str(), idx).is_some() {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Pipeline has several stages named {}", stage.name),
+                ));
+            }
+        }
+
+        let mut linked_inputs = HashSet::new();
+        let mut dependencies = vec![HashSet::new(); self.stages.len()];
+        for link in &self.links {
+            let (Some(from), Some(to)) = (
+                indices.get(link.from.as_str()),
+                indices.get(link.to.as_str()),
+            ) else {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Link {}.{} -> {}.{} refers to an unknown stage",
+                        link.from, link.output, link.to, link.input
+                    ),
+                ));
+            };
+            if !linked_inputs.insert((*to, link.input.as_str())) {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Input {} of stage {} is linked several times",
+                        link.input, link.to
+                    ),
+                ));
+            }
+            dependencies[*to].insert(*from);
+        }
+
+        graph::topological_order(&dependencies).map_err(|idx| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Stage {} can't be scheduled: links form a cycle",
+                    self.stages[idx].name
+                ),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule() {
+        let pipeline = ModelPipeline::new()
+            .add_stage(Stage::new("yolov8"))
+            .stage("preproc")
+            .add_stage(Stage::new("classifier").name("crop_classifier"))
+            .link_stages("preproc", "out0", "yolov8", "images")
+            .link_stages("yolov8", "output0", "crop_classifier", "boxes")
+            .link_stages("preproc", "out0", "crop_classifier", "image");
+        assert_eq!(
+            pipeline.schedule().unwrap(),
+            ["preproc", "yolov8", "crop_classifier"]
+        );
+
+        let chain = ModelPipeline::new()
+            .stage("preproc")
+            .stage("yolov8")
+            .link("out0", "images");
+        assert_eq!(chain.links[0].from, "preproc");
+        assert_eq!(chain.links[0].to, "yolov8");
+
+        let cycle = chain.link_stages("yolov8", "output0", "preproc", "raw");
+        assert!(cycle.schedule().is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Common postprocessing of the inference results: softmax, sigmoid, top-k and non-maximum suppression.
+//!
+//! The operations work with host f32 data, e.g. the result of [Output::to_f32_vec]
+//! (Gpu outputs are copied to the host, Fp16 and Bf16 outputs are converted).
+//! ```ignore
+//! let logits = response.get_output("logits").unwrap();
+//! let top5 = postprocess::classify(logits, 5)?;
+//!
+//! let detections = boxes
+//!     .chunks_exact(4)
+//!     .zip(scores)
+//!     .zip(classes)
+//!     .map(|((bbox, score), class)| Detection::new(bbox.try_into().unwrap(), score, class))
+//!     .filter(|detection| detection.score > 0.25);
+//! let detections = postprocess::nms(detections, 0.45);
+//! ```
+
+use crate::{
+    error::{Error, ErrorCode},
+    response::Output,
+};
+
+/// Replace the `logits` with their softmax. Numerically stable: the maximum is subtracted before the exponent.
+pub fn softmax(logits: &mut [f32]) {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let mut sum = 0.;
+    for value in logits.iter_mut() {
+        *value = (*value - max).exp();
+        sum += *value;
+    }
+    for value in logits.iter_mut() {
+        *value /= sum;
+    }
+}
+
+/// Replace the `values` with their logistic sigmoid.
+pub fn sigmoid(values: &mut [f32]) {
+    for value in values.iter_mut() {
+        *value = 1. / (1. + (-*value).exp());
+    }
+}
+
+/// Index of the maximum of the `values`. None if the values are empty. NaNs are ignored.
+pub fn argmax(values: &[f32]) -> Option<usize> {
+    top_k(values, 1).first().map(|(idx, _)| *idx)
+}
+
+/// Indices and values of the `k` greatest `values` in the descending order. NaNs are ignored.
+pub fn top_k(values: &[f32], k: usize) -> Vec<(usize, f32)> {
+    let mut top: Vec<(usize, f32)> = values
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, value)| !value.is_nan())
+        .collect();
+    let descending = |a: &(usize, f32), b: &(usize, f32)| b.1.total_cmp(&a.1);
+    if k < top.len() {
+        if k == 0 {
+            return Vec::new();
+        }
+        top.select_nth_unstable_by(k - 1, descending);
+        top.truncate(k);
+    }
+    top.sort_by(descending);
+    top
+}
+
+/// Softmax and top-k classes of each row of the classification `output` (the last dimension is the classes). \
+/// Returns error if the output can't be converted to f32 (check [Output::to_f32_vec]).
+///
+/// **Note**: If output.memory_type() is Gpu, should be called in sync with cuda context pinned (check [crate::memory] module level documentation for more info).
+pub fn classify(output: &Output, k: usize) -> Result<Vec<Vec<(usize, f32)>>, Error> {
+    let mut logits = output.to_f32_vec()?;
+    let classes = output.shape.last().copied().unwrap_or(1);
+    if classes <= 0 {
+        return Err(Error::new(
+            ErrorCode::InvalidArg,
+            format!(
+                "Output {} of shape {:?} has no classes",
+                output.name, output.shape
+            ),
+        ));
+    }
+    Ok(logits
+        .chunks_exact_mut(classes as usize)
+        .map(|row| {
+            softmax(row);
+            top_k(row, k)
+        })
+        .collect())
+}
+
+/// Detected object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    /// Box corners: x1, y1, x2, y2.
+    pub bbox: [f32; 4],
+    /// Confidence of the detection.
+    pub score: f32,
+    /// Index of the class.
+    pub class: usize,
+}
+
+impl Detection {
+    /// Detection with the box given by the corners.
+    pub fn new(bbox: [f32; 4], score: f32, class: usize) -> Self {
+        Detection { bbox, score, class }
+    }
+
+    /// Detection with the box given by the center, width and height (e.g. YOLO outputs).
+    pub fn from_center(
+        center_x: f32,
+        center_y: f32,
+        width: f32,
+        height: f32,
+        score: f32,
+        class: usize,
+    ) -> Self {
+        let (half_width, half_height) = (width / 2., height / 2.);
+        Detection::new(
+            [
+                center_x - half_width,
+                center_y - half_height,
+                center_x + half_width,
+                center_y + half_height,
+            ],
+            score,
+            class,
+        )
+    }
+
+    /// Area of the box.
+    pub fn area(&self) -> f32 {
+        let [x1, y1, x2, y2] = self.bbox;
+        (x2 - x1).max(0.) * (y2 - y1).max(0.)
+    }
+
+    /// Intersection over union of the boxes.
+    pub fn iou(&self, other: &Detection) -> f32 {
+        let intersection = Detection::new(
+            [
+                self.bbox[0].max(other.bbox[0]),
+                self.bbox[1].max(other.bbox[1]),
+                self.bbox[2].min(other.bbox[2]),
+                self.bbox[3].min(other.bbox[3]),
+            ],
+            0.,
+            0,
+        )
+        .area();
+        let union = self.area() + other.area() - intersection;
+        if union > 0. {
+            intersection / union
+        } else {
+            0.
+        }
+    }
+}
+
+/// Non-maximum suppression: keep the detections in the descending order of the score,
+/// dropping the ones overlapping a kept detection of the same class with IoU greater than `iou_threshold`.
+pub fn nms<D: IntoIterator<Item = Detection>>(detections: D, iou_threshold: f32) -> Vec<Detection> {
+    let mut detections: Vec<Detection> = detections.into_iter().collect();
+    detections.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut kept: Vec<Detection> = Vec::new();
+    for detection in detections {
+        if kept
+            .iter()
+            .all(|other| other.class != detection.class || other.iou(&detection) <= iou_threshold)
+        {
+            kept.push(detection);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ops() {
+        let mut logits = [1000., 1000., f32::MIN];
+        softmax(&mut logits);
+        assert_eq!(logits, [0.5, 0.5, 0.]);
+
+        let mut values = [0.];
+        sigmoid(&mut values);
+        assert_eq!(values, [0.5]);
+
+        let scores = [0.1, f32::NAN, 0.7, 0.3, 0.9];
+        assert_eq!(top_k(&scores, 2), [(4, 0.9), (2, 0.7)]);
+        assert_eq!(top_k(&scores, 10).len(), 4);
+        assert_eq!(argmax(&scores), Some(4));
+        assert_eq!(argmax(&[]), None);
+    }
+
+    #[test]
+    fn suppression() {
+        let detections = [
+            Detection::new([0., 0., 10., 10.], 0.8, 0),
+            Detection::new([1., 1., 10., 10.], 0.9, 0),
+            // Same box, other class.
+            Detection::new([1., 1., 10., 10.], 0.5, 1),
+            Detection::from_center(25., 25., 10., 10., 0.6, 0),
+        ];
+        assert!((detections[0].iou(&detections[1]) - 0.81).abs() < 1e-6);
+
+        let kept = nms(detections, 0.5);
+        let scores: Vec<_> = kept.iter().map(|d| d.score).collect();
+        assert_eq!(scores, [0.9, 0.6, 0.5]);
+    }
+}
@@ -0,0 +1,398 @@
+//! Recording of the inference inputs and their replay against two models to compare the outputs. \
+//! Intended for the model promotion gates: record the production inputs, then check in CI
+//! that the candidate model version (or server) produces the same outputs as the current one.
+//!
+//! ```ignore
+//! let recording = Recording::open("recordings/detector")?;
+//! let report = compare(
+//!     &recording,
+//!     ReplayTarget::new(&server, "detector", 1),
+//!     ReplayTarget::new(&server, "detector", 2),
+//!     Tolerance::default(),
+//! )
+//! .await?;
+//! println!("{report}");
+//! assert!(report.passed());
+//! ```
+//!
+//! The recording is a directory with a subdirectory per request containing the inputs in NumPy `.npy` format
+//! (check [Buffer::save_npy]), so it can be prepared or inspected with Python as well.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, ErrorCode},
+    memory::{Buffer, Byte, DataType, Sample},
+    response::Output,
+    Response, Server,
+};
+
+/// Inputs of the recorded requests. Check [Recording::record] and [Recording::open].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Recording {
+    dir: PathBuf,
+    /// Directories of the requests in the order of recording.
+    requests: Vec<PathBuf>,
+}
+
+impl Recording {
+    /// Open the recording in `dir`. Empty if the directory doesn't exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut requests = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| {
+                    let index = entry.file_name().to_str()?.parse::<usize>().ok()?;
+                    entry.path().is_dir().then(|| (index, entry.path()))
+                })
+                .collect::<Vec<_>>(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(Error::new(
+                    ErrorCode::Internal,
+                    format!("Can't read recording {}: {err}", dir.display()),
+                ))
+            }
+        };
+        requests.sort();
+        Ok(Recording {
+            dir,
+            requests: requests.into_iter().map(|(_, path)| path).collect(),
+        })
+    }
+
+    /// Record the inputs of the request. \
+    /// `inputs`: name, content and shape of each input. [DataType::Bytes] and [DataType::Bf16] inputs are not supported.
+    pub fn record<'b, N, I>(&mut self, inputs: I) -> Result<&mut Self, Error>
+    where
+        N: AsRef<str>,
+        I: IntoIterator<Item = (N, &'b Buffer, &'b [i64])>,
+    {
+        let request = self.dir.join(self.requests.len().to_string());
+        fs::create_dir_all(&request).map_err(|err| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't create {}: {err}", request.display()),
+            )
+        })?;
+        for (name, buffer, shape) in inputs {
+            buffer.save_npy(request.join(format!("{}.npy", name.as_ref())), Some(shape))?;
+        }
+        self.requests.push(request);
+        Ok(self)
+    }
+
+    /// Number of the recorded requests.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns true if there are no recorded requests.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Load the inputs of the `index`-th request: name, CPU buffer and shape of each input.
+    pub fn inputs(&self, index: usize) -> Result<Vec<(String, Buffer, Vec<i64>)>, Error> {
+        let request = self.requests.get(index).ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("Recording has only {} requests", self.requests.len()),
+            )
+        })?;
+        let entries = fs::read_dir(request).map_err(|err| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't read {}: {err}", request.display()),
+            )
+        })?;
+
+        let mut inputs = Vec::new();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_some_and(|ext| ext == "npy") {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                let (buffer, shape) = Buffer::load_npy(&path)?;
+                inputs.push((name.into_owned(), buffer, shape));
+            }
+        }
+        inputs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(inputs)
+    }
+}
+
+/// Model the recorded requests are replayed against.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayTarget<'s> {
+    pub server: &'s Server,
+    pub model: &'s str,
+    /// Version of the model. -1 means the server chooses the version based on the model's policy.
+    pub version: i64,
+}
+
+impl<'s> ReplayTarget<'s> {
+    pub fn new(server: &'s Server, model: &'s str, version: i64) -> Self {
+        ReplayTarget {
+            server,
+            model,
+            version,
+        }
+    }
+
+    async fn infer(&self, inputs: Vec<(String, Buffer, Vec<i64>)>) -> Result<Response, Error> {
+        let mut request = self.server.create_request(self.model, self.version)?;
+        request.add_default_allocator();
+        for (name, buffer, shape) in inputs {
+            request.add_input_with_dims(name, buffer, shape)?;
+        }
+        request.infer_async()?.await.map_err(|err| err.error)
+    }
+}
+
+/// Allowed difference of the output elements:
+/// the elements match if `|candidate - baseline| <= abs + rel * |baseline|`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    pub abs: f64,
+    pub rel: f64,
+}
+
+impl Default for Tolerance {
+    /// `abs`: 1e-5, `rel`: 1e-3.
+    fn default() -> Self {
+        Tolerance {
+            abs: 1e-5,
+            rel: 1e-3,
+        }
+    }
+}
+
+impl Tolerance {
+    /// Only the equal elements match.
+    pub fn exact() -> Self {
+        Tolerance { abs: 0., rel: 0. }
+    }
+}
+
+/// Difference of one output over all the replayed requests.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputDiff {
+    /// Number of the requests the output was compared in.
+    pub compared: usize,
+    /// Number of the requests where the output is missing in one of the responses.
+    pub missing: usize,
+    /// Number of the requests where the output shapes or data types differ.
+    pub mismatched_shapes: usize,
+    /// Number of the elements out of the tolerance.
+    pub mismatched_elements: usize,
+    pub max_abs_error: f64,
+    pub max_rel_error: f64,
+    /// NaN elements of the baseline output.
+    pub baseline_nans: usize,
+    /// NaN elements of the candidate output.
+    pub candidate_nans: usize,
+}
+
+impl OutputDiff {
+    /// Returns true if the outputs matched in all the requests.
+    pub fn passed(&self) -> bool {
+        self.missing == 0
+            && self.mismatched_shapes == 0
+            && self.mismatched_elements == 0
+            && self.baseline_nans == self.candidate_nans
+    }
+
+    /// Add the difference of the output elements. NaNs match only NaNs.
+    pub(crate) fn add_values(&mut self, baseline: &[f64], candidate: &[f64], tolerance: Tolerance) {
+        self.compared += 1;
+        for (&expected, &actual) in baseline.iter().zip(candidate) {
+            self.baseline_nans += expected.is_nan() as usize;
+            self.candidate_nans += actual.is_nan() as usize;
+            if expected.is_nan() || actual.is_nan() {
+                self.mismatched_elements += (expected.is_nan() != actual.is_nan()) as usize;
+                continue;
+            }
+            let abs_error = (actual - expected).abs();
+            let rel_error = if expected == 0. {
+                if abs_error == 0. {
+                    0.
+                } else {
+                    f64::INFINITY
+                }
+            } else {
+                abs_error / expected.abs()
+            };
+            self.max_abs_error = self.max_abs_error.max(abs_error);
+            self.max_rel_error = self.max_rel_error.max(rel_error);
+            if abs_error > tolerance.abs + tolerance.rel * expected.abs() {
+                self.mismatched_elements += 1;
+            }
+        }
+    }
+}
+
+/// Result of [compare].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    /// Number of the replayed requests.
+    pub requests: usize,
+    /// Differences by output name.
+    pub outputs: BTreeMap<String, OutputDiff>,
+}
+
+impl DiffReport {
+    /// Returns true if all the outputs matched in all the requests.
+    pub fn passed(&self) -> bool {
+        self.outputs.values().all(OutputDiff::passed)
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} requests replayed: {}",
+            self.requests,
+            if self.passed() { "PASSED" } else { "FAILED" }
+        )?;
+        for (name, diff) in &self.outputs {
+            writeln!(
+                f,
+                "  {name}: {} (max abs error {:e}, max rel error {:e}, {} mismatched elements, {} mismatched shapes, {} missing, NaNs {}/{})",
+                if diff.passed() { "ok" } else { "MISMATCH" },
+                diff.max_abs_error,
+                diff.max_rel_error,
+                diff.mismatched_elements,
+                diff.mismatched_shapes,
+                diff.missing,
+                diff.baseline_nans,
+                diff.candidate_nans,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Replay the `recording` against the `baseline` and `candidate` models and compare their outputs with the `tolerance`. \
+/// Requests are replayed one by one, an error of any inference fails the comparison.
+///
+/// **Note**: [DataType::Bytes] outputs are compared byte by byte.
+pub async fn compare(
+    recording: &Recording,
+    baseline: ReplayTarget<'_>,
+    candidate: ReplayTarget<'_>,
+    tolerance: Tolerance,
+) -> Result<DiffReport, Error> {
+    let mut report = DiffReport::default();
+    for index in 0..recording.len() {
+        let expected = baseline.infer(recording.inputs(index)?).await?;
+        let actual = candidate.infer(recording.inputs(index)?).await?;
+        report.requests += 1;
+
+        for output in expected.get_outputs() {
+            let diff = report.outputs.entry(output.name.clone()).or_default();
+            match actual.get_output(&output.name) {
+                None => diff.missing += 1,
+                Some(other)
+                    if other.shape != output.shape || other.data_type() != output.data_type() =>
+                {
+                    diff.mismatched_shapes += 1
+                }
+                Some(other) => diff.add_values(&values(output)?, &values(other)?, tolerance),
+            }
+        }
+        for output in actual.get_outputs() {
+            if expected.get_output(&output.name).is_none() {
+                report
+                    .outputs
+                    .entry(output.name.clone())
+                    .or_default()
+                    .missing += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Elements of the output as f64.
+pub(crate) fn values(output: &Output) -> Result<Vec<f64>, Error> {
+    fn convert<T: Sample, F: Fn(T) -> f64>(output: &Output, f: F) -> Result<Vec<f64>, Error> {
+        Ok(output.to_vec::<T>()?.into_iter().map(f).collect())
+    }
+
+    match output.data_type() {
+        DataType::Bool => convert(output, |v: bool| v as u8 as f64),
+        DataType::Uint8 => convert(output, |v: u8| v as f64),
+        DataType::Uint16 => convert(output, |v: u16| v as f64),
+        DataType::Uint32 => convert(output, |v: u32| v as f64),
+        DataType::Uint64 => convert(output, |v: u64| v as f64),
+        DataType::Int8 => convert(output, |v: i8| v as f64),
+        DataType::Int16 => convert(output, |v: i16| v as f64),
+        DataType::Int32 => convert(output, |v: i32| v as f64),
+        DataType::Int64 => convert(output, |v: i64| v as f64),
+        DataType::Fp16 => convert(output, |v: half::f16| v.to_f64()),
+        DataType::Bf16 => convert(output, |v: half::bf16| v.to_f64()),
+        DataType::Fp32 => convert(output, |v: f32| v as f64),
+        DataType::Fp64 => convert(output, |v: f64| v),
+        DataType::Bytes => convert(output, |v: Byte| v.0 as f64),
+        DataType::Invalid => Err(Error::new(
+            ErrorCode::InvalidArg,
+            format!("Output {} has invalid data type", output.name),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff() {
+        let mut diff = OutputDiff::default();
+        diff.add_values(
+            &[1., 0., 100., f64::NAN],
+            &[1.000001, 0., 100.05, f64::NAN],
+            Tolerance::default(),
+        );
+        assert!(diff.passed());
+        assert_eq!(diff.baseline_nans, 1);
+        assert!((diff.max_abs_error - 0.05).abs() < 1e-9);
+
+        diff.add_values(&[2., 1.], &[2.1, f64::NAN], Tolerance::default());
+        assert!(!diff.passed());
+        assert_eq!(diff.compared, 2);
+        assert_eq!(diff.mismatched_elements, 2);
+        assert_eq!(diff.candidate_nans, 2);
+    }
+
+    #[test]
+    fn recording() {
+        let dir =
+            std::env::temp_dir().join(format!("tritonserver-rs-replay-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut recording = Recording::open(&dir).unwrap();
+        assert!(recording.is_empty());
+        let images = Buffer::from([1f32, 2., 3., 4.]);
+        let scale = Buffer::from([0.5f64]);
+        recording
+            .record([
+                ("images", &images, &[2i64, 2][..]),
+                ("scale", &scale, &[1][..]),
+            ])
+            .unwrap();
+
+        let recording = Recording::open(&dir).unwrap();
+        assert_eq!(recording.len(), 1);
+        let inputs = recording.inputs(0).unwrap();
+        assert_eq!(inputs[0].0, "images");
+        assert_eq!(inputs[0].2, [2, 2]);
+        assert_eq!(inputs[1].1.bytes(), scale.bytes());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
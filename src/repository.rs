@@ -0,0 +1,242 @@
+//! Remote model repositories (S3, GCS, Azure Storage).
+//!
+//! Triton built with the cloud storage support reads the remote repositories itself:
+//! [Options::new](crate::options::Options::new) and [Options::model_repository](crate::options::Options::model_repository)
+//! accept their URIs as is. \
+//! Otherwise (or to avoid the downloads on each model load) the repository can be synced to the local cache directory
+//! with [fetch] before the server start and passed to the options as the local path:
+//! ```ignore
+//! let remote = RemoteRepository::parse("s3://bucket/models")?;
+//! let local = fetch(&remote, "/var/cache/models").await?;
+//! let options = Options::new(&local)?;
+//! ```
+//!
+//! The sync is made by the cloud CLI tool that must be installed and authorized:
+//! `aws s3 sync` for S3, `gsutil rsync` for GCS and `azcopy sync` for Azure Storage.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::error::{Error, ErrorCode};
+
+/// Storage of the remote model repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Storage {
+    /// Amazon S3 (or S3 compatible storage), "s3://bucket/path".
+    S3,
+    /// Google Cloud Storage, "gs://bucket/path".
+    Gcs,
+    /// Azure Storage, "as://account/container/path".
+    Azure,
+}
+
+impl Storage {
+    fn scheme(self) -> &'static str {
+        match self {
+            Storage::S3 => "s3://",
+            Storage::Gcs => "gs://",
+            Storage::Azure => "as://",
+        }
+    }
+}
+
+/// URI of the remote model repository in the Triton format.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RemoteRepository {
+    storage: Storage,
+    uri: String,
+}
+
+impl RemoteRepository {
+    /// Parse the URI of the remote repository ("s3://", "gs://" or "as://" scheme).
+    pub fn parse<U: AsRef<str>>(uri: U) -> Result<Self, Error> {
+        let uri = uri.as_ref().trim_end_matches('/');
+        let storage = storage_of(uri).ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("{uri} is not an URI of the remote model repository"),
+            )
+        })?;
+        Ok(RemoteRepository {
+            storage,
+            uri: uri.to_string(),
+        })
+    }
+
+    /// Storage of the repository.
+    pub fn storage(&self) -> Storage {
+        self.storage
+    }
+
+    /// URI of the repository.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Directory of the repository copy in the `cache_dir`.
+    pub fn local_path<P: AsRef<Path>>(&self, cache_dir: P) -> PathBuf {
+        let path = &self.uri[self.storage.scheme().len()..];
+        let storage = match self.storage {
+            Storage::S3 => "s3",
+            Storage::Gcs => "gs",
+            Storage::Azure => "as",
+        };
+        cache_dir.as_ref().join(storage).join(path)
+    }
+
+    /// Command syncing the repository to the `target` directory.
+    fn sync_command(&self, target: &Path) -> Command {
+        let mut command;
+        match self.storage {
+            Storage::S3 => {
+                command = Command::new("aws");
+                command
+                    .args(["s3", "sync", "--delete", "--only-show-errors"])
+                    .arg(&self.uri);
+            }
+            Storage::Gcs => {
+                command = Command::new("gsutil");
+                command
+                    .args(["-m", "-q", "rsync", "-r", "-d"])
+                    .arg(&self.uri);
+            }
+            Storage::Azure => {
+                // as://account/container/path -> https://account.blob.core.windows.net/container/path
+                let path = &self.uri[self.storage.scheme().len()..];
+                let (account, path) = path.split_once('/').unwrap_or((path, ""));
+                command = Command::new("azcopy");
+                command
+                    .arg("sync")
+                    .arg(format!("https://{account}.blob.core.windows.net/{path}"));
+                command.args(["--recursive", "--delete-destination=true"]);
+            }
+        }
+        command.arg(target);
+        command
+    }
+}
+
+fn storage_of(uri: &str) -> Option<Storage> {
+    [Storage::S3, Storage::Gcs, Storage::Azure]
+        .into_iter()
+        .find(|storage| {
+            uri.strip_prefix(storage.scheme())
+                .is_some_and(|path| !path.trim_end_matches('/').is_empty())
+        })
+}
+
+/// Check if `repository` is an URI of the remote model repository.
+pub fn is_remote<R: AsRef<str>>(repository: R) -> bool {
+    storage_of(repository.as_ref()).is_some()
+}
+
+/// Sync the `remote` repository to the `cache_dir` and return the path of the local copy
+/// (check [RemoteRepository::local_path]). Only the changed files are downloaded,
+/// files removed from the remote repository are removed from the copy.
+pub async fn fetch<P: AsRef<Path>>(
+    remote: &RemoteRepository,
+    cache_dir: P,
+) -> Result<PathBuf, Error> {
+    let local = remote.local_path(cache_dir);
+    std::fs::create_dir_all(&local).map_err(|err| {
+        Error::new(
+            ErrorCode::Internal,
+            format!("Can't create cache directory {}: {err}", local.display()),
+        )
+    })?;
+
+    let mut command = remote.sync_command(&local);
+    let uri = remote.uri.clone();
+    let output = tokio::task::spawn_blocking(move || command.output())
+        .await
+        .expect("tokio failed to join thread")
+        .map_err(|err| {
+            Error::new(
+                ErrorCode::Unavailable,
+                format!("Can't run the sync of {uri}: {err}"),
+            )
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorCode::Unavailable,
+            format!(
+                "Sync of {} failed ({}): {}",
+                remote.uri,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    log::debug!(
+        "Model repository {} is synced to {}",
+        remote.uri,
+        local.display()
+    );
+    Ok(local)
+}
+
+/// Re-sync the `remote` repository to the `cache_dir` every `period` on the tokio runtime. \
+/// The returned receiver is updated with the time of each successful sync: on change call
+/// [Server::poll_model_repository](crate::Server::poll_model_repository) to apply the changes
+/// (requires [Control::Poll](crate::options::Control::Poll)). Failed syncs are logged and retried on the next period. \
+/// Abort the returned handle to stop the re-sync.
+pub fn spawn_resync<P: AsRef<Path>>(
+    remote: RemoteRepository,
+    cache_dir: P,
+    period: Duration,
+) -> (JoinHandle<()>, watch::Receiver<SystemTime>) {
+    let cache_dir = cache_dir.as_ref().to_path_buf();
+    let (tx, rx) = watch::channel(SystemTime::now());
+
+    let handle = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        // The first tick is immediate, the repository is expected to be fetched already.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match fetch(&remote, &cache_dir).await {
+                Ok(_) => {
+                    if tx.send(SystemTime::now()).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => log::warn!("Error syncing model repository {}: {err}", remote.uri),
+            }
+        }
+    });
+    (handle, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let remote = RemoteRepository::parse("s3://bucket/models/").unwrap();
+        assert_eq!(remote.storage(), Storage::S3);
+        assert_eq!(remote.uri(), "s3://bucket/models");
+        assert_eq!(
+            remote.local_path("/cache"),
+            Path::new("/cache/s3/bucket/models")
+        );
+
+        let remote = RemoteRepository::parse("as://account/container/models").unwrap();
+        let command = remote.sync_command(Path::new("/cache"));
+        assert_eq!(command.get_program(), "azcopy");
+        assert_eq!(
+            command.get_args().nth(1).unwrap(),
+            "https://account.blob.core.windows.net/container/models"
+        );
+
+        assert!(is_remote("gs://bucket"));
+        assert!(!is_remote("/models"));
+        assert!(!is_remote("s3://"));
+    }
+}
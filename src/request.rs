@@ -1,16 +1,33 @@
+mod builder;
 pub(crate) mod infer;
+pub mod retry;
+mod sequence;
+mod shm;
+mod template;
 mod utils;
+mod writer;
+pub use crate::allocator::QueryMismatch;
 pub use crate::trace::Trace;
-pub use infer::{InferenceError, InputRelease, ResponseFuture};
+pub use builder::{NoAllocator, NoInputs, RequestBuilder, WithAllocator, WithInputs};
+pub use infer::{BorrowedInputs, InferenceError, InputRelease, ResponseFuture, ResponseStream};
+pub use sequence::Sequence;
+pub use shm::{ShmAllocator, SHM_OUTPUT_HEADER_SIZE, SHM_OUTPUT_MAGIC, SHM_OUTPUT_MAX_DIMS};
+pub use template::RequestTemplate;
+pub use writer::{InputWriter, DEFAULT_INPUT_CHUNK};
 
-use std::{collections::HashMap, mem::transmute, os::raw::c_char, ptr::null, time::Duration};
+use std::{collections::HashMap, ops::Deref, os::raw::c_char, ptr::null, time::Duration};
 
 use crate::{
+    allocator::UserAllocator,
     error::ErrorCode,
     from_char_array,
-    memory::{Buffer, DataType, MemoryType},
+    memory::{
+        Buffer, BufferAttributes, DataType, MemoryType, Sample, TritonBufferAttributes,
+        CUDA_IPC_HANDLE_SIZE,
+    },
     message::Shape,
-    parameter::{Parameter, ParameterContent},
+    parameter::{base64_encode, ParameterContent, RequestParameter, RESPONSE_CACHE_PARAMETER},
+    request::infer::InputRefs,
     run_in_context,
     sys::{
         self, TRITONSERVER_InferenceRequestRemoveAllInputData,
@@ -19,12 +36,39 @@ use crate::{
     to_cstring, Error, Server,
 };
 
-/// Inference request sequence flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-#[repr(u32)]
-pub enum Sequence {
-    Start = sys::tritonserver_requestflag_enum_TRITONSERVER_REQUEST_FLAG_SEQUENCE_START,
-    End = sys::tritonserver_requestflag_enum_TRITONSERVER_REQUEST_FLAG_SEQUENCE_END,
+bitflags::bitflags! {
+    /// Inference request flags. Any combination of flags can be set. \
+    /// Flags can be combined: ([RequestFlags::SEQUENCE_START] | [RequestFlags::SEQUENCE_END]) marks the sequence of the single request. \
+    /// Check [Sequence] for the convenient way to send the sequence of requests.
+    pub struct RequestFlags: u32 {
+        /// The request starts the sequence.
+        const SEQUENCE_START = sys::tritonserver_requestflag_enum_TRITONSERVER_REQUEST_FLAG_SEQUENCE_START;
+        /// The request ends the sequence.
+        const SEQUENCE_END = sys::tritonserver_requestflag_enum_TRITONSERVER_REQUEST_FLAG_SEQUENCE_END;
+    }
+}
+
+/// Input buffer size check mode. \
+/// Determines how the byte size of an input buffer is matched against the byte size required by the input shape. \
+/// Can be set per request via [Request::set_size_check].
+///
+/// **Note**: if the shape is empty, contains dynamic (-1) dimensions or the input datatype is [DataType::Bytes],
+/// the required size can't be determined exactly, so every mode only checks that the buffer is not smaller than the shape min size.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SizeCheck {
+    /// Buffer byte size must be equal to the shape byte size.
+    Strict,
+    /// Buffer byte size must be greater or equal to the shape byte size.
+    /// The whole buffer is passed to Triton.
+    #[default]
+    AllowLarger,
+    /// Buffer byte size must be greater or equal to the shape byte size.
+    /// Only the first shape byte size bytes of the buffer are passed to Triton.
+    Truncate,
+    /// Buffers smaller than the shape byte size are copied to the new buffer of the required size padded with zeros
+    /// (the padded buffer will be returned after the inference instead of the original one). \
+    /// Buffers larger than the shape byte size are truncated as in [SizeCheck::Truncate].
+    PadZero,
 }
 
 /// Allocator, that user provides in order to allocate output buffers when they are needed for Triton. \
@@ -44,7 +88,8 @@ pub trait Allocator: Send {
     /// - It's not necessary to allocate buffer on exact requested_memory_type: for example,
     ///     it's fine to allocate buffer on Pinned when Triton requested GPU buffer.
     ///     The only requirement is not to allocate CPU memory when GPU is requested and vice versa.
-    /// - Buffer of greater or equal size than requested `byte_size` can be allocated but not the smaller.
+    /// - Buffer of greater or equal size than requested `byte_size` should be allocated.
+    ///     If the smaller one is returned, [Allocator::grow] is called.
     /// - Allocated buffer's datatype must match this output datatype specified in the model's config.
     /// - Method will be invoked in asynchronous context.
     async fn allocate(
@@ -55,6 +100,57 @@ pub trait Allocator: Send {
         data_type: DataType,
     ) -> Result<Buffer, Error>;
 
+    /// Allocate output buffer for output with name `tensor_name` on the device `device_id` requested by Triton
+    /// (memory type id of the request, it's meaningful only for the GPU memory). \
+    /// This is the method Triton allocation calls. Default implementation ignores the device and calls [Allocator::allocate],
+    /// so the allocators that place buffers on the fixed device ([Allocator::device_id]) don't need to implement it.
+    async fn allocate_on(
+        &mut self,
+        tensor_name: String,
+        requested_memory_type: MemoryType,
+        device_id: i32,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        let _ = device_id;
+        self.allocate(tensor_name, requested_memory_type, byte_size, data_type)
+            .await
+    }
+
+    /// Allocate output buffer for output with name `tensor_name` in the memory domain of the host policy `host_policy`
+    /// set for the output with [Request::set_output_host_policy] (e.g. on the NUMA node the policy pins the model instance to). \
+    /// Called instead of [Allocator::allocate_on] for the outputs with the host policy.
+    /// Default implementation ignores the policy and calls [Allocator::allocate_on].
+    async fn allocate_with_policy(
+        &mut self,
+        tensor_name: String,
+        requested_memory_type: MemoryType,
+        device_id: i32,
+        host_policy: String,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        let _ = host_policy;
+        self.allocate_on(
+            tensor_name,
+            requested_memory_type,
+            device_id,
+            byte_size,
+            data_type,
+        )
+        .await
+    }
+
+    /// Called when Triton releases the output buffer (the response is dropped or its buffers are returned),
+    /// before the buffer is given back to the user with [crate::Response::return_buffers] or [InferenceError::output_buffers]. \
+    /// Pooling allocators can reclaim the buffer right away by returning None: then it's not given back to the user.
+    /// It's also the exact moment the output memory is no longer used by Triton. \
+    /// Default implementation returns the `buffer`.
+    async fn on_release(&mut self, tensor_name: String, buffer: Buffer) -> Option<Buffer> {
+        let _ = tensor_name;
+        Some(buffer)
+    }
+
     /// Unable or not a pre allocation queriing. For more info about queriing see [Allocator::pre_allocation_query]. \
     /// Default is false.
     fn enable_queries(&self) -> bool {
@@ -80,32 +176,326 @@ pub trait Allocator: Send {
     ) -> MemoryType {
         requested_memory_type
     }
+
+    /// Id of the device on which the allocator places GPU buffers. \
+    /// None means that buffers are allocated on the device requested by Triton. \
+    /// Default is None.
+    fn device_id(&self) -> Option<i32> {
+        None
+    }
+
+    /// Called when the buffer returned by [Allocator::allocate] is smaller than `byte_size` required by Triton,
+    /// for example, if the allocator estimates size of the variable-size BYTES output instead of using the requested size. \
+    /// `buffer`: the buffer returned by [Allocator::allocate]. \
+    /// `byte_size`: exact size that Triton requires. \
+    /// Returned buffer must be at least `byte_size` bytes, otherwise the inference fails with error.
+    ///
+    /// Default implementation drops the `buffer` and allocates a new one of exact `byte_size` bytes
+    /// with the same memory and data type on the [Allocator::device_id] device (the device of the `buffer` if None).
+    async fn grow(
+        &mut self,
+        tensor_name: String,
+        buffer: Buffer,
+        byte_size: usize,
+    ) -> Result<Buffer, Error> {
+        log::debug!(
+            "Growing buffer of output {tensor_name} from {}b to {byte_size}b",
+            buffer.len
+        );
+        let data_type_size = buffer.data_type.size();
+        #[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+        let device_id = self.device_id().unwrap_or(buffer.device_id);
+        run_in_context!(device_id, {
+            let grown = Buffer::alloc_with_data_type(
+                (byte_size as f32 / data_type_size as f32).ceil() as usize,
+                buffer.memory_type,
+                buffer.data_type,
+            );
+            // Free the old buffer in the context it was allocated in.
+            drop(buffer);
+            grown
+        })
+    }
+}
+
+/// Inputs of the request: name, content and shape of each input.
+pub type Inputs = Vec<(String, Buffer, Vec<i64>)>;
+
+/// Synchronous variant of [Allocator] for the allocators that never await, e.g. the lookups in the buffer pool. \
+/// Its methods are called right in the Triton callbacks, bypassing the round trip to the async runtime
+/// the [Allocator] methods take on each call. Check [Request::add_blocking_allocator].
+///
+/// **Note**: the methods block the Triton thread, so they should be fast. \
+/// **Note**: unlike [Allocator::grow], the buffer smaller than the requested `byte_size` fails the allocation.
+pub trait BlockingAllocator: Send {
+    /// Allocate output buffer for output with name `tensor_name`. Check [Allocator::allocate] for the requirements.
+    fn allocate(
+        &mut self,
+        tensor_name: &str,
+        requested_memory_type: MemoryType,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error>;
+
+    /// Allocate output buffer on the device `device_id` requested by Triton. Check [Allocator::allocate_on]. \
+    /// Default implementation ignores the device and calls [BlockingAllocator::allocate].
+    fn allocate_on(
+        &mut self,
+        tensor_name: &str,
+        requested_memory_type: MemoryType,
+        device_id: i32,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        let _ = device_id;
+        self.allocate(tensor_name, requested_memory_type, byte_size, data_type)
+    }
+
+    /// Allocate output buffer in the memory domain of the `host_policy`. Check [Allocator::allocate_with_policy]. \
+    /// Default implementation ignores the policy and calls [BlockingAllocator::allocate_on].
+    fn allocate_with_policy(
+        &mut self,
+        tensor_name: &str,
+        requested_memory_type: MemoryType,
+        device_id: i32,
+        host_policy: &str,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        let _ = host_policy;
+        self.allocate_on(
+            tensor_name,
+            requested_memory_type,
+            device_id,
+            byte_size,
+            data_type,
+        )
+    }
+
+    /// Enable the pre allocation queries. Check [Allocator::enable_queries]. Default is false.
+    fn enable_queries(&self) -> bool {
+        false
+    }
+
+    /// Query the allocator's preferred memory type. Check [Allocator::pre_allocation_query].
+    #[allow(unused_variables)]
+    fn pre_allocation_query(
+        &mut self,
+        tensor_name: Option<&str>,
+        byte_size: Option<usize>,
+        requested_memory_type: MemoryType,
+    ) -> MemoryType {
+        requested_memory_type
+    }
+
+    /// Id of the device on which the allocator places GPU buffers. Check [Allocator::device_id]. Default is None.
+    fn device_id(&self) -> Option<i32> {
+        None
+    }
+
+    /// Called when Triton releases the output buffer. Check [Allocator::on_release]. \
+    /// Default implementation returns the `buffer`.
+    fn on_release(&mut self, tensor_name: &str, buffer: Buffer) -> Option<Buffer> {
+        let _ = tensor_name;
+        Some(buffer)
+    }
 }
 
 /// Default allocator.
 ///
-/// Will allocate exact `byte_size` bytes of datatype `data_type` of `requested_memory_type` for each output.
-#[derive(Debug, Default, Clone, Copy)]
-pub struct DefaultAllocator;
+/// Will allocate exact `byte_size` bytes of datatype `data_type` of `requested_memory_type` for each output
+/// on the device requested by Triton (device 0 if [Allocator::allocate] is called directly). \
+/// [DefaultAllocator::cpu_only] and [DefaultAllocator::prefer] override the memory type within the host memory (Cpu and Pinned)
+/// and report it to Triton with the pre allocation queries, so Triton can request it instead of the memory type it prefers. \
+/// Check [DeviceAllocator] to place the outputs on the specific device of the multi-GPU system.
+/// ```ignore
+/// // CPU-only deployment.
+/// request.add_allocator(Box::new(DefaultAllocator::cpu_only()));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DefaultAllocator {
+    memory_type: Option<MemoryType>,
+    /// Allocate the requested memory type if [DefaultAllocator::memory_type] can't be used.
+    fallback: bool,
+}
+
+impl DefaultAllocator {
+    /// Allocator placing all the outputs in the CPU memory, e.g. for CPU-only deployments. \
+    /// Pinned outputs are allocated in the CPU memory. The allocation of the GPU output fails with [ErrorCode::Unsupported],
+    /// so Triton reports the error instead of the output.
+    pub fn cpu_only() -> Self {
+        DefaultAllocator {
+            memory_type: Some(MemoryType::Cpu),
+            fallback: false,
+        }
+    }
+
+    /// Allocator preferring `memory_type` memory for the outputs: it's reported to Triton with the pre allocation queries. \
+    /// The host outputs (Cpu and Pinned) are allocated in `memory_type` memory if it's the host memory too.
+    /// Otherwise, or if the allocation fails, the memory type requested by Triton is allocated.
+    pub fn prefer(memory_type: MemoryType) -> Self {
+        DefaultAllocator {
+            memory_type: Some(memory_type),
+            fallback: true,
+        }
+    }
+}
+
+/// Can the buffer of `memory_type` be returned to Triton requesting `requested` memory:
+/// both are either host (Cpu, Pinned) or Gpu.
+fn compatible(memory_type: MemoryType, requested: MemoryType) -> bool {
+    (memory_type == MemoryType::Gpu) == (requested == MemoryType::Gpu)
+}
 
 #[async_trait::async_trait]
 impl Allocator for DefaultAllocator {
     async fn allocate(
         &mut self,
-        _tensor_name: String,
+        tensor_name: String,
         requested_mem_type: MemoryType,
         byte_size: usize,
         data_type: DataType,
     ) -> Result<Buffer, Error> {
-        let data_type_size = data_type.size();
-        run_in_context!(
-            0,
-            Buffer::alloc_with_data_type(
-                (byte_size as f32 / data_type_size as f32).ceil() as usize,
-                requested_mem_type,
-                data_type,
-            )
+        self.allocate_on(tensor_name, requested_mem_type, 0, byte_size, data_type)
+            .await
+    }
+
+    async fn allocate_on(
+        &mut self,
+        tensor_name: String,
+        requested_mem_type: MemoryType,
+        device_id: i32,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        let mem_type = match self.memory_type {
+            None => requested_mem_type,
+            Some(mem_type) if compatible(mem_type, requested_mem_type) => mem_type,
+            Some(_) if self.fallback => requested_mem_type,
+            Some(mem_type) => {
+                return Err(Error::new(
+                    ErrorCode::Unsupported,
+                    format!(
+                        "Output {tensor_name} is requested in {requested_mem_type:?} memory, while the allocator places outputs in {mem_type:?} memory only"
+                    ),
+                ))
+            }
+        };
+        match alloc_exact(mem_type, device_id, byte_size, data_type).await {
+            Err(err) if self.fallback && mem_type != requested_mem_type => {
+                log::debug!(
+                    "Can't allocate {mem_type:?} for output {tensor_name}, allocating {requested_mem_type:?}: {err}"
+                );
+                alloc_exact(requested_mem_type, device_id, byte_size, data_type).await
+            }
+            result => result,
+        }
+    }
+
+    fn enable_queries(&self) -> bool {
+        self.memory_type.is_some()
+    }
+
+    async fn pre_allocation_query(
+        &mut self,
+        _tensor_name: Option<String>,
+        _byte_size: Option<usize>,
+        requested_memory_type: MemoryType,
+    ) -> MemoryType {
+        self.memory_type.unwrap_or(requested_memory_type)
+    }
+}
+
+/// Allocate exact `byte_size` bytes of `data_type` with the cuda context of `device_id` pinned.
+#[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+async fn alloc_exact(
+    mem_type: MemoryType,
+    device_id: i32,
+    byte_size: usize,
+    data_type: DataType,
+) -> Result<Buffer, Error> {
+    let data_type_size = data_type.size();
+    run_in_context!(
+        device_id,
+        Buffer::alloc_with_data_type(
+            (byte_size as f32 / data_type_size as f32).ceil() as usize,
+            mem_type,
+            data_type,
         )
+    )
+}
+
+/// Allocator placing GPU and Pinned buffers on the specified device.
+///
+/// Will allocate exact `byte_size` bytes of datatype `data_type` of `requested_memory_type` for each output,
+/// as [DefaultAllocator] does, but with the cuda context of the device `device_id` pinned instead of the device requested by Triton. \
+/// Pinned buffers are placed on the NUMA node the device is attached to when it's known (Linux only),
+/// so the host-device copies don't cross the socket interconnect. Otherwise, or if such allocation fails,
+/// they are allocated as usual with the device context.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceAllocator {
+    device_id: i32,
+    /// NUMA node of the device, looked up on the first Pinned allocation.
+    numa_node: Option<Option<u32>>,
+}
+
+impl DeviceAllocator {
+    /// Create allocator placing buffers on the device `device_id`.
+    pub fn new(device_id: i32) -> Self {
+        DeviceAllocator {
+            device_id,
+            numa_node: None,
+        }
+    }
+
+    /// NUMA node of the device. None if it's unknown.
+    #[cfg(all(feature = "gpu", target_os = "linux"))]
+    fn numa_node(&mut self) -> Option<u32> {
+        let device_id = self.device_id;
+        *self.numa_node.get_or_insert_with(|| {
+            crate::context::get_context(device_id)
+                .and_then(|_| crate::context::CuDevice::new(device_id)?.get_numa_node())
+                .unwrap_or_else(|err| {
+                    log::debug!("Can't get NUMA node of device {device_id}: {err}");
+                    None
+                })
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Allocator for DeviceAllocator {
+    async fn allocate(
+        &mut self,
+        _tensor_name: String,
+        requested_mem_type: MemoryType,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        let count = (byte_size as f32 / data_type.size() as f32).ceil() as usize;
+        #[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+        let device_id = self.device_id;
+        #[cfg(all(feature = "gpu", target_os = "linux"))]
+        let node = match requested_mem_type {
+            MemoryType::Pinned => self.numa_node(),
+            _ => None,
+        };
+        run_in_context!(device_id, {
+            #[cfg(all(feature = "gpu", target_os = "linux"))]
+            if let Some(node) = node {
+                match Buffer::alloc_pinned_on_node(count, data_type, device_id, node) {
+                    Ok(buffer) => return Ok(buffer),
+                    Err(err) => {
+                        log::debug!("Can't allocate pinned memory on NUMA node {node}: {err}")
+                    }
+                }
+            }
+            Buffer::alloc_with_data_type(count, requested_mem_type, data_type)
+        })
+    }
+
+    fn device_id(&self) -> Option<i32> {
+        Some(self.device_id)
     }
 }
 
@@ -115,27 +505,77 @@ impl Allocator for DefaultAllocator {
 /// It's required to add input data and Allocator to this structure before the inference via one of [add_input](Request::add_input) methods  via [Request::add_allocator] or [Request::add_default_allocator] method.
 pub struct Request<'a> {
     ptr: *mut sys::TRITONSERVER_InferenceRequest,
-    model_name: String,
+    pub(crate) model_name: String,
+    /// Version of the model, -1 if the server chooses it.
+    version: i64,
     input: HashMap<String, Buffer>,
-    custom_allocator: Option<Box<dyn Allocator>>,
+    /// Chunks of the inputs added with [Request::append_input_chunk]. Appended to Triton on the inference start.
+    input_chunks: HashMap<String, Vec<Buffer>>,
+    custom_allocator: Option<UserAllocator>,
     custom_trace: Option<Trace>,
+    size_check: SizeCheck,
+    /// Inputs added with [BorrowedInputs].
+    input_refs: Option<InputRefs>,
+    /// Namespace the request usage is accounted to. Check [Request::set_namespace].
+    namespace: Option<String>,
+    /// Outputs selected with [Request::request_outputs]. None means all the model outputs.
+    requested_outputs: Option<Vec<String>>,
+    /// CUDA IPC handles of the inputs added with [Request::add_input_with_attributes].
+    /// Triton keeps the pointers to them until the request is released, so they are boxed to not move.
+    #[allow(clippy::vec_box)]
+    ipc_handles: Vec<Box<[u8; CUDA_IPC_HANDLE_SIZE]>>,
+    /// Host policies of the outputs set with [Request::set_output_host_policy].
+    output_host_policies: HashMap<String, String>,
+    /// GPU the request is pinned to with [Request::set_device].
+    device: Option<i32>,
+    /// Generation of the server the request is created on. Check [crate::server::Supervisor].
+    generation: u64,
     // Уверяемся, что Server не дропнется во время выполнения Request. \
     // Server(Arc<Inner>)
-    server: &'a Server,
+    server: ServerRef<'a>,
+}
+
+/// Server of the request: borrowed ([Server::create_request]) or owned ([Server::create_request_owned]).
+pub(crate) enum ServerRef<'a> {
+    Borrowed(&'a Server),
+    Owned(Server),
+}
+
+impl Deref for ServerRef<'_> {
+    type Target = Server;
+
+    fn deref(&self) -> &Server {
+        match self {
+            ServerRef::Borrowed(server) => server,
+            ServerRef::Owned(server) => server,
+        }
+    }
 }
 
 impl<'a> Request<'a> {
     pub(crate) fn new<M: AsRef<str>>(
         ptr: *mut sys::TRITONSERVER_InferenceRequest,
-        server: &'a Server,
+        generation: u64,
+        server: ServerRef<'a>,
         model: M,
+        version: i64,
     ) -> Result<Request<'a>, Error> {
         Ok(Request {
             ptr,
             model_name: model.as_ref().to_string(),
+            version,
             input: HashMap::new(),
+            input_chunks: HashMap::new(),
             custom_allocator: None,
             custom_trace: None,
+            size_check: SizeCheck::default(),
+            input_refs: None,
+            namespace: None,
+            requested_outputs: None,
+            ipc_handles: Vec::new(),
+            output_host_policies: HashMap::new(),
+            device: None,
+            generation,
             server,
         })
     }
@@ -143,14 +583,30 @@ impl<'a> Request<'a> {
     /// Add custom Allocator to the request. \
     /// Check [Allocator] trait for more info.
     pub fn add_allocator(&mut self, custom_allocator: Box<dyn Allocator>) -> &mut Self {
-        let _ = self.custom_allocator.replace(custom_allocator);
+        let _ = self
+            .custom_allocator
+            .replace(UserAllocator::Async(custom_allocator));
+        self
+    }
+
+    /// Add custom blocking allocator to the request. Replaces the allocator added before. \
+    /// Check [BlockingAllocator] trait for more info.
+    pub fn add_blocking_allocator(
+        &mut self,
+        custom_allocator: Box<dyn BlockingAllocator>,
+    ) -> &mut Self {
+        let _ = self
+            .custom_allocator
+            .replace(UserAllocator::Blocking(custom_allocator));
         self
     }
 
     /// Add [DefaultAllocator] to the request. \
     /// Check [Allocator] trait and [DefaultAllocator] for more info.
     pub fn add_default_allocator(&mut self) -> &mut Self {
-        let _ = self.custom_allocator.replace(Box::new(DefaultAllocator));
+        let _ = self
+            .custom_allocator
+            .replace(UserAllocator::Async(Box::<DefaultAllocator>::default()));
         self
     }
 
@@ -162,6 +618,22 @@ impl<'a> Request<'a> {
         self
     }
 
+    /// Account the usage of the request (request count, inputs and outputs size, compute time)
+    /// to the model `namespace` and check the quota of the namespace on the inference. \
+    /// Check [Server::namespace_usage] and [Server::set_namespace_quota].
+    pub fn set_namespace<N: AsRef<str>>(&mut self, namespace: N) -> &mut Self {
+        self.namespace = Some(namespace.as_ref().to_string());
+        self
+    }
+
+    /// Set the mode of input buffer size checking. \
+    /// Affects only inputs added after this call. Default is [SizeCheck::AllowLarger]. \
+    /// Check [SizeCheck] for more info.
+    pub fn set_size_check(&mut self, size_check: SizeCheck) -> &mut Self {
+        self.size_check = size_check;
+        self
+    }
+
     /// Get the ID of the request.
     pub fn get_id(&self) -> Result<String, Error> {
         let mut id = null::<c_char>();
@@ -171,6 +643,47 @@ impl<'a> Request<'a> {
         )
     }
 
+    /// Pin the request to the GPU `device_id`: GPU outputs are allocated on this device
+    /// (the allocator gets it instead of the device requested by Triton, e.g. [DefaultAllocator] pushes its cuda context)
+    /// and GPU inputs must be located on it. \
+    /// Returns error if the device doesn't exist or the GPU input added before is located on another device.
+    pub fn set_device(&mut self, device_id: i32) -> Result<&mut Self, Error> {
+        if device_id < 0 {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!("Invalid device id {device_id}"),
+            ));
+        }
+        #[cfg(feature = "gpu")]
+        crate::context::get_context(device_id)?;
+
+        self.device = Some(device_id);
+        let chunks = self
+            .input_chunks
+            .iter()
+            .flat_map(|(name, chunks)| chunks.iter().map(move |chunk| (name, chunk)));
+        for (name, buffer) in self.input.iter().chain(chunks) {
+            self.check_device(name, buffer)?;
+        }
+        Ok(self)
+    }
+
+    /// Check the GPU `buffer` of input `input_name` is located on the device of the request.
+    fn check_device(&self, input_name: &str, buffer: &Buffer) -> Result<(), Error> {
+        match self.device {
+            Some(device) if buffer.memory_type == MemoryType::Gpu && buffer.device_id != device => {
+                Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Input \"{input_name}\" is located on device {}, but the request is pinned to device {device}",
+                        buffer.device_id
+                    ),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Set the ID of the request.
     pub fn set_id<I: AsRef<str>>(&mut self, id: I) -> Result<&mut Self, Error> {
         let id = to_cstring(id)?;
@@ -181,21 +694,20 @@ impl<'a> Request<'a> {
     }
 
     /// Get the flag(s) associated with the request. \
-    /// Check [Sequence] for available flags.
-    pub fn get_flags(&self) -> Result<Sequence, Error> {
+    /// Check [RequestFlags] for available flags.
+    pub fn get_flags(&self) -> Result<RequestFlags, Error> {
         let mut flag: u32 = 0;
-        triton_call!(sys::TRITONSERVER_InferenceRequestFlags(
-            self.ptr,
-            &mut flag as *mut _
-        ))?;
-        unsafe { Ok(transmute::<u32, Sequence>(flag)) }
+        triton_call!(
+            sys::TRITONSERVER_InferenceRequestFlags(self.ptr, &mut flag as *mut _),
+            RequestFlags::from_bits_truncate(flag)
+        )
     }
 
     /// Set the flag(s) associated with a request. \
-    /// Check [Sequence] for available flags.
-    pub fn set_flags(&mut self, flags: Sequence) -> Result<&mut Self, Error> {
+    /// Check [RequestFlags] for available flags.
+    pub fn set_flags(&mut self, flags: RequestFlags) -> Result<&mut Self, Error> {
         triton_call!(
-            sys::TRITONSERVER_InferenceRequestSetFlags(self.ptr, flags as _),
+            sys::TRITONSERVER_InferenceRequestSetFlags(self.ptr, flags.bits()),
             self
         )
     }
@@ -292,6 +804,17 @@ impl<'a> Request<'a> {
         )
     }
 
+    /// Let the request skip the response cache while it stays enabled for the other requests of the model
+    /// (check [ModelConfigBuilder::response_cache](crate::message::ModelConfigBuilder::response_cache)). \
+    /// The request is neither looked up in the cache nor its response is inserted to it.
+    pub fn disable_response_cache(&mut self) -> Result<&mut Self, Error> {
+        let name = to_cstring(RESPONSE_CACHE_PARAMETER)?;
+        triton_call!(
+            sys::TRITONSERVER_InferenceRequestSetBoolParameter(self.ptr, name.as_ptr(), false),
+            self
+        )
+    }
+
     /// Add an input to the request.\
     /// `input_name`: The name of the input. \
     /// `buffer`: input data containing buffer. \
@@ -304,6 +827,47 @@ impl<'a> Request<'a> {
         self.add_input_inner(input_name, buffer, None::<String>, None::<Vec<i64>>)
     }
 
+    /// Append the chunk of the input data, so the input assembled from several buffers
+    /// (e.g. the network fragments) doesn't need to be concatenated.\
+    /// `input_name`: The name of the input. \
+    /// `buffer`: the next chunk of the input data.
+    ///
+    /// The chunks are appended to Triton in the order of the calls on the inference start,
+    /// the input dims are resolved from the model config and the total size of the chunks
+    /// (at most one dynamic dim is allowed). \
+    /// Note: unlike the other inputs, the chunks are not returned by [ResponseFuture::get_input_release],
+    /// they are dropped when Triton releases the request.
+    pub fn append_input_chunk<N: AsRef<str>>(
+        &mut self,
+        input_name: N,
+        buffer: Buffer,
+    ) -> Result<&mut Self, Error> {
+        let input_name = input_name.as_ref();
+        if self.input.contains_key(input_name) {
+            return Err(Error::new(
+                ErrorCode::Alreadyxists,
+                format!("Request already has buffer for input \"{input_name}\""),
+            ));
+        }
+        let shape = self.get_shape(input_name)?;
+        if shape.datatype != buffer.data_type {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "input chunk datatype {:?} missmatches model shape datatype: {:?}. input name: {input_name}",
+                    buffer.data_type, shape.datatype
+                ),
+            ));
+        }
+        self.check_device(input_name, &buffer)?;
+
+        self.input_chunks
+            .entry(input_name.to_string())
+            .or_default()
+            .push(buffer);
+        Ok(self)
+    }
+
     /// Add an input with the specified shape to the request.\
     /// `input_name`: The name of the input. \
     /// `buffer`: input data containing buffer. \
@@ -322,6 +886,42 @@ impl<'a> Request<'a> {
         self.add_input_inner(input_name, buffer, None::<String>, Some(dims))
     }
 
+    /// Add an input to the request with the dims taken from the `array` shape.\
+    /// `input_name`: The name of the input. \
+    /// `array`: input data. Copied to the CPU buffer.\
+    /// Note: input data will be returned after the inference. Check [ResponseFuture::get_input_release] for more info.
+    #[cfg(feature = "ndarray")]
+    pub fn add_input_array<N, T, D>(
+        &mut self,
+        input_name: N,
+        array: ndarray::Array<T, D>,
+    ) -> Result<&mut Self, Error>
+    where
+        N: AsRef<str>,
+        T: Sample,
+        D: ndarray::Dimension,
+    {
+        let dims: Vec<i64> = array.shape().iter().map(|dim| *dim as i64).collect();
+        let buffer = Buffer::try_from(array)?;
+        self.add_input_inner(input_name, buffer, None::<String>, Some(dims))
+    }
+
+    /// Add the columns of the `batch` as the inputs named after the columns. \
+    /// The first dim of each input is the number of the batch rows
+    /// (`FixedSizeList` columns have the second dim of the list size). Check [Buffer::from_arrow] for the supported columns.\
+    /// Note: input data will be returned after the inference. Check [ResponseFuture::get_input_release] for more info.
+    #[cfg(feature = "arrow")]
+    pub fn add_record_batch(
+        &mut self,
+        batch: &arrow_array::RecordBatch,
+    ) -> Result<&mut Self, Error> {
+        for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+            let (buffer, dims) = Buffer::from_arrow(column.as_ref())?;
+            self.add_input_inner(field.name(), buffer, None::<String>, Some(dims))?;
+        }
+        Ok(self)
+    }
+
     /// Add an input with the specified host policy to the request.\
     /// `input_name`: The name of the input.\
     /// `buffer`: input data containing buffer. \
@@ -361,6 +961,45 @@ impl<'a> Request<'a> {
         self.add_input_inner(input_name, buffer, Some(policy), Some(dims))
     }
 
+    /// Add an input with the explicit buffer attributes to the request.\
+    /// `input_name`: The name of the input.\
+    /// `buffer`: input data containing buffer. \
+    /// `attributes`: attributes of the buffer passed to Triton: memory type id, byte size and CUDA IPC handle.
+    /// The attributes that are not set are taken from the buffer.\
+    /// Note: input data will be returned after the inference. Check [ResponseFuture::get_input_release] for more info.
+    pub fn add_input_with_attributes<N: AsRef<str>>(
+        &mut self,
+        input_name: N,
+        buffer: Buffer,
+        attributes: BufferAttributes,
+    ) -> Result<&mut Self, Error> {
+        let (buffer, byte_size) =
+            self.prepare_input(&input_name, buffer, None::<Vec<i64>>, self.size_check)?;
+        self.append_input_data_with_attributes(input_name, buffer, byte_size, attributes)
+    }
+
+    /// Add an input with the explicit buffer attributes and shape to the request.\
+    /// `input_name`: The name of the input.\
+    /// `buffer`: input data containing buffer. \
+    /// `attributes`: attributes of the buffer passed to Triton (check [Request::add_input_with_attributes]).\
+    /// `dims`: Dimensions of the input.\
+    /// Note: input data will be returned after the inference. Check [ResponseFuture::get_input_release] for more info.
+    pub fn add_input_with_attributes_and_dims<N, D>(
+        &mut self,
+        input_name: N,
+        buffer: Buffer,
+        attributes: BufferAttributes,
+        dims: D,
+    ) -> Result<&mut Self, Error>
+    where
+        N: AsRef<str>,
+        D: AsRef<[i64]>,
+    {
+        let (buffer, byte_size) =
+            self.prepare_input(&input_name, buffer, Some(dims), self.size_check)?;
+        self.append_input_data_with_attributes(input_name, buffer, byte_size, attributes)
+    }
+
     fn add_input_inner<N, P, D>(
         &mut self,
         input_name: N,
@@ -373,7 +1012,70 @@ impl<'a> Request<'a> {
         P: AsRef<str>,
         D: AsRef<[i64]>,
     {
-        if self.input.contains_key(input_name.as_ref()) {
+        let (buffer, byte_size) = self.prepare_input(&input_name, buffer, dims, self.size_check)?;
+        if let Some(policy) = policy {
+            self.append_input_data_with_policy(input_name, &policy, buffer, byte_size)?;
+        } else {
+            self.append_input_data(input_name, buffer, byte_size)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Add an input to the request with the dims resolved from the `dims` pattern, the model config and the buffer length.\
+    /// `input_name`: The name of the input. \
+    /// `buffer`: input data containing buffer. \
+    /// `dims`: pattern of the input dims, e.g. `&[Some(1), None, Some(3)]`: `Some` dims must match the model config ones
+    /// (unless they are dynamic), `None` dims are taken from the model config.
+    /// One dim that is still unknown (`None` for the dynamic model dim) is inferred from the buffer length. \
+    /// If all the dims are known, the buffer smaller than the shape is padded with zeros as in [SizeCheck::PadZero].\
+    /// Note: input data will be returned after the inference. Check [ResponseFuture::get_input_release] for more info.
+    pub fn add_input_auto<N: AsRef<str>>(
+        &mut self,
+        input_name: N,
+        buffer: Buffer,
+        dims: &[Option<i64>],
+    ) -> Result<&mut Self, Error> {
+        self.check_device(input_name.as_ref(), &buffer)?;
+        let model_shape = self.get_shape(input_name.as_ref())?;
+        let dims = resolve_dims(
+            dims,
+            &model_shape.dims,
+            element_size(model_shape.datatype),
+            buffer.size(),
+        )
+        .map_err(|err| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!("{err}. input name: {}", input_name.as_ref()),
+            )
+        })?;
+        let size_check = match self.size_check {
+            SizeCheck::Strict => SizeCheck::Strict,
+            _ => SizeCheck::PadZero,
+        };
+        let (buffer, byte_size) =
+            self.prepare_input(&input_name, buffer, Some(dims), size_check)?;
+        self.append_input_data(input_name, buffer, byte_size)
+    }
+
+    /// Check the input buffer against the input shape and add the input to Triton request.
+    /// If `dims` are not set, the model config ones are used with the only dynamic dim inferred from the buffer length.
+    /// Returns the buffer and the number of its bytes to append.
+    fn prepare_input<N, D>(
+        &mut self,
+        input_name: N,
+        buffer: Buffer,
+        dims: Option<D>,
+        size_check: SizeCheck,
+    ) -> Result<(Buffer, usize), Error>
+    where
+        N: AsRef<str>,
+        D: AsRef<[i64]>,
+    {
+        if self.input.contains_key(input_name.as_ref())
+            || self.input_chunks.contains_key(input_name.as_ref())
+        {
             return Err(Error::new(
                 ErrorCode::Alreadyxists,
                 format!(
@@ -391,29 +1093,49 @@ impl<'a> Request<'a> {
                 dims: dims.as_ref().to_vec(),
             }
         } else {
+            let pattern = vec![None; model_shape.dims.len()];
             Shape {
                 name: input_name.as_ref().to_string(),
                 datatype: model_shape.datatype,
-                dims: model_shape.dims.clone(),
+                dims: resolve_dims(
+                    &pattern,
+                    &model_shape.dims,
+                    element_size(model_shape.datatype),
+                    buffer.size(),
+                )
+                .unwrap_or_else(|_| model_shape.dims.clone()),
             }
         };
 
-        assert_buffer_shape(&shape, &buffer, input_name.as_ref())?;
-
-        self.add_input_triton(&input_name, &shape)?;
-
-        if let Some(policy) = policy {
-            self.append_input_data_with_policy(input_name, &policy, buffer)?;
-        } else {
-            self.append_input_data(input_name, buffer)?;
+        match (
+            self.server.max_batch_size(&self.model_name),
+            shape.dims.first(),
+        ) {
+            (Some(max_batch_size), Some(&batch))
+                if max_batch_size > 0 && batch > max_batch_size as i64 =>
+            {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Batch dim {batch} of input {} exceeds max_batch_size {max_batch_size} of model {}",
+                        input_name.as_ref(),
+                        self.model_name
+                    ),
+                ));
+            }
+            _ => {}
         }
 
-        Ok(self)
+        let (buffer, byte_size) =
+            check_buffer_shape(&shape, buffer, input_name.as_ref(), size_check)?;
+
+        self.add_input_triton(&input_name, &shape)?;
+        Ok((buffer, byte_size))
     }
 
-    fn get_shape<N: AsRef<str>>(&self, source: N) -> Result<&Shape, Error> {
+    fn get_shape<N: AsRef<str>>(&self, source: N) -> Result<Shape, Error> {
         let model_name = &self.model_name;
-        let model = self.server.get_model(model_name)?;
+        let model = self.server.get_model_version(model_name, self.version)?;
 
         match model
             .inputs
@@ -424,7 +1146,7 @@ impl<'a> Request<'a> {
                 ErrorCode::Internal,
                 format!("Model {model_name} has no input named: {}", source.as_ref()),
             )),
-            Some(shape) => Ok(shape),
+            Some(shape) => Ok(shape.clone()),
         }
     }
 
@@ -443,26 +1165,80 @@ impl<'a> Request<'a> {
         &mut self,
         input_name: I,
         buffer: Buffer,
+        byte_size: usize,
     ) -> Result<&mut Self, Error> {
         let name = to_cstring(&input_name)?;
         triton_call!(sys::TRITONSERVER_InferenceRequestAppendInputData(
             self.ptr,
             name.as_ptr(),
             buffer.ptr,
-            buffer.len,
+            byte_size,
             buffer.memory_type as u32,
-            0,
+            buffer.memory_type_id(),
         ))?;
 
         let _ = self.input.insert(input_name.as_ref().to_string(), buffer);
         Ok(self)
     }
 
+    /// Add the inputs assembled with [Request::append_input_chunk] to Triton request.
+    pub(super) fn add_chunked_inputs(&self) -> Result<(), Error> {
+        for (input_name, chunks) in &self.input_chunks {
+            let model_shape = self.get_shape(input_name)?;
+            let byte_size = chunks.iter().map(Buffer::size).sum();
+            let dims = resolve_dims(
+                &vec![None; model_shape.dims.len()],
+                &model_shape.dims,
+                element_size(model_shape.datatype),
+                byte_size,
+            )
+            .map_err(|err| {
+                Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Can't resolve dims of the chunked input {input_name}: {err}"),
+                )
+            })?;
+            if let Some(element_size) = element_size(model_shape.datatype) {
+                let shape_size = dims.iter().product::<i64>() as usize * element_size;
+                if shape_size != byte_size {
+                    return Err(Error::new(
+                        ErrorCode::InvalidArg,
+                        format!(
+                            "Chunks of input {input_name} have total size: {byte_size}, that not equal to shape size: {shape_size}"
+                        ),
+                    ));
+                }
+            }
+            self.add_input_triton(
+                input_name,
+                &Shape {
+                    name: input_name.clone(),
+                    datatype: model_shape.datatype,
+                    dims,
+                },
+            )?;
+
+            let name = to_cstring(input_name)?;
+            for chunk in chunks {
+                triton_call!(sys::TRITONSERVER_InferenceRequestAppendInputData(
+                    self.ptr,
+                    name.as_ptr(),
+                    chunk.ptr,
+                    chunk.size(),
+                    chunk.memory_type as u32,
+                    chunk.memory_type_id(),
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
     fn append_input_data_with_policy<I: AsRef<str>, P: AsRef<str>>(
         &mut self,
         input_name: I,
         policy: P,
         buffer: Buffer,
+        byte_size: usize,
     ) -> Result<&mut Self, Error> {
         let name = to_cstring(&input_name)?;
         let policy = to_cstring(policy)?;
@@ -471,9 +1247,9 @@ impl<'a> Request<'a> {
                 self.ptr,
                 name.as_ptr(),
                 buffer.ptr,
-                buffer.len,
+                byte_size,
                 buffer.memory_type as u32,
-                0,
+                buffer.memory_type_id(),
                 policy.as_ptr(),
             )
         )?;
@@ -482,6 +1258,53 @@ impl<'a> Request<'a> {
         Ok(self)
     }
 
+    fn append_input_data_with_attributes<I: AsRef<str>>(
+        &mut self,
+        input_name: I,
+        buffer: Buffer,
+        byte_size: usize,
+        attributes: BufferAttributes,
+    ) -> Result<&mut Self, Error> {
+        let byte_size = match attributes.byte_size {
+            Some(size) if size > buffer.size() => {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Byte size {size} of input {} exceeds its buffer size {}",
+                        input_name.as_ref(),
+                        buffer.size()
+                    ),
+                ))
+            }
+            Some(size) => size,
+            None => byte_size,
+        };
+
+        let triton_attributes = TritonBufferAttributes::new()?;
+        triton_attributes.set_memory_type(buffer.memory_type)?;
+        triton_attributes
+            .set_memory_type_id(attributes.memory_type_id.unwrap_or(buffer.memory_type_id()))?;
+        triton_attributes.set_byte_size(byte_size)?;
+        if let Some(handle) = attributes.cuda_ipc_handle {
+            let mut handle = Box::new(handle);
+            triton_attributes.set_cuda_ipc_handle(&mut handle)?;
+            self.ipc_handles.push(handle);
+        }
+
+        let name = to_cstring(&input_name)?;
+        triton_call!(
+            sys::TRITONSERVER_InferenceRequestAppendInputDataWithBufferAttributes(
+                self.ptr,
+                name.as_ptr(),
+                buffer.ptr,
+                triton_attributes.0,
+            )
+        )?;
+
+        self.input.insert(input_name.as_ref().to_string(), buffer);
+        Ok(self)
+    }
+
     /// Remove an input from a request. Returns appended to the input data.
     ///
     /// `name` The name of the input. \
@@ -509,7 +1332,9 @@ impl<'a> Request<'a> {
     }
 
     /// Remove all the inputs from a request. Returns appended to the inputs data.
+    /// The chunks added with [Request::append_input_chunk] are dropped.
     pub fn remove_all_inputs(&mut self) -> Result<HashMap<String, Buffer>, Error> {
+        self.input_chunks.clear();
         let mut buffers = HashMap::new();
         std::mem::swap(&mut buffers, &mut self.input);
 
@@ -519,11 +1344,80 @@ impl<'a> Request<'a> {
         )
     }
 
+    /// Request only the `outputs` of the model instead of all of them,
+    /// so the buffers are allocated only for these outputs. \
+    /// Returns error if the model has no output with one of the names. Replaces the outputs selected by the previous call.
+    pub fn request_outputs<I, N>(&mut self, outputs: I) -> Result<&mut Self, Error>
+    where
+        I: IntoIterator<Item = N>,
+        N: AsRef<str>,
+    {
+        let model = self
+            .server
+            .get_model_version(&self.model_name, self.version)?;
+        let mut requested = Vec::new();
+        for name in outputs {
+            let name = name.as_ref();
+            if !model.outputs.iter().any(|output| output.name == name) {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Model {} has no output named: {name}", self.model_name),
+                ));
+            }
+            if !requested.iter().any(|requested| requested == name) {
+                requested.push(name.to_string());
+            }
+        }
+        self.requested_outputs = Some(requested);
+        Ok(self)
+    }
+
+    /// Allocate the output `output_name` in the memory domain of the host policy `policy`
+    /// (set with [crate::options::Options::host_policy]): the policy name is passed to [Allocator::allocate_with_policy],
+    /// so NUMA-pinned deployments can allocate the output next to the model instance. \
+    /// Returns error if the model has no output with this name.
+    pub fn set_output_host_policy<O: AsRef<str>, P: AsRef<str>>(
+        &mut self,
+        output_name: O,
+        policy: P,
+    ) -> Result<&mut Self, Error> {
+        let output_name = output_name.as_ref();
+        let model = self
+            .server
+            .get_model_version(&self.model_name, self.version)?;
+        if !model
+            .outputs
+            .iter()
+            .any(|output| output.name == output_name)
+        {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Model {} has no output named: {output_name}",
+                    self.model_name
+                ),
+            ));
+        }
+        self.output_host_policies
+            .insert(output_name.to_string(), policy.as_ref().to_string());
+        Ok(self)
+    }
+
+    /// Add the outputs of the model (or the ones selected with [Request::request_outputs]) to the request.
+    /// Returns their data types.
     pub(crate) fn add_outputs(&mut self) -> Result<HashMap<String, DataType>, Error> {
-        let model = self.server.get_model(&self.model_name)?;
+        let model = self
+            .server
+            .get_model_version(&self.model_name, self.version)?;
         let mut datatype_hints = HashMap::new();
 
-        for output in &model.outputs {
+        let requested = self.requested_outputs.take();
+        let outputs = model.outputs.iter().filter(|output| {
+            requested
+                .as_ref()
+                .is_none_or(|requested| requested.contains(&output.name))
+        });
+        for output in outputs {
             self.add_output(&output.name)?;
             datatype_hints.insert(output.name.clone(), output.datatype);
         }
@@ -544,18 +1438,39 @@ impl<'a> Request<'a> {
         )
     }
 
-    /// Set a parameter in the request. Does not support ParameterContent::Bytes.   
-    pub fn set_parameter(&mut self, parameter: Parameter) -> Result<&mut Self, Error> {
-        let name = to_cstring(&parameter.name)?;
-        match parameter.content.clone() {
+    /// Set a parameter in the request. \
+    /// Triton C API has no bytes parameters of the request, so [ParameterContent::Bytes] is passed as the base64 encoded string
+    /// (backend should decode it, e.g. TensorRT-LLM accepts the binary parameters this way). \
+    /// Accepts typed [RequestParameter] or [Parameter](crate::parameter::Parameter) (that is treated as [RequestParameter::Custom]). \
+    /// Parameter is validated before passing to Triton, check [RequestParameter::validate].
+    pub fn set_parameter<P: Into<RequestParameter>>(
+        &mut self,
+        parameter: P,
+    ) -> Result<&mut Self, Error> {
+        let parameter = parameter.into();
+        parameter.validate()?;
+
+        let (name, content) = match parameter {
+            RequestParameter::Priority(priority) => return self.set_priority(priority),
+            RequestParameter::Timeout(timeout) => return self.set_timeout(timeout),
+            RequestParameter::Custom { name, content } => (to_cstring(name)?, content),
+        };
+        match content {
             ParameterContent::Bool(value) => triton_call!(
                 sys::TRITONSERVER_InferenceRequestSetBoolParameter(self.ptr, name.as_ptr(), value),
                 self
             ),
-            ParameterContent::Bytes(_) => Err(Error::new(
-                ErrorCode::Unsupported,
-                "Request::set_parameter does not support ParameterContent::Bytes",
-            )),
+            ParameterContent::Bytes(value) => {
+                let value = to_cstring(base64_encode(&value))?;
+                triton_call!(
+                    sys::TRITONSERVER_InferenceRequestSetStringParameter(
+                        self.ptr,
+                        name.as_ptr(),
+                        value.as_ptr()
+                    ),
+                    self
+                )
+            }
             ParameterContent::Int(value) => triton_call!(
                 sys::TRITONSERVER_InferenceRequestSetIntParameter(self.ptr, name.as_ptr(), value),
                 self
@@ -585,6 +1500,53 @@ impl<'a> Request<'a> {
     }
 }
 
+impl<'b> BorrowedInputs<'_, '_, 'b> {
+    /// Add an input to the request without taking the ownership of the `buffer`.\
+    /// `input_name`: The name of the input. \
+    /// `buffer`: input data containing buffer. Borrowed until Triton releases the input.
+    pub fn add_input<N: AsRef<str>>(
+        &mut self,
+        input_name: N,
+        buffer: &'b Buffer,
+    ) -> Result<&mut Self, Error> {
+        let view = Buffer {
+            ptr: buffer.ptr,
+            len: buffer.len,
+            data_type: buffer.data_type,
+            memory_type: buffer.memory_type,
+            device_id: buffer.device_id,
+            owned: false,
+            deleter: None,
+        };
+        self.add_view(input_name, view)
+    }
+
+    /// Add an input to the request without copying the `data`.\
+    /// `input_name`: The name of the input. \
+    /// `data`: input data. Borrowed until Triton releases the input.
+    pub fn add_input_borrowed<N: AsRef<str>, T: Sample>(
+        &mut self,
+        input_name: N,
+        data: &'b [T],
+    ) -> Result<&mut Self, Error> {
+        // SAFETY: the view doesn't outlive the inference, [Request::infer_with_borrowed] waits for the release of the inputs.
+        let view = unsafe { Buffer::from_borrowed_slice(data) };
+        self.add_view(input_name, view)
+    }
+
+    /// Add the view of the borrowed data as the input. The view is not returned by [InputRelease].
+    fn add_view<N: AsRef<str>>(&mut self, input_name: N, view: Buffer) -> Result<&mut Self, Error> {
+        self.request
+            .add_input_inner(&input_name, view, None::<String>, None::<Vec<i64>>)?;
+        self.request
+            .input_refs
+            .get_or_insert_with(Default::default)
+            .names
+            .insert(input_name.as_ref().to_string());
+        Ok(self)
+    }
+}
+
 unsafe impl Send for Request<'_> {}
 
 impl Drop for Request<'_> {
@@ -592,14 +1554,81 @@ impl Drop for Request<'_> {
         unsafe {
             sys::TRITONSERVER_InferenceRequestDelete(self.ptr);
         }
+        // Triton no longer uses the borrowed inputs.
+        drop(self.input_refs.take());
     }
 }
 
-fn assert_buffer_shape<N: AsRef<str>>(
+/// Size of the `datatype` element, None for [DataType::Bytes].
+fn element_size(datatype: DataType) -> Option<usize> {
+    (datatype != DataType::Bytes).then(|| datatype.size() as usize)
+}
+
+/// Resolve the input dims from the `pattern` (check [Request::add_input_auto]), the `model_dims`
+/// and the `byte_size` of the buffer. `element_size`: size of the input element, None for [DataType::Bytes]. \
+/// Returns the description of the error if the dims can't be resolved.
+fn resolve_dims(
+    pattern: &[Option<i64>],
+    model_dims: &[i64],
+    element_size: Option<usize>,
+    byte_size: usize,
+) -> Result<Vec<i64>, String> {
+    if !model_dims.is_empty() && pattern.len() != model_dims.len() {
+        return Err(format!(
+            "Dims {pattern:?} have rank {}, while model input has dims {model_dims:?}",
+            pattern.len()
+        ));
+    }
+    let mut dims = Vec::with_capacity(pattern.len());
+    let mut unknown = None;
+    for (index, dim) in pattern.iter().enumerate() {
+        let model_dim = model_dims.get(index).copied().unwrap_or(-1);
+        match *dim {
+            Some(dim) if dim < 0 => return Err(format!("Dim {index} is negative: {dim}")),
+            Some(dim) if model_dim >= 0 && dim != model_dim => {
+                return Err(format!(
+                    "Dim {index} is {dim}, while model input has dims {model_dims:?}"
+                ))
+            }
+            Some(dim) => dims.push(dim),
+            None if model_dim >= 0 => dims.push(model_dim),
+            None if unknown.is_some() => {
+                return Err(format!(
+                    "Only one dynamic dim can be inferred, dims: {pattern:?}, model input dims: {model_dims:?}"
+                ))
+            }
+            None => {
+                unknown = Some(index);
+                dims.push(-1);
+            }
+        }
+    }
+
+    let Some(unknown) = unknown else {
+        return Ok(dims);
+    };
+    let Some(element_size) = element_size else {
+        return Err("Dynamic dim of BYTES input can't be inferred from the buffer".to_string());
+    };
+    let known = dims.iter().filter(|dim| **dim >= 0).product::<i64>() as usize;
+    let row_size = known * element_size;
+    if row_size == 0 || !byte_size.is_multiple_of(row_size) {
+        return Err(format!(
+            "Buffer size {byte_size} is not a multiple of {row_size} bytes of the dims {dims:?}"
+        ));
+    }
+    dims[unknown] = (byte_size / row_size) as i64;
+    Ok(dims)
+}
+
+/// Check `buffer` against `shape` according to `size_check`. \
+/// Returns the buffer to append to the request and the byte size of its data that should be passed to Triton.
+fn check_buffer_shape<N: AsRef<str>>(
     shape: &Shape,
-    buffer: &Buffer,
+    buffer: Buffer,
     source: N,
-) -> Result<(), Error> {
+    size_check: SizeCheck,
+) -> Result<(Buffer, usize), Error> {
     if shape.datatype != buffer.data_type {
         return Err(Error::new(
             ErrorCode::InvalidArg,
@@ -611,22 +1640,99 @@ fn assert_buffer_shape<N: AsRef<str>>(
             ),
         ));
     }
-    let shape_size = if shape.dims.iter().any(|n| *n < 0) || shape.dims.is_empty() {
+    let dynamic = shape.dims.iter().any(|n| *n < 0) || shape.dims.is_empty();
+    let exact = !dynamic && shape.datatype != DataType::Bytes;
+    let shape_size = if dynamic {
         0
     } else {
-        shape.dims.iter().product::<i64>() as u32 * shape.datatype.size()
+        shape.dims.iter().product::<i64>() as usize * shape.datatype.size() as usize
     };
 
-    if shape_size as usize > buffer.size() {
-        Err(Error::new(
+    match size_check {
+        SizeCheck::Strict if exact && shape_size != buffer.size() => Err(Error::new(
+            ErrorCode::InvalidArg,
+            format!(
+                "Buffer has size: {}, that not equal to shape size: {shape_size}. input name: {}",
+                buffer.size(),
+                source.as_ref()
+            ),
+        )),
+        SizeCheck::PadZero if exact && shape_size > buffer.size() => {
+            let padded = if buffer.memory_type == MemoryType::Cpu {
+                buffer.zero_padded(shape_size)?
+            } else {
+                run_in_context_sync!(buffer.device_id, buffer.zero_padded(shape_size))?
+            };
+            Ok((padded, shape_size))
+        }
+        _ if shape_size > buffer.size() => Err(Error::new(
             ErrorCode::InvalidArg,
             format!(
                 "Buffer has size: {}, that less than shape min size: {shape_size}. input name: {}",
                 buffer.size(),
                 source.as_ref()
             ),
-        ))
-    } else {
-        Ok(())
+        )),
+        SizeCheck::Truncate | SizeCheck::PadZero if exact => Ok((buffer, shape_size)),
+        _ => {
+            let byte_size = buffer.size();
+            Ok((buffer, byte_size))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dims_inference() {
+        assert_eq!(
+            resolve_dims(&[None, None], &[-1, 3], Some(4), 24),
+            Ok(vec![2, 3])
+        );
+        assert_eq!(
+            resolve_dims(&[Some(1), None, Some(3)], &[-1, -1, 3], Some(1), 12),
+            Ok(vec![1, 4, 3])
+        );
+        assert_eq!(
+            resolve_dims(&[Some(2), None], &[-1, 3], None, 5),
+            Ok(vec![2, 3])
+        );
+        assert!(resolve_dims(&[None, None], &[-1, 3], Some(4), 20).is_err());
+        assert!(resolve_dims(&[None, None], &[-1, -1], Some(4), 16).is_err());
+        assert!(resolve_dims(&[None], &[-1], None, 16).is_err());
+        assert!(resolve_dims(&[Some(2), Some(4)], &[-1, 3], Some(4), 24).is_err());
+        assert!(resolve_dims(&[None], &[-1, 3], Some(4), 24).is_err());
+    }
+
+    #[test]
+    fn size_check() {
+        let shape = Shape {
+            name: "input".to_string(),
+            datatype: DataType::Fp32,
+            dims: vec![1, 4],
+        };
+        let check = |len: usize, size_check| {
+            check_buffer_shape(&shape, Buffer::from(vec![1f32; len]), "input", size_check)
+                .map(|(buffer, byte_size)| (buffer.size(), byte_size))
+        };
+
+        assert!(check(3, SizeCheck::AllowLarger).is_err());
+        assert_eq!(check(5, SizeCheck::AllowLarger).unwrap(), (20, 20));
+        assert!(check(5, SizeCheck::Strict).is_err());
+        assert_eq!(check(4, SizeCheck::Strict).unwrap(), (16, 16));
+        assert_eq!(check(5, SizeCheck::Truncate).unwrap(), (20, 16));
+        assert!(check(3, SizeCheck::Truncate).is_err());
+
+        let (padded, byte_size) = check_buffer_shape(
+            &shape,
+            Buffer::from([1f32, 2., 3.]),
+            "input",
+            SizeCheck::PadZero,
+        )
+        .unwrap();
+        assert_eq!(byte_size, 16);
+        assert_eq!(AsRef::<[f32]>::as_ref(&padded)[..4], [1., 2., 3., 0.]);
     }
 }
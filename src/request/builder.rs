@@ -0,0 +1,273 @@
+use std::{marker::PhantomData, time::Duration};
+
+use super::{Allocator, DefaultAllocator, SizeCheck, Trace};
+use crate::{memory::Buffer, Error, Request, Server};
+
+/// Typestate of [RequestBuilder]: no inputs were added yet.
+#[derive(Debug, Clone, Copy)]
+pub struct NoInputs;
+/// Typestate of [RequestBuilder]: at least one input was added.
+#[derive(Debug, Clone, Copy)]
+pub struct WithInputs;
+/// Typestate of [RequestBuilder]: allocator was not set yet.
+#[derive(Debug, Clone, Copy)]
+pub struct NoAllocator;
+/// Typestate of [RequestBuilder]: allocator was set.
+#[derive(Debug, Clone, Copy)]
+pub struct WithAllocator;
+
+struct Input {
+    name: String,
+    buffer: Buffer,
+    dims: Option<Vec<i64>>,
+}
+
+/// Builder of the inference [Request]. \
+/// One can get this item using [Server::request].
+///
+/// [RequestBuilder::build] is available only after at least one input and the allocator are set,
+/// so it's impossible to get a request that will fail on [Request::infer_async] because of their absence:
+/// ```ignore
+/// let response = server
+///     .request("model")
+///     .version(2)
+///     .input("IMAGE", buffer)
+///     .allocator(DefaultAllocator::default())
+///     .timeout(Duration::from_secs(1))
+///     .build()?
+///     .infer_async()?
+///     .await?;
+/// ```
+///
+/// The builder can't be created without the model, and the request can't be built without the inputs or the allocator:
+/// ```no_run
+/// # use tritonserver_rs::{request::DefaultAllocator, Buffer, Error, Request, Server};
+/// # fn build(server: &Server, buffer: Buffer) -> Result<Request<'_>, Error> {
+/// server
+///     .request("model")
+///     .input("IMAGE", buffer)
+///     .allocator(DefaultAllocator::default())
+///     .build()
+/// # }
+/// ```
+/// ```compile_fail,E0061
+/// # use tritonserver_rs::{request::DefaultAllocator, Buffer, Error, Request, Server};
+/// # fn build(server: &Server, buffer: Buffer) -> Result<Request<'_>, Error> {
+/// server
+///     .request()
+///     .input("IMAGE", buffer)
+///     .allocator(DefaultAllocator::default())
+///     .build()
+/// # }
+/// ```
+/// ```compile_fail,E0599
+/// # use tritonserver_rs::{request::DefaultAllocator, Buffer, Error, Request, Server};
+/// # fn build(server: &Server, buffer: Buffer) -> Result<Request<'_>, Error> {
+/// server
+///     .request("model")
+///     .allocator(DefaultAllocator::default())
+///     .build()
+/// # }
+/// ```
+/// ```compile_fail,E0599
+/// # use tritonserver_rs::{request::DefaultAllocator, Buffer, Error, Request, Server};
+/// # fn build(server: &Server, buffer: Buffer) -> Result<Request<'_>, Error> {
+/// server.request("model").input("IMAGE", buffer).build()
+/// # }
+/// ```
+pub struct RequestBuilder<'a, I = NoInputs, A = NoAllocator> {
+    server: &'a Server,
+    model: String,
+    version: i64,
+    inputs: Vec<Input>,
+    allocator: Option<Box<dyn Allocator>>,
+    trace: Option<Trace>,
+    id: Option<String>,
+    correlation_id: Option<u64>,
+    priority: Option<u32>,
+    timeout: Option<Duration>,
+    size_check: Option<SizeCheck>,
+    _state: PhantomData<(I, A)>,
+}
+
+impl<'a> RequestBuilder<'a, NoInputs, NoAllocator> {
+    pub(crate) fn new<M: AsRef<str>>(server: &'a Server, model: M) -> Self {
+        RequestBuilder {
+            server,
+            model: model.as_ref().to_string(),
+            version: -1,
+            inputs: Vec::new(),
+            allocator: None,
+            trace: None,
+            id: None,
+            correlation_id: None,
+            priority: None,
+            timeout: None,
+            size_check: None,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<'a, I, A> RequestBuilder<'a, I, A> {
+    /// Set the version of the model. \
+    /// Default is -1, meaning that the server will choose a version based on the model's policy.
+    pub fn version(mut self, version: i64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Add an input to the request. \
+    /// Check [Request::add_input] for more info.
+    pub fn input<N: AsRef<str>>(
+        self,
+        input_name: N,
+        buffer: Buffer,
+    ) -> RequestBuilder<'a, WithInputs, A> {
+        self.push_input(input_name, buffer, None)
+    }
+
+    /// Add an input with the specified shape to the request. \
+    /// Check [Request::add_input_with_dims] for more info.
+    pub fn input_with_dims<N, D>(
+        self,
+        input_name: N,
+        buffer: Buffer,
+        dims: D,
+    ) -> RequestBuilder<'a, WithInputs, A>
+    where
+        N: AsRef<str>,
+        D: AsRef<[i64]>,
+    {
+        self.push_input(input_name, buffer, Some(dims.as_ref().to_vec()))
+    }
+
+    /// Set the allocator of the output buffers. \
+    /// Check [Allocator] trait for more info.
+    pub fn allocator<Alloc: Allocator + 'static>(
+        self,
+        allocator: Alloc,
+    ) -> RequestBuilder<'a, I, WithAllocator> {
+        self.boxed_allocator(Box::new(allocator))
+    }
+
+    /// Set the boxed allocator of the output buffers. \
+    /// Check [Allocator] trait for more info.
+    pub fn boxed_allocator(
+        mut self,
+        allocator: Box<dyn Allocator>,
+    ) -> RequestBuilder<'a, I, WithAllocator> {
+        self.allocator = Some(allocator);
+        self.cast()
+    }
+
+    /// Set [DefaultAllocator] as the allocator of the output buffers.
+    pub fn default_allocator(self) -> RequestBuilder<'a, I, WithAllocator> {
+        self.allocator(DefaultAllocator::default())
+    }
+
+    /// Set the trace of the request. Check [Request::add_trace] for more info.
+    pub fn trace(mut self, trace: Trace) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Set the ID of the request.
+    pub fn id<S: AsRef<str>>(mut self, id: S) -> Self {
+        self.id = Some(id.as_ref().to_string());
+        self
+    }
+
+    /// Set the correlation ID of the request. Check [Request::set_correlation_id] for more info.
+    pub fn correlation_id(mut self, id: u64) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
+
+    /// Set the priority of the request. Check [Request::set_priority] for more info.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Set the timeout of the request. Check [Request::set_timeout] for more info.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the mode of input buffer size checking. Check [SizeCheck] for more info.
+    pub fn size_check(mut self, size_check: SizeCheck) -> Self {
+        self.size_check = Some(size_check);
+        self
+    }
+
+    fn push_input<N: AsRef<str>>(
+        mut self,
+        input_name: N,
+        buffer: Buffer,
+        dims: Option<Vec<i64>>,
+    ) -> RequestBuilder<'a, WithInputs, A> {
+        self.inputs.push(Input {
+            name: input_name.as_ref().to_string(),
+            buffer,
+            dims,
+        });
+        self.cast()
+    }
+
+    fn cast<I2, A2>(self) -> RequestBuilder<'a, I2, A2> {
+        RequestBuilder {
+            server: self.server,
+            model: self.model,
+            version: self.version,
+            inputs: self.inputs,
+            allocator: self.allocator,
+            trace: self.trace,
+            id: self.id,
+            correlation_id: self.correlation_id,
+            priority: self.priority,
+            timeout: self.timeout,
+            size_check: self.size_check,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<'a> RequestBuilder<'a, WithInputs, WithAllocator> {
+    /// Create the request. \
+    /// Returns error if the model is not found or any of the inputs mismatches the model's inputs.
+    pub fn build(self) -> Result<Request<'a>, Error> {
+        let mut request = self.server.create_request(&self.model, self.version)?;
+
+        if let Some(size_check) = self.size_check {
+            request.set_size_check(size_check);
+        }
+        if let Some(id) = self.id {
+            request.set_id(id)?;
+        }
+        if let Some(correlation_id) = self.correlation_id {
+            request.set_correlation_id(correlation_id)?;
+        }
+        if let Some(priority) = self.priority {
+            request.set_priority(priority)?;
+        }
+        if let Some(timeout) = self.timeout {
+            request.set_timeout(timeout)?;
+        }
+        if let Some(trace) = self.trace {
+            request.add_trace(trace);
+        }
+        for Input { name, buffer, dims } in self.inputs {
+            match dims {
+                Some(dims) => request.add_input_with_dims(name, buffer, dims)?,
+                None => request.add_input(name, buffer)?,
+            };
+        }
+        if let Some(allocator) = self.allocator {
+            request.add_allocator(allocator);
+        }
+
+        Ok(request)
+    }
+}
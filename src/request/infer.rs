@@ -1,21 +1,30 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ffi::c_void,
+    marker::PhantomData,
     ptr::null_mut,
     sync::{atomic::AtomicBool, Arc},
 };
 
 use log::trace;
-use tokio::{
-    runtime::Handle,
-    sync::oneshot::{self, Receiver},
+use tokio::sync::{
+    mpsc,
+    oneshot::{self, Receiver},
 };
 
 use crate::{
     allocator::Allocator,
     error::{Error, ErrorCode},
     memory::Buffer,
-    sys, Request, Response,
+    response::{Quantization, ResponseFlags},
+    runtime::{self, Runtime},
+    server::{
+        usage::{self, Accounting},
+        InFlight,
+    },
+    sys,
+    testing::{self, DeliveryTicket},
+    Request, Response,
 };
 
 /// Inference result error. Contains output buffers that was allocated by user provided Allocator during the inference.
@@ -40,7 +49,11 @@ impl std::fmt::Display for InferenceError {
     }
 }
 
-impl std::error::Error for InferenceError {}
+impl std::error::Error for InferenceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
 
 /// Future that returns the inference response. \
 /// The request can be cancelled by dropping this structure.
@@ -50,6 +63,8 @@ pub struct ResponseFuture {
     pub(super) response_receiver: Receiver<Result<Response, InferenceError>>,
     pub(super) input_release: Option<InputRelease>,
     pub(super) request_ptr: Arc<RequestCanceller>,
+    /// Turn of the response, if responses are delivered in order (check [crate::testing]).
+    pub(super) ticket: Option<DeliveryTicket>,
 }
 
 pub(super) struct RequestCanceller {
@@ -66,16 +81,146 @@ unsafe impl Sync for RequestCanceller {}
 /// Input buffers will be dropped if no one will await on this struct.
 pub struct InputRelease(pub(super) oneshot::Receiver<HashMap<String, Buffer>>);
 
+/// Stream of the responses of the request, returned by [Request::infer_stream]. \
+/// Yields every response the model sends (decoupled models may send several of them or none)
+/// and ends after the final one. The request is cancelled if the stream is dropped before the end.
+///
+/// Also the input buffers assigned to the request can be returned via [get_input_release](ResponseStream::get_input_release).
+pub struct ResponseStream {
+    pub(super) response_receiver: mpsc::UnboundedReceiver<Result<Response, InferenceError>>,
+    pub(super) input_release: Option<InputRelease>,
+    pub(super) request_ptr: Arc<RequestCanceller>,
+}
+
+/// Where the response callback delivers the responses.
+enum ResponseSender {
+    /// First response goes to [ResponseFuture]. None after it is sent.
+    Single(Option<oneshot::Sender<Result<Response, InferenceError>>>),
+    /// All the responses go to [ResponseStream].
+    Stream(mpsc::UnboundedSender<Result<Response, InferenceError>>),
+}
+
+/// Inputs of the request borrowed for the [Request::infer_with_borrowed] call. \
+/// The borrowed inputs are not returned by [InputRelease].
+pub struct BorrowedInputs<'r, 'a, 'b> {
+    pub(crate) request: &'r mut Request<'a>,
+    pub(crate) _inputs: PhantomData<&'b Buffer>,
+}
+
+/// Inputs of the request added with [BorrowedInputs].
+#[derive(Debug, Default)]
+pub(crate) struct InputRefs {
+    /// Names of the inputs. Their buffers are not returned by [InputRelease].
+    pub(crate) names: HashSet<String>,
+    /// Dropped with the request, when Triton no longer uses the inputs. Set on the inference start.
+    pub(crate) released: Option<oneshot::Sender<()>>,
+}
+
 /// Start inference.
-impl Request<'_> {
+impl<'a> Request<'a> {
     /// Perform inference using the metadata and inputs supplied by the Request(self). \
     /// If the function returns success,
     /// the returned struct can be used to get results (.await) of the inference and
     /// to return input buffers after the inference start [ResponseFuture::get_input_release]. \
     /// Note: output buffer will be returned with [Response] or [InferenceError]. \
-    pub fn infer_async(mut self) -> Result<ResponseFuture, Error> {
+    /// Decoupled models may send several responses per request: only the first one is returned, use [Request::infer_stream] to get all of them.
+    pub fn infer_async(self) -> Result<ResponseFuture, Error> {
+        self.infer_future()
+    }
+
+    /// Perform inference and get all the responses of the request as a stream,
+    /// e.g. of the decoupled models sending several responses per request. \
+    /// The last response has [Response::is_final] set, unless the model sends the final flag separately:
+    /// then the stream just ends after the last response. Check [ResponseStream].
+    pub fn infer_stream(self) -> Result<ResponseStream, Error> {
+        let (response_tx, response_rx) = mpsc::unbounded_channel();
+        let (input_release, request_ptr) =
+            self.start_inference(ResponseSender::Stream(response_tx))?;
+        Ok(ResponseStream {
+            response_receiver: response_rx,
+            input_release: Some(input_release),
+            request_ptr,
+        })
+    }
+
+    /// Start the inference delivering the first response to the returned future.
+    fn infer_future(self) -> Result<ResponseFuture, Error> {
+        let ticket = testing::response_ticket(&self.server.ptr.delivery);
+        let (response_tx, response_rx) = oneshot::channel();
+        let (input_release, request_ptr) =
+            self.start_inference(ResponseSender::Single(Some(response_tx)))?;
+        Ok(ResponseFuture {
+            response_receiver: response_rx,
+            input_release: Some(input_release),
+            request_ptr,
+            ticket,
+        })
+    }
+
+    /// Perform inference and block the current thread until the response is received. \
+    /// Doesn't require any async runtime. Check [crate::runtime] for more info. \
+    /// Must not be called from the async context: it blocks the worker thread of the runtime.
+    #[cfg(feature = "sync")]
+    pub fn infer_blocking(self) -> Result<Response, InferenceError> {
+        futures::executor::block_on(self.infer_async()?)
+    }
+
+    /// Perform inference of the request with the inputs borrowed without taking the ownership of their buffers. \
+    /// `add_inputs` adds the borrowed inputs (check [BorrowedInputs]), the other inputs are added to the request before the call.
+    ///
+    /// Blocks the current thread until the response is received and Triton releases the inputs,
+    /// so the borrow can't end while Triton reads them. Inside the multi-threaded tokio runtime
+    /// the other tasks of the worker are moved to the other workers meanwhile.
+    /// ```ignore
+    /// let image = Buffer::alloc::<f32>(3 * 224 * 224, MemoryType::Cpu)?;
+    /// for _ in 0..frames {
+    ///     let mut request = server.create_request("resnet", -1)?;
+    ///     request.add_default_allocator();
+    ///     let response = request.infer_with_borrowed(|inputs| {
+    ///         inputs.add_input("IMAGE", &image)?;
+    ///         Ok(())
+    ///     })?;
+    /// }
+    /// ```
+    pub fn infer_with_borrowed<'b, F>(mut self, add_inputs: F) -> Result<Response, InferenceError>
+    where
+        F: FnOnce(&mut BorrowedInputs<'_, 'a, 'b>) -> Result<(), Error>,
+    {
+        add_inputs(&mut BorrowedInputs {
+            request: &mut self,
+            _inputs: PhantomData,
+        })?;
+        let (tx, rx) = oneshot::channel();
+        self.input_refs
+            .get_or_insert_with(Default::default)
+            .released = Some(tx);
+        let future = self.infer_future();
+        runtime::block_in_place(|| wait_borrowed(future, rx))
+    }
+
+    /// Start the inference. The errors get the model and the ID of the request in the context.
+    fn start_inference(
+        self,
+        responses: ResponseSender,
+    ) -> Result<(InputRelease, Arc<RequestCanceller>), Error> {
+        let model = self.model_name.clone();
+        let version = self.version;
+        let id = self.get_id().ok();
+        self.launch(responses).map_err(|err| {
+            let err = err.with_model(model, version);
+            match id {
+                Some(id) => err.with_request_id(id),
+                None => err,
+            }
+        })
+    }
+
+    fn launch(
+        mut self,
+        responses: ResponseSender,
+    ) -> Result<(InputRelease, Arc<RequestCanceller>), Error> {
         // Check on all buffers are set.
-        if self.input.is_empty() {
+        if self.input.is_empty() && self.input_chunks.is_empty() {
             return Err(Error::new(
                 ErrorCode::NotFound,
                 "Request's output buffer is not set",
@@ -87,16 +232,35 @@ impl Request<'_> {
                 "Request's output buffers allocator is not set",
             ));
         }
+        self.add_chunked_inputs()?;
+        let in_flight = self.server.ptr.begin_inference(self.generation)?;
+        let accounting = self.server.ptr.accounting.clone();
+        let query_mismatches = self.server.ptr.query_mismatches.clone();
+        let namespace = self.namespace.take();
+        if let Some(namespace) = &namespace {
+            let chunks = self.input_chunks.values().flatten();
+            accounting.admit(
+                namespace,
+                self.input.values().chain(chunks).map(Buffer::size).sum(),
+            )?;
+        }
         let custom_allocator = self.custom_allocator.take().unwrap();
-        let trace = self.custom_trace.take();
+        let trace = match (self.custom_trace.take(), &namespace) {
+            (None, Some(namespace)) => usage::compute_trace(&accounting, namespace),
+            (None, None) => self.server.sample_trace(&self.model_name),
+            (trace, _) => trace,
+        };
 
         // Add outputs.
         let datatype_hints = self.add_outputs()?;
-        let outputs_count = self.server.get_model(&self.model_name)?.outputs.len();
+        let host_policies = std::mem::take(&mut self.output_host_policies);
+        let device = self.device;
+        let outputs_count = datatype_hints.len();
+        let quantization = self.server.output_quantization(&self.model_name);
 
-        let runtime = self.server.runtime.clone();
+        let runtime = self.server.executor.clone();
         let request_ptr = self.ptr;
-        let server_ptr = self.server.ptr.as_mut_ptr();
+        let server_ptr = in_flight.server_ptr();
 
         // Канал, по которому мы вернем input buffer пользователю.
         let (input_tx, input_rx) = oneshot::channel();
@@ -120,73 +284,109 @@ impl Request<'_> {
         if !err.is_null() {
             drop_boxed_request(boxed_request_input_recover);
 
-            let err = Error {
-                ptr: err,
-                owned: true,
-            };
-            return Err(err);
+            return Err(
+                Error::from(err).with_call("TRITONSERVER_InferenceRequestSetReleaseCallback")
+            );
         }
 
         // Allocator отправляется в alloc -> release, там он выдает запрашиваемые тритоном буферы в alloc и шлет их обратно в release.
         // Так как Allocator используется тритоном в методе release, который вызывается после удаления Response,
         // необходимо отправить алокатор в response_wrapper -> Response, чтобы Arc не дропнулся раньше времени.
         // Имена буферов отправляется в response_wrapper, на нем будем ждать возвращенные буферы для Response.
-        let allocator = Arc::new(Allocator::new(
+        let allocator = match Allocator::new(
             custom_allocator,
             datatype_hints,
+            host_policies,
+            device,
+            query_mismatches,
             runtime.clone(),
-        )?);
+        ) {
+            Ok(allocator) => Arc::new(allocator),
+            Err(err) => {
+                drop_boxed_request(boxed_request_input_recover);
+                return Err(err);
+            }
+        };
 
         let allocator_ptr = Arc::as_ptr(&allocator);
-        // response_tx отправляется в response_wrapper,
-        // когда там сконструируется Response, он будет положен в tx.
-        // response_rx отправляется юзеру внутри ResponseFuture, он на нем await-ится.
-        let (response_tx, response_rx) = oneshot::channel();
-
-        triton_call!(sys::TRITONSERVER_InferenceRequestSetResponseCallback(
-            request_ptr,
-            allocator.get_allocator(),
-            allocator_ptr as *mut c_void,
-            Some(responce_wrapper),
-            Box::into_raw(Box::new(ResponseCallbackItems {
-                response_tx,
-                allocator,
-                outputs_count,
-                runtime,
-            })) as *mut _,
-        ))?;
+        let triton_allocator = allocator.get_allocator();
+        // responses отправляется в response_wrapper,
+        // когда там сконструируется Response, он будет отправлен в ResponseFuture или ResponseStream.
 
+        let callback_items = Box::into_raw(Box::new(ResponseCallbackItems {
+            responses,
+            allocator,
+            outputs_count,
+            quantization,
+            runtime,
+            namespace: namespace.map(|namespace| (accounting, namespace)),
+            _in_flight: in_flight,
+        }));
         let trace_ptr = trace
             .as_ref()
             .map(|trace| trace.ptr.0)
             .unwrap_or_else(null_mut);
 
-        triton_call!(sys::TRITONSERVER_ServerInferAsync(
-            server_ptr,
+        let started = triton_call!(sys::TRITONSERVER_InferenceRequestSetResponseCallback(
             request_ptr,
-            trace_ptr
-        ))?;
+            triton_allocator,
+            allocator_ptr as *mut c_void,
+            Some(responce_wrapper),
+            callback_items as *mut _,
+        ))
+        .and_then(|_| {
+            triton_call!(sys::TRITONSERVER_ServerInferAsync(
+                server_ptr,
+                request_ptr,
+                trace_ptr
+            ))
+        });
+        if let Err(err) = started {
+            // Triton doesn't own the request that failed to start, so the callbacks are not called.
+            drop(unsafe { Box::from_raw(callback_items) });
+            drop_boxed_request(boxed_request_input_recover);
+            return Err(err);
+        }
 
         if let Some(trace) = trace {
             std::mem::forget(trace.ptr);
         }
 
-        Ok(ResponseFuture {
-            response_receiver: response_rx,
-            input_release: Some(InputRelease(input_rx)),
-            request_ptr: Arc::new(RequestCanceller {
+        Ok((
+            InputRelease(input_rx),
+            Arc::new(RequestCanceller {
                 request_ptr,
                 is_inferenced: AtomicBool::new(false),
             }),
-        })
+        ))
     }
 }
 
+/// Wait for the response of the request with borrowed inputs, then for the release of the inputs. \
+/// The sender of `released` is dropped with the request, also if the inference fails to start.
+fn wait_borrowed(
+    future: Result<ResponseFuture, Error>,
+    released: Receiver<()>,
+) -> Result<Response, InferenceError> {
+    let response = match future {
+        Ok(future) => futures::executor::block_on(future),
+        Err(err) => Err(err.into()),
+    };
+    let _ = futures::executor::block_on(released);
+    response
+}
+
 struct ResponseCallbackItems {
-    response_tx: oneshot::Sender<Result<Response, InferenceError>>,
+    responses: ResponseSender,
     allocator: Arc<Allocator>,
     outputs_count: usize,
-    runtime: Handle,
+    /// Quantization parameters of the model outputs.
+    quantization: Arc<HashMap<String, Quantization>>,
+    runtime: Runtime,
+    /// Namespace the outputs size is accounted to.
+    namespace: Option<(Arc<Accounting>, String)>,
+    /// Keeps the inference registered until the final response.
+    _in_flight: InFlight,
 }
 
 /// C-code returns the ownership on Request using this method.
@@ -203,6 +403,10 @@ unsafe extern "C" fn release_callback(
     // Drain the input buffers
     let mut buffers = HashMap::new();
     std::mem::swap(&mut buffers, &mut request.input);
+    // Borrowed inputs are not returned: their views must not outlive the borrow.
+    if let Some(input_refs) = &request.input_refs {
+        buffers.retain(|name, _| !input_refs.names.contains(name));
+    }
 
     if input_tx.send(buffers).is_err() {
         log::debug!("InputRelease was dropped before the input buffers returned from triton. Input buffers will be dropped");
@@ -213,32 +417,145 @@ unsafe extern "C" fn release_callback(
 }
 
 /// C-code calls this method when Response is ready.
+/// Decoupled models may call it several times per request: [ResponseFuture] gets only the first response,
+/// [ResponseStream] gets all of them. The callback items are released on the final call.
 unsafe extern "C" fn responce_wrapper(
     response: *mut sys::TRITONSERVER_InferenceResponse,
-    _flags: u32,
+    flags: u32,
     user_data: *mut c_void,
 ) {
     trace!("response wrapper is called");
-    assert!(!response.is_null());
     assert!(!user_data.is_null());
 
+    let flags = ResponseFlags::from_bits_truncate(flags);
+    let is_final = flags.contains(ResponseFlags::COMPLETE_FINAL);
+
     // Allocator присылали сюда только для того, чтобы он не дропнулся во время реквеста.
-    let ResponseCallbackItems {
-        response_tx,
-        allocator,
-        outputs_count,
-        runtime,
-    } = *Box::from_raw(user_data as *mut ResponseCallbackItems);
-
-    let send_res = response_tx.send(Response::new(
-        response,
-        outputs_count as u32,
-        allocator,
-        runtime,
-    ));
-    if send_res.is_err() {
-        log::error!("error sending the result of the inference. It will be lost (including the output buffer)")
-    } else {
-        trace!("response wrapper: result is sent to oneshot");
+    let items = &mut *(user_data as *mut ResponseCallbackItems);
+
+    if !response.is_null() {
+        let response = Response::new(
+            response,
+            flags,
+            items.outputs_count as u32,
+            items.allocator.clone(),
+            &items.quantization,
+            items.runtime.clone(),
+        );
+        if let (Some((accounting, namespace)), Ok(response)) = (&items.namespace, &response) {
+            let output_bytes = response
+                .get_outputs()
+                .iter()
+                .map(|output| output.get_buffer().size())
+                .sum();
+            accounting.add_output_bytes(namespace, output_bytes);
+        }
+        match &mut items.responses {
+            ResponseSender::Single(response_tx) => match response_tx.take() {
+                Some(response_tx) => {
+                    if response_tx.send(response).is_err() {
+                        log::error!("error sending the result of the inference. It will be lost (including the output buffer)")
+                    } else {
+                        trace!("response wrapper: result is sent to oneshot");
+                    }
+                }
+                None => log::warn!(
+                    "Model sent more than one response for the request. Only the first one is returned, others are dropped. \
+                    Use Request::infer_stream to get all of them"
+                ),
+            },
+            ResponseSender::Stream(response_tx) => {
+                if response_tx.send(response).is_err() {
+                    log::debug!("ResponseStream was dropped. The response will be lost (including the output buffer)")
+                }
+            }
+        }
+    }
+
+    if is_final {
+        // Dropping the stream sender ends the ResponseStream.
+        let ResponseCallbackItems { responses, .. } =
+            *Box::from_raw(user_data as *mut ResponseCallbackItems);
+        if let ResponseSender::Single(Some(response_tx)) = responses {
+            let _ = response_tx.send(Err(Error::new(
+                ErrorCode::NotFound,
+                "Model completed the request without any response",
+            )
+            .into()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{FutureExt, StreamExt};
+
+    use super::*;
+
+    #[test]
+    fn stream_yields_all_responses() {
+        let (response_tx, response_rx) = mpsc::unbounded_channel();
+        let (_input_tx, input_rx) = oneshot::channel();
+        let request_ptr = Arc::new(RequestCanceller {
+            is_inferenced: AtomicBool::new(false),
+            request_ptr: null_mut(),
+        });
+        let mut stream = ResponseStream {
+            response_receiver: response_rx,
+            input_release: Some(InputRelease(input_rx)),
+            request_ptr: request_ptr.clone(),
+        };
+
+        for message in ["first", "second"] {
+            let error = Error::new(ErrorCode::Internal, message);
+            assert!(response_tx.send(Err(error.into())).is_ok());
+        }
+        // The final call of the callback drops the sender.
+        drop(response_tx);
+
+        let messages: Vec<_> = stream
+            .by_ref()
+            .map(|response| match response {
+                Err(err) => err.error.message().to_string(),
+                Ok(_) => panic!("unexpected response"),
+            })
+            .collect()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(messages, ["first", "second"]);
+        assert!(request_ptr
+            .is_inferenced
+            .load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[test]
+    fn borrowed_waits_release() {
+        let (response_tx, response_rx) = oneshot::channel();
+        let (_input_tx, input_rx) = oneshot::channel();
+        let (released_tx, released_rx) = oneshot::channel::<()>();
+        let future = ResponseFuture {
+            response_receiver: response_rx,
+            input_release: Some(InputRelease(input_rx)),
+            request_ptr: Arc::new(RequestCanceller {
+                is_inferenced: AtomicBool::new(true),
+                request_ptr: null_mut(),
+            }),
+            ticket: None,
+        };
+        let error = Error::new(ErrorCode::Internal, "inference failed");
+        assert!(response_tx.send(Err(error.into())).is_ok());
+
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            drop(released_tx);
+        });
+        let started = std::time::Instant::now();
+        // The response is returned only after Triton releases the inputs.
+        match wait_borrowed(Ok(future), released_rx) {
+            Err(err) => assert_eq!(err.error.code(), ErrorCode::Internal),
+            Ok(_) => panic!("unexpected response"),
+        }
+        assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+        release.join().unwrap();
     }
 }
@@ -0,0 +1,175 @@
+//! Inference with the retries of the transient failures.
+//!
+//! [Request::infer_async](crate::Request::infer_async) consumes the request, so [infer_with_retry] builds the new one
+//! for each attempt from the inputs generator:
+//! ```ignore
+//! let response = retry::infer_with_retry(
+//!     &server,
+//!     "detector",
+//!     |_| Ok(vec![("IMAGE".to_string(), image.clone(), vec![1, 640, 640, 3])]),
+//!     &RetryPolicy::default().max_attempts(5),
+//! )
+//! .await?;
+//! ```
+
+use std::time::Duration;
+
+use super::{infer::InferenceError, Inputs};
+use crate::{
+    error::{Error, ErrorCode},
+    Response, Server,
+};
+
+/// When and how often the failed inference is retried. Check [infer_with_retry].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// Version of the model. -1 means the server chooses the version based on the model's policy.
+    pub version: i64,
+    /// Maximum number of the attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Each next delay is this times longer than the previous one.
+    pub multiplier: u32,
+    /// Upper limit of the delay.
+    pub max_backoff: Duration,
+    /// Substrings of the messages of [ErrorCode::Internal] errors that are transient.
+    /// [ErrorCode::Unavailable] errors are always transient.
+    pub transient_messages: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    /// Latest version, 3 attempts with 50ms, 100ms delays (up to 2s), out of memory errors are transient.
+    fn default() -> Self {
+        RetryPolicy {
+            version: -1,
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            multiplier: 2,
+            max_backoff: Duration::from_secs(2),
+            transient_messages: vec![
+                crate::error::OUT_OF_MEMORY.to_string(),
+                "out of memory".to_string(),
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Set the version of the model.
+    pub fn version(mut self, version: i64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the maximum number of the attempts. 1 disables the retries.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the exponential backoff: the first delay, the multiplier of the next ones and the upper limit.
+    pub fn backoff(mut self, initial: Duration, multiplier: u32, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.multiplier = multiplier;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Add the substring of the messages of transient [ErrorCode::Internal] errors.
+    pub fn transient_message<S: AsRef<str>>(mut self, message: S) -> Self {
+        self.transient_messages.push(message.as_ref().to_string());
+        self
+    }
+
+    /// Check if the error of `code` with `message` is worth retrying.
+    pub fn is_transient(&self, code: ErrorCode, message: &str) -> bool {
+        match code {
+            ErrorCode::Unavailable => true,
+            ErrorCode::Internal => self
+                .transient_messages
+                .iter()
+                .any(|transient| message.contains(transient.as_str())),
+            _ => false,
+        }
+    }
+
+    /// Delay before the retry number `retry` (starting from 0).
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.max(1).saturating_pow(retry);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Infer the `model` retrying the transient failures (check [RetryPolicy::is_transient]) with the exponential backoff. \
+/// `inputs`: generator of the request inputs, takes the number of the attempt (starting from 0).
+/// An error of the generator stops the retries. \
+/// The requests use [DefaultAllocator](crate::request::DefaultAllocator).
+/// Returns the error of the last attempt if all of them failed.
+pub async fn infer_with_retry<G>(
+    server: &Server,
+    model: &str,
+    inputs: G,
+    policy: &RetryPolicy,
+) -> Result<Response, InferenceError>
+where
+    G: Fn(u32) -> Result<Inputs, Error>,
+{
+    let mut attempt = 0;
+    loop {
+        let error = match infer(server, model, policy.version, inputs(attempt)?).await {
+            Ok(response) => return Ok(response),
+            Err(error) => error,
+        };
+        attempt += 1;
+        if attempt >= policy.max_attempts
+            || !policy.is_transient(error.error.code(), error.error.message())
+        {
+            return Err(error);
+        }
+
+        let delay = policy.delay(attempt - 1);
+        log::debug!(
+            "Inference of model {model} failed (attempt {attempt}), retrying in {delay:?}: {}",
+            error.error
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn infer(
+    server: &Server,
+    model: &str,
+    version: i64,
+    inputs: Inputs,
+) -> Result<Response, InferenceError> {
+    let mut request = server.create_request(model, version)?;
+    request.add_default_allocator();
+    for (name, buffer, shape) in inputs {
+        request.add_input_with_dims(name, buffer, shape)?;
+    }
+    request.infer_async()?.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy() {
+        let policy =
+            RetryPolicy::default().backoff(Duration::from_millis(100), 3, Duration::from_secs(1));
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(1), Duration::from_millis(300));
+        assert_eq!(policy.delay(2), Duration::from_millis(900));
+        assert_eq!(policy.delay(3), Duration::from_secs(1));
+        assert_eq!(policy.delay(100), Duration::from_secs(1));
+
+        assert!(policy.is_transient(ErrorCode::Unavailable, "Server is not ready"));
+        assert!(policy.is_transient(ErrorCode::Internal, "OutOfMemory. Cuda result"));
+        assert!(!policy.is_transient(ErrorCode::Internal, "failed"));
+        assert!(!policy.is_transient(ErrorCode::InvalidArg, "out of memory"));
+    }
+}
@@ -0,0 +1,126 @@
+use super::RequestFlags;
+use crate::{
+    error::{Error, ErrorCode},
+    Request, Server,
+};
+
+/// Sequence of the requests to the stateful model (model with the sequence batcher). \
+/// One can get this item using [Server::start_sequence].
+///
+/// Each request of the sequence gets the correlation ID of the sequence.
+/// The first one is flagged with [RequestFlags::SEQUENCE_START] and the last one, created by [Sequence::last_request],
+/// with [RequestFlags::SEQUENCE_END]. [Sequence::last_request] consumes the sequence, so no request can be created after the end. \
+/// If the sequence consists of the only request, it's flagged with both flags.
+///
+/// Requests should be sent (via [Request::infer_async]) in the order of their creation.
+/// ```ignore
+/// let mut sequence = server.start_sequence("stateful_model", 42)?;
+/// for chunk in chunks {
+///     let mut request = sequence.next_request()?;
+///     request.add_input("INPUT", chunk)?.add_default_allocator();
+///     request.infer_async()?.await?;
+/// }
+/// let mut request = sequence.last_request()?;
+/// ```
+///
+/// **Note**: if the sequence is dropped without [Sequence::last_request],
+/// Triton keeps the sequence slot until the sequence idle timeout of the model expires.
+#[derive(Debug)]
+pub struct Sequence<'a> {
+    server: &'a Server,
+    model: String,
+    version: i64,
+    correlation_id: u64,
+    sent: usize,
+    ended: bool,
+}
+
+impl<'a> Sequence<'a> {
+    pub(crate) fn new<M: AsRef<str>>(
+        server: &'a Server,
+        model: M,
+        correlation_id: u64,
+    ) -> Result<Self, Error> {
+        if correlation_id == 0 {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                "Sequence correlation ID must be non-zero",
+            ));
+        }
+        Ok(Sequence {
+            server,
+            model: model.as_ref().to_string(),
+            version: -1,
+            correlation_id,
+            sent: 0,
+            ended: false,
+        })
+    }
+
+    /// Set the version of the model. Can be changed only before the first request is created. \
+    /// Default is -1, meaning that the server will choose a version based on the model's policy.
+    pub fn set_version(&mut self, version: i64) -> Result<&mut Self, Error> {
+        if self.sent != 0 {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                "Version of the sequence can't be changed after the start",
+            ));
+        }
+        self.version = version;
+        Ok(self)
+    }
+
+    /// Get the correlation ID of the sequence.
+    pub fn correlation_id(&self) -> u64 {
+        self.correlation_id
+    }
+
+    /// Get the number of requests created in the sequence.
+    pub fn len(&self) -> usize {
+        self.sent
+    }
+
+    /// True if no requests were created in the sequence.
+    pub fn is_empty(&self) -> bool {
+        self.sent == 0
+    }
+
+    /// Create the next request of the sequence.
+    /// The first one is flagged with [RequestFlags::SEQUENCE_START].
+    pub fn next_request(&mut self) -> Result<Request<'a>, Error> {
+        self.create_request(RequestFlags::empty())
+    }
+
+    /// Create the last request of the sequence, flagged with [RequestFlags::SEQUENCE_END]
+    /// (and [RequestFlags::SEQUENCE_START], if it's the only request of the sequence).
+    pub fn last_request(mut self) -> Result<Request<'a>, Error> {
+        let request = self.create_request(RequestFlags::SEQUENCE_END)?;
+        self.ended = true;
+        Ok(request)
+    }
+
+    fn create_request(&mut self, mut flags: RequestFlags) -> Result<Request<'a>, Error> {
+        if self.sent == 0 {
+            flags |= RequestFlags::SEQUENCE_START;
+        }
+
+        let mut request = self.server.create_request(&self.model, self.version)?;
+        request
+            .set_correlation_id(self.correlation_id)?
+            .set_flags(flags)?;
+        self.sent += 1;
+        Ok(request)
+    }
+}
+
+impl Drop for Sequence<'_> {
+    fn drop(&mut self) {
+        if self.sent != 0 && !self.ended {
+            log::warn!(
+                "Sequence {} of model {} was dropped without the last request, it will be ended by the idle timeout",
+                self.correlation_id,
+                self.model
+            );
+        }
+    }
+}
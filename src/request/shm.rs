@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    io,
+    ptr::null_mut,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+#[cfg(feature = "gpu")]
+use cuda_driver_sys::{cuMemHostRegister_v2, cuMemHostUnregister, CU_MEMHOSTREGISTER_PORTABLE};
+use libc::c_void;
+
+use super::Allocator;
+use crate::{
+    error::{Error, ErrorCode},
+    memory::{Buffer, DataType, Deleter, MemoryType},
+    to_cstring, Response,
+};
+
+/// Magic bytes at the start of each output segment of [ShmAllocator].
+pub const SHM_OUTPUT_MAGIC: [u8; 8] = *b"TRSHMOUT";
+/// Size of the output segment header of [ShmAllocator]. Output data starts at this offset.
+pub const SHM_OUTPUT_HEADER_SIZE: usize = 256;
+/// Max number of dims of the output that can be written to the segment header.
+pub const SHM_OUTPUT_MAX_DIMS: usize = 8;
+
+/// Header of the output segment. Check [ShmAllocator] for the layout description.
+#[repr(C)]
+struct Header {
+    magic: [u8; 8],
+    generation: AtomicU64,
+    capacity: u64,
+    byte_size: u64,
+    data_type: [u8; 16],
+    dims_count: u32,
+    _reserved: u32,
+    dims: [i64; SHM_OUTPUT_MAX_DIMS],
+    name: [u8; 128],
+}
+
+const _: () = assert!(std::mem::size_of::<Header>() <= SHM_OUTPUT_HEADER_SIZE);
+
+fn copy_str(target: &mut [u8], value: &str) {
+    target.fill(0);
+    let len = value.len().min(target.len() - 1);
+    target[..len].copy_from_slice(&value.as_bytes()[..len]);
+}
+
+fn os_error(name: &str, call: &str) -> Error {
+    Error::new(
+        ErrorCode::Internal,
+        format!(
+            "{call} of shared memory {name} failed: {}",
+            io::Error::last_os_error()
+        ),
+    )
+}
+
+/// Mapped output segment.
+struct Segment {
+    name: String,
+    base: *mut c_void,
+    map_len: usize,
+    pinned: bool,
+}
+
+// Segment memory is accessed only via the Buffer given to Triton and under the ShmAllocator lock.
+unsafe impl Send for Segment {}
+unsafe impl Sync for Segment {}
+
+impl Segment {
+    fn create(name: String, capacity: usize) -> Result<Self, Error> {
+        let c_name = to_cstring(&name)?;
+        let map_len = SHM_OUTPUT_HEADER_SIZE + capacity;
+
+        let base = unsafe {
+            let fd = libc::shm_open(
+                c_name.as_ptr(),
+                libc::O_RDWR | libc::O_CREAT,
+                libc::S_IRUSR | libc::S_IWUSR,
+            );
+            if fd < 0 {
+                return Err(os_error(&name, "shm_open"));
+            }
+            if libc::ftruncate(fd, map_len as libc::off_t) != 0 {
+                libc::close(fd);
+                return Err(os_error(&name, "ftruncate"));
+            }
+            let base = libc::mmap(
+                null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            );
+            libc::close(fd);
+            if base == libc::MAP_FAILED {
+                return Err(os_error(&name, "mmap"));
+            }
+            base
+        };
+
+        #[cfg_attr(not(feature = "gpu"), allow(unused_mut))]
+        let mut segment = Segment {
+            name,
+            base,
+            map_len,
+            pinned: false,
+        };
+
+        // Page-lock the segment, so it can be returned for the GPU outputs as Pinned memory.
+        #[cfg(feature = "gpu")]
+        {
+            run_in_context_sync!(
+                0,
+                cuda_call!(cuMemHostRegister_v2(
+                    segment.base,
+                    segment.map_len,
+                    CU_MEMHOSTREGISTER_PORTABLE
+                ))
+            )?;
+            segment.pinned = true;
+        }
+
+        let header = segment.header_mut();
+        header.magic = SHM_OUTPUT_MAGIC;
+        header.capacity = capacity as u64;
+        header.generation.store(0, Ordering::Release);
+        Ok(segment)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn header_mut(&self) -> &mut Header {
+        unsafe { &mut *(self.base as *mut Header) }
+    }
+
+    fn data(&self) -> *mut c_void {
+        (self.base as usize + SHM_OUTPUT_HEADER_SIZE) as *mut c_void
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        #[cfg(feature = "gpu")]
+        if self.pinned {
+            let base = self.base;
+            let unregister = || -> Result<(), Error> {
+                run_in_context_sync!(0, cuda_call!(cuMemHostUnregister(base)))
+            };
+            if let Err(err) = unregister() {
+                log::error!("Error unregistering shared memory {}: {err}", self.name);
+            }
+        }
+        unsafe {
+            libc::munmap(self.base, self.map_len);
+        }
+    }
+}
+
+struct Inner {
+    prefix: String,
+    capacity: usize,
+    segments: HashMap<String, Arc<Segment>>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        for segment in self.segments.values() {
+            if let Ok(name) = to_cstring(&segment.name) {
+                unsafe { libc::shm_unlink(name.as_ptr()) };
+            }
+        }
+    }
+}
+
+/// Allocator placing each output into the POSIX shared memory segment,
+/// so other processes can consume inference results without copies. \
+/// Segment of the output is named "{prefix}_{output name}" (check [ShmAllocator::segment_name])
+/// and is created on the first allocation of the output. Segments are removed when the last clone of the allocator is dropped.
+///
+/// Segment starts with the [SHM_OUTPUT_HEADER_SIZE] bytes header followed by the output data (native byte order):
+/// | Offset | Type | Field |
+/// |---|---|---|
+/// | 0 | [u8; 8] | magic [SHM_OUTPUT_MAGIC] |
+/// | 8 | u64 | generation counter |
+/// | 16 | u64 | data capacity in bytes |
+/// | 24 | u64 | data byte size |
+/// | 32 | [u8; 16] | data type name ("FP32"), null terminated |
+/// | 48 | u32 | number of dims (up to [SHM_OUTPUT_MAX_DIMS]) |
+/// | 56 | [i64; 8] | dims |
+/// | 120 | [u8; 128] | output name, null terminated |
+///
+/// Generation counter is odd while Triton writes the output and becomes even after [ShmAllocator::publish].
+/// Consumer should read the generation, then the header and data, and check that the generation is the same and even.
+///
+/// The allocator is cheap to clone: pass one clone to the request and call [ShmAllocator::publish] on the other one,
+/// when the response is received. Only one request per allocator should be in flight.
+/// ```ignore
+/// let allocator = ShmAllocator::new("/detector", 16 << 20);
+/// request.add_allocator(Box::new(allocator.clone()));
+/// let response = request.infer_async()?.await?;
+/// allocator.publish(&response)?;
+/// ```
+///
+/// **Note**: segment memory is CPU memory. With "gpu" feature it's page-locked and returned as [MemoryType::Pinned].
+#[derive(Clone)]
+pub struct ShmAllocator(Arc<Mutex<Inner>>);
+
+impl ShmAllocator {
+    /// Create allocator.\
+    /// `prefix`: prefix of the segment names, e.g. "/detector". \
+    /// `capacity`: max byte size of each output.
+    pub fn new<P: AsRef<str>>(prefix: P, capacity: usize) -> Self {
+        let prefix = prefix.as_ref();
+        let prefix = if prefix.starts_with('/') {
+            prefix.to_string()
+        } else {
+            format!("/{prefix}")
+        };
+        ShmAllocator(Arc::new(Mutex::new(Inner {
+            prefix,
+            capacity,
+            segments: HashMap::new(),
+        })))
+    }
+
+    /// Get the name of the shared memory segment of the output `tensor_name`.
+    pub fn segment_name<N: AsRef<str>>(&self, tensor_name: N) -> String {
+        let prefix = self
+            .lock()
+            .map(|inner| inner.prefix.clone())
+            .unwrap_or_default();
+        segment_name(&prefix, tensor_name.as_ref())
+    }
+
+    /// Write shapes of the `response` outputs to the segment headers and make the outputs available to the consumers.
+    pub fn publish(&self, response: &Response) -> Result<(), Error> {
+        let inner = self.lock()?;
+        for output in response.get_outputs() {
+            let Some(segment) = inner.segments.get(&output.name) else {
+                continue;
+            };
+            if output.get_buffer().ptr != segment.data() {
+                return Err(Error::new(
+                    ErrorCode::Internal,
+                    format!(
+                        "Output {} is not placed in shared memory {}",
+                        output.name, segment.name
+                    ),
+                ));
+            }
+            if output.shape.len() > SHM_OUTPUT_MAX_DIMS {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Output {} has {} dims, max {SHM_OUTPUT_MAX_DIMS} can be published",
+                        output.name,
+                        output.shape.len()
+                    ),
+                ));
+            }
+
+            let header = segment.header_mut();
+            header.byte_size = output.get_buffer().len as u64;
+            copy_str(&mut header.data_type, output.data_type().as_str());
+            header.dims = [0; SHM_OUTPUT_MAX_DIMS];
+            header.dims[..output.shape.len()].copy_from_slice(&output.shape);
+            header.dims_count = output.shape.len() as u32;
+
+            let generation = header.generation.load(Ordering::Acquire);
+            if generation % 2 == 1 {
+                header.generation.store(generation + 1, Ordering::Release);
+            }
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Inner>, Error> {
+        self.0.lock().map_err(|err| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("ShmAllocator lock is poisoned: {err}"),
+            )
+        })
+    }
+}
+
+fn segment_name(prefix: &str, tensor_name: &str) -> String {
+    // '/' is not allowed in the shared memory name except the first char.
+    format!("{prefix}_{}", tensor_name.replace('/', "_"))
+}
+
+#[async_trait::async_trait]
+impl Allocator for ShmAllocator {
+    async fn allocate(
+        &mut self,
+        tensor_name: String,
+        requested_mem_type: MemoryType,
+        byte_size: usize,
+        data_type: DataType,
+    ) -> Result<Buffer, Error> {
+        let mut inner = self.lock()?;
+        if byte_size > inner.capacity {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Output {tensor_name} of {byte_size} bytes exceeds shared memory capacity {}",
+                    inner.capacity
+                ),
+            ));
+        }
+
+        let segment = match inner.segments.get(&tensor_name) {
+            Some(segment) => segment.clone(),
+            None => {
+                let name = segment_name(&inner.prefix, &tensor_name);
+                let segment = Arc::new(Segment::create(name, inner.capacity)?);
+                inner.segments.insert(tensor_name.clone(), segment.clone());
+                segment
+            }
+        };
+
+        let memory_type = match (requested_mem_type, segment.pinned) {
+            (MemoryType::Cpu, _) => MemoryType::Cpu,
+            (_, true) => MemoryType::Pinned,
+            (MemoryType::Pinned, false) => MemoryType::Cpu,
+            (MemoryType::Gpu, false) => {
+                return Err(Error::new(
+                    ErrorCode::Unsupported,
+                    format!("ShmAllocator can't place output {tensor_name} in GPU memory"),
+                ))
+            }
+        };
+
+        let header = segment.header_mut();
+        // Mark the output as being written.
+        let generation = header.generation.load(Ordering::Acquire);
+        header
+            .generation
+            .store(generation + 1 + generation % 2, Ordering::Release);
+        header.byte_size = byte_size as u64;
+        copy_str(&mut header.data_type, data_type.as_str());
+        copy_str(&mut header.name, &tensor_name);
+        header.dims_count = 0;
+
+        Ok(Buffer {
+            ptr: segment.data(),
+            len: byte_size,
+            data_type,
+            memory_type,
+            device_id: 0,
+            owned: true,
+            // Segment is unmapped when the allocator and all its buffers are dropped.
+            deleter: Some(Deleter(Box::new(move |_| drop(segment)))),
+        })
+    }
+
+    fn enable_queries(&self) -> bool {
+        true
+    }
+
+    async fn pre_allocation_query(
+        &mut self,
+        _tensor_name: Option<String>,
+        _byte_size: Option<usize>,
+        requested_memory_type: MemoryType,
+    ) -> MemoryType {
+        let pinned = cfg!(feature = "gpu");
+        match requested_memory_type {
+            MemoryType::Cpu => MemoryType::Cpu,
+            _ if pinned => MemoryType::Pinned,
+            _ => MemoryType::Cpu,
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{sync::Arc, time::Duration};
+
+use super::{Allocator, DefaultAllocator, Inputs, SizeCheck};
+use crate::{parameter::RequestParameter, server::ServerHandle, Error, Request};
+
+type AllocatorFactory = Arc<dyn Fn() -> Box<dyn Allocator> + Send + Sync>;
+
+/// Settings of the repeated requests to the same model. \
+/// One can obtain this item using [crate::Server::request_template].
+///
+/// The template keeps the model, the selected outputs, the parameters and the allocator factory,
+/// so each request is stamped out with a single call:
+/// ```ignore
+/// let template = server
+///     .request_template("detector")
+///     .outputs(["BOXES", "SCORES"])
+///     .timeout(Duration::from_millis(200));
+///
+/// for image in images {
+///     let response = template
+///         .instantiate(vec![("IMAGE".to_string(), image, vec![1, 640, 640, 3])])?
+///         .infer_async()?
+///         .await?;
+/// }
+/// ```
+///
+/// The template owns the [ServerHandle], so it (and the requests it creates) can be moved to the spawned tasks.
+#[derive(Clone)]
+pub struct RequestTemplate {
+    server: ServerHandle,
+    model: String,
+    version: i64,
+    outputs: Option<Vec<String>>,
+    output_host_policies: Vec<(String, String)>,
+    parameters: Vec<RequestParameter>,
+    priority: Option<u32>,
+    timeout: Option<Duration>,
+    size_check: Option<SizeCheck>,
+    namespace: Option<String>,
+    allocator: Option<AllocatorFactory>,
+}
+
+impl RequestTemplate {
+    pub(crate) fn new<M: AsRef<str>>(server: ServerHandle, model: M) -> Self {
+        RequestTemplate {
+            server,
+            model: model.as_ref().to_string(),
+            version: -1,
+            outputs: None,
+            output_host_policies: Vec::new(),
+            parameters: Vec::new(),
+            priority: None,
+            timeout: None,
+            size_check: None,
+            namespace: None,
+            allocator: None,
+        }
+    }
+
+    /// Set the version of the model. \
+    /// Default is -1, meaning that the server will choose a version based on the model's policy.
+    pub fn version(mut self, version: i64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Request only the `outputs` of the model. Check [Request::request_outputs].
+    pub fn outputs<I, N>(mut self, outputs: I) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: AsRef<str>,
+    {
+        self.outputs = Some(
+            outputs
+                .into_iter()
+                .map(|name| name.as_ref().to_string())
+                .collect(),
+        );
+        self
+    }
+
+    /// Allocate the output in the memory domain of the host policy. Check [Request::set_output_host_policy].
+    pub fn output_host_policy<O: AsRef<str>, P: AsRef<str>>(
+        mut self,
+        output: O,
+        policy: P,
+    ) -> Self {
+        self.output_host_policies
+            .push((output.as_ref().to_string(), policy.as_ref().to_string()));
+        self
+    }
+
+    /// Add the parameter of the requests. Check [Request::set_parameter].
+    pub fn parameter<P: Into<RequestParameter>>(mut self, parameter: P) -> Self {
+        self.parameters.push(parameter.into());
+        self
+    }
+
+    /// Set the priority of the requests. Check [Request::set_priority].
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Set the timeout of the requests. Check [Request::set_timeout].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the input buffer size check mode. Check [Request::set_size_check].
+    pub fn size_check(mut self, size_check: SizeCheck) -> Self {
+        self.size_check = Some(size_check);
+        self
+    }
+
+    /// Set the namespace the usage of the requests is accounted to. Check [Request::set_namespace].
+    pub fn namespace<N: AsRef<str>>(mut self, namespace: N) -> Self {
+        self.namespace = Some(namespace.as_ref().to_string());
+        self
+    }
+
+    /// Set the factory of the output allocator of each request. \
+    /// Default is [DefaultAllocator].
+    pub fn allocator_factory<F>(mut self, factory: F) -> Self
+    where
+        F: Fn() -> Box<dyn Allocator> + Send + Sync + 'static,
+    {
+        self.allocator = Some(Arc::new(factory));
+        self
+    }
+
+    /// Get the model of the template.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Create the request with the template settings and the `inputs`. \
+    /// Returns error if the model is not found or any of the inputs, outputs or parameters mismatches the model.
+    pub fn instantiate(&self, inputs: Inputs) -> Result<Request<'static>, Error> {
+        let mut request = self
+            .server
+            .create_request_owned(&self.model, self.version)?;
+
+        if let Some(size_check) = self.size_check {
+            request.set_size_check(size_check);
+        }
+        if let Some(namespace) = &self.namespace {
+            request.set_namespace(namespace);
+        }
+        if let Some(priority) = self.priority {
+            request.set_priority(priority)?;
+        }
+        if let Some(timeout) = self.timeout {
+            request.set_timeout(timeout)?;
+        }
+        for parameter in &self.parameters {
+            request.set_parameter(parameter.clone())?;
+        }
+        if let Some(outputs) = &self.outputs {
+            request.request_outputs(outputs)?;
+        }
+        for (output, policy) in &self.output_host_policies {
+            request.set_output_host_policy(output, policy)?;
+        }
+        for (name, buffer, dims) in inputs {
+            request.add_input_with_dims(name, buffer, dims)?;
+        }
+        match &self.allocator {
+            Some(factory) => request.add_allocator(factory()),
+            None => request.add_allocator(Box::<DefaultAllocator>::default()),
+        };
+
+        Ok(request)
+    }
+}
@@ -21,7 +21,14 @@ impl Future for ResponseFuture {
         }
         let request_canceller = self.request_ptr.clone();
 
-        let res = unsafe { self.map_unchecked_mut(|this| &mut this.response_receiver) }
+        if let Some(ticket) = &self.ticket {
+            if !ticket.is_turn(cx.waker()) {
+                return std::task::Poll::Pending;
+            }
+        }
+
+        let this = self.get_mut();
+        let res = std::pin::Pin::new(&mut this.response_receiver)
             .poll(cx)
             .map(|recv_res| match recv_res {
                 Ok(res) => res,
@@ -36,6 +43,8 @@ impl Future for ResponseFuture {
             request_canceller
                 .is_inferenced
                 .store(true, std::sync::atomic::Ordering::SeqCst);
+            // Pass the turn to the next response.
+            this.ticket = None;
         }
         res
     }
@@ -49,6 +58,9 @@ impl ResponseFuture {
     /// This function panics if called within an asynchronous execution context.
     pub fn blocking_recv(self) -> Result<Response, InferenceError> {
         let request_canceller = self.request_ptr.clone();
+        if let Some(ticket) = &self.ticket {
+            ticket.wait_turn();
+        }
         let res = match self.response_receiver.blocking_recv() {
             Ok(res) => res,
             Err(recv_err) => Err(Error::new(
@@ -78,6 +90,52 @@ impl ResponseFuture {
     }
 }
 
+/// Yields the results of the inference: Ok([Response]) or Err([InferenceError]), until the final response.
+impl futures::Stream for ResponseStream {
+    type Item = Result<Response, InferenceError>;
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let res = this.response_receiver.poll_recv(cx);
+        if let std::task::Poll::Ready(None) = res {
+            this.request_ptr
+                .is_inferenced
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+        res
+    }
+}
+
+impl ResponseStream {
+    /// Blocking receive of the next response to call outside of asynchronous contexts.
+    /// Returns None after the final response.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if called within an asynchronous execution context.
+    pub fn blocking_next(&mut self) -> Option<Result<Response, InferenceError>> {
+        let res = self.response_receiver.blocking_recv();
+        if res.is_none() {
+            self.request_ptr
+                .is_inferenced
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+        res
+    }
+
+    /// Get the future to return the input buffers assigned to the Request.
+    /// Check [ResponseFuture::get_input_release].
+    pub fn get_input_release(&mut self) -> InputRelease {
+        self.input_release.take().unwrap_or_else(|| {
+            log::error!("ResponseStream::get_input_release was invoked twice in a row. Empty future is returned");
+            let (_, rx) = tokio::sync::oneshot::channel();
+            InputRelease(rx)
+        })
+    }
+}
+
 impl RequestCanceller {
     fn is_cancelled(&self) -> Result<bool, Error> {
         let mut res = false;
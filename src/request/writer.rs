@@ -0,0 +1,153 @@
+use std::{
+    io::{self, Write},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::AsyncWrite;
+
+use super::Request;
+use crate::{
+    error::Error,
+    memory::{Buffer, DataType, MemoryType},
+};
+
+/// Default size of the chunks of [InputWriter].
+pub const DEFAULT_INPUT_CHUNK: usize = 1 << 20;
+
+/// Writer filling the input of the request incrementally, chunk by chunk
+/// (check [Request::append_input_chunk]), e.g. to decode the audio or video directly into the input buffers. \
+/// One can obtain this item using [Request::input_writer].
+///
+/// Implements [std::io::Write] and [tokio::io::AsyncWrite] (the data is copied synchronously).
+/// The last chunk is appended on [InputWriter::finish], flush or drop.
+/// ```ignore
+/// let mut writer = request.input_writer("AUDIO")?.chunk_size(64 * 1024);
+/// decoder.decode_into(&mut writer)?;
+/// writer.finish()?;
+/// ```
+pub struct InputWriter<'r, 'a> {
+    request: &'r mut Request<'a>,
+    name: String,
+    data_type: DataType,
+    memory_type: MemoryType,
+    chunk_size: usize,
+    /// Chunk being filled.
+    chunk: Option<Buffer>,
+    /// Number of the written bytes of the chunk.
+    filled: usize,
+}
+
+impl<'a> Request<'a> {
+    /// Get the writer filling the input `input_name` incrementally. Check [InputWriter]. \
+    /// Returns error if the model has no such input.
+    pub fn input_writer<N: AsRef<str>>(
+        &mut self,
+        input_name: N,
+    ) -> Result<InputWriter<'_, 'a>, Error> {
+        let shape = self.get_shape(input_name.as_ref())?;
+        Ok(InputWriter {
+            request: self,
+            name: input_name.as_ref().to_string(),
+            data_type: shape.datatype,
+            memory_type: MemoryType::Cpu,
+            chunk_size: DEFAULT_INPUT_CHUNK,
+            chunk: None,
+            filled: 0,
+        })
+    }
+}
+
+impl InputWriter<'_, '_> {
+    /// Set the memory type of the chunks. Default is [MemoryType::Cpu]. \
+    /// GPU chunks are allocated on the device of the request (check [Request::set_device]), device 0 by default.
+    pub fn memory_type(mut self, memory_type: MemoryType) -> Self {
+        self.memory_type = memory_type;
+        self
+    }
+
+    /// Set the byte size of the chunks. Default is [DEFAULT_INPUT_CHUNK].
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Append the last chunk. Unlike drop, returns the error of it.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.append_chunk()
+    }
+
+    /// Append the chunk being filled to the request, cut to the written size.
+    fn append_chunk(&mut self) -> Result<(), Error> {
+        if let Some(mut chunk) = self.chunk.take() {
+            chunk.len = self.filled;
+            self.filled = 0;
+            self.request.append_input_chunk(&self.name, chunk)?;
+        }
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<usize, Error> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        let chunk = match &mut self.chunk {
+            Some(chunk) => chunk,
+            None => {
+                let sample_size = self.data_type.size() as usize;
+                let count = self.chunk_size.div_ceil(sample_size);
+                let device = self.request.device.unwrap_or(0);
+                self.chunk.insert(Buffer::alloc_with_data_type_on(
+                    device,
+                    count,
+                    self.memory_type,
+                    self.data_type,
+                )?)
+            }
+        };
+
+        let written = data.len().min(chunk.size() - self.filled);
+        chunk.copy_from_slice(self.filled, &data[..written])?;
+        self.filled += written;
+        if self.filled == chunk.size() {
+            self.append_chunk()?;
+        }
+        Ok(written)
+    }
+}
+
+impl Write for InputWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.write_bytes(buf)?)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(self.append_chunk()?)
+    }
+}
+
+impl AsyncWrite for InputWriter<'_, '_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.get_mut().flush())
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl Drop for InputWriter<'_, '_> {
+    fn drop(&mut self) {
+        if let Err(err) = self.append_chunk() {
+            log::warn!("Can't append the last chunk of input {}: {err}", self.name);
+        }
+    }
+}
@@ -1,9 +1,17 @@
 #![allow(clippy::arc_with_non_send_sync)]
 
+mod batch;
+mod classification;
+mod dequantize;
+pub use batch::BatchIter;
+pub use classification::Classification;
+pub(crate) use dequantize::from_config as quantization_from_config;
+pub use dequantize::Quantization;
+
 use std::{
+    borrow::Cow,
     collections::HashMap,
     ffi::{c_void, CStr},
-    hint,
     mem::transmute,
     os::raw::c_char,
     ptr::{null, null_mut},
@@ -12,18 +20,27 @@ use std::{
 };
 
 use log::trace;
-use tokio::runtime::Handle;
 
 use crate::{
     allocator::Allocator,
-    error::{Error, CSTR_CONVERT_ERROR_PLUG},
+    error::{Error, ErrorCode, CSTR_CONVERT_ERROR_PLUG},
     from_char_array,
-    memory::{Buffer, DataType, MemoryType},
+    memory::{bytes_to_f32, Buffer, DataType, MemoryType, Sample},
     parameter::{Parameter, ParameterContent},
     request::infer::InferenceError,
-    sys,
+    runtime::Runtime,
+    sys, testing,
 };
 
+bitflags::bitflags! {
+    /// Flags of the inference response.
+    pub struct ResponseFlags: u32 {
+        /// The response is the last one for the request. \
+        /// Decoupled models (and ensembles with decoupled steps) may send the final response without outputs.
+        const COMPLETE_FINAL = sys::tritonserver_responsecompleteflag_enum_TRITONSERVER_RESPONSE_COMPLETE_FINAL;
+    }
+}
+
 /// Output tensor of the model.
 ///
 /// Must not outlive the parent Response.
@@ -38,6 +55,8 @@ pub struct Output {
     /// Shape (dims) of the output tensor.
     pub shape: Vec<i64>,
     buffer: Buffer,
+    /// Quantization parameters declared in the model config.
+    quantization: Option<Quantization>,
     parent_response: Arc<InferenceResponseWrapper>,
     index_in_parent_response: u32,
 }
@@ -66,10 +85,85 @@ impl Output {
         self.buffer.data_type
     }
 
+    /// Copy the output to the array of the output shape.\
+    /// Returns error if `T` does not match the output data type.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check [crate::memory] module level documentation for more info).
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray<T: crate::memory::Sample>(&self) -> Result<ndarray::ArrayD<T>, Error> {
+        self.buffer.to_ndarray(&self.shape)
+    }
+
+    /// Copy the output to the arrow array: flat array for the outputs of rank 1,
+    /// `FixedSizeList` array of `shape[0]` rows for the outputs of the higher rank.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check [crate::memory] module level documentation for more info).
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self) -> Result<arrow_array::ArrayRef, Error> {
+        self.buffer.to_arrow(&self.shape)
+    }
+
+    /// Copy the output to the vector of `T`. \
+    /// Returns error if `T` does not match the output data type.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check [crate::memory] module level documentation for more info).
+    pub fn to_vec<T: Sample>(&self) -> Result<Vec<T>, Error> {
+        if T::DATA_TYPE != self.data_type() {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Output {} is of {:?}, not of {:?}",
+                    self.name,
+                    self.data_type(),
+                    T::DATA_TYPE
+                ),
+            ));
+        }
+        let bytes = self.host_bytes()?;
+        Ok(bytes
+            .chunks_exact(std::mem::size_of::<T>())
+            .map(|chunk| unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const T) })
+            .collect())
+    }
+
+    /// Copy the output of Fp32, Fp16 or Bf16 data type to the vector of f32, converting each value
+    /// (check [Buffer::from_f32_as] for the inputs). \
+    /// Returns error for the other data types.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check [crate::memory] module level documentation for more info).
+    pub fn to_f32_vec(&self) -> Result<Vec<f32>, Error> {
+        bytes_to_f32(self.data_type(), &self.host_bytes()?).ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Output {} is of {:?}, it can't be converted to f32",
+                    self.name,
+                    self.data_type()
+                ),
+            )
+        })
+    }
+
     /// Get a classification label associated with the output.
+    /// Returns error if the model config has no labels for the output.
     pub fn classification_label(&self, class: u64) -> Result<String, Error> {
         self.parent_response
-            .classification_label(self.index_in_parent_response, class)
+            .classification_label(self.index_in_parent_response, class)?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorCode::NotFound,
+                    format!("Output {} has no label of class {class}", self.name),
+                )
+            })
+    }
+
+    /// Get the output content as host located bytes. Gpu content is copied.
+    fn host_bytes(&self) -> Result<Cow<'_, [u8]>, Error> {
+        if self.memory_type() == MemoryType::Gpu {
+            self.buffer.get_owned_slice(..).map(Cow::Owned)
+        } else {
+            Ok(Cow::Borrowed(self.buffer.bytes()))
+        }
     }
 }
 
@@ -81,6 +175,7 @@ pub struct Response {
     /// release(), в которых участвует алокатор. Соответсвенно, он не должен быть уничтожен до этого момента.
     allocator: Arc<Allocator>,
     parameters: Vec<Parameter>,
+    flags: ResponseFlags,
 }
 
 unsafe impl Send for Response {}
@@ -90,38 +185,33 @@ impl Response {
     /// Read the inference result, obtain output.
     pub(crate) fn new(
         ptr: *mut sys::TRITONSERVER_InferenceResponse,
+        flags: ResponseFlags,
         buffers_count: u32,
         allocator: Arc<Allocator>,
-        runtime: Handle,
+        quantization: &HashMap<String, Quantization>,
+        runtime: Runtime,
     ) -> Result<Self, InferenceError> {
         trace!("Response::new() is called");
         let wrapper = Arc::new(InferenceResponseWrapper(ptr));
 
         // Ошибка в ходе выполнения.
-        if let Some(error) = wrapper.error() {
+        if let Some(mut error) = wrapper.error() {
+            if let Ok((model, version)) = wrapper.model() {
+                error = error.with_model(model, version);
+            }
+            if let Ok(id) = wrapper.id() {
+                error = error.with_request_id(id);
+            }
             drop(wrapper);
 
             if allocator.is_alloc_called() {
                 // Waiting for the end of the release
-
-                while allocator
-                    .0
-                    .returned_buffers
-                    .load(std::sync::atomic::Ordering::Relaxed)
-                    < buffers_count
-                {
-                    hint::spin_loop()
-                }
+                let waiting = allocator.clone();
+                testing::block_on_callback(runtime, move || async move {
+                    waiting.wait_returned(buffers_count).await
+                });
             }
-
-            let bufs = std::thread::spawn(move || {
-                runtime.block_on(async move {
-                    let mut bufs = allocator.0.output_buffers.write().await;
-                    bufs.drain().collect()
-                })
-            })
-            .join()
-            .unwrap();
+            let bufs = allocator.take_output_buffers();
 
             return Err(InferenceError {
                 error,
@@ -130,6 +220,12 @@ impl Response {
         }
 
         let output_count = wrapper.output_count()?;
+        // Empty final response of the decoupled model doesn't own any output buffers.
+        let buffers_count = if output_count == 0 && flags.contains(ResponseFlags::COMPLETE_FINAL) {
+            0
+        } else {
+            buffers_count
+        };
 
         if output_count != buffers_count {
             log::error!(
@@ -141,14 +237,22 @@ impl Response {
         let mut output_ids = Vec::new();
         trace!("Response::new() obtaining outputs");
         for output_id in 0..output_count {
-            let output = wrapper.output(output_id)?;
+            let mut output = wrapper.output(output_id)?;
+            output.quantization = quantization.get(&output.name).copied();
             output_ids.push(output.name.clone());
             outputs.push(output);
         }
 
         let mut parameters = Vec::new();
         for parameter_id in 0..wrapper.parameter_count()? {
-            parameters.push(wrapper.parameter(parameter_id)?);
+            match wrapper.parameter(parameter_id) {
+                Ok(parameter) => parameters.push(parameter),
+                // Unreadable parameter should not fail the inference results.
+                Err(err) if err.code() == ErrorCode::Unsupported => {
+                    log::warn!("Response parameter is skipped: {err}")
+                }
+                Err(err) => return Err(err.into()),
+            }
         }
 
         Ok(Self {
@@ -157,6 +261,7 @@ impl Response {
             buffers_count,
             allocator,
             parameters,
+            flags,
         })
     }
 
@@ -170,6 +275,21 @@ impl Response {
         self.outputs.iter().find(|o| o.name == output_name.as_ref())
     }
 
+    /// Collect the outputs to the arrow record batch with the column per output (check [Output::to_arrow]). \
+    /// Returns error if the outputs have different first dims (number of rows).
+    ///
+    /// **Note**: If the outputs are located on Gpu, method should be called in sync with cuda context pinned (check [crate::memory] module level documentation for more info).
+    #[cfg(feature = "arrow")]
+    pub fn to_record_batch(&self) -> Result<arrow_array::RecordBatch, Error> {
+        let columns = self
+            .outputs
+            .iter()
+            .map(|output| Ok((output.name.as_str(), output.to_arrow()?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        arrow_array::RecordBatch::try_from_iter(columns)
+            .map_err(|err| Error::new(ErrorCode::InvalidArg, err.to_string()))
+    }
+
     /// Deconstruct the Response and get all the allocated output buffers back. \
     /// If you want just an immutable result of the inference, use [Response::get_outputs] or [Response::get_output] method.
     pub async fn return_buffers(self) -> Result<HashMap<String, Buffer>, Error> {
@@ -181,22 +301,9 @@ impl Response {
         drop(self.outputs);
         drop(self.triton_ptr_wrapper);
         trace!("return_buffer() awaiting on output receivers");
-        let buffers_count = self.buffers_count;
-
-        while self
-            .allocator
-            .0
-            .returned_buffers
-            .load(std::sync::atomic::Ordering::Relaxed)
-            < buffers_count
-        {
-            hint::spin_loop()
-        }
+        self.allocator.wait_returned(self.buffers_count).await;
 
-        let res = {
-            let mut bufs = self.allocator.0.output_buffers.write().await;
-            bufs.drain().collect()
-        };
+        let res = self.allocator.take_output_buffers();
 
         drop(self.allocator);
         Ok(res)
@@ -212,6 +319,17 @@ impl Response {
         self.triton_ptr_wrapper.id()
     }
 
+    /// Get the flags of the response.
+    pub fn flags(&self) -> ResponseFlags {
+        self.flags
+    }
+
+    /// True if the response is the last one for the request. \
+    /// Final response of the decoupled model may contain no outputs.
+    pub fn is_final(&self) -> bool {
+        self.flags.contains(ResponseFlags::COMPLETE_FINAL)
+    }
+
     /// Get all information about the response parameters.
     pub fn parameters(&self) -> Vec<Parameter> {
         self.parameters.clone()
@@ -234,6 +352,7 @@ impl InferenceResponseWrapper {
             Some(Error {
                 ptr: err,
                 owned: false,
+                context: None,
             })
         }
     }
@@ -288,8 +407,12 @@ impl InferenceResponseWrapper {
             &mut value as *mut _,
         ))?;
 
-        assert!(!name.is_null());
-        assert!(!value.is_null());
+        if name.is_null() || value.is_null() {
+            return Err(Error::new(
+                ErrorCode::Internal,
+                format!("Response parameter {index} has no name or value"),
+            ));
+        }
         let name = unsafe { CStr::from_ptr(name) }
             .to_str()
             .unwrap_or(CSTR_CONVERT_ERROR_PLUG);
@@ -306,9 +429,25 @@ impl InferenceResponseWrapper {
                 ParameterContent::Int(unsafe { *(value as *mut i64) })
             }
             sys::TRITONSERVER_parametertype_enum_TRITONSERVER_PARAMETER_BOOL => {
-                ParameterContent::Bool(unsafe { *(value as *mut bool) })
+                // Read as byte: any value except 0 is true, while the invalid `bool` is UB.
+                ParameterContent::Bool(unsafe { *(value as *const u8) } != 0)
+            }
+            sys::TRITONSERVER_parametertype_enum_TRITONSERVER_PARAMETER_DOUBLE => {
+                ParameterContent::Double(unsafe { *(value as *mut f64) })
+            }
+            // Triton C API doesn't expose the size of the bytes parameter value.
+            sys::TRITONSERVER_parametertype_enum_TRITONSERVER_PARAMETER_BYTES => {
+                return Err(Error::new(
+                    ErrorCode::Unsupported,
+                    format!("Can't read bytes parameter \"{name}\" of the response: Triton doesn't expose its size"),
+                ))
+            }
+            unknown => {
+                return Err(Error::new(
+                    ErrorCode::Internal,
+                    format!("Response parameter \"{name}\" has unknown type {unknown}"),
+                ))
             }
-            _ => unreachable!(),
         };
         Parameter::new(name, value)
     }
@@ -373,19 +512,23 @@ impl InferenceResponseWrapper {
             len: byte_size as usize,
             data_type,
             memory_type,
+            device_id: memory_type_id as i32,
             owned: false,
+            deleter: None,
         };
         Ok(Output {
             name,
             shape,
             buffer,
+            quantization: None,
             index_in_parent_response: index,
             parent_response: self.clone(),
         })
     }
 
     /// Get a classification label associated with an output for a given index.
-    fn classification_label(&self, index: u32, class: u64) -> Result<String, Error> {
+    /// None if there is no label.
+    fn classification_label(&self, index: u32, class: u64) -> Result<Option<String>, Error> {
         let mut label = null::<c_char>();
         triton_call!(
             sys::TRITONSERVER_InferenceResponseOutputClassificationLabel(
@@ -394,7 +537,7 @@ impl InferenceResponseWrapper {
                 class as usize,
                 &mut label as *mut _,
             ),
-            from_char_array(label)
+            (!label.is_null()).then(|| from_char_array(label))
         )
     }
 }
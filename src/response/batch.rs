@@ -0,0 +1,114 @@
+//! Iteration over the batch elements of the outputs.
+
+use std::{borrow::Cow, slice::ChunksExact, vec::IntoIter};
+
+use super::Output;
+use crate::{
+    error::{Error, ErrorCode},
+    memory::{MemoryType, Sample},
+};
+
+/// Iterator over the batch elements of the [Output]. One can obtain this item using [Output::iter_batch].
+///
+/// Host outputs are borrowed, Gpu (and unaligned) outputs are copied to the host once.
+#[derive(Debug)]
+pub struct BatchIter<'a, T: Sample> {
+    items: BatchItems<'a, T>,
+}
+
+#[derive(Debug)]
+enum BatchItems<'a, T> {
+    Borrowed(ChunksExact<'a, T>),
+    Owned(IntoIter<Vec<T>>),
+}
+
+impl<'a, T: Sample> Iterator for BatchIter<'a, T> {
+    type Item = Cow<'a, [T]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.items {
+            BatchItems::Borrowed(chunks) => chunks.next().map(Cow::Borrowed),
+            BatchItems::Owned(items) => items.next().map(Cow::Owned),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.items {
+            BatchItems::Borrowed(chunks) => chunks.size_hint(),
+            BatchItems::Owned(items) => items.size_hint(),
+        }
+    }
+}
+
+impl<T: Sample> ExactSizeIterator for BatchIter<'_, T> {}
+
+impl Output {
+    /// Get the batch size of the output: the first dimension of its shape.
+    pub fn batch_size(&self) -> Option<usize> {
+        self.shape.first().map(|dim| (*dim).max(0) as usize)
+    }
+
+    /// Iterate over the elements of the batch (the first dimension of the output shape):
+    /// each item holds `shape[1..].product()` values, e.g. 4 coordinates of the box for the `[N, 4]` output. \
+    /// Returns error if `T` doesn't match the output data type, the output is a scalar
+    /// or the output is smaller than its shape.
+    /// ```ignore
+    /// for (bbox, score) in boxes.iter_batch::<f32>()?.zip(scores.iter_batch::<f32>()?) {
+    ///     draw(bbox[0], bbox[1], bbox[2], bbox[3], score[0]);
+    /// }
+    /// ```
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned (check [crate::memory] module level documentation for more info).
+    pub fn iter_batch<T: Sample>(&self) -> Result<BatchIter<'_, T>, Error> {
+        let Some(batch) = self.batch_size() else {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Output {} is a scalar, it has no batch dimension",
+                    self.name
+                ),
+            ));
+        };
+        let item_len = self.shape[1..]
+            .iter()
+            .map(|dim| (*dim).max(0) as usize)
+            .product::<usize>();
+        let len = batch * item_len;
+
+        if self.memory_type() != MemoryType::Gpu && T::DATA_TYPE == self.data_type() && len > 0 {
+            // Safety: the data type is checked, any bits are valid samples.
+            if let ([], values, _) = unsafe { self.buffer.bytes().align_to::<T>() } {
+                if values.len() >= len {
+                    return Ok(BatchIter {
+                        items: BatchItems::Borrowed(values[..len].chunks_exact(item_len)),
+                    });
+                }
+            }
+        }
+
+        let values = self.to_vec::<T>()?;
+        if values.len() < len {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Output {} of {} values is smaller than its shape {:?}",
+                    self.name,
+                    values.len(),
+                    self.shape
+                ),
+            ));
+        }
+        if item_len == 0 {
+            return Ok(BatchIter {
+                items: BatchItems::Owned(vec![Vec::new(); batch].into_iter()),
+            });
+        }
+        let items: Vec<Vec<T>> = values[..len]
+            .chunks_exact(item_len)
+            .map(<[T]>::to_vec)
+            .collect();
+        Ok(BatchIter {
+            items: BatchItems::Owned(items.into_iter()),
+        })
+    }
+}
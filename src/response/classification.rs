@@ -0,0 +1,100 @@
+//! Top-K classification over the output scores.
+
+use std::cmp::Ordering;
+
+use half::{bf16, f16};
+
+use super::Output;
+use crate::{
+    error::{Error, ErrorCode},
+    memory::DataType,
+};
+
+/// Class of the classification output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    /// Index of the class in the output.
+    pub index: usize,
+    pub score: f32,
+    /// Label of the class from the model config label file. None if the model has no labels.
+    pub label: Option<String>,
+}
+
+impl Output {
+    /// Get `k` classes with the highest scores, sorted by score in descending order. \
+    /// Scores are the output values converted to f32 (dequantized, if the output has [super::Quantization]).
+    /// Labels are taken from the label file of the model config.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned
+    /// (check [crate::memory] module level documentation for more info).
+    pub fn top_k(&self, k: usize) -> Result<Vec<Classification>, Error> {
+        let scores = self.scores()?;
+
+        let mut indices: Vec<usize> = (0..scores.len()).collect();
+        let by_score = |a: &usize, b: &usize| {
+            scores[*b]
+                .partial_cmp(&scores[*a])
+                .unwrap_or(Ordering::Equal)
+        };
+        if k < indices.len() {
+            indices.select_nth_unstable_by(k, by_score);
+            indices.truncate(k);
+        }
+        indices.sort_by(by_score);
+
+        indices
+            .into_iter()
+            .map(|index| {
+                Ok(Classification {
+                    index,
+                    score: scores[index],
+                    label: self
+                        .parent_response
+                        .classification_label(self.index_in_parent_response, index as u64)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Output values as f32.
+    fn scores(&self) -> Result<Vec<f32>, Error> {
+        if self.quantization.is_some() {
+            return self.dequantized::<f32>();
+        }
+
+        let bytes = self.host_bytes()?;
+        macro_rules! convert {
+            ($type:ty, $size:expr, $to_f32:expr) => {
+                bytes
+                    .chunks_exact($size)
+                    .map(|v| $to_f32(<$type>::from_ne_bytes(v.try_into().unwrap())))
+                    .collect()
+            };
+        }
+
+        let scores = match self.data_type() {
+            DataType::Fp32 => convert!(f32, 4, |v| v),
+            DataType::Fp64 => convert!(f64, 8, |v| v as f32),
+            DataType::Fp16 => convert!(f16, 2, f16::to_f32),
+            DataType::Bf16 => convert!(bf16, 2, bf16::to_f32),
+            DataType::Int8 => convert!(i8, 1, |v| v as f32),
+            DataType::Uint8 => convert!(u8, 1, |v| v as f32),
+            DataType::Int16 => convert!(i16, 2, |v| v as f32),
+            DataType::Uint16 => convert!(u16, 2, |v| v as f32),
+            DataType::Int32 => convert!(i32, 4, |v| v as f32),
+            DataType::Uint32 => convert!(u32, 4, |v| v as f32),
+            DataType::Int64 => convert!(i64, 8, |v| v as f32),
+            DataType::Uint64 => convert!(u64, 8, |v| v as f32),
+            data_type => {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!(
+                        "Output {} of {data_type:?} can't be used as scores",
+                        self.name
+                    ),
+                ))
+            }
+        };
+        Ok(scores)
+    }
+}
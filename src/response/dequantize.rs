@@ -0,0 +1,161 @@
+//! Dequantization of the outputs of the quantized models.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::Output;
+use crate::{
+    error::{Error, ErrorCode},
+    memory::{dequantize_bytes, DataType, QuantizationParams, Sample},
+};
+
+/// Suffix of the model config parameter with the output scale.
+const SCALE_SUFFIX: &str = ".scale";
+/// Suffix of the model config parameter with the output zero point.
+const ZERO_POINT_SUFFIX: &str = ".zero_point";
+
+/// Quantization parameters of the output: `real value = (quantized value - zero_point) * scale`. \
+/// Parameters are declared in the model config as "{output name}.scale" and "{output name}.zero_point" (0 if not set):
+/// ```text
+/// parameters: { key: "logits.scale" value: { string_value: "0.0235" } }
+/// parameters: { key: "logits.zero_point" value: { string_value: "-128" } }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantization {
+    pub scale: f32,
+    pub zero_point: i32,
+}
+
+/// Read quantization parameters of the outputs from the model `config` (JSON).
+pub(crate) fn from_config(config: &Value) -> HashMap<String, Quantization> {
+    let Some(parameters) = config.get("parameters").and_then(Value::as_object) else {
+        return HashMap::new();
+    };
+    let value = |key: &str| {
+        parameters
+            .get(key)?
+            .get("string_value")?
+            .as_str()
+            .map(str::trim)
+    };
+
+    let mut res = HashMap::new();
+    for key in parameters.keys() {
+        let Some(output) = key.strip_suffix(SCALE_SUFFIX) else {
+            continue;
+        };
+        let scale = value(key).and_then(|scale| scale.parse::<f32>().ok());
+        let zero_point = match value(&format!("{output}{ZERO_POINT_SUFFIX}")) {
+            Some(zero_point) => zero_point.parse::<i32>().ok(),
+            None => Some(0),
+        };
+        match (scale, zero_point) {
+            (Some(scale), Some(zero_point)) => {
+                res.insert(output.to_string(), Quantization { scale, zero_point });
+            }
+            _ => log::warn!("Invalid quantization parameters of the output {output} are ignored"),
+        }
+    }
+    res
+}
+
+impl Output {
+    /// Quantization parameters of the output declared in the model config. Check [Quantization].
+    pub fn quantization(&self) -> Option<Quantization> {
+        self.quantization
+    }
+
+    /// Dequantize the output with the parameters declared in the model config (check [Quantization]). \
+    /// Output must be of integer data type up to 32 bits.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned
+    /// (check [crate::memory] module level documentation for more info).
+    pub fn dequantized<T: Sample + From<f32>>(&self) -> Result<Vec<T>, Error> {
+        let Quantization { scale, zero_point } = self.quantization.ok_or_else(|| {
+            Error::new(
+                ErrorCode::NotFound,
+                format!(
+                    "Output {} has no quantization parameters in the model config",
+                    self.name
+                ),
+            )
+        })?;
+
+        let bytes = self.host_bytes()?;
+
+        let dequantize = |value: i64| T::from((value - zero_point as i64) as f32 * scale);
+        let values = match self.data_type() {
+            DataType::Int8 => bytes.iter().map(|v| dequantize(*v as i8 as i64)).collect(),
+            DataType::Uint8 => bytes.iter().map(|v| dequantize(*v as i64)).collect(),
+            DataType::Int16 => bytes
+                .chunks_exact(2)
+                .map(|v| dequantize(i16::from_ne_bytes([v[0], v[1]]) as i64))
+                .collect(),
+            DataType::Uint16 => bytes
+                .chunks_exact(2)
+                .map(|v| dequantize(u16::from_ne_bytes([v[0], v[1]]) as i64))
+                .collect(),
+            DataType::Int32 => bytes
+                .chunks_exact(4)
+                .map(|v| dequantize(i32::from_ne_bytes([v[0], v[1], v[2], v[3]]) as i64))
+                .collect(),
+            data_type => {
+                return Err(Error::new(
+                    ErrorCode::InvalidArg,
+                    format!("Output {} of {data_type:?} can't be dequantized", self.name),
+                ))
+            }
+        };
+        Ok(values)
+    }
+
+    /// Dequantize the Int8 or Uint8 output with the per-tensor or per-channel `params`,
+    /// e.g. the ones of the TensorRT INT8 engine that are not declared in the model config. \
+    /// Returns error for the other data types.
+    ///
+    /// **Note**: If self.memory_type() is Gpu, method should be called in sync with cuda context pinned
+    /// (check [crate::memory] module level documentation for more info).
+    pub fn dequantize_to_f32(&self, params: &QuantizationParams) -> Result<Vec<f32>, Error> {
+        dequantize_bytes(params, self.data_type(), &self.host_bytes()?).ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArg,
+                format!(
+                    "Output {} of {:?} can't be dequantized",
+                    self.name,
+                    self.data_type()
+                ),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn config_parameters() {
+        let config = json!({
+            "parameters": {
+                "logits.scale": { "string_value": "0.5" },
+                "logits.zero_point": { "string_value": "-128" },
+                "boxes.scale": { "string_value": "2" },
+                "broken.scale": { "string_value": "x" },
+                "threshold": { "string_value": "0.3" },
+            }
+        });
+        let quantization = from_config(&config);
+        assert_eq!(quantization.len(), 2);
+        assert_eq!(
+            quantization["logits"],
+            Quantization {
+                scale: 0.5,
+                zero_point: -128
+            }
+        );
+        assert_eq!(quantization["boxes"].zero_point, 0);
+    }
+}
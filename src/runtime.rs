@@ -0,0 +1,139 @@
+//! Executors of the asynchronous code the crate runs from the synchronous Triton callbacks
+//! ([Allocator](crate::Allocator) methods, return of the output buffers).
+//!
+//! By default the server uses [TokioExecutor] if it's created inside the tokio runtime and [BlockingExecutor] otherwise,
+//! so the crate can be used without tokio: from the other async runtimes or from the plain synchronous code
+//! (check [Request::infer_blocking](crate::Request::infer_blocking), feature "sync"). \
+//! Set the custom executor with [Server::set_executor](crate::Server::set_executor).
+//!
+//! **Note**: the methods of the crate that spawn the tasks or sleep
+//! (e.g. [Server::load_model_async](crate::Server::load_model_async), [Server::spawn_repository_watcher](crate::Server::spawn_repository_watcher))
+//! still require the tokio runtime.
+
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+use crate::error::{Error, ErrorCode};
+
+/// Future run by the [Executor].
+pub type LocalBoxFuture<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// Runs the futures from the synchronous Triton callbacks.
+pub trait Executor: Send + Sync + 'static {
+    /// Run the `future` to completion on the current thread. \
+    /// Called from the threads of the crate that are not the workers of any async runtime.
+    fn block_on(&self, future: LocalBoxFuture<'_>);
+}
+
+/// Executor running the futures in the context of the tokio runtime.
+#[derive(Debug, Clone)]
+pub struct TokioExecutor(pub Handle);
+
+impl Executor for TokioExecutor {
+    fn block_on(&self, future: LocalBoxFuture<'_>) {
+        self.0.block_on(future)
+    }
+}
+
+/// Executor running the futures on the current thread without any runtime. \
+/// The allocators used with it must not rely on the tokio reactor or timers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockingExecutor;
+
+impl Executor for BlockingExecutor {
+    fn block_on(&self, future: LocalBoxFuture<'_>) {
+        futures::executor::block_on(future)
+    }
+}
+
+/// Executor of the server.
+#[derive(Clone)]
+pub(crate) struct Runtime(Arc<dyn Executor>);
+
+impl fmt::Debug for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Runtime")
+    }
+}
+
+impl Runtime {
+    pub(crate) fn new<E: Executor>(executor: E) -> Self {
+        Runtime(Arc::new(executor))
+    }
+
+    /// [TokioExecutor] of the current tokio runtime, [BlockingExecutor] if there is none.
+    pub(crate) fn current() -> Self {
+        match Handle::try_current() {
+            Ok(handle) => Runtime::new(TokioExecutor(handle)),
+            Err(_) => Runtime::new(BlockingExecutor),
+        }
+    }
+
+    /// Run the `future` to completion on the current thread.
+    pub(crate) fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut output = None;
+        self.0.block_on(Box::pin(async {
+            output = Some(future.await);
+        }));
+        output.expect("executor didn't complete the future")
+    }
+}
+
+/// Run the blocking `f` on the tokio blocking thread pool if there is the tokio runtime, on the current thread otherwise.
+#[doc(hidden)]
+pub async fn run_blocking<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match Handle::try_current() {
+        Ok(handle) => handle
+            .spawn_blocking(f)
+            .await
+            .expect("tokio failed to join thread"),
+        Err(_) => f(),
+    }
+}
+
+/// Run the blocking `f` on the current thread. On the worker of the multi-threaded tokio runtime
+/// its other tasks are moved to the other workers meanwhile.
+pub(crate) fn block_in_place<T, F: FnOnce() -> T>(f: F) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// [run_blocking] of the fallible `f`. The failure to join the blocking task (panic of `f` or the runtime shutdown)
+/// is returned as the error with [ErrorCode::Internal].
+pub(crate) async fn try_run_blocking<T, F>(f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    match Handle::try_current() {
+        Ok(handle) => handle.spawn_blocking(f).await.map_err(|err| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Blocking task is not completed: {err}"),
+            )
+        })?,
+        Err(_) => f(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocking() {
+        assert!(Handle::try_current().is_err());
+        let runtime = Runtime::current();
+        assert_eq!(runtime.block_on(run_blocking(|| 42)), 42);
+        assert_eq!(runtime.block_on(try_run_blocking(|| Ok(42))).unwrap(), 42);
+    }
+}
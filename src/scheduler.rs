@@ -0,0 +1,313 @@
+//! Client side queue of the inference requests. \
+//! Bursts of requests fired with [Request::infer_async] at once are all accepted by Triton,
+//! which may exhaust the GPU memory. [RequestQueue] limits the number of the in-flight inferences per model
+//! and lets the waiting requests in by priority, dropping the ones that wait past their deadline.
+//!
+//! ```ignore
+//! let queue = RequestQueue::new(4);
+//! queue.set_model_limit("detector", 2);
+//!
+//! // Infer the request when the model has a free slot.
+//! let response = queue
+//!     .infer(request, QueueOptions::default().priority(10).timeout(Duration::from_millis(200)))
+//!     .await?;
+//!
+//! // Or hold the permit for the custom work.
+//! let permit = queue.acquire("detector", QueueOptions::default()).await?;
+//! ```
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tokio::{sync::oneshot, time::Instant};
+
+use crate::{
+    error::{Error, ErrorCode},
+    request::InferenceError,
+    Request, Response,
+};
+
+/// Options of the request waiting in the [RequestQueue].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct QueueOptions {
+    /// Requests with the higher priority are let in first, requests of the same priority are let in in the order of arrival. \
+    /// Not related to the Triton request priority ([Request::set_priority]).
+    pub priority: u32,
+    /// The request is rejected if it's not let in until the deadline.
+    pub deadline: Option<Instant>,
+}
+
+impl QueueOptions {
+    /// Set the priority of the request.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the deadline of the request.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the deadline of the request to `timeout` from now.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+}
+
+/// Request waiting for the slot.
+struct Waiter {
+    priority: u32,
+    /// Order of arrival.
+    seq: u64,
+    tx: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    /// The greatest waiter is let in first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct ModelQueue {
+    /// None means the default limit of the queue.
+    limit: Option<usize>,
+    in_flight: usize,
+    waiters: BinaryHeap<Waiter>,
+}
+
+struct State {
+    default_limit: usize,
+    models: HashMap<String, ModelQueue>,
+    next_seq: u64,
+}
+
+impl State {
+    /// Let the waiting requests of the `model` in while there are free slots.
+    fn let_in(&mut self, model: &str) {
+        let default_limit = self.default_limit;
+        let Some(queue) = self.models.get_mut(model) else {
+            return;
+        };
+        while queue.in_flight < queue.limit.unwrap_or(default_limit) {
+            let Some(waiter) = queue.waiters.pop() else {
+                break;
+            };
+            // The waiter is gone if its deadline passed.
+            if waiter.tx.send(()).is_ok() {
+                queue.in_flight += 1;
+            }
+        }
+    }
+}
+
+/// Queue of the requests limiting the number of the in-flight inferences per model. Check [crate::scheduler].
+#[derive(Clone)]
+pub struct RequestQueue {
+    state: Arc<Mutex<State>>,
+}
+
+/// Slot of the model in the [RequestQueue]. Released on drop.
+pub struct Permit {
+    state: Arc<Mutex<State>>,
+    model: String,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(queue) = state.models.get_mut(&self.model) {
+            queue.in_flight = queue.in_flight.saturating_sub(1);
+        }
+        state.let_in(&self.model);
+    }
+}
+
+impl RequestQueue {
+    /// Create the queue letting in up to `default_limit` requests of each model at once.
+    pub fn new(default_limit: usize) -> Self {
+        RequestQueue {
+            state: Arc::new(Mutex::new(State {
+                default_limit: default_limit.max(1),
+                models: HashMap::new(),
+                next_seq: 0,
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Set the limit of the in-flight requests of the `model`, overriding the default one.
+    pub fn set_model_limit<M: AsRef<str>>(&self, model: M, limit: usize) -> &Self {
+        let mut state = self.lock();
+        let model = model.as_ref();
+        state.models.entry(model.to_string()).or_default().limit = Some(limit.max(1));
+        state.let_in(model);
+        self
+    }
+
+    /// Number of the in-flight requests of the `model`.
+    pub fn in_flight<M: AsRef<str>>(&self, model: M) -> usize {
+        self.lock()
+            .models
+            .get(model.as_ref())
+            .map_or(0, |queue| queue.in_flight)
+    }
+
+    /// Number of the requests of the `model` waiting for the slot.
+    pub fn queued<M: AsRef<str>>(&self, model: M) -> usize {
+        self.lock().models.get(model.as_ref()).map_or(0, |queue| {
+            queue
+                .waiters
+                .iter()
+                .filter(|waiter| !waiter.tx.is_closed())
+                .count()
+        })
+    }
+
+    /// Get the slot of the `model` without waiting. Returns error if there is no free slot.
+    pub fn try_acquire<M: AsRef<str>>(&self, model: M) -> Result<Permit, Error> {
+        let model = model.as_ref();
+        let mut state = self.lock();
+        let default_limit = state.default_limit;
+        let queue = state.models.entry(model.to_string()).or_default();
+        if queue.in_flight >= queue.limit.unwrap_or(default_limit) || !queue.waiters.is_empty() {
+            return Err(Error::new(
+                ErrorCode::Unavailable,
+                format!("No free slot for model {model}"),
+            ));
+        }
+        queue.in_flight += 1;
+        Ok(self.permit(model))
+    }
+
+    /// Wait for the slot of the `model`. \
+    /// Returns error if the deadline of the `options` passes before the slot is given.
+    pub async fn acquire<M: AsRef<str>>(
+        &self,
+        model: M,
+        options: QueueOptions,
+    ) -> Result<Permit, Error> {
+        let model = model.as_ref();
+        let mut rx = {
+            let mut state = self.lock();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            let default_limit = state.default_limit;
+            let queue = state.models.entry(model.to_string()).or_default();
+            if queue.in_flight < queue.limit.unwrap_or(default_limit) && queue.waiters.is_empty() {
+                queue.in_flight += 1;
+                return Ok(self.permit(model));
+            }
+            let (tx, rx) = oneshot::channel();
+            queue.waiters.push(Waiter {
+                priority: options.priority,
+                seq,
+                tx,
+            });
+            rx
+        };
+
+        let let_in = match options.deadline {
+            None => (&mut rx).await.is_ok(),
+            Some(deadline) => {
+                matches!(tokio::time::timeout_at(deadline, &mut rx).await, Ok(Ok(())))
+            }
+        };
+        if let_in {
+            return Ok(self.permit(model));
+        }
+
+        // Closing the receiver under the lock, so the slot can't be given after the check.
+        let _state = self.lock();
+        rx.close();
+        if rx.try_recv().is_ok() {
+            return Ok(self.permit(model));
+        }
+        Err(Error::new(
+            ErrorCode::Unavailable,
+            format!("Deadline of the request to model {model} passed in the queue"),
+        ))
+    }
+
+    /// Wait for the slot of the request model and infer the request. The slot is released when the response is received.
+    pub async fn infer(
+        &self,
+        request: Request<'_>,
+        options: QueueOptions,
+    ) -> Result<Response, InferenceError> {
+        let _permit = self.acquire(&request.model_name, options).await?;
+        request.infer_async()?.await
+    }
+
+    fn permit(&self, model: &str) -> Permit {
+        Permit {
+            state: self.state.clone(),
+            model: model.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn priorities() {
+        let queue = RequestQueue::new(1);
+        let first = queue
+            .acquire("model", QueueOptions::default())
+            .await
+            .unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for priority in [1, 5, 3] {
+            let (queue, order) = (queue.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                let _permit = queue
+                    .acquire("model", QueueOptions::default().priority(priority))
+                    .await
+                    .unwrap();
+                order.lock().unwrap().push(priority);
+            }));
+            tokio::task::yield_now().await;
+        }
+        while queue.queued("model") < 3 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(queue.in_flight("model"), 1);
+
+        drop(first);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [5, 3, 1]);
+        assert_eq!(queue.in_flight("model"), 0);
+    }
+}
@@ -2,22 +2,62 @@ use std::{
     collections::HashMap,
     ffi::{c_void, CStr},
     mem::transmute,
-    path::Path,
+    ops::Deref,
+    path::{Path, PathBuf},
     ptr::null_mut,
-    sync::Arc,
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock, PoisonError, RwLock, Weak,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use serde_json::{from_slice, Value};
+use serde_json::{from_slice, json, Value};
 
 use crate::{
-    message::{self, Index, Message, Model},
-    metrics::{self, Metrics},
-    options::Options,
+    allocator::QueryMismatches,
+    compat::Compatibility,
+    error,
+    logging::{self, LogRedirect},
+    message::{
+        self, Index, Message, Model, ModelConfig, ModelStatistics, VersionPolicy, VersionState,
+        VersionStatus,
+    },
+    metrics::{self, Metrics, ModelMetrics},
+    options::{redacted_setting, LogFormat, Options},
     parameter::{Parameter, ParameterContent},
-    path_to_cstring, sys, to_cstring, Error, ErrorCode, Request,
+    path_to_cstring,
+    request::{QueryMismatch, RequestBuilder, RequestTemplate, Sequence, ServerRef},
+    response::{self, Quantization},
+    runtime::{self, Executor, Runtime},
+    sys, testing, to_cstring,
+    trace::{Trace, TraceSampler},
+    Error, ErrorCode, Request,
 };
 
+mod batch;
+mod batcher;
+#[cfg(feature = "http")]
+mod http;
+mod progress;
+#[cfg(feature = "signals")]
+mod signals;
+mod stats;
+mod supervisor;
+pub(crate) mod usage;
+mod watcher;
+
+pub use crate::logging::{log_message, LogLevel};
+pub use batch::BatchOutput;
+pub use batcher::{Batcher, BatcherOptions};
+pub use progress::LoadProgress;
+pub use supervisor::Supervisor;
+pub use usage::{NamespaceQuota, NamespaceUsage};
+pub use watcher::RepositoryEvent;
+
+/// Backend directory Triton uses if [Options::backend_directory] is not set.
+pub(crate) const DEFAULT_BACKEND_DIRECTORY: &str = "/opt/tritonserver/backends";
+
 /// Batch properties of the model.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u32)]
@@ -71,32 +111,316 @@ impl InstanceGroup {
     }
 }
 
+/// One Triton server of the [Inner]. Replaced with the new one by [Supervisor::restart].
+#[derive(Debug)]
+pub(crate) struct Instance {
+    ptr: OnceLock<*mut sys::TRITONSERVER_Server>,
+    /// Options of the server which start is deferred. Taken on start.
+    options: Mutex<Option<Options>>,
+    /// Options the server was created with. Check [Options::settings].
+    settings: Vec<(&'static str, String)>,
+    /// Loaded on the start of the server.
+    info: RwLock<Option<ModelsInfo>>,
+    /// Number of the restarts before the instance was created. Check [Supervisor::restarts].
+    generation: u64,
+    /// New inferences are rejected. Check [Server::shutdown].
+    shutting_down: AtomicBool,
+    /// Number of the inferences which final response is not received yet.
+    in_flight: AtomicUsize,
+    exit_timeout: Mutex<Duration>,
+    /// Server is deleted by [Server::shutdown].
+    deleted: AtomicBool,
+    /// Forwarding of the logs to the [log] facade. Check [Options::log_to_facade].
+    log: Mutex<Option<LogRedirect>>,
+}
+
+/// State shared by all the objects of the server: its clones, handles, requests and background tasks.
 #[derive(Debug)]
-pub(crate) struct Inner(*mut sys::TRITONSERVER_Server);
+pub(crate) struct Inner {
+    /// Current Triton server. Swapped by [Supervisor::restart], so all the objects of the server follow the restart.
+    instance: RwLock<Arc<Instance>>,
+    /// Usage and quotas of the model namespaces. Check [Request::set_namespace].
+    pub(crate) accounting: Arc<usage::Accounting>,
+    /// Query/allocation mismatches of the requests. Check [Server::query_mismatches].
+    pub(crate) query_mismatches: Arc<QueryMismatches>,
+    /// Sampler of the traced requests. Check [Server::set_trace_sampler].
+    trace_sampler: RwLock<Option<Arc<TraceSampler>>>,
+    /// Order of the responses delivery. Check [crate::testing::Deterministic::ordered_responses].
+    pub(crate) delivery: Arc<testing::Delivery>,
+}
+
+/// Inference in flight. Unregisters the inference on drop.
+pub(crate) struct InFlight(Arc<Instance>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl InFlight {
+    /// Pointer of the server the inference runs on.
+    pub(crate) fn server_ptr(&self) -> *mut sys::TRITONSERVER_Server {
+        self.0.as_mut_ptr()
+    }
+}
+
 impl Inner {
+    fn deferred(options: Options) -> Self {
+        Inner {
+            instance: RwLock::new(Arc::new(Instance::deferred(options, 0))),
+            accounting: Arc::default(),
+            query_mismatches: Arc::default(),
+            trace_sampler: RwLock::new(None),
+            delivery: Arc::default(),
+        }
+    }
+
+    /// Current Triton server.
+    pub(crate) fn instance(&self) -> Arc<Instance> {
+        self.instance
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Number of the restarts of the server. Check [Supervisor::restarts].
+    pub(crate) fn generation(&self) -> u64 {
+        self.instance().generation
+    }
+
+    /// Replace the current server, that must be shut down, with the one created with `options`. \
+    /// The new server is started before the swap unless its start is deferred with [Options::lazy_startup],
+    /// so the current server is kept if the new one fails to start.
+    pub(crate) fn replace(&self, options: Options) -> Result<(), Error> {
+        let lazy = options.lazy_startup;
+        let mut current = self
+            .instance
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let instance = Instance::deferred(options, current.generation + 1);
+        if !lazy {
+            instance.started()?;
+        }
+        *current = Arc::new(instance);
+        Ok(())
+    }
+
+    /// Get the server pointer, creating the server if its start was deferred.
+    pub(crate) fn started(&self) -> Result<*mut sys::TRITONSERVER_Server, Error> {
+        self.instance().started()
+    }
+
+    pub(crate) fn is_started(&self) -> bool {
+        self.instance().is_started()
+    }
+
+    /// Register the new inference. Returns error if the server is shutting down
+    /// or is restarted after the `generation` the request was created for.
+    pub(crate) fn begin_inference(&self, generation: u64) -> Result<InFlight, Error> {
+        let instance = self.instance();
+        if instance.generation != generation {
+            return Err(Error::new(
+                ErrorCode::Unavailable,
+                "Server is restarted after the request was created",
+            ));
+        }
+        instance.begin_inference()
+    }
+
+    fn set_exit_timeout(&self, timeout: Duration) {
+        self.instance().set_exit_timeout(timeout)
+    }
+
+    /// Reject new inferences, wait for the in-flight ones up to the exit timeout, stop and delete the current server.
+    pub(crate) async fn shutdown(&self) -> Result<(), Error> {
+        self.instance().shutdown().await
+    }
+
+    pub(crate) fn stop(&self) -> Result<(), Error> {
+        self.instance().stop()
+    }
+
+    pub(crate) fn is_live(&self) -> Result<bool, Error> {
+        self.instance().is_live()
+    }
+}
+
+impl Instance {
+    fn deferred(options: Options, generation: u64) -> Self {
+        Instance {
+            ptr: OnceLock::new(),
+            exit_timeout: Mutex::new(options.exit_timeout),
+            settings: options.settings().to_vec(),
+            options: Mutex::new(Some(options)),
+            info: RwLock::new(None),
+            generation,
+            shutting_down: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            deleted: AtomicBool::new(false),
+            log: Mutex::new(None),
+        }
+    }
+
+    /// Get the server pointer, creating the server if its start was deferred.
+    pub(crate) fn started(&self) -> Result<*mut sys::TRITONSERVER_Server, Error> {
+        if self.deleted.load(Ordering::SeqCst) {
+            return Err(Error::new(ErrorCode::Unavailable, "Server is shut down"));
+        }
+        if let Some(ptr) = self.ptr.get() {
+            return Ok(*ptr);
+        }
+
+        let mut options = self.options.lock().unwrap_or_else(PoisonError::into_inner);
+        // Server could be started while the lock was awaited.
+        if let Some(ptr) = self.ptr.get() {
+            return Ok(*ptr);
+        }
+        let Some(pending) = options.as_ref() else {
+            return Err(Error::new(
+                ErrorCode::Internal,
+                "Options of the deferred server are lost",
+            ));
+        };
+
+        let compat = Server::check_compat()?;
+        if !compat.is_full() {
+            log::warn!(
+                "Triton library API version {}.{} is older than the bindings API version {}.{}, unsupported: {:?}",
+                compat.library.0,
+                compat.library.1,
+                compat.bindings.0,
+                compat.bindings.1,
+                compat.missing
+            );
+        }
+        let log = if pending.log_to_facade {
+            let redirect = logging::redirect()?;
+            let pipe = path_to_cstring(redirect.path())?;
+            triton_call!(sys::TRITONSERVER_ServerOptionsSetLogFormat(
+                pending.ptr,
+                LogFormat::Default as _
+            ))?;
+            triton_call!(sys::TRITONSERVER_ServerOptionsSetLogFile(
+                pending.ptr,
+                pipe.as_ptr()
+            ))?;
+            Some(redirect)
+        } else {
+            None
+        };
+        let mut server = null_mut::<sys::TRITONSERVER_Server>();
+        triton_call!(sys::TRITONSERVER_ServerNew(
+            &mut server as *mut _,
+            pending.ptr
+        ))?;
+        assert!(!server.is_null());
+
+        *options = None;
+        *self.log.lock().unwrap_or_else(PoisonError::into_inner) = log;
+        Ok(*self.ptr.get_or_init(|| server))
+    }
+
+    pub(crate) fn is_started(&self) -> bool {
+        self.ptr.get().is_some()
+    }
+
+    /// Register the new inference. Returns error if the server is shutting down.
+    fn begin_inference(self: &Arc<Self>) -> Result<InFlight, Error> {
+        // Counter is incremented before the check, so the shutdown can't miss the inference.
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let in_flight = InFlight(self.clone());
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(Error::new(
+                ErrorCode::Unavailable,
+                "Server is shutting down, new inferences are not accepted",
+            ));
+        }
+        Ok(in_flight)
+    }
+
+    fn set_exit_timeout(&self, timeout: Duration) {
+        *self
+            .exit_timeout
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = timeout;
+    }
+
+    /// Reject new inferences, wait for the in-flight ones up to the exit timeout, stop and delete the server.
+    pub(crate) async fn shutdown(&self) -> Result<(), Error> {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        if !self.is_started() || self.deleted.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let timeout = *self
+            .exit_timeout
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if poll_with_backoff(timeout, || Ok(self.in_flight.load(Ordering::SeqCst) == 0))
+            .await
+            .is_err()
+        {
+            log::warn!(
+                "{} inferences are not finished in {timeout:?}, server is stopped anyway",
+                self.in_flight.load(Ordering::SeqCst)
+            );
+        }
+
+        self.stop()?;
+        poll_with_backoff(timeout, || self.is_live().map(|live| !live))
+            .await
+            .map_err(|last_err| {
+                Error::new(
+                    ErrorCode::Unavailable,
+                    format!(
+                        "Server is live {timeout:?} after stop.{}",
+                        last_err
+                            .map(|err| format!(" Last error: {err}"))
+                            .unwrap_or_default()
+                    ),
+                )
+            })?;
+
+        if self.deleted.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let deleted = self.delete();
+        // Stops the log forwarding and removes the log pipe.
+        self.log
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        deleted
+    }
+
     pub(crate) fn stop(&self) -> Result<(), Error> {
-        triton_call!(sys::TRITONSERVER_ServerStop(self.0))
+        triton_call!(sys::TRITONSERVER_ServerStop(self.as_mut_ptr()))
     }
 
     pub(crate) fn is_live(&self) -> Result<bool, Error> {
         let mut result = false;
         triton_call!(
-            sys::TRITONSERVER_ServerIsLive(self.0, &mut result as *mut _),
+            sys::TRITONSERVER_ServerIsLive(self.as_mut_ptr(), &mut result as *mut _),
             result
         )
     }
 
-    pub(crate) fn delete(&self) -> Result<(), Error> {
-        triton_call!(sys::TRITONSERVER_ServerDelete(self.0))
+    fn delete(&self) -> Result<(), Error> {
+        triton_call!(sys::TRITONSERVER_ServerDelete(self.as_mut_ptr()))
     }
 
+    /// Pointer of the started server. Null if the server is not started.
     pub(crate) fn as_mut_ptr(&self) -> *mut sys::TRITONSERVER_Server {
-        self.0
+        self.ptr.get().copied().unwrap_or_else(null_mut)
     }
 }
 
-impl Drop for Inner {
+impl Drop for Instance {
     fn drop(&mut self) {
+        if !self.is_started() || *self.deleted.get_mut() {
+            return;
+        }
         let _ = self
             .is_live()
             .and_then(|live| {
@@ -115,156 +439,635 @@ impl Drop for Inner {
 }
 
 /// # SAFETY
-/// Inner is Send. But it's not Sync! \
-/// However, it's used only in Server and Server is never clones Inner,
+/// Instance is Send. But it's not Sync! \
+/// However, it's used only in Server and Server is never clones Instance,
 /// so there is always only 1 copy of it.
-unsafe impl Send for Inner {}
-unsafe impl Sync for Inner {}
+unsafe impl Send for Instance {}
+unsafe impl Sync for Instance {}
+
+/// Metadata of the models known to the server.
+#[derive(Debug, Default)]
+pub(crate) struct ModelsInfo {
+    models: HashMap<String, Arc<Model>>,
+    /// Metadata of the specific model versions requested with [Server::get_model_version].
+    versions: HashMap<(String, i64), Arc<Model>>,
+    /// Quantization parameters of the model outputs declared in the model configs.
+    quantization: HashMap<String, Arc<HashMap<String, Quantization>>>,
+    /// `max_batch_size` of the model configs. Missing if config is not available.
+    max_batch_sizes: HashMap<String, i32>,
+    /// Time when the server observed the (model, version) became ready.
+    load_times: HashMap<(String, i64), SystemTime>,
+    /// Duration of the latest successful [Server::load_model_with_progress] of the model.
+    load_durations: HashMap<String, Duration>,
+}
+
+impl ModelsInfo {
+    /// Remember when each of the model versions became ready. \
+    /// `states`: current states of the model versions. \
+    /// `reloaded`: model which versions were (re)loaded right now, so their load time should be renewed.
+    fn update_load_times<M: AsRef<str>>(
+        &mut self,
+        states: Vec<VersionStatus>,
+        reloaded: Option<M>,
+    ) {
+        if let Some(reloaded) = reloaded {
+            self.load_times
+                .retain(|(name, _), _| name != reloaded.as_ref());
+        }
 
-/// Inference server object.
+        let now = SystemTime::now();
+        let mut ready = Vec::new();
+        for status in states {
+            if let (Some(version), Some(VersionState::Ready)) = (status.version, status.state) {
+                ready.push((status.name, version));
+            }
+        }
+
+        self.load_times.retain(|key, _| ready.contains(key));
+        for key in ready {
+            self.load_times.entry(key).or_insert(now);
+        }
+    }
+}
+
+/// Model repositories registered at runtime by their paths with the name mappings of their models.
+pub type Repositories = HashMap<PathBuf, HashMap<String, String>>;
+
+/// Inference server object. \
+/// Model management methods (loading, unloading, polling of the repository) take `&self`,
+/// so the server can be shared between the tasks without locking and the requests are not blocked by the model reloads.
+///
+/// If the server is created with [Options::lazy_startup], Triton server is started on the first call
+/// that needs it (e.g. [Server::create_request]) or on [Server::ensure_started].
 #[derive(Debug)]
 pub struct Server {
     pub(crate) ptr: Arc<Inner>,
-    pub(crate) models: HashMap<String, Model>,
-    pub(crate) runtime: tokio::runtime::Handle,
+    /// Repositories registered with [Server::register_repository] and their name mappings.
+    /// Shared with the background tasks of the server.
+    pub(crate) repositories: Arc<RwLock<Repositories>>,
+    /// Runs the async code of the Triton callbacks. Check [crate::runtime].
+    pub(crate) executor: Runtime,
+    /// Runtime the server is created in. None if it's created outside of the tokio runtime.
+    pub(crate) tokio: Option<tokio::runtime::Handle>,
 }
 
 unsafe impl Send for Server {}
+
+/// Owned handle of the server that can be cloned and moved into the spawned tasks
+/// (e.g. to create the requests with [Server::create_request_owned]). \
+/// Handles share the server: it's stopped when the server object and all the handles are dropped.
+///
+/// ```ignore
+/// let server = server.handle();
+/// tokio::spawn(async move {
+///     let mut request = server.create_request_owned("detector", -1)?;
+///     request.add_default_allocator().add_input("IMAGE", image)?;
+///     request.infer_async()?.await
+/// });
+/// ```
+#[derive(Debug)]
+pub struct ServerHandle(Server);
+
+impl Clone for ServerHandle {
+    fn clone(&self) -> Self {
+        ServerHandle(self.0.share())
+    }
+}
+
+impl Deref for ServerHandle {
+    type Target = Server;
+
+    fn deref(&self) -> &Server {
+        &self.0
+    }
+}
+
+/// Reference to the server of its background tasks that doesn't keep the server alive.
+#[derive(Debug, Clone)]
+pub(crate) struct WeakServer {
+    ptr: Weak<Inner>,
+    repositories: Arc<RwLock<Repositories>>,
+    executor: Runtime,
+    tokio: Option<tokio::runtime::Handle>,
+}
+
+impl WeakServer {
+    /// Get the server if it's not dropped yet.
+    pub(crate) fn upgrade(&self) -> Option<Server> {
+        Some(Server {
+            ptr: self.ptr.upgrade()?,
+            repositories: self.repositories.clone(),
+            executor: self.executor.clone(),
+            tokio: self.tokio.clone(),
+        })
+    }
+}
+
+/// Error of the background tasks of the server started without the tokio runtime. Check [Server::tokio_handle].
+fn no_tokio_runtime() -> Error {
+    Error::new(
+        ErrorCode::Unsupported,
+        "Server is created outside of the tokio runtime and there is no current one",
+    )
+}
+
 impl Server {
     /// Create new server object.
     pub async fn new(options: Options) -> Result<Self, Error> {
-        let mut server = null_mut::<sys::TRITONSERVER_Server>();
-        triton_call!(sys::TRITONSERVER_ServerNew(
-            &mut server as *mut _,
-            options.0
-        ))?;
-
-        assert!(!server.is_null());
-
-        let mut server = Server {
-            ptr: Arc::new(Inner(server)),
-            models: HashMap::new(),
-            runtime: tokio::runtime::Handle::current(),
+        let lazy = options.lazy_startup;
+        let server = Server {
+            ptr: Arc::new(Inner::deferred(options)),
+            repositories: Default::default(),
+            executor: Runtime::current(),
+            tokio: tokio::runtime::Handle::try_current().ok(),
         };
-        server.update_all_models()?;
+        if !lazy {
+            server.ensure_started()?;
+        }
 
         Ok(server)
     }
 
-    pub(crate) fn get_model<M: AsRef<str>>(&self, model: M) -> Result<&Model, Error> {
-        self.models.get(model.as_ref()).ok_or_else(|| {
-            Error::new(
-                ErrorCode::NotFound,
-                format!(
-                    "Model {} is not found in server model metadata storage.",
-                    model.as_ref()
-                ),
-            )
-        })
+    /// Compare the API version of the loaded Triton library with the bindings the crate is built with. \
+    /// Returns error if the library can't be used (the major versions differ). The older library of the same major version
+    /// is accepted: the methods calling the functions missing in it return [ErrorCode::Unsupported]
+    /// (check [Compatibility::missing]). \
+    /// Called on the server start, [crate::check_api_version] is the strict alternative.
+    pub fn check_compat() -> Result<Compatibility, Error> {
+        Compatibility::check()
+    }
+
+    /// Set the executor running the async code of the Triton callbacks ([crate::Allocator] methods). \
+    /// Affects the requests created after the call. Check [crate::runtime] for more info.
+    pub fn set_executor<E: Executor>(&mut self, executor: E) -> &mut Self {
+        self.executor = Runtime::new(executor);
+        self
+    }
+
+    pub(crate) fn downgrade(&self) -> WeakServer {
+        WeakServer {
+            ptr: Arc::downgrade(&self.ptr),
+            repositories: self.repositories.clone(),
+            executor: self.executor.clone(),
+            tokio: self.tokio.clone(),
+        }
+    }
+
+    /// Handle of the tokio runtime the server is created in, or of the current one. \
+    /// None if the server is created outside of the tokio runtime (e.g. with [Server::new_blocking]) and there is no current one.
+    pub(crate) fn tokio_handle(&self) -> Option<tokio::runtime::Handle> {
+        self.tokio
+            .clone()
+            .or_else(|| tokio::runtime::Handle::try_current().ok())
+    }
+
+    /// Create new server object without async runtime. \
+    /// The server uses [BlockingExecutor](crate::runtime::BlockingExecutor) unless it's called inside the tokio runtime.
+    #[cfg(feature = "sync")]
+    pub fn new_blocking(options: Options) -> Result<Self, Error> {
+        futures::executor::block_on(Server::new(options))
+    }
+
+    /// Start the server if its start was deferred with [Options::lazy_startup]. Does nothing if the server is started.
+    pub fn ensure_started(&self) -> Result<(), Error> {
+        self.read_models_info(|_| ())
+    }
+
+    /// Options the server was created with. Check [Options::settings].
+    pub(crate) fn settings(&self) -> Vec<(&'static str, String)> {
+        self.ptr.instance().settings.clone()
     }
 
-    fn update_all_models(&mut self) -> Result<(), Error> {
+    /// Returns true if Triton server is started. Always true if the server is created without [Options::lazy_startup].
+    pub fn is_started(&self) -> bool {
+        self.ptr.is_started()
+    }
+
+    pub(crate) fn get_model<M: AsRef<str>>(&self, model: M) -> Result<Arc<Model>, Error> {
+        self.read_models_info(|info| info.models.get(model.as_ref()).cloned())?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorCode::NotFound,
+                    format!(
+                        "Model {} is not found in server model metadata storage.",
+                        model.as_ref()
+                    ),
+                )
+            })
+    }
+
+    /// Get the metadata of the `version` of the `model`, loading and caching it on the first access. \
+    /// `-1` means the version chosen by the server ([Server::get_model]).
+    pub(crate) fn get_model_version<M: AsRef<str>>(
+        &self,
+        model: M,
+        version: i64,
+    ) -> Result<Arc<Model>, Error> {
+        if version < 0 {
+            return self.get_model(model);
+        }
+        let key = (model.as_ref().to_string(), version);
+        if let Some(metadata) = self.read_models_info(|info| info.versions.get(&key).cloned())? {
+            return Ok(metadata);
+        }
+        let metadata = Arc::new(self.model_metadata(&model, version)?);
+        self.write_models_info(|info| info.versions.insert(key, metadata.clone()))?;
+        Ok(metadata)
+    }
+
+    /// Read the models metadata, starting the server and loading the metadata if needed.
+    pub(crate) fn read_models_info<R, F: FnOnce(&ModelsInfo) -> R>(
+        &self,
+        f: F,
+    ) -> Result<R, Error> {
+        if let Some(info) = self
+            .ptr
+            .instance()
+            .info
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            return Ok(f(info));
+        }
+        self.write_models_info(|info| f(info))
+    }
+
+    /// Modify the models metadata, starting the server and loading the metadata if needed.
+    pub(crate) fn write_models_info<R, F: FnOnce(&mut ModelsInfo) -> R>(
+        &self,
+        f: F,
+    ) -> Result<R, Error> {
+        let instance = self.ptr.instance();
+        let mut info = instance
+            .info
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if info.is_none() {
+            *info = Some(self.load_models_info()?);
+        }
+        Ok(f(info.as_mut().expect("models info is loaded")))
+    }
+
+    fn load_models_info(&self) -> Result<ModelsInfo, Error> {
+        self.ptr.started()?;
+        let mut info = ModelsInfo::default();
+        for model in self.model_index(State::all())? {
+            let metadata = self.model_metadata(&model.name, -1)?;
+            let (quantization, max_batch_size) = self.read_config_info(&model.name);
+            info.quantization.insert(model.name.clone(), quantization);
+            if let Some(max_batch_size) = max_batch_size {
+                info.max_batch_sizes
+                    .insert(model.name.clone(), max_batch_size);
+            }
+            info.models.insert(model.name, Arc::new(metadata));
+        }
+        info.update_load_times(self.model_states()?, None::<&str>);
+        Ok(info)
+    }
+
+    fn update_all_models(&self) -> Result<(), Error> {
         for model in self.model_index(State::all())? {
             self.update_model_info(model.name)?;
         }
-        Ok(())
+        self.update_load_times(None::<&str>)
     }
 
-    fn update_model_info<M: AsRef<str>>(&mut self, model: M) -> Result<(), Error> {
-        self.models
-            .insert(model.as_ref().to_string(), self.model_metadata(model, -1)?);
-        Ok(())
+    fn update_model_info<M: AsRef<str>>(&self, model: M) -> Result<(), Error> {
+        let metadata = self.model_metadata(&model, -1)?;
+        let (quantization, max_batch_size) = self.read_config_info(&model);
+        self.write_models_info(|info| {
+            info.models
+                .insert(model.as_ref().to_string(), Arc::new(metadata));
+            info.versions.retain(|(name, _), _| name != model.as_ref());
+            info.quantization
+                .insert(model.as_ref().to_string(), quantization);
+            match max_batch_size {
+                Some(max_batch_size) => info
+                    .max_batch_sizes
+                    .insert(model.as_ref().to_string(), max_batch_size),
+                None => info.max_batch_sizes.remove(model.as_ref()),
+            };
+        })
+    }
+
+    /// Reload the cached metadata of all the versions of the `model` (shapes of the inputs and outputs and the config info),
+    /// e.g. after its config was changed in the repository. \
+    /// Model loading methods of the server refresh the metadata of the model automatically.
+    pub fn refresh_model<M: AsRef<str>>(&self, model: M) -> Result<(), Error> {
+        self.update_model_info(&model)?;
+        self.update_load_times(Some(model))
+    }
+
+    /// Read quantization parameters of the model outputs and `max_batch_size` from its config.
+    /// Empty and None if config is not available.
+    fn read_config_info<M: AsRef<str>>(
+        &self,
+        model: M,
+    ) -> (Arc<HashMap<String, Quantization>>, Option<i32>) {
+        match self.model_config(&model, -1, 1) {
+            Ok(config) => (
+                Arc::new(response::quantization_from_config(&config)),
+                config
+                    .get("max_batch_size")
+                    .and_then(Value::as_i64)
+                    .map(|max_batch_size| max_batch_size as i32),
+            ),
+            Err(_) => Default::default(),
+        }
+    }
+
+    /// Get `max_batch_size` of the `model` config: the maximum batch dimension of its inputs.
+    /// 0 means the model doesn't support batching and its inputs have no batch dimension. \
+    /// None if the model is unknown or its config is not available.
+    pub fn max_batch_size<M: AsRef<str>>(&self, model: M) -> Option<i32> {
+        self.read_models_info(|info| info.max_batch_sizes.get(model.as_ref()).copied())
+            .ok()
+            .flatten()
+    }
+
+    /// Quantization parameters of the `model` outputs. Check [Quantization].
+    pub(crate) fn output_quantization<M: AsRef<str>>(
+        &self,
+        model: M,
+    ) -> Arc<HashMap<String, Quantization>> {
+        self.ptr
+            .instance()
+            .info
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .and_then(|info| info.quantization.get(model.as_ref()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Remember when each of the model versions became ready. \
+    /// `reloaded`: model which versions were (re)loaded right now, so their load time should be renewed.
+    fn update_load_times<M: AsRef<str>>(&self, reloaded: Option<M>) -> Result<(), Error> {
+        let states = self.model_states()?;
+        self.write_models_info(|info| info.update_load_times(states, reloaded))
     }
 
     /// Stop a server object. A server can't be restarted once it has been stopped.
     pub fn stop(&self) -> Result<(), Error> {
+        if !self.ptr.is_started() {
+            return Ok(());
+        }
+        self.ptr.started()?;
         self.ptr.stop()
     }
 
+    /// Shut down the server gracefully: reject new inferences, wait for the in-flight ones up to the exit timeout
+    /// (check [Options::exit_timeout], [Server::set_exit_timeout]), then stop and delete the server. \
+    /// After the shutdown all the methods that use the server return error with [ErrorCode::Unavailable].
+    pub async fn shutdown(&self) -> Result<(), Error> {
+        self.ptr.shutdown().await
+    }
+
     /// Create a request to the model `model` of version `version`. \
     /// If version is set as `-1`, the server will choose a version based on the model's policy.
     pub fn create_request<M: AsRef<str>>(&self, model: M, version: i64) -> Result<Request, Error> {
+        let (ptr, generation) = self.new_request_ptr(&model, version)?;
+        Request::new(ptr, generation, ServerRef::Borrowed(self), model, version)
+    }
+
+    /// Create a request to the model `model` of version `version` that owns the handle of the server,
+    /// so it can be moved into the spawned task. Inputs can't be borrowed by such a request. \
+    /// If version is set as `-1`, the server will choose a version based on the model's policy.
+    pub fn create_request_owned<M: AsRef<str>>(
+        &self,
+        model: M,
+        version: i64,
+    ) -> Result<Request<'static>, Error> {
+        let (ptr, generation) = self.new_request_ptr(&model, version)?;
+        Request::new(
+            ptr,
+            generation,
+            ServerRef::Owned(self.share()),
+            model,
+            version,
+        )
+    }
+
+    /// Create the Triton request on the current server. Returns the request and the generation of the server
+    /// (check [Inner::begin_inference]).
+    fn new_request_ptr<M: AsRef<str>>(
+        &self,
+        model: M,
+        version: i64,
+    ) -> Result<(*mut sys::TRITONSERVER_InferenceRequest, u64), Error> {
         let model_name = to_cstring(model.as_ref())?;
         let mut ptr = null_mut::<sys::TRITONSERVER_InferenceRequest>();
+        let instance = self.ptr.instance();
 
         triton_call!(sys::TRITONSERVER_InferenceRequestNew(
             &mut ptr as *mut _,
-            self.ptr.as_mut_ptr(),
+            instance.started()?,
             model_name.as_ptr(),
             version,
         ))?;
 
         assert!(!ptr.is_null());
-        Request::new(ptr, self, model)
+        Ok((ptr, instance.generation))
+    }
+
+    /// Create the template of the repeated requests to the model `model`. Check [RequestTemplate].
+    pub fn request_template<M: AsRef<str>>(&self, model: M) -> RequestTemplate {
+        RequestTemplate::new(self.handle(), model)
+    }
+
+    /// Get the owned handle of the server. Check [ServerHandle].
+    pub fn handle(&self) -> ServerHandle {
+        ServerHandle(self.share())
+    }
+
+    /// Another object of the same server.
+    pub(crate) fn share(&self) -> Server {
+        Server {
+            ptr: self.ptr.clone(),
+            repositories: self.repositories.clone(),
+            executor: self.executor.clone(),
+            tokio: self.tokio.clone(),
+        }
+    }
+
+    /// Start building a request to the model `model`. \
+    /// Check [RequestBuilder] for more info.
+    pub fn request<M: AsRef<str>>(&self, model: M) -> RequestBuilder<'_> {
+        RequestBuilder::new(self, model)
+    }
+
+    /// Start the sequence of requests to the stateful model `model`
+    /// (model with the sequence batcher). \
+    /// `correlation_id`: non-zero ID of the sequence, unique among the sequences in flight. \
+    /// Check [Sequence] for more info.
+    pub fn start_sequence<M: AsRef<str>>(
+        &self,
+        model: M,
+        correlation_id: u64,
+    ) -> Result<Sequence<'_>, Error> {
+        Sequence::new(self, model, correlation_id)
     }
 
     /// Check the model repository for changes and update server state based on those changes.
-    pub fn poll_model_repository(&mut self) -> Result<(), Error> {
+    pub fn poll_model_repository(&self) -> Result<(), Error> {
         triton_call!(sys::TRITONSERVER_ServerPollModelRepository(
-            self.ptr.as_mut_ptr()
+            self.ptr.started()?
         ))?;
 
         self.update_all_models()
     }
 
+    /// Set the sampler adding the traces to the requests without the trace (check [TraceSampler]).
+    /// None disables the sampling.
+    pub fn set_trace_sampler(&self, sampler: Option<TraceSampler>) {
+        *self
+            .ptr
+            .trace_sampler
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = sampler.map(Arc::new);
+    }
+
+    /// Trace of the request to the `model` if the sampler samples it.
+    pub(crate) fn sample_trace(&self, model: &str) -> Option<Trace> {
+        let sampler = self
+            .ptr
+            .trace_sampler
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()?;
+        sampler.sample(model)
+    }
+
     /// Set the exit timeout on the server object. This value overrides the value initially set through server options and provides a mechanism to update the exit timeout while the serving is running.
     ///
     /// `timeout` The exit timeout.
     pub fn set_exit_timeout(&mut self, timeout: Duration) -> Result<&mut Self, Error> {
-        triton_call!(
-            sys::TRITONSERVER_ServerSetExitTimeout(self.ptr.as_mut_ptr(), timeout.as_secs() as _),
-            self
-        )
+        triton_call!(sys::TRITONSERVER_ServerSetExitTimeout(
+            self.ptr.started()?,
+            timeout.as_secs() as _
+        ))?;
+        self.ptr.set_exit_timeout(timeout);
+        Ok(self)
     }
 
+    /// Deprecated. See [Server::register_repository] instead. \
     /// Register a new model repository. Not available in polling mode.
     ///
     /// `repository` The full path to the model repository. \
     /// `name_mapping` List of name_mapping parameters.
     /// Each mapping has the model directory name as its key,
     /// overridden model name as its value.
+    #[deprecated]
     pub fn register_model_repo<P: AsRef<Path>, N: AsRef<str>>(
         &mut self,
         repository: P,
         name_mapping: HashMap<String, String>,
     ) -> Result<&mut Self, Error> {
-        let path = path_to_cstring(repository)?;
-
-        let mut mapping_params = name_mapping
-            .into_iter()
-            .map(|(k, v)| {
-                Parameter::new(k, ParameterContent::String(v)).map(|param| param.ptr as *const _)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        triton_call!(
-            sys::TRITONSERVER_ServerRegisterModelRepository(
-                self.ptr.as_mut_ptr(),
-                path.as_ptr(),
-                mapping_params.as_mut_ptr(),
-                mapping_params.len() as _
-            ),
-            self
-        )
+        self.register_repository(repository, name_mapping)?;
+        Ok(self)
     }
 
+    /// Deprecated. See [Server::unregister_repository] instead. \
     /// Unregister a model repository. Not available in polling mode.
     ///
     /// `repository_path` The full path to the model repository.
+    #[deprecated]
     pub fn unregister_model_repo<P: AsRef<Path>, N: AsRef<str>>(
         &mut self,
         repository: P,
     ) -> Result<&mut Self, Error> {
-        let path = path_to_cstring(repository)?;
+        self.unregister_repository(repository)?;
+        Ok(self)
+    }
 
-        triton_call!(
-            sys::TRITONSERVER_ServerUnregisterModelRepository(self.ptr.as_mut_ptr(), path.as_ptr()),
-            self
-        )
+    /// Register a new model repository, e.g. a freshly downloaded model pack, without restarting the server. \
+    /// Not available in polling mode. Models of the repository are not loaded: use [Server::load_model] (or its analogs) for that. \
+    /// `repository`: The full path to the model repository. \
+    /// `name_mappings`: pairs of (model directory name, model name) to serve the model of the directory under another name.
+    /// Useful when the repository has the model with the same name as the other registered repository.
+    pub fn register_repository<P, M, K, V>(
+        &self,
+        repository: P,
+        name_mappings: M,
+    ) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+        M: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let path = path_to_cstring(&repository)?;
+        let name_mappings = name_mappings
+            .into_iter()
+            .map(|(dir, name)| (dir.as_ref().to_string(), name.as_ref().to_string()))
+            .collect::<HashMap<_, _>>();
+
+        // Parameters must outlive the call.
+        let params = name_mappings
+            .iter()
+            .map(|(dir, name)| Parameter::new(dir, ParameterContent::String(name.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut param_ptrs = params
+            .iter()
+            .map(|param| param.ptr.cast_const())
+            .collect::<Vec<_>>();
+
+        triton_call!(sys::TRITONSERVER_ServerRegisterModelRepository(
+            self.ptr.started()?,
+            path.as_ptr(),
+            param_ptrs.as_mut_ptr(),
+            param_ptrs.len() as _
+        ))?;
+
+        self.repositories
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(repository.as_ref().to_path_buf(), name_mappings);
+        Ok(())
+    }
+
+    /// Unregister the model repository registered by [Server::register_repository]. Not available in polling mode. \
+    /// `repository`: The full path to the model repository.
+    pub fn unregister_repository<P: AsRef<Path>>(&self, repository: P) -> Result<(), Error> {
+        let path = path_to_cstring(&repository)?;
+
+        triton_call!(sys::TRITONSERVER_ServerUnregisterModelRepository(
+            self.ptr.started()?,
+            path.as_ptr()
+        ))?;
+
+        self.repositories
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(repository.as_ref());
+        Ok(())
+    }
+
+    /// Model repositories registered at runtime by [Server::register_repository] with their name mappings.
+    pub fn registered_repositories(&self) -> Repositories {
+        self.repositories
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Get the statistics of the query/allocation mismatches of the requests of the server per output name,
+    /// collected since the start or the last [Server::reset_query_mismatches] call. \
+    /// Only allocators with [Allocator::enable_queries](crate::Allocator::enable_queries) are checked.
+    pub fn query_mismatches(&self) -> HashMap<String, QueryMismatch> {
+        self.ptr.query_mismatches.get()
+    }
+
+    /// Clear the statistics of the query/allocation mismatches of the server.
+    pub fn reset_query_mismatches(&self) {
+        self.ptr.query_mismatches.reset();
     }
 
     /// Returns true if server is live, false otherwise.
     pub fn is_live(&self) -> Result<bool, Error> {
+        self.ptr.started()?;
         self.ptr.is_live()
     }
 
@@ -273,7 +1076,7 @@ impl Server {
         let mut result = false;
 
         triton_call!(
-            sys::TRITONSERVER_ServerIsReady(self.ptr.as_mut_ptr(), &mut result as *mut _),
+            sys::TRITONSERVER_ServerIsReady(self.ptr.started()?, &mut result as *mut _),
             result
         )
     }
@@ -287,7 +1090,7 @@ impl Server {
 
         triton_call!(
             sys::TRITONSERVER_ServerModelIsReady(
-                self.ptr.as_mut_ptr(),
+                self.ptr.started()?,
                 name.as_ptr(),
                 version,
                 &mut result as *mut _,
@@ -296,6 +1099,57 @@ impl Server {
         )
     }
 
+    /// Wait until the server is ready. \
+    /// Readiness is polled with exponential backoff (from 10ms up to 1s between the checks). \
+    /// `timeout`: maximum time to wait. Error with [ErrorCode::Unavailable] is returned if the server is not ready after it.
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<(), Error> {
+        poll_with_backoff(timeout, || self.is_ready())
+            .await
+            .map_err(|last_err| {
+                Error::new(
+                    ErrorCode::Unavailable,
+                    format!(
+                        "Server is not ready after {timeout:?}.{}",
+                        last_err
+                            .map(|err| format!(" Last error: {err}"))
+                            .unwrap_or_default()
+                    ),
+                )
+            })
+    }
+
+    /// Wait until the model is ready. \
+    /// Readiness is polled with exponential backoff (from 10ms up to 1s between the checks).
+    /// Errors of the readiness check (for example, if model is not yet found in the repository) are treated as "not ready". \
+    /// When the model becomes ready, its metadata is updated, so the requests to it can be created right away. \
+    /// `name`: The name of the model to wait for. \
+    /// `version`: The version of the model to wait for. If -1 then the server will choose a version based on the model's policy. \
+    /// `timeout`: maximum time to wait. Error with [ErrorCode::Unavailable] is returned if the model is not ready after it.
+    pub async fn wait_for_model_ready<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: i64,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        poll_with_backoff(timeout, || self.model_is_ready(&name, version))
+            .await
+            .map_err(|last_err| {
+                Error::new(
+                    ErrorCode::Unavailable,
+                    format!(
+                        "Model {} (version {version}) is not ready after {timeout:?}.{}",
+                        name.as_ref(),
+                        last_err
+                            .map(|err| format!(" Last error: {err}"))
+                            .unwrap_or_default()
+                    ),
+                )
+            })?;
+
+        self.update_model_info(&name)?;
+        self.update_load_times(Some(name))
+    }
+
     /// Get the batch properties of the model. \
     /// `name`: The name of the model. \
     /// `version`: The version of the model. If -1 then the server will choose a version based on the model's policy. \
@@ -309,7 +1163,7 @@ impl Server {
         let mut ptr = null_mut::<c_void>();
 
         triton_call!(sys::TRITONSERVER_ServerModelBatchProperties(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             name.as_ptr(),
             version,
             &mut result as *mut _,
@@ -331,7 +1185,7 @@ impl Server {
         let mut ptr = null_mut::<c_void>();
 
         triton_call!(sys::TRITONSERVER_ServerModelTransactionProperties(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             name.as_ptr(),
             version,
             &mut result as *mut _,
@@ -345,7 +1199,7 @@ impl Server {
         let mut result = null_mut::<sys::TRITONSERVER_Message>();
 
         triton_call!(sys::TRITONSERVER_ServerMetadata(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             &mut result as *mut _
         ))?;
 
@@ -363,7 +1217,7 @@ impl Server {
         let mut result = null_mut::<sys::TRITONSERVER_Message>();
 
         triton_call!(sys::TRITONSERVER_ServerModelMetadata(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             name.as_ptr(),
             version,
             &mut result as *mut _,
@@ -383,7 +1237,7 @@ impl Server {
         let mut result = null_mut::<sys::TRITONSERVER_Message>();
 
         triton_call!(sys::TRITONSERVER_ServerModelStatistics(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             name.as_ptr(),
             version,
             &mut result as *mut _,
@@ -395,6 +1249,17 @@ impl Server {
         })
     }
 
+    /// Get the statistics of the model `name` as [ModelStatistics], one per version. \
+    /// `name`: The name of the model. If empty, the statistics of all the models are returned. \
+    /// `version`: The version of the model. If -1, the statistics of all the model versions are returned.
+    pub fn typed_model_statistics<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: i64,
+    ) -> Result<Vec<ModelStatistics>, Error> {
+        ModelStatistics::from_json(&self.model_statistics(name, version)?)
+    }
+
     /// Get the configuration of a model as a Message(json) object. \
     /// `name`: The name of the model. \
     /// `version`: The version of the model. If -1 then the server will choose a version based on the model's policy. \
@@ -411,7 +1276,7 @@ impl Server {
         let mut result = null_mut::<sys::TRITONSERVER_Message>();
 
         triton_call!(sys::TRITONSERVER_ServerModelConfig(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             name.as_ptr(),
             version,
             config,
@@ -424,12 +1289,22 @@ impl Server {
         })
     }
 
+    /// Get the configuration of the model `name` of the `version` as [ModelConfig]. \
+    /// If `version` is -1, the server will choose a version based on the model's policy.
+    pub fn typed_model_config<N: AsRef<str>>(
+        &self,
+        name: N,
+        version: i64,
+    ) -> Result<ModelConfig, Error> {
+        ModelConfig::from_json(&self.model_config(name, version, 1)?)
+    }
+
     /// Get the index of all unique models in the model repositories as a Message(json) object.
     pub fn model_index(&self, flags: State) -> Result<Vec<Index>, Error> {
         let mut result = null_mut::<sys::TRITONSERVER_Message>();
 
         triton_call!(sys::TRITONSERVER_ServerModelIndex(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             flags.bits(),
             &mut result as *mut _,
         ))?;
@@ -440,18 +1315,150 @@ impl Server {
         })
     }
 
+    /// Get the status of every version of every model in the model repositories. \
+    /// Unlike [Server::model_index], also returns the models (versions) that are not ready with the reason of it.
+    pub fn model_states(&self) -> Result<Vec<VersionStatus>, Error> {
+        let mut result = null_mut::<sys::TRITONSERVER_Message>();
+
+        triton_call!(sys::TRITONSERVER_ServerModelIndex(
+            self.ptr.started()?,
+            State::empty().bits(),
+            &mut result as *mut _,
+        ))?;
+
+        assert!(!result.is_null());
+        Message(result).to_json().and_then(|json| {
+            from_slice(json).map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))
+        })
+    }
+
+    /// Get the handle on the model `name` to inspect its versions. \
+    /// Returns error if there is no such model in the model repositories.
+    pub fn model<N: AsRef<str>>(&self, name: N) -> Result<ModelHandle<'_>, Error> {
+        let name = name.as_ref();
+        if !self
+            .model_states()?
+            .iter()
+            .any(|status| status.name == name)
+        {
+            return Err(Error::new(
+                ErrorCode::NotFound,
+                format!("Model {name} is not found in the model repositories."),
+            ));
+        }
+
+        Ok(ModelHandle {
+            server: self,
+            name: name.to_string(),
+        })
+    }
+
+    /// Get the sorted list of the currently loaded (ready) versions of the model `name`,
+    /// so the version can be chosen explicitly instead of passing -1. \
+    /// Returns error if there is no such model in the model repositories.
+    pub fn model_versions<N: AsRef<str>>(&self, name: N) -> Result<Vec<i64>, Error> {
+        self.model(name)?.ready_versions()
+    }
+
+    /// Returns true if the `version` of the model `name` is loaded and ready. \
+    /// Unlike [Server::model_is_ready], the version is checked exactly: -1 is never ready. \
+    /// Returns error if there is no such model in the model repositories.
+    pub fn model_version_ready<N: AsRef<str>>(&self, name: N, version: i64) -> Result<bool, Error> {
+        Ok(self.model_versions(name)?.contains(&version))
+    }
+
+    /// Collect the environment info useful for the bug reports into a single JSON: \
+    /// crate and Triton API versions, path of the loaded Triton library, server metadata, backend directory contents,
+    /// states of the models in the repository, options the server was created with
+    /// and the last errors (kept only if enabled by [crate::error::record_recent_errors]). \
+    /// Values of the options that may hold secrets (backend configs, host policies and cache configs) are redacted. \
+    /// Parts that can't be collected contain the description of the failure instead.
+    pub fn diagnostic_bundle(&self) -> Value {
+        fn or_error<T>(result: Result<T, Error>, to_json: impl FnOnce(T) -> Value) -> Value {
+            result
+                .map(to_json)
+                .unwrap_or_else(|err| json!({ "error": err.to_string() }))
+        }
+
+        let settings = self.settings();
+        let backend_directory = settings
+            .iter()
+            .rev()
+            .find(|(option, _)| *option == "backend_directory")
+            .map_or(DEFAULT_BACKEND_DIRECTORY, |(_, path)| path.as_str());
+        let backends = std::fs::read_dir(backend_directory)
+            .map(|entries| {
+                let mut names: Vec<_> = entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .collect();
+                names.sort();
+                json!(names)
+            })
+            .unwrap_or_else(|err| json!({ "error": err.to_string() }));
+
+        let mut options = serde_json::Map::new();
+        for (option, value) in &settings {
+            let value = redacted_setting(option, value);
+            match options.get_mut(*option) {
+                None => {
+                    options.insert(option.to_string(), json!(value));
+                }
+                Some(Value::Array(values)) => values.push(json!(value)),
+                Some(previous) => *previous = json!([previous.take(), value]),
+            }
+        }
+
+        let errors: Vec<_> = error::recent_errors()
+            .into_iter()
+            .map(|(time, error)| {
+                let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+                json!({ "time": since_epoch.as_secs_f64(), "error": error })
+            })
+            .collect();
+
+        json!({
+            "crate_version": env!("CARGO_PKG_VERSION"),
+            "api_version": {
+                "bindings": [sys::TRITONSERVER_API_VERSION_MAJOR, sys::TRITONSERVER_API_VERSION_MINOR],
+                "library": or_error(crate::api_version(), |(major, minor)| json!([major, minor])),
+            },
+            "library_path": crate::library_path().map(|path| path.display().to_string()),
+            "server": or_error(self.metadata(), |server| json!({
+                "name": server.name,
+                "version": server.version,
+                "extensions": server.extensions,
+            })),
+            "backend_directory": { "path": backend_directory, "contents": backends },
+            "models": or_error(self.model_states(), |states| {
+                states
+                    .into_iter()
+                    .map(|status| json!({
+                        "name": status.name,
+                        "version": status.version,
+                        "state": status.state.map(|state| format!("{state:?}").to_uppercase()),
+                        "reason": status.reason,
+                    }))
+                    .collect()
+            }),
+            "options": options,
+            "recent_errors": errors,
+        })
+    }
+
     /// Load the requested model or reload the model if it is already loaded. \
     /// The function does not return until the model is loaded or fails to load \.
     /// `name`: The name of the model.
-    pub fn load_model<N: AsRef<str>>(&mut self, name: N) -> Result<(), Error> {
+    pub fn load_model<N: AsRef<str>>(&self, name: N) -> Result<(), Error> {
         let model_name = to_cstring(&name)?;
 
         triton_call!(sys::TRITONSERVER_ServerLoadModel(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             model_name.as_ptr()
         ))?;
 
-        self.update_model_info(name)
+        self.update_model_info(&name)?;
+        self.update_load_times(Some(name))
     }
 
     /// Load the requested model or reload the model if it is already loaded, with load parameters provided. \
@@ -468,7 +1475,7 @@ impl Server {
     /// `name`: The name of the model. \
     /// `parameters`: slice of parameters.
     pub fn load_model_with_parametrs<N: AsRef<str>, P: AsRef<[Parameter]>>(
-        &mut self,
+        &self,
         name: N,
         parameters: P,
     ) -> Result<(), Error> {
@@ -481,28 +1488,70 @@ impl Server {
             .collect::<Vec<_>>();
 
         triton_call!(sys::TRITONSERVER_ServerLoadModelWithParameters(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             model_name.as_ptr(),
             parametrs.as_mut_ptr(),
             params_count as _,
         ))?;
 
-        self.update_model_info(name)
+        self.update_model_info(&name)?;
+        self.update_load_times(Some(name))
     }
 
     /// Unload the requested model. \
     /// Unloading a model that is not loaded on server has no affect and success code will be returned. \
     /// The function does not wait for the requested model to be fully unload and success code will be returned. \
     /// `name`: The name of the model.
-    pub fn unload_model<N: AsRef<str>>(&mut self, name: N) -> Result<(), Error> {
+    pub fn unload_model<N: AsRef<str>>(&self, name: N) -> Result<(), Error> {
         let model_name = to_cstring(&name)?;
 
         triton_call!(sys::TRITONSERVER_ServerUnloadModel(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             model_name.as_ptr()
         ))?;
 
-        self.update_model_info(name)
+        self.update_model_info(&name)?;
+        self.update_load_times(None::<&str>)
+    }
+
+    /// Asynchronous version of [Server::load_model]. \
+    /// The blocking load is run on the blocking thread pool of the tokio runtime (on the current thread without it), so the async runtime is not stalled while the model is loaded. \
+    /// Model metadata is refreshed after the load is completed. \
+    /// `name`: The name of the model.
+    pub async fn load_model_async<N: AsRef<str>>(&self, name: N) -> Result<(), Error> {
+        let model_name = to_cstring(&name)?;
+        let server = self.ptr.clone();
+
+        runtime::try_run_blocking(move || {
+            triton_call!(sys::TRITONSERVER_ServerLoadModel(
+                server.started()?,
+                model_name.as_ptr()
+            ))
+        })
+        .await?;
+
+        self.update_model_info(&name)?;
+        self.update_load_times(Some(name))
+    }
+
+    /// Asynchronous version of [Server::unload_model]. \
+    /// The unload call is run on the blocking thread pool of the tokio runtime (on the current thread without it). Model metadata is refreshed after the call is completed. \
+    /// As [Server::unload_model], does not wait for the requested model to be fully unload. \
+    /// `name`: The name of the model.
+    pub async fn unload_model_async<N: AsRef<str>>(&self, name: N) -> Result<(), Error> {
+        let model_name = to_cstring(&name)?;
+        let server = self.ptr.clone();
+
+        runtime::try_run_blocking(move || {
+            triton_call!(sys::TRITONSERVER_ServerUnloadModel(
+                server.started()?,
+                model_name.as_ptr()
+            ))
+        })
+        .await?;
+
+        self.update_model_info(&name)?;
+        self.update_load_times(None::<&str>)
     }
 
     /// Unload the requested model, and also unload any dependent model that was loaded along with the requested model
@@ -510,15 +1559,16 @@ impl Server {
     /// Unloading a model that is not loaded on server has no affect and success code will be returned. \
     /// The function does not wait for the requested model and all dependent models to be fully unload and success code will be returned. \
     /// `name`: The name of the model.
-    pub fn unload_model_and_dependents<N: AsRef<str>>(&mut self, name: N) -> Result<(), Error> {
+    pub fn unload_model_and_dependents<N: AsRef<str>>(&self, name: N) -> Result<(), Error> {
         let model_name = to_cstring(&name)?;
 
         triton_call!(sys::TRITONSERVER_ServerUnloadModelAndDependents(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             model_name.as_ptr(),
         ))?;
 
-        self.update_model_info(name)
+        self.update_model_info(&name)?;
+        self.update_load_times(None::<&str>)
     }
 
     /// Get the current metrics for the server.
@@ -526,7 +1576,7 @@ impl Server {
         let mut metrics = null_mut::<sys::TRITONSERVER_Metrics>();
 
         triton_call!(sys::TRITONSERVER_ServerMetrics(
-            self.ptr.as_mut_ptr(),
+            self.ptr.started()?,
             &mut metrics as *mut _
         ))?;
 
@@ -534,16 +1584,139 @@ impl Server {
         Ok(Metrics(metrics))
     }
 
+    /// Get the inference counters of the model `name` summed over its versions. \
+    /// Check [ModelMetrics::from_families] to get the counters of the single version.
+    pub fn model_metrics<N: AsRef<str>>(&self, name: N) -> Result<ModelMetrics, Error> {
+        let families = self.metrics()?.to_map()?;
+        Ok(ModelMetrics::from_families(&families, name, None))
+    }
+
+    /// Return idle memory to the system, e.g. during traffic lulls. \
+    /// Buffers cached by the crate (buffer pools of [crate::graph::Graph]) are released on the next use of the pool
+    /// (use [crate::graph::Graph::trim] to release them immediately).
+    ///
+    /// `backends`: also return the host heap memory freed by Triton and the backends to the system (glibc `malloc_trim`, no-op on other platforms).
+    ///
+    /// **Note**: Triton C API has no call to make backends release their cached GPU memory,
+    /// and Triton pinned and cuda memory pools (check [Options::pinned_memory_pool_byte_size]) are allocated once and are not released.
+    pub fn trim_memory(&self, backends: bool) {
+        crate::memory::request_trim();
+        if backends {
+            crate::memory::trim_host_heap();
+        }
+    }
+
     pub fn is_log_enabled(&self, level: LogLevel) -> bool {
         unsafe { sys::TRITONSERVER_LogIsEnabled(level as u32) }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-#[repr(u32)]
-pub enum LogLevel {
-    Info = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_INFO,
-    Warn = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_WARN,
-    Error = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_ERROR,
-    Verbose = sys::TRITONSERVER_loglevel_enum_TRITONSERVER_LOG_VERBOSE,
+/// Handle on the model of the server. \
+/// One can get this item using [Server::model].
+///
+/// Gives typed access to the version policy of the model and to the lifecycle of its versions,
+/// so it can be checked that exactly the intended versions are served.
+#[derive(Debug, Clone)]
+pub struct ModelHandle<'a> {
+    server: &'a Server,
+    name: String,
+}
+
+/// Lifecycle information of the model version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionInfo {
+    pub version: i64,
+    pub state: VersionState,
+    /// Reason of the state (for example, the error of the load).
+    pub reason: String,
+    /// Time when the server observed the version became ready.
+    /// None if the version is not ready.
+    pub last_load_time: Option<SystemTime>,
+}
+
+impl ModelHandle<'_> {
+    /// Name of the model.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the version policy of the model from its configuration.
+    pub fn version_policy(&self) -> Result<VersionPolicy, Error> {
+        VersionPolicy::from_config(&self.server.model_config(&self.name, -1, 1)?)
+    }
+
+    /// Get the lifecycle information of every known version of the model.
+    pub fn versions(&self) -> Result<Vec<VersionInfo>, Error> {
+        let mut versions = self
+            .server
+            .model_states()?
+            .into_iter()
+            .filter(|status| status.name == self.name)
+            .filter_map(|status| {
+                let version = status.version?;
+                Some(VersionInfo {
+                    version,
+                    state: status.state.unwrap_or(VersionState::Unknown),
+                    reason: status.reason,
+                    last_load_time: self.last_load_time(version),
+                })
+            })
+            .collect::<Vec<_>>();
+        versions.sort_by_key(|info| info.version);
+        Ok(versions)
+    }
+
+    /// Get the sorted list of versions of the model that are ready for inference.
+    pub fn ready_versions(&self) -> Result<Vec<i64>, Error> {
+        Ok(self
+            .versions()?
+            .into_iter()
+            .filter(|info| info.state == VersionState::Ready)
+            .map(|info| info.version)
+            .collect())
+    }
+
+    /// Get the time when the server observed the `version` of the model became ready. \
+    /// None if the version is not ready.
+    pub fn last_load_time(&self, version: i64) -> Option<SystemTime> {
+        self.server
+            .ptr
+            .instance()
+            .info
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()?
+            .load_times
+            .get(&(self.name.clone(), version))
+            .copied()
+    }
+}
+
+/// Poll `check` with exponential backoff until it returns `Ok(true)` or `timeout` expires. \
+/// On timeout returns the last error `check` returned, if any.
+async fn poll_with_backoff<F>(timeout: Duration, mut check: F) -> Result<(), Option<Error>>
+where
+    F: FnMut() -> Result<bool, Error>,
+{
+    const MIN_DELAY: Duration = Duration::from_millis(10);
+    const MAX_DELAY: Duration = Duration::from_secs(1);
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut delay = MIN_DELAY;
+    let mut last_err = None;
+
+    loop {
+        match check() {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            Err(err) => last_err = Some(err),
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Err(last_err);
+        }
+        tokio::time::sleep(delay.min(deadline - now)).await;
+        delay = (delay * 2).min(MAX_DELAY);
+    }
 }
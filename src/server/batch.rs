@@ -0,0 +1,315 @@
+use std::{collections::HashMap, hash::Hash};
+
+use futures::future::try_join_all;
+
+use super::{Batch, Server};
+use crate::{
+    error::{Error, ErrorCode},
+    memory::DataType,
+    Buffer, Response,
+};
+
+/// Output of the item of [Server::infer_batch].
+#[derive(Debug)]
+pub struct BatchOutput {
+    /// Shape of the output with the batch dimension of 1 (if the model supports batching).
+    pub shape: Vec<i64>,
+    /// CPU buffer with the output content.
+    pub buffer: Buffer,
+}
+
+/// Item inputs concatenated along the batch dimension.
+struct Batched {
+    data_type: DataType,
+    /// Shape of the item without the batch dimension.
+    item_dims: Vec<i64>,
+    bytes: Vec<u8>,
+}
+
+impl Server {
+    /// Infer the `items` (inputs of one sample each, by input name) and return the outputs of each item (by output name). \
+    /// If the model supports batching along the first dimension ([Batch::FirstDim]), the inputs of the items are concatenated
+    /// along it and inferred with one request (or several, if there are more items than the max batch size of the model),
+    /// then the outputs are split back into the items. Otherwise the items are inferred with a request each.
+    ///
+    /// Shapes of the item inputs are taken from the model metadata without the batch dimension,
+    /// one dynamic dimension (-1) is deduced from the buffer size. All the items must have the same input shapes.
+    pub async fn infer_batch<M, N>(
+        &self,
+        model: M,
+        items: Vec<HashMap<N, Buffer>>,
+    ) -> Result<Vec<HashMap<String, BatchOutput>>, Error>
+    where
+        M: AsRef<str>,
+        N: AsRef<str> + Eq + Hash,
+    {
+        let model = model.as_ref();
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if self.model_batch_properties(model, -1)? != Batch::FirstDim {
+            let requests = items
+                .into_iter()
+                .map(|inputs| self.infer_item(model, inputs));
+            return try_join_all(requests).await;
+        }
+
+        let max_batch_size = self.typed_model_config(model, -1)?.max_batch_size.max(1) as usize;
+        let mut outputs = Vec::with_capacity(items.len());
+        let mut items = items.into_iter().peekable();
+        while items.peek().is_some() {
+            let chunk = items.by_ref().take(max_batch_size).collect::<Vec<_>>();
+            outputs.extend(self.infer_batched(model, chunk).await?);
+        }
+        Ok(outputs)
+    }
+
+    /// Infer the item without batching.
+    async fn infer_item<N: AsRef<str>>(
+        &self,
+        model: &str,
+        inputs: HashMap<N, Buffer>,
+    ) -> Result<HashMap<String, BatchOutput>, Error> {
+        let mut request = self.create_request(model, -1)?;
+        request.add_default_allocator();
+        for (name, buffer) in inputs {
+            request.add_input(name, buffer)?;
+        }
+        let response = request.infer_async()?.await.map_err(|err| err.error)?;
+        response_outputs(&response, 1)?
+            .pop()
+            .ok_or_else(|| Error::new(ErrorCode::Internal, "Response outputs are not collected"))
+    }
+
+    /// Infer the items with one request.
+    async fn infer_batched<N: AsRef<str> + Eq + Hash>(
+        &self,
+        model: &str,
+        items: Vec<HashMap<N, Buffer>>,
+    ) -> Result<Vec<HashMap<String, BatchOutput>>, Error> {
+        let metadata = self.get_model(model)?;
+        let batch_size = items.len();
+
+        let mut request = self.create_request(model, -1)?;
+        request.add_default_allocator();
+        for input in &metadata.inputs {
+            let mut batched: Option<Batched> = None;
+            for (index, inputs) in items.iter().enumerate() {
+                let buffer = inputs
+                    .iter()
+                    .find(|(name, _)| name.as_ref() == input.name)
+                    .map(|(_, buffer)| buffer)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorCode::InvalidArg,
+                            format!("Item {index} has no input {}", input.name),
+                        )
+                    })?;
+                let bytes = buffer.get_owned_slice(..)?;
+                let item_dims = item_dims(
+                    input.dims.get(1..).unwrap_or_default(),
+                    element_count(&bytes, buffer.data_type())?,
+                )
+                .map_err(|message| {
+                    Error::new(
+                        ErrorCode::InvalidArg,
+                        format!("Input {} of item {index}: {message}", input.name),
+                    )
+                })?;
+
+                match &mut batched {
+                    None => {
+                        let mut all_bytes = Vec::with_capacity(bytes.len() * batch_size);
+                        all_bytes.extend_from_slice(&bytes);
+                        batched = Some(Batched {
+                            data_type: buffer.data_type(),
+                            item_dims,
+                            bytes: all_bytes,
+                        });
+                    }
+                    Some(batched) => {
+                        if batched.item_dims != item_dims || batched.data_type != buffer.data_type()
+                        {
+                            return Err(Error::new(
+                                ErrorCode::InvalidArg,
+                                format!(
+                                    "Input {} of item {index} is {:?} {item_dims:?}, first item's one is {:?} {:?}",
+                                    input.name,
+                                    buffer.data_type(),
+                                    batched.data_type,
+                                    batched.item_dims
+                                ),
+                            ));
+                        }
+                        batched.bytes.extend_from_slice(&bytes);
+                    }
+                }
+            }
+
+            let Batched {
+                data_type,
+                item_dims,
+                bytes,
+            } = batched.expect("batch is not empty");
+            let mut dims = vec![batch_size as i64];
+            dims.extend(item_dims);
+            request.add_input_with_dims(&input.name, cpu_buffer(&bytes, data_type), dims)?;
+        }
+
+        let response = request.infer_async()?.await.map_err(|err| err.error)?;
+        response_outputs(&response, batch_size)
+    }
+}
+
+/// Split the outputs of the `response` into `batch_size` items along the first dimension.
+fn response_outputs(
+    response: &Response,
+    batch_size: usize,
+) -> Result<Vec<HashMap<String, BatchOutput>>, Error> {
+    let mut items = (0..batch_size).map(|_| HashMap::new()).collect::<Vec<_>>();
+    for output in response.get_outputs() {
+        let bytes = output.get_buffer().get_owned_slice(..)?;
+        let data_type = output.data_type();
+        let mut shape = output.shape.clone();
+
+        let parts = if batch_size == 1 {
+            vec![bytes]
+        } else {
+            if shape.first() != Some(&(batch_size as i64)) {
+                return Err(Error::new(
+                    ErrorCode::Internal,
+                    format!(
+                        "Output {} of shape {shape:?} is not batched by {batch_size}",
+                        output.name
+                    ),
+                ));
+            }
+            shape[0] = 1;
+            split(&bytes, data_type, batch_size)?
+        };
+
+        for (item, part) in items.iter_mut().zip(parts) {
+            item.insert(
+                output.name.clone(),
+                BatchOutput {
+                    shape: shape.clone(),
+                    buffer: cpu_buffer(&part, data_type),
+                },
+            );
+        }
+    }
+    Ok(items)
+}
+
+/// CPU buffer of `data_type` with the copy of `bytes`.
+fn cpu_buffer(bytes: &[u8], data_type: DataType) -> Buffer {
+    let mut buffer = Buffer::from(bytes);
+    buffer.data_type = data_type;
+    buffer
+}
+
+/// Number of the elements in `bytes` of `data_type`. [DataType::Bytes] elements are prefixed with their 4-byte length.
+fn element_count(bytes: &[u8], data_type: DataType) -> Result<usize, Error> {
+    if data_type != DataType::Bytes {
+        return Ok(bytes.len() / data_type.size() as usize);
+    }
+    Ok(bytes_elements(bytes)?.len())
+}
+
+/// Offsets of the [DataType::Bytes] elements ends.
+fn bytes_elements(bytes: &[u8]) -> Result<Vec<usize>, Error> {
+    let mut ends = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let len = bytes
+            .get(offset..offset + 4)
+            .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+            .ok_or_else(|| Error::new(ErrorCode::InvalidArg, "BYTES tensor is truncated"))?;
+        offset += 4 + len;
+        if offset > bytes.len() {
+            return Err(Error::new(
+                ErrorCode::InvalidArg,
+                "BYTES tensor is truncated",
+            ));
+        }
+        ends.push(offset);
+    }
+    Ok(ends)
+}
+
+/// Shape of the item of `count` elements by the model `dims` (without the batch dimension).
+fn item_dims(dims: &[i64], count: usize) -> Result<Vec<i64>, String> {
+    let known = dims.iter().filter(|dim| **dim >= 0).product::<i64>() as usize;
+    let mut dims = dims.to_vec();
+    match dims.iter().filter(|dim| **dim < 0).count() {
+        0 if known == count => Ok(dims),
+        1 if known != 0 && count.is_multiple_of(known) => {
+            for dim in dims.iter_mut().filter(|dim| **dim < 0) {
+                *dim = (count / known) as i64;
+            }
+            Ok(dims)
+        }
+        0 | 1 => Err(format!("{count} elements don't match the shape {dims:?}")),
+        _ => Err(format!(
+            "shape {dims:?} has several dynamic dimensions, can't deduce them"
+        )),
+    }
+}
+
+/// Split `bytes` of `data_type` into `parts` of the same number of elements.
+fn split(bytes: &[u8], data_type: DataType, parts: usize) -> Result<Vec<Vec<u8>>, Error> {
+    if data_type != DataType::Bytes {
+        let part_len = bytes.len() / parts;
+        return Ok(bytes.chunks(part_len.max(1)).map(<[u8]>::to_vec).collect());
+    }
+
+    let ends = bytes_elements(bytes)?;
+    let per_part = ends.len() / parts;
+    let mut start = 0;
+    Ok((1..=parts)
+        .map(|part| {
+            let end = if per_part == 0 {
+                start
+            } else {
+                ends[part * per_part - 1]
+            };
+            let chunk = bytes[start..end].to_vec();
+            start = end;
+            chunk
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes() {
+        assert_eq!(item_dims(&[3, -1, 2], 24), Ok(vec![3, 4, 2]));
+        assert_eq!(item_dims(&[3, 2], 6), Ok(vec![3, 2]));
+        assert!(item_dims(&[3, 2], 7).is_err());
+        assert!(item_dims(&[-1, -1], 4).is_err());
+    }
+
+    #[test]
+    fn split_bytes() {
+        let floats = [1f32, 2., 3., 4.]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        let parts = split(&floats, DataType::Fp32, 2).unwrap();
+        assert_eq!(parts[1], floats[8..]);
+
+        let mut strings = Vec::new();
+        for string in ["a", "bc", "", "def"] {
+            strings.extend((string.len() as u32).to_le_bytes());
+            strings.extend(string.as_bytes());
+        }
+        let parts = split(&strings, DataType::Bytes, 2).unwrap();
+        assert_eq!(parts[0], strings[..11]);
+        assert_eq!(parts[1], strings[11..]);
+        assert_eq!(element_count(&strings, DataType::Bytes).unwrap(), 4);
+    }
+}
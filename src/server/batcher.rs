@@ -0,0 +1,129 @@
+use std::{collections::HashMap, time::Duration};
+
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::Instant,
+};
+
+use super::{no_tokio_runtime, BatchOutput, Server, WeakServer};
+use crate::{
+    error::{Error, ErrorCode},
+    Buffer,
+};
+
+/// Options of the [Batcher].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BatcherOptions {
+    /// Maximum number of the items in the batch.
+    /// Batches are also limited by the max batch size of the model (check [Server::infer_batch]).
+    pub max_items: usize,
+    /// Maximum time the first item of the batch waits for the others.
+    pub max_delay: Duration,
+}
+
+impl Default for BatcherOptions {
+    /// `max_items`: 8, `max_delay`: 5ms.
+    fn default() -> Self {
+        BatcherOptions {
+            max_items: 8,
+            max_delay: Duration::from_millis(5),
+        }
+    }
+}
+
+type Reply = oneshot::Sender<Result<HashMap<String, BatchOutput>, Error>>;
+
+/// Aggregates the single items inferred concurrently into the batched requests to the model,
+/// for the models without Triton dynamic batching. Created with [Server::batcher].
+///
+/// The items are collected until there are [BatcherOptions::max_items] of them or [BatcherOptions::max_delay] passes
+/// since the first one, then inferred with [Server::infer_batch] and its outputs are returned to the callers. \
+/// The batcher doesn't keep the server alive: items inferred after the server is dropped fail with [ErrorCode::Unavailable].
+/// Clones of the batcher share the same queue.
+#[derive(Debug, Clone)]
+pub struct Batcher {
+    tx: mpsc::UnboundedSender<(HashMap<String, Buffer>, Reply)>,
+}
+
+impl Batcher {
+    /// Infer the item (inputs of one sample by input name, without the batch dimension) in the next batch
+    /// and return its outputs (by output name). \
+    /// The error of the batch inference is returned to all the items of the batch.
+    pub async fn infer<N: AsRef<str>>(
+        &self,
+        inputs: HashMap<N, Buffer>,
+    ) -> Result<HashMap<String, BatchOutput>, Error> {
+        let inputs = inputs
+            .into_iter()
+            .map(|(name, buffer)| (name.as_ref().to_string(), buffer))
+            .collect();
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send((inputs, reply))
+            .map_err(|_| Error::new(ErrorCode::Unavailable, "Batcher is stopped"))?;
+        rx.await
+            .map_err(|_| Error::new(ErrorCode::Unavailable, "Batcher is stopped"))?
+    }
+}
+
+impl Server {
+    /// Create the [Batcher] of the `model`. \
+    /// Spawns the task collecting the batches in the tokio runtime of the server, the task stops when all clones of the batcher are dropped. \
+    /// Returns error with [ErrorCode::Unsupported] if there is no tokio runtime.
+    pub fn batcher<M: AsRef<str>>(
+        &self,
+        model: M,
+        options: BatcherOptions,
+    ) -> Result<Batcher, Error> {
+        let runtime = self.tokio_handle().ok_or_else(no_tokio_runtime)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let model = model.as_ref().to_string();
+        let server = self.downgrade();
+        runtime.spawn(collect_batches(server, model, options, rx));
+        Ok(Batcher { tx })
+    }
+}
+
+/// Collect the items from `rx` into the batches and infer each batch in its own task.
+async fn collect_batches(
+    server: WeakServer,
+    model: String,
+    options: BatcherOptions,
+    mut rx: mpsc::UnboundedReceiver<(HashMap<String, Buffer>, Reply)>,
+) {
+    let max_items = options.max_items.max(1);
+    while let Some(first) = rx.recv().await {
+        let deadline = Instant::now() + options.max_delay;
+        let mut items = vec![first];
+        while items.len() < max_items {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(item)) => items.push(item),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let (inputs, replies): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+        let Some(server) = server.upgrade() else {
+            for reply in replies {
+                let _ = reply.send(Err(Error::new(ErrorCode::Unavailable, "Server is dropped")));
+            }
+            continue;
+        };
+        let model = model.clone();
+        tokio::spawn(async move {
+            log::trace!("Batcher of {model} infers {} items", inputs.len());
+            match server.infer_batch(&model, inputs).await {
+                Ok(outputs) => {
+                    for (reply, outputs) in replies.into_iter().zip(outputs) {
+                        let _ = reply.send(Ok(outputs));
+                    }
+                }
+                Err(err) => {
+                    for reply in replies {
+                        let _ = reply.send(Err(Error::new(err.code(), err.message())));
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,151 @@
+//! Health, readiness and metrics HTTP endpoints of the in-process server.
+
+use std::net::{TcpListener as StdTcpListener, ToSocketAddrs};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use super::{no_tokio_runtime, Server, WeakServer};
+use crate::{
+    error::{Error, ErrorCode},
+    metrics::Format,
+};
+
+/// Maximum size of the request head read from the connection.
+const MAX_HEAD: usize = 8 << 10;
+
+impl Server {
+    /// Serve the endpoints for the liveness/readiness probes (e.g. of Kubernetes)
+    /// on `addr` without the full Triton HTTP frontend:
+    /// - `GET /v2/health/live`: 200 if [Server::is_live], 503 otherwise;
+    /// - `GET /v2/health/ready`: 200 if [Server::is_ready], 503 otherwise;
+    /// - `GET /metrics`: [Server::metrics] in Prometheus format.
+    ///
+    /// The endpoints don't keep the server alive: after it is dropped, the probes return 503. \
+    /// Returns the handle of the task accepting the connections. Abort it to stop serving. \
+    /// Returns error with [ErrorCode::Unsupported] if there is no tokio runtime.
+    pub fn serve_http<A: ToSocketAddrs>(&self, addr: A) -> Result<JoinHandle<()>, Error> {
+        let bind_error = |err: std::io::Error| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't bind HTTP endpoints: {err}"),
+            )
+        };
+        let runtime = self.tokio_handle().ok_or_else(no_tokio_runtime)?;
+        let _runtime = runtime.enter();
+        let listener = StdTcpListener::bind(addr).map_err(bind_error)?;
+        listener.set_nonblocking(true).map_err(bind_error)?;
+        let listener = TcpListener::from_std(listener).map_err(bind_error)?;
+        if let Ok(addr) = listener.local_addr() {
+            log::info!("Serving health endpoints on {addr}");
+        }
+
+        let server = self.downgrade();
+        Ok(runtime.spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve_connection(server.clone(), stream));
+                    }
+                    Err(err) => log::warn!("Can't accept HTTP connection: {err}"),
+                }
+            }
+        }))
+    }
+}
+
+/// Get method and path of the request from its head.
+fn parse_request_line(head: &str) -> Option<(&str, &str)> {
+    let mut parts = head.lines().next()?.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    parts.next()?.starts_with("HTTP/").then_some(())?;
+    let path = target.split_once('?').map_or(target, |(path, _)| path);
+    Some((method, path))
+}
+
+/// Status, content type and body of the response to the request of `method` and `path`.
+fn respond(server: &WeakServer, method: &str, path: &str) -> (u16, &'static str, Vec<u8>) {
+    const TEXT: &str = "text/plain; charset=utf-8";
+    if method != "GET" && method != "HEAD" {
+        return (405, TEXT, b"Method Not Allowed".to_vec());
+    }
+    let server = server.upgrade();
+    let probe = |check: fn(&Server) -> Result<bool, Error>| match server.as_ref().map(check) {
+        Some(Ok(true)) => (200, TEXT, Vec::new()),
+        _ => (503, TEXT, Vec::new()),
+    };
+    match path {
+        "/v2/health/live" => probe(Server::is_live),
+        "/v2/health/ready" => probe(Server::is_ready),
+        "/metrics" => {
+            let metrics = server
+                .as_ref()
+                .ok_or_else(|| Error::new(ErrorCode::Unavailable, "Server is dropped"))
+                .and_then(|server| server.metrics())
+                .and_then(|metrics| metrics.formatted(Format::Prometheus).map(<[u8]>::to_vec));
+            match metrics {
+                Ok(metrics) => (200, "text/plain; version=0.0.4", metrics),
+                Err(err) => (503, TEXT, err.to_string().into_bytes()),
+            }
+        }
+        _ => (404, TEXT, b"Not Found".to_vec()),
+    }
+}
+
+async fn serve_connection(server: WeakServer, mut stream: TcpStream) {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") && head.len() < MAX_HEAD {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => head.extend_from_slice(&chunk[..read]),
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let (status, content_type, body) = match parse_request_line(&head) {
+        Some((method, path)) => {
+            let (status, content_type, body) = respond(&server, method, path);
+            (
+                status,
+                content_type,
+                if method == "HEAD" { Vec::new() } else { body },
+            )
+        }
+        None => (400, "text/plain; charset=utf-8", b"Bad Request".to_vec()),
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    if stream.write_all(response.as_bytes()).await.is_ok() {
+        let _ = stream.write_all(&body).await;
+    }
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_line() {
+        assert_eq!(
+            parse_request_line("GET /v2/health/ready?verbose=1 HTTP/1.1\r\nHost: pod\r\n\r\n"),
+            Some(("GET", "/v2/health/ready"))
+        );
+        assert_eq!(parse_request_line("GET /metrics\r\n"), None);
+        assert_eq!(parse_request_line(""), None);
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use super::Server;
+use crate::{message::VersionState, runtime, sys, to_cstring, Error};
+
+/// Load throughput assumed for the model the server never loaded before, in bytes per second.
+const ASSUMED_LOAD_THROUGHPUT: f64 = 256.0 * 1024.0 * 1024.0;
+/// Estimated fraction of the load in progress never exceeds this value.
+const MAX_ESTIMATED_FRACTION: f32 = 0.99;
+
+/// Progress of the model load reported by [Server::load_model_with_progress].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadProgress {
+    /// Name of the model.
+    pub model: String,
+    /// Time passed since the start of the load.
+    pub elapsed: Duration,
+    /// Total size of the model files. None if the model directory is not found in the model repositories of the server.
+    pub model_bytes: Option<u64>,
+    /// Estimated fraction of the completed load in range [0, 1]. None if there is nothing to estimate it with. \
+    /// **Note**: it's a best-effort heuristic, a load can be completed both before and after the estimation reaches 1.
+    pub fraction: Option<f32>,
+    /// Latest state of the model versions reported by the server.
+    pub state: Option<VersionState>,
+    /// Load is completed (successfully or not). The last reported progress.
+    pub done: bool,
+}
+
+/// What the load progress is estimated with.
+#[derive(Debug, Clone, Copy)]
+struct Estimation {
+    model_bytes: Option<u64>,
+    /// Duration of the previous load of the model by this server.
+    previous_load: Option<Duration>,
+}
+
+impl Estimation {
+    fn fraction(&self, elapsed: Duration) -> Option<f32> {
+        let expected = match (self.previous_load, self.model_bytes) {
+            (Some(previous), _) => previous.as_secs_f64(),
+            (None, Some(bytes)) => bytes as f64 / ASSUMED_LOAD_THROUGHPUT,
+            (None, None) => return None,
+        };
+        if expected <= 0. {
+            return Some(MAX_ESTIMATED_FRACTION);
+        }
+        Some(((elapsed.as_secs_f64() / expected) as f32).min(MAX_ESTIMATED_FRACTION))
+    }
+}
+
+/// Total size of the files in `path`. Unreadable entries are skipped.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+impl Server {
+    /// Asynchronous version of [Server::load_model] reporting the progress of the load. \
+    /// `callback` is called every `interval` while the model is loading and once more after the load is completed, with [LoadProgress::done] set. \
+    /// Triton doesn't report the load progress, so [LoadProgress::fraction] is estimated by the duration of the previous load of the model by this server
+    /// or, if there was none, by the size of the model directory in the model repositories of the server. \
+    /// `name`: The name of the model.
+    pub async fn load_model_with_progress<N, F>(
+        &self,
+        name: N,
+        interval: Duration,
+        mut callback: F,
+    ) -> Result<(), Error>
+    where
+        N: AsRef<str>,
+        F: FnMut(&LoadProgress),
+    {
+        let model = name.as_ref().to_string();
+        let model_name = to_cstring(&model)?;
+        let server = self.ptr.clone();
+        server.started()?;
+
+        let estimation = Estimation {
+            model_bytes: self.model_bytes(&model),
+            previous_load: self
+                .read_models_info(|info| info.load_durations.get(&model).copied())
+                .ok()
+                .flatten(),
+        };
+        let progress = |server: &Server, elapsed: Duration, done: bool| LoadProgress {
+            model: model.clone(),
+            elapsed,
+            model_bytes: estimation.model_bytes,
+            fraction: if done {
+                Some(1.)
+            } else {
+                estimation.fraction(elapsed)
+            },
+            state: server.load_state(&model),
+            done,
+        };
+
+        let start = Instant::now();
+        let load = runtime::try_run_blocking(move || {
+            triton_call!(sys::TRITONSERVER_ServerLoadModel(
+                server.started()?,
+                model_name.as_ptr()
+            ))
+        });
+        tokio::pin!(load);
+        let mut ticker = tokio::time::interval(interval);
+        let result = loop {
+            tokio::select! {
+                result = &mut load => break result,
+                _ = ticker.tick() => callback(&progress(self, start.elapsed(), false)),
+            }
+        };
+        let elapsed = start.elapsed();
+
+        if result.is_ok() {
+            let _ =
+                self.write_models_info(|info| info.load_durations.insert(model.clone(), elapsed));
+        }
+        callback(&progress(self, elapsed, true));
+        result?;
+
+        self.update_model_info(&model)?;
+        self.update_load_times(Some(&model))
+    }
+
+    /// Total size of the `model` directory in the model repositories of the server options
+    /// or the ones registered with [Server::register_repository].
+    fn model_bytes(&self, model: &str) -> Option<u64> {
+        let settings = self.settings();
+        let option_dirs = settings
+            .iter()
+            .filter(|(option, _)| *option == "model_repository")
+            .map(|(_, repository)| Path::new(repository).join(model));
+        let repositories = self.registered_repositories();
+        let registered_dirs = repositories.iter().map(|(repository, mappings)| {
+            let dir = mappings
+                .iter()
+                .find(|(_, name)| *name == model)
+                .map_or(model, |(dir, _)| dir.as_str());
+            repository.join(dir)
+        });
+
+        option_dirs
+            .chain(registered_dirs)
+            .find(|path| path.is_dir())
+            .map(|path| dir_size(&path))
+    }
+
+    /// State of the `model`: [VersionState::Loading] if any of its versions is loading, otherwise the state of its first version.
+    fn load_state(&self, model: &str) -> Option<VersionState> {
+        let states = self
+            .model_states()
+            .ok()?
+            .into_iter()
+            .filter(|status| status.name == model)
+            .filter_map(|status| status.state)
+            .collect::<Vec<_>>();
+        states
+            .iter()
+            .find(|state| **state == VersionState::Loading)
+            .or(states.first())
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimation() {
+        let by_size = Estimation {
+            model_bytes: Some(ASSUMED_LOAD_THROUGHPUT as u64 * 4),
+            previous_load: None,
+        };
+        assert_eq!(by_size.fraction(Duration::from_secs(1)), Some(0.25));
+        assert_eq!(
+            by_size.fraction(Duration::from_secs(10)),
+            Some(MAX_ESTIMATED_FRACTION)
+        );
+
+        let by_previous = Estimation {
+            previous_load: Some(Duration::from_secs(2)),
+            ..by_size
+        };
+        assert_eq!(by_previous.fraction(Duration::from_secs(1)), Some(0.5));
+
+        let unknown = Estimation {
+            model_bytes: None,
+            previous_load: None,
+        };
+        assert_eq!(unknown.fraction(Duration::from_secs(1)), None);
+    }
+}
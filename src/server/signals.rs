@@ -0,0 +1,49 @@
+//! Graceful shutdown of the server on SIGTERM/SIGINT.
+
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+};
+
+use super::{no_tokio_runtime, Server};
+use crate::error::{Error, ErrorCode};
+
+impl Server {
+    /// Shut down the server and exit the process on SIGTERM or SIGINT. \
+    /// On the signal the server is shut down with [Server::shutdown]: new inferences are rejected,
+    /// the in-flight ones are awaited up to the exit timeout, then the server is stopped and deleted.
+    /// After that the process exits with code 0 (1 if the shutdown failed).
+    ///
+    /// Returns the handle of the task awaiting the signals. Abort it to disable the handling. \
+    /// Returns error with [ErrorCode::Unsupported] if there is no tokio runtime.
+    pub fn exit_on_signals(&self) -> Result<JoinHandle<()>, Error> {
+        let runtime = self.tokio_handle().ok_or_else(no_tokio_runtime)?;
+        let _runtime = runtime.enter();
+        let signal_error = |err: std::io::Error| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't install signal handler: {err}"),
+            )
+        };
+        let mut terminate = signal(SignalKind::terminate()).map_err(signal_error)?;
+        let mut interrupt = signal(SignalKind::interrupt()).map_err(signal_error)?;
+
+        let server = self.ptr.clone();
+        Ok(runtime.spawn(async move {
+            let received = tokio::select! {
+                _ = terminate.recv() => "SIGTERM",
+                _ = interrupt.recv() => "SIGINT",
+            };
+            log::info!("{received} is received, shutting down the server");
+
+            let code = match server.shutdown().await {
+                Ok(()) => 0,
+                Err(err) => {
+                    log::error!("Server shutdown failed: {err}");
+                    1
+                }
+            };
+            std::process::exit(code);
+        }))
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+
+use super::{no_tokio_runtime, Server};
+use crate::{metrics::ServerStats, Error};
+
+impl Server {
+    /// Sample the server metrics every `interval` on the tokio runtime of the server
+    /// and broadcast them as [ServerStats] snapshots, e.g. for dashboards or adaptive concurrency limits. \
+    /// The initial value of the receiver is empty ([ServerStats::sampled_at] is None) until the first sample.
+    /// Errors of the sampling are logged and retried on the next tick. \
+    /// The task stops when the server or all the receivers are dropped. \
+    /// Returns error with [ErrorCode::Unsupported](crate::ErrorCode::Unsupported) if there is no tokio runtime.
+    /// ```ignore
+    /// let mut stats = server.spawn_stats_sampler(Duration::from_secs(1))?;
+    /// while stats.changed().await.is_ok() {
+    ///     let pending = stats.borrow().models.get("resnet").map(|model| model.pending_requests);
+    /// }
+    /// ```
+    pub fn spawn_stats_sampler(
+        &self,
+        interval: Duration,
+    ) -> Result<watch::Receiver<ServerStats>, Error> {
+        let runtime = self.tokio_handle().ok_or_else(no_tokio_runtime)?;
+        let (tx, rx) = watch::channel(ServerStats::default());
+        let server = self.downgrade();
+
+        runtime.spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => (),
+                    _ = tx.closed() => return,
+                }
+                let Some(shadow) = server.upgrade() else {
+                    return;
+                };
+                let previous = tx.borrow().clone();
+                let sampled = tokio::task::spawn_blocking(move || {
+                    let families = shadow.metrics()?.to_map()?;
+                    Ok::<_, crate::Error>(ServerStats::from_families(
+                        &families,
+                        SystemTime::now(),
+                        Some(&previous),
+                    ))
+                })
+                .await
+                .expect("tokio failed to join thread");
+
+                match sampled {
+                    Ok(stats) => {
+                        if tx.send(stats).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => log::warn!("Error sampling server metrics: {err}"),
+                }
+            }
+        });
+        Ok(rx)
+    }
+}
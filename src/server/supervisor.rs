@@ -0,0 +1,280 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use tokio::sync::Mutex;
+
+use super::{Repositories, Server, ServerHandle, State};
+use crate::{options::Options, Error};
+
+type Blueprint = Arc<dyn Fn() -> Result<Options, Error> + Send + Sync>;
+
+/// Restartable server. \
+/// A stopped Triton server can't be started again, so the supervisor keeps the blueprint creating the [Options]
+/// and replaces the server with the new one on [Supervisor::restart], e.g. to apply the changed configuration.
+///
+/// The Triton server is swapped inside the state shared by the server objects, so the supervisor, its clones,
+/// the [ServerHandle]s obtained with [Supervisor::server] and the background tasks
+/// (e.g. [Server::spawn_repository_watcher]) follow the restart. \
+/// Requests created before the restart are bound to the stopped server:
+/// their inference returns [ErrorCode::Unavailable](crate::ErrorCode::Unavailable).
+/// ```ignore
+/// let supervisor = Supervisor::new(|| Options::new("/models")).await?;
+/// let response = supervisor.server().create_request("resnet", -1)?.infer_async()?.await?;
+///
+/// supervisor
+///     .restart_with(|| {
+///         let mut options = Options::new("/models")?;
+///         options.strict_model_config(false)?;
+///         Ok(options)
+///     })
+///     .await?;
+/// ```
+#[derive(Clone)]
+pub struct Supervisor {
+    inner: Arc<SupervisorInner>,
+}
+
+struct SupervisorInner {
+    blueprint: RwLock<Blueprint>,
+    server: ServerHandle,
+    /// Serializes the restarts. Holds the state of the server stopped by the failed restart,
+    /// so the retry restores it instead of the state of the stopped server.
+    restart: Mutex<Option<Restore>>,
+}
+
+impl fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("server", &self.server())
+            .field("restarts", &self.restarts())
+            .finish()
+    }
+}
+
+impl Supervisor {
+    /// Create the server with the options of the `blueprint`. \
+    /// The blueprint is called again on each restart.
+    pub async fn new<F>(blueprint: F) -> Result<Self, Error>
+    where
+        F: Fn() -> Result<Options, Error> + Send + Sync + 'static,
+    {
+        let blueprint: Blueprint = Arc::new(blueprint);
+        let server = Server::new(blueprint()?).await?;
+        Ok(Supervisor {
+            inner: Arc::new(SupervisorInner {
+                blueprint: RwLock::new(blueprint),
+                server: server.handle(),
+                restart: Mutex::new(None),
+            }),
+        })
+    }
+
+    /// Get the handle of the server. \
+    /// The handle follows the restarts, check [Supervisor].
+    pub fn server(&self) -> ServerHandle {
+        self.inner.server.clone()
+    }
+
+    /// Number of the successful restarts.
+    pub fn restarts(&self) -> u64 {
+        self.inner.server.ptr.generation()
+    }
+
+    /// Shut down the server gracefully (check [Server::shutdown]) and start the new one with the options of the blueprint. \
+    /// The state of the stopped server is restored on the new one:
+    /// - repositories registered with [Server::register_repository];
+    /// - models that were ready and are not loaded on the start of the new server (e.g. with [Control::Explicit](crate::options::Control::Explicit)).
+    ///
+    /// Executor of the callbacks (check [Server::set_executor]), the trace sampler (check [Server::set_trace_sampler])
+    /// and the namespace quotas are kept by the server objects, so they are not changed by the restart.
+    ///
+    /// Model metadata of the new server is loaded on its start and on the model loads.
+    /// Parameters of the loads ([Server::load_model_with_parametrs]) are not restored.
+    ///
+    /// Returns error if the new server can't be created. If the blueprint fails, the server is not stopped.
+    /// Otherwise the stopped server is kept, and its state is kept for the retry of the restart. \
+    /// If the state can't be restored, the new server is kept and the first error is returned.
+    pub async fn restart(&self) -> Result<(), Error> {
+        let mut pending = self.inner.restart.lock().await;
+        let blueprint = self
+            .inner
+            .blueprint
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        self.replace(&mut pending, &blueprint).await?
+    }
+
+    /// Replace the blueprint of the options and [restart](Supervisor::restart) the server with it. \
+    /// The blueprint is replaced only if the new server is created, so the failed restart can be retried with the previous one.
+    pub async fn restart_with<F>(&self, blueprint: F) -> Result<(), Error>
+    where
+        F: Fn() -> Result<Options, Error> + Send + Sync + 'static,
+    {
+        let mut pending = self.inner.restart.lock().await;
+        let blueprint: Blueprint = Arc::new(blueprint);
+        let restored = self.replace(&mut pending, &blueprint).await?;
+        *self
+            .inner
+            .blueprint
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = blueprint;
+        restored
+    }
+
+    /// Replace the server with the one created with the options of the `blueprint`. \
+    /// Returns the error of the creation, or the result of the restore of the state on the created server.
+    async fn replace(
+        &self,
+        pending: &mut Option<Restore>,
+        blueprint: &Blueprint,
+    ) -> Result<Result<(), Error>, Error> {
+        let options = blueprint()?;
+        let server = &self.inner.server;
+        let restore = pending.take().unwrap_or_else(|| Restore::capture(server));
+
+        let replaced = match server.ptr.shutdown().await {
+            Ok(()) => server.ptr.replace(options),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = replaced {
+            *pending = Some(restore);
+            return Err(err);
+        }
+
+        log::info!("Server is restarted");
+        Ok(restore.apply(server).await)
+    }
+}
+
+/// State of the stopped server restored on the new one. Check [Supervisor::restart].
+struct Restore {
+    repositories: Repositories,
+    ready: HashSet<String>,
+}
+
+impl Restore {
+    fn capture(server: &Server) -> Self {
+        // Ready models of the server that is not started yet are not queried: the query would start it.
+        let ready = if server.is_started() {
+            match server.model_index(State::READY) {
+                Ok(index) => index.into_iter().map(|index| index.name).collect(),
+                Err(err) => {
+                    log::warn!(
+                        "Can't get the ready models of the server before the restart: {err}"
+                    );
+                    HashSet::new()
+                }
+            }
+        } else {
+            HashSet::new()
+        };
+        Restore {
+            repositories: server.registered_repositories(),
+            ready,
+        }
+    }
+
+    async fn apply(self, server: &Server) -> Result<(), Error> {
+        // Repositories are registered again, the ones failed to register are not kept.
+        server
+            .repositories
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+
+        let mut restored = Ok(());
+        for (repository, name_mappings) in self.repositories {
+            if let Err(err) = server.register_repository(&repository, name_mappings) {
+                log::warn!(
+                    "Can't register repository {} after the restart: {err}",
+                    repository.display()
+                );
+                restored = restored.and(Err(err));
+            }
+        }
+        for model in self.ready {
+            if server.model_is_ready(&model, -1).unwrap_or(false) {
+                continue;
+            }
+            if let Err(err) = server.load_model_async(&model).await {
+                log::warn!("Can't load model {model} after the restart: {err}");
+                restored = restored.and(Err(err));
+            }
+        }
+        restored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+
+    fn lazy() -> Result<Options, Error> {
+        let mut options = Options::new(std::env::temp_dir())?;
+        options.lazy_startup(true);
+        Ok(options)
+    }
+
+    #[tokio::test]
+    async fn failed_restart() {
+        let supervisor = Supervisor::new(lazy).await.unwrap();
+        let old = supervisor.server().ptr.instance();
+
+        let err = supervisor
+            .restart_with(|| Err(Error::new(ErrorCode::InvalidArg, "bad options")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidArg);
+        // The failed blueprint doesn't stop the server and is not kept.
+        assert!(Arc::ptr_eq(&old, &supervisor.server().ptr.instance()));
+        assert_eq!(supervisor.restarts(), 0);
+
+        supervisor.restart().await.unwrap();
+        assert_eq!(supervisor.restarts(), 1);
+        assert!(!Arc::ptr_eq(&old, &supervisor.server().ptr.instance()));
+    }
+
+    #[tokio::test]
+    async fn handles_follow_restart() {
+        let supervisor = Supervisor::new(lazy).await.unwrap();
+        let handle = supervisor.server();
+        let clone = supervisor.clone();
+
+        supervisor.restart().await.unwrap();
+        assert_eq!(handle.ptr.generation(), 1);
+        assert_eq!(clone.restarts(), 1);
+        assert!(Arc::ptr_eq(
+            &handle.ptr.instance(),
+            &supervisor.server().ptr.instance()
+        ));
+
+        let err = handle.ptr.begin_inference(0).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::Unavailable);
+        assert!(handle.ptr.begin_inference(1).is_ok());
+    }
+
+    #[tokio::test]
+    async fn pending_restore() {
+        let supervisor = Supervisor::new(lazy).await.unwrap();
+        let repository = std::env::temp_dir().join("tritonserver-rs-pending-restore");
+        // State captured by the restart that failed to create the server.
+        *supervisor.inner.restart.lock().await = Some(Restore {
+            repositories: Repositories::from([(repository.clone(), Default::default())]),
+            ready: HashSet::new(),
+        });
+
+        // Registration of the missing repository fails, but the pending state is consumed.
+        let _ = supervisor.restart().await;
+        assert_eq!(supervisor.restarts(), 1);
+        assert!(supervisor.inner.restart.lock().await.is_none());
+        assert!(!supervisor
+            .server()
+            .registered_repositories()
+            .contains_key(&repository));
+    }
+}
@@ -0,0 +1,224 @@
+//! Usage accounting and quotas of the model namespaces. Check [Request::set_namespace](crate::Request::set_namespace).
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use super::Server;
+use crate::{
+    error::{Error, ErrorCode},
+    trace::{Activity, Trace, TraceHandler},
+};
+
+/// Usage of the model namespace by the requests accounted to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NamespaceUsage {
+    /// Number of the accepted requests.
+    pub requests: u64,
+    /// Number of the requests rejected because the quota was exceeded.
+    pub rejected: u64,
+    /// Total size of the inputs of the accepted requests.
+    pub input_bytes: u64,
+    /// Total size of the outputs of the responses.
+    pub output_bytes: u64,
+    /// Total time of the requests computation (from the compute start to the compute end trace activities). \
+    /// Requests with the custom trace ([Request::add_trace](crate::Request::add_trace)) are not accounted here.
+    pub compute_time: Duration,
+}
+
+/// Limits of the namespace usage. New requests are rejected once any of the limits is reached. \
+/// Limits are checked against the accumulated [NamespaceUsage], reset it with [Server::reset_namespace_usage]
+/// to start the new accounting period.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NamespaceQuota {
+    pub max_requests: Option<u64>,
+    /// Limit of the inputs and outputs total size.
+    pub max_bytes: Option<u64>,
+    pub max_compute_time: Option<Duration>,
+}
+
+impl NamespaceQuota {
+    /// Name of the limit reached by `usage`.
+    fn exceeded_by(&self, usage: &NamespaceUsage) -> Option<&'static str> {
+        if self.max_requests.is_some_and(|max| usage.requests >= max) {
+            Some("requests")
+        } else if self
+            .max_bytes
+            .is_some_and(|max| usage.input_bytes + usage.output_bytes >= max)
+        {
+            Some("bytes")
+        } else if self
+            .max_compute_time
+            .is_some_and(|max| usage.compute_time >= max)
+        {
+            Some("compute time")
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Account {
+    usage: NamespaceUsage,
+    quota: Option<NamespaceQuota>,
+}
+
+/// Usage and quotas of all the namespaces of the server.
+#[derive(Debug, Default)]
+pub(crate) struct Accounting {
+    accounts: Mutex<HashMap<String, Account>>,
+}
+
+impl Accounting {
+    fn with_account<R, F: FnOnce(&mut Account) -> R>(&self, namespace: &str, f: F) -> R {
+        let mut accounts = self.accounts.lock().unwrap_or_else(PoisonError::into_inner);
+        f(accounts.entry(namespace.to_string()).or_default())
+    }
+
+    /// Account the new request of the `namespace`. Returns error if the quota of the namespace is exceeded.
+    pub(crate) fn admit(&self, namespace: &str, input_bytes: usize) -> Result<(), Error> {
+        let exceeded = self.with_account(namespace, |account| {
+            let exceeded = account
+                .quota
+                .as_ref()
+                .and_then(|quota| quota.exceeded_by(&account.usage));
+            if exceeded.is_some() {
+                account.usage.rejected += 1;
+            } else {
+                account.usage.requests += 1;
+                account.usage.input_bytes += input_bytes as u64;
+            }
+            exceeded
+        });
+
+        match exceeded {
+            None => Ok(()),
+            Some(limit) => Err(Error::new(
+                ErrorCode::Unavailable,
+                format!("Quota of {limit} of the namespace {namespace} is exceeded"),
+            )),
+        }
+    }
+
+    pub(crate) fn add_output_bytes(&self, namespace: &str, output_bytes: usize) {
+        self.with_account(namespace, |account| {
+            account.usage.output_bytes += output_bytes as u64
+        });
+    }
+
+    fn add_compute_time(&self, namespace: &str, compute_time: Duration) {
+        self.with_account(namespace, |account| {
+            account.usage.compute_time += compute_time
+        });
+    }
+}
+
+/// Trace handler accounting the compute time of the request to the namespace.
+struct ComputeTimer {
+    accounting: Arc<Accounting>,
+    namespace: String,
+    /// Compute start by trace id.
+    starts: Mutex<HashMap<u64, Duration>>,
+}
+
+impl TraceHandler for ComputeTimer {
+    fn trace_activity(&self, trace: &Trace, event: Activity, event_time: Duration) {
+        let Ok(id) = trace.id() else {
+            return;
+        };
+        let mut starts = self.starts.lock().unwrap_or_else(PoisonError::into_inner);
+        match event {
+            Activity::ComputeStart => {
+                starts.insert(id, event_time);
+            }
+            Activity::ComputeEnd => {
+                if let Some(start) = starts.remove(&id) {
+                    self.accounting
+                        .add_compute_time(&self.namespace, event_time.saturating_sub(start));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Trace accounting the compute time of the request to the `namespace`.
+/// None if Triton doesn't support tracing.
+pub(crate) fn compute_trace(accounting: &Arc<Accounting>, namespace: &str) -> Option<Trace> {
+    let timer = ComputeTimer {
+        accounting: accounting.clone(),
+        namespace: namespace.to_string(),
+        starts: Mutex::new(HashMap::new()),
+    };
+    Trace::new_with_handle(0, Some(timer), None::<()>)
+        .map_err(|err| log::debug!("Compute time of namespace {namespace} is not accounted: {err}"))
+        .ok()
+}
+
+impl Server {
+    /// Get the usage of the model `namespace`. Zero if no request was accounted to it.
+    pub fn namespace_usage<N: AsRef<str>>(&self, namespace: N) -> NamespaceUsage {
+        self.ptr
+            .accounting
+            .with_account(namespace.as_ref(), |account| account.usage)
+    }
+
+    /// Get the usage of all the namespaces requests were accounted to.
+    pub fn namespaces_usage(&self) -> HashMap<String, NamespaceUsage> {
+        self.ptr
+            .accounting
+            .accounts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(namespace, account)| (namespace.clone(), account.usage))
+            .collect()
+    }
+
+    /// Reset the usage of the model `namespace` to zero, e.g. on the start of the new billing period.
+    pub fn reset_namespace_usage<N: AsRef<str>>(&self, namespace: N) {
+        self.ptr
+            .accounting
+            .with_account(namespace.as_ref(), |account| {
+                account.usage = NamespaceUsage::default()
+            });
+    }
+
+    /// Set the quota of the model `namespace`. None removes the quota. \
+    /// Requests accounted to the namespace that exceeded the quota fail on [Request::infer_async](crate::Request::infer_async)
+    /// with [ErrorCode::Unavailable].
+    pub fn set_namespace_quota<N: AsRef<str>>(&self, namespace: N, quota: Option<NamespaceQuota>) {
+        self.ptr
+            .accounting
+            .with_account(namespace.as_ref(), |account| account.quota = quota);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quota() {
+        let quota = NamespaceQuota {
+            max_requests: Some(10),
+            max_bytes: Some(1000),
+            ..Default::default()
+        };
+        let mut usage = NamespaceUsage {
+            requests: 3,
+            input_bytes: 400,
+            output_bytes: 500,
+            ..Default::default()
+        };
+        assert_eq!(quota.exceeded_by(&usage), None);
+
+        usage.output_bytes = 600;
+        assert_eq!(quota.exceeded_by(&usage), Some("bytes"));
+        usage.requests = 10;
+        assert_eq!(quota.exceeded_by(&usage), Some("requests"));
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+
+use super::{no_tokio_runtime, Server};
+use crate::{
+    message::{VersionState, VersionStatus},
+    repository, runtime, Error,
+};
+
+/// Change of the model versions found by [Server::spawn_repository_watcher].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RepositoryEvent {
+    /// Version of the model became ready (loaded or reloaded).
+    VersionReady { model: String, version: i64 },
+    /// Version of the model is no longer ready (unloaded, removed from the repository or failed to load).
+    VersionUnavailable {
+        model: String,
+        version: i64,
+        /// Reason of the state reported by Triton.
+        reason: String,
+    },
+}
+
+impl Server {
+    /// Poll the model repositories every `interval` on the tokio runtime of the server. \
+    /// If the local repositories changed since the last check (remote ones are polled on each tick),
+    /// [Server::poll_model_repository] is called and the changes of the model versions are sent to the returned receiver.
+    /// Errors of the polling are logged and retried on the next tick. \
+    /// Requires [Control::Poll](crate::options::Control::Poll). The task stops when the server
+    /// or the receiver is dropped. Abort the returned handle to stop it earlier. \
+    /// Returns error with [ErrorCode::Unsupported](crate::ErrorCode::Unsupported) if there is no tokio runtime.
+    pub fn spawn_repository_watcher(
+        &self,
+        interval: Duration,
+    ) -> Result<(JoinHandle<()>, UnboundedReceiver<RepositoryEvent>), Error> {
+        let runtime = self.tokio_handle().ok_or_else(no_tokio_runtime)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let server = self.downgrade();
+        let dirs = self.local_repositories();
+
+        let handle = runtime.spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut fingerprint = None;
+            loop {
+                ticker.tick().await;
+                if tx.is_closed() {
+                    return;
+                }
+                let Some(shadow) = server.upgrade() else {
+                    return;
+                };
+                let dirs = dirs.clone();
+                let tx = tx.clone();
+                let polled = runtime::try_run_blocking(move || {
+                    let current = Some(dirs_fingerprint(&dirs));
+                    if !dirs.is_empty() && current == fingerprint {
+                        return Ok(fingerprint);
+                    }
+                    shadow.poll_changes(&tx).map(|()| current)
+                })
+                .await;
+                match polled {
+                    Ok(polled) => fingerprint = polled,
+                    Err(err) => log::warn!("Error polling model repository: {err}"),
+                }
+            }
+        });
+        Ok((handle, rx))
+    }
+
+    /// Poll the model repository and send the changes of the model versions to `tx`.
+    fn poll_changes(&self, tx: &UnboundedSender<RepositoryEvent>) -> Result<(), Error> {
+        let before = self.model_states()?;
+        self.poll_model_repository()?;
+        let after = self.model_states()?;
+        for event in version_events(&before, &after) {
+            log::info!("Model repository change: {event:?}");
+            let _ = tx.send(event);
+        }
+        Ok(())
+    }
+
+    /// Local model repositories of the server options and the ones registered with [Server::register_repository].
+    fn local_repositories(&self) -> Vec<PathBuf> {
+        self.settings()
+            .iter()
+            .filter(|(option, repository)| {
+                *option == "model_repository" && !repository::is_remote(repository)
+            })
+            .map(|(_, repository)| PathBuf::from(repository))
+            .chain(self.registered_repositories().into_keys())
+            .collect()
+    }
+}
+
+/// Hash of the paths, sizes and modification times of the files in `dirs`.
+fn dirs_fingerprint(dirs: &[PathBuf]) -> u64 {
+    fn visit(path: &Path, hasher: &mut DefaultHasher) {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        let mut entries = entries.flatten().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            entry.path().hash(hasher);
+            meta.len().hash(hasher);
+            meta.modified().ok().hash(hasher);
+            if meta.is_dir() {
+                visit(&entry.path(), hasher);
+            }
+        }
+    }
+
+    let mut hasher = DefaultHasher::new();
+    for dir in dirs {
+        visit(dir, &mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Changes of the ready versions between two [Server::model_states] results.
+fn version_events(before: &[VersionStatus], after: &[VersionStatus]) -> Vec<RepositoryEvent> {
+    let ready = |states: &[VersionStatus]| {
+        states
+            .iter()
+            .filter(|status| status.state == Some(VersionState::Ready))
+            .filter_map(|status| Some((status.name.clone(), status.version?)))
+            .collect::<HashSet<_>>()
+    };
+    let (was_ready, is_ready) = (ready(before), ready(after));
+
+    let mut appeared = is_ready.difference(&was_ready).collect::<Vec<_>>();
+    appeared.sort();
+    let mut disappeared = was_ready.difference(&is_ready).collect::<Vec<_>>();
+    disappeared.sort();
+
+    let ready_events = appeared
+        .into_iter()
+        .map(|(model, version)| RepositoryEvent::VersionReady {
+            model: model.clone(),
+            version: *version,
+        });
+    let unavailable_events = disappeared.into_iter().map(|(model, version)| {
+        let reason = after
+            .iter()
+            .find(|status| status.name == *model && status.version == Some(*version))
+            .map(|status| status.reason.clone())
+            .unwrap_or_else(|| "removed from the model repository".to_string());
+        RepositoryEvent::VersionUnavailable {
+            model: model.clone(),
+            version: *version,
+            reason,
+        }
+    });
+    ready_events.chain(unavailable_events).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(name: &str, version: i64, state: VersionState, reason: &str) -> VersionStatus {
+        VersionStatus {
+            name: name.to_string(),
+            version: Some(version),
+            state: Some(state),
+            reason: reason.to_string(),
+        }
+    }
+
+    #[test]
+    fn events() {
+        let before = [
+            status("detector", 1, VersionState::Ready, ""),
+            status("classifier", 1, VersionState::Ready, ""),
+            status("embedder", 3, VersionState::Ready, ""),
+        ];
+        let after = [
+            status("detector", 1, VersionState::Ready, ""),
+            status("detector", 2, VersionState::Ready, ""),
+            status("classifier", 1, VersionState::Unavailable, "unloaded"),
+        ];
+        assert_eq!(
+            version_events(&before, &after),
+            [
+                RepositoryEvent::VersionReady {
+                    model: "detector".to_string(),
+                    version: 2
+                },
+                RepositoryEvent::VersionUnavailable {
+                    model: "classifier".to_string(),
+                    version: 1,
+                    reason: "unloaded".to_string()
+                },
+                RepositoryEvent::VersionUnavailable {
+                    model: "embedder".to_string(),
+                    version: 3,
+                    reason: "removed from the model repository".to_string()
+                },
+            ]
+        );
+    }
+}
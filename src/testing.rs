@@ -0,0 +1,274 @@
+//! Configuration that makes crate-side behavior deterministic, to stabilize integration tests. \
+//! Check [set_deterministic] for more info.
+//!
+//! **Note**: maps of buffers returned by the crate ([crate::Response::return_buffers], [crate::request::InferenceError])
+//! are [HashMap]s, so collect them into [BTreeMap](std::collections::BTreeMap) to iterate in a stable order.
+
+use std::{
+    collections::BTreeSet,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    task::Waker,
+};
+
+#[cfg(doc)]
+use std::collections::HashMap;
+
+use crate::runtime::Runtime;
+
+/// Parts of the crate behavior that can be made deterministic.
+///
+/// **Warning**: with [Deterministic::ordered_responses] a [crate::request::ResponseFuture] that is kept
+/// but never awaited (or awaited after the later ones) blocks the delivery of every later response of the server forever.
+/// Await the futures in the order of the [crate::Request::infer_async] calls (or concurrently) or drop the unneeded ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Deterministic {
+    /// Run the Triton callbacks (output allocation and release) on one dedicated worker thread
+    /// instead of spawning a new thread for each callback.
+    pub single_callback_worker: bool,
+    /// [crate::request::ResponseFuture]s of the server resolve in the order of its [crate::Request::infer_async] calls.
+    /// The order is kept per server: responses of the different servers don't wait for each other. \
+    /// **Note**: the futures must be awaited in this order (or concurrently), otherwise awaiting the later one will never end.
+    pub ordered_responses: bool,
+    /// Buffers allocated by [crate::Buffer::alloc] (and so by the default allocators) of all memory types
+    /// are filled with the pseudo-random bytes generated from this seed. \
+    /// Each allocation is filled from the start of the sequence, so the uninitialized outputs
+    /// read the same values from run to run. None keeps the allocations as they are (CPU ones are zeroed).
+    pub allocation_seed: Option<u64>,
+}
+
+impl Deterministic {
+    /// Everything is deterministic. Allocations are seeded with 0.
+    pub fn all() -> Self {
+        Deterministic {
+            single_callback_worker: true,
+            ordered_responses: true,
+            allocation_seed: Some(0),
+        }
+    }
+}
+
+const SINGLE_CALLBACK_WORKER: u8 = 1;
+const ORDERED_RESPONSES: u8 = 1 << 1;
+const SEEDED_ALLOCATIONS: u8 = 1 << 2;
+
+static MODE: AtomicU8 = AtomicU8::new(0);
+static ALLOCATION_SEED: AtomicU64 = AtomicU64::new(0);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Turns of the responses delivery.
+#[derive(Debug, Default)]
+struct Turns {
+    /// Ticket of the response that should be delivered next.
+    next: u64,
+    /// Tickets that are delivered (or dropped) before their turn.
+    finished: BTreeSet<u64>,
+    wakers: Vec<Waker>,
+}
+
+/// Order of the responses delivery of one server.
+#[derive(Debug, Default)]
+pub(crate) struct Delivery {
+    next_ticket: AtomicU64,
+    turns: Mutex<Turns>,
+    condvar: Condvar,
+}
+
+lazy_static::lazy_static! {
+    static ref CALLBACK_WORKER: Mutex<mpsc::Sender<Job>> = {
+        let (tx, rx) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("triton-callbacks".to_string())
+            .spawn(move || {
+                while let Ok(job) = rx.recv() {
+                    job();
+                }
+            })
+            .expect("failed to spawn callback worker thread");
+        Mutex::new(tx)
+    };
+}
+
+/// Set the deterministic mode of the crate for all the servers of the process. Should be called before the servers are created. \
+/// By default nothing is deterministic. Check the warning of [Deterministic].
+pub fn set_deterministic(config: Deterministic) {
+    let mut mode = 0;
+    if config.single_callback_worker {
+        mode |= SINGLE_CALLBACK_WORKER;
+    }
+    if config.ordered_responses {
+        mode |= ORDERED_RESPONSES;
+    }
+    if let Some(seed) = config.allocation_seed {
+        ALLOCATION_SEED.store(seed, Ordering::SeqCst);
+        mode |= SEEDED_ALLOCATIONS;
+    }
+    MODE.store(mode, Ordering::SeqCst);
+}
+
+/// Get the current deterministic mode of the crate.
+pub fn deterministic() -> Deterministic {
+    let mode = MODE.load(Ordering::SeqCst);
+    Deterministic {
+        single_callback_worker: mode & SINGLE_CALLBACK_WORKER != 0,
+        ordered_responses: mode & ORDERED_RESPONSES != 0,
+        allocation_seed: (mode & SEEDED_ALLOCATIONS != 0)
+            .then(|| ALLOCATION_SEED.load(Ordering::SeqCst)),
+    }
+}
+
+/// Seed the allocations should be filled from, if any.
+pub(crate) fn allocation_seed() -> Option<u64> {
+    (MODE.load(Ordering::Relaxed) & SEEDED_ALLOCATIONS != 0)
+        .then(|| ALLOCATION_SEED.load(Ordering::Relaxed))
+}
+
+/// Fill `bytes` with the pseudo-random sequence (splitmix64) generated from the `seed`.
+pub(crate) fn fill_seeded(bytes: &mut [u8], seed: u64) {
+    let mut state = seed;
+    for chunk in bytes.chunks_mut(8) {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+    }
+}
+
+/// Run the future created by `task` to completion from the synchronous Triton callback.
+pub(crate) fn block_on_callback<F, Fut>(runtime: Runtime, task: F) -> Fut::Output
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future,
+    Fut::Output: Send + 'static,
+{
+    if MODE.load(Ordering::Relaxed) & SINGLE_CALLBACK_WORKER == 0 {
+        return std::thread::spawn(move || runtime.block_on(task()))
+            .join()
+            .unwrap();
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let job: Job = Box::new(move || {
+        let _ = tx.send(runtime.block_on(task()));
+    });
+    CALLBACK_WORKER
+        .lock()
+        .expect("callback worker lock is poisoned")
+        .send(job)
+        .expect("callback worker is stopped");
+    rx.recv().expect("callback worker dropped the job")
+}
+
+/// Turn of the response in the ordered delivery. Passes the turn to the next response on drop.
+pub(crate) struct DeliveryTicket {
+    delivery: Arc<Delivery>,
+    ticket: u64,
+}
+
+/// Get the ticket of the new response in the `delivery` of its server, if responses should be ordered.
+pub(crate) fn response_ticket(delivery: &Arc<Delivery>) -> Option<DeliveryTicket> {
+    (MODE.load(Ordering::SeqCst) & ORDERED_RESPONSES != 0).then(|| DeliveryTicket {
+        delivery: delivery.clone(),
+        ticket: delivery.next_ticket.fetch_add(1, Ordering::SeqCst),
+    })
+}
+
+impl Delivery {
+    fn turns(&self) -> std::sync::MutexGuard<'_, Turns> {
+        self.turns.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl DeliveryTicket {
+    /// Check if it's the turn of the response to be delivered.
+    /// If not, `waker` will be woken when the turn changes.
+    pub(crate) fn is_turn(&self, waker: &Waker) -> bool {
+        let mut turns = self.delivery.turns();
+        if turns.next == self.ticket {
+            return true;
+        }
+        turns.wakers.push(waker.clone());
+        false
+    }
+
+    /// Block the thread until it's the turn of the response to be delivered.
+    pub(crate) fn wait_turn(&self) {
+        let mut turns = self.delivery.turns();
+        while turns.next != self.ticket {
+            turns = self
+                .delivery
+                .condvar
+                .wait(turns)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+impl Drop for DeliveryTicket {
+    fn drop(&mut self) {
+        let mut turns = self.delivery.turns();
+        turns.finished.insert(self.ticket);
+        loop {
+            let next = turns.next;
+            if !turns.finished.remove(&next) {
+                break;
+            }
+            turns.next += 1;
+        }
+        let wakers = std::mem::take(&mut turns.wakers);
+        drop(turns);
+
+        self.delivery.condvar.notify_all();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(delivery: &Arc<Delivery>) -> DeliveryTicket {
+        DeliveryTicket {
+            delivery: delivery.clone(),
+            ticket: delivery.next_ticket.fetch_add(1, Ordering::SeqCst),
+        }
+    }
+
+    #[test]
+    fn ordered_delivery() {
+        let waker = futures::task::noop_waker();
+        let delivery = Arc::new(Delivery::default());
+        let (first, second, third) = (ticket(&delivery), ticket(&delivery), ticket(&delivery));
+
+        // Other server's responses don't wait for the first one.
+        let other = Arc::new(Delivery::default());
+        assert!(ticket(&other).is_turn(&waker));
+
+        assert!(first.is_turn(&waker));
+        assert!(!second.is_turn(&waker));
+        // Dropped out of turn: the turn of the third is after the second.
+        drop(third);
+        drop(first);
+        assert!(second.is_turn(&waker));
+        drop(second);
+        assert_eq!(delivery.turns().next, 3);
+    }
+
+    #[test]
+    fn seeded_fill() {
+        let (mut first, mut second) = ([0u8; 13], [0u8; 13]);
+        fill_seeded(&mut first, 42);
+        fill_seeded(&mut second, 42);
+        assert_eq!(first, second);
+        assert_ne!(first, [0; 13]);
+
+        fill_seeded(&mut second, 43);
+        assert_ne!(first, second);
+    }
+}
@@ -48,6 +48,29 @@
 //!    ).unwrap());
 //! }
 //! ```
+//!
+//! With the "tracing" feature enabled, `TracingSubscriberHandler` reports the activities as `tracing` spans. \
+//! With the "opentelemetry" feature enabled, `otel` module propagates the W3C trace context to the traces. \
+//! [FileTraceCollector] writes the traces in the Triton trace file format,
+//! [ChromeTraceExporter] converts them to the Chrome trace events. \
+//! [ClockSync] maps the Triton steady clock timestamps to the wall clock time. \
+//! [TraceSampler] traces a fraction of the requests of the server.
+
+mod chrome;
+mod clock;
+mod file;
+#[cfg(feature = "opentelemetry")]
+pub mod otel;
+mod sampler;
+#[cfg(feature = "tracing")]
+mod subscriber;
+
+pub use chrome::ChromeTraceExporter;
+pub use clock::{steady_now, ClockSync};
+pub use file::FileTraceCollector;
+pub use sampler::TraceSampler;
+#[cfg(feature = "tracing")]
+pub use subscriber::TracingSubscriberHandler;
 
 use core::slice;
 use std::{
@@ -60,6 +83,7 @@ use std::{
 };
 
 use crate::{
+    compat,
     error::{Error, CSTR_CONVERT_ERROR_PLUG},
     from_char_array,
     message::Shape,
@@ -276,6 +300,17 @@ impl Trace {
         Ok(trace)
     }
 
+    /// Create the trace reporting the activities (and the tensors, if `tensors` is true) as [tracing] spans
+    /// nested in the current span. Check [TracingSubscriberHandler] for more info.
+    ///
+    /// `parent_id`: The parent trace id for this trace.
+    /// A value of 0 indicates that there is not parent trace.
+    #[cfg(feature = "tracing")]
+    pub fn new_with_tracing(parent_id: u64, tensors: bool) -> Result<Self, Error> {
+        let handler = TracingSubscriberHandler::new();
+        Trace::new_with_handle(parent_id, Some(handler.clone()), tensors.then_some(handler))
+    }
+
     /// Report a trace activity. All the traces reported using this API will be send [Activity::CustomActivity] type.
     ///
     /// `timestamp` The timestamp associated with the trace activity. \
@@ -285,6 +320,7 @@ impl Trace {
         timestamp: Duration,
         activity_name: N,
     ) -> Result<(), Error> {
+        compat::require(c"TRITONSERVER_InferenceTraceReportActivity")?;
         let name = to_cstring(activity_name)?;
         triton_call!(sys::TRITONSERVER_InferenceTraceReportActivity(
             self.ptr.0,
@@ -293,6 +329,13 @@ impl Trace {
         ))
     }
 
+    /// Report a trace activity with the current time of the steady clock (check [steady_now]).
+    ///
+    /// `name` The trace activity name.
+    pub fn report_activity_now<N: AsRef<str>>(&self, activity_name: N) -> Result<(), Error> {
+        self.report_activity(steady_now(), activity_name)
+    }
+
     /// Get the id associated with the trace.
     /// Every trace is assigned an id that is unique across all traces created for a Triton server.
     pub fn id(&self) -> Result<u64, Error> {
@@ -360,6 +403,7 @@ impl Trace {
 
     /// Set context to Triton Trace.
     pub fn set_context(&mut self, context: String) -> Result<&mut Self, Error> {
+        compat::require(c"TRITONSERVER_InferenceTraceSetContext")?;
         let context = to_cstring(context)?;
         triton_call!(
             sys::TRITONSERVER_InferenceTraceSetContext(self.ptr.0, context.as_ptr()),
@@ -369,6 +413,7 @@ impl Trace {
 
     /// Get Triton Trace context.
     pub fn context(&self) -> Result<String, Error> {
+        compat::require(c"TRITONSERVER_InferenceTraceContext")?;
         let mut context = null::<c_char>();
         triton_call!(
             sys::TRITONSERVER_InferenceTraceContext(self.ptr.0, &mut context as *mut _),
@@ -433,7 +478,7 @@ unsafe extern "C" fn tensor_activity_wrapper<H: TraceHandler, T: TensorTraceHand
     shape: *const i64,
     dim_count: u64,
     memory_type: sys::TRITONSERVER_MemoryType,
-    _memory_type_id: i64,
+    memory_type_id: i64,
     userp: *mut ::std::os::raw::c_void,
 ) {
     if !userp.is_null() {
@@ -459,7 +504,9 @@ unsafe extern "C" fn tensor_activity_wrapper<H: TraceHandler, T: TensorTraceHand
             len: byte_size,
             data_type,
             memory_type,
+            device_id: memory_type_id as i32,
             owned: false,
+            deleter: None,
         };
 
         if let Some(tensor_activity_handler) = handle.tensor_activity_handler.as_ref() {
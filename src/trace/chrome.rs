@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use super::{
+    file::{record_activity, TraceRecord, TraceRecords},
+    Activity, Trace, TraceHandler,
+};
+use crate::error::{Error, ErrorCode};
+
+/// Phases of the request shown as the slices: name, start and end activities.
+const PHASES: [(&str, Activity, Activity); 6] = [
+    ("request", Activity::RequestStart, Activity::RequestEnd),
+    ("queue", Activity::QueueStart, Activity::ComputeStart),
+    ("compute", Activity::ComputeStart, Activity::ComputeEnd),
+    (
+        "compute_input",
+        Activity::ComputeStart,
+        Activity::ComputeInputEnd,
+    ),
+    (
+        "compute_infer",
+        Activity::ComputeInputEnd,
+        Activity::ComputeOutputStart,
+    ),
+    (
+        "compute_output",
+        Activity::ComputeOutputStart,
+        Activity::ComputeEnd,
+    ),
+];
+
+fn micros(time: Duration) -> f64 {
+    time.as_nanos() as f64 / 1000.
+}
+
+/// Chrome trace events of the `records`: each model version is a process, each trace is a thread of it.
+fn chrome_events(records: &TraceRecords) -> Value {
+    let mut processes = HashMap::<(&str, i64), usize>::new();
+    let mut events = Vec::new();
+    for (&id, record) in records {
+        let next_pid = processes.len() + 1;
+        let pid = *processes
+            .entry((&record.model_name, record.model_version))
+            .or_insert_with(|| {
+                events.push(json!({
+                    "name": "process_name",
+                    "ph": "M",
+                    "pid": next_pid,
+                    "args": {"name": format!("{} (version {})", record.model_name, record.model_version)},
+                }));
+                next_pid
+            });
+        events.push(json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": pid,
+            "tid": id,
+            "args": {"name": format!("trace {id} {}", record.request_id)},
+        }));
+
+        let time = |activity: Activity| {
+            record
+                .timestamps
+                .iter()
+                .find(|(recorded, _)| *recorded == activity)
+                .map(|(_, time)| *time)
+        };
+        for (name, start, end) in PHASES {
+            if let (Some(start), Some(end)) = (time(start), time(end)) {
+                events.push(json!({
+                    "name": name,
+                    "cat": "triton",
+                    "ph": "X",
+                    "ts": micros(start),
+                    "dur": micros(end.saturating_sub(start)),
+                    "pid": pid,
+                    "tid": id,
+                    "args": {"request_id": record.request_id, "parent_id": record.parent_id},
+                }));
+            }
+        }
+        for (_, time) in record
+            .timestamps
+            .iter()
+            .filter(|(activity, _)| *activity == Activity::CustomActivity)
+        {
+            events.push(json!({
+                "name": "custom",
+                "cat": "triton",
+                "ph": "i",
+                "s": "t",
+                "ts": micros(*time),
+                "pid": pid,
+                "tid": id,
+            }));
+        }
+    }
+    json!({"traceEvents": events, "displayTimeUnit": "ns"})
+}
+
+/// [TraceHandler] converting the activities of the traced requests to the Chrome trace events JSON,
+/// so the timelines can be inspected in `chrome://tracing` or Perfetto UI
+/// (e.g. the requests waiting in the queue while the GPU is idle).
+///
+/// Each model version is shown as a process, each trace as a thread of it
+/// with `request`, `queue`, `compute` and `compute_input`/`compute_infer`/`compute_output` slices. \
+/// Timestamps are the Triton steady clock ones.
+///
+/// ```ignore
+/// let exporter = ChromeTraceExporter::new();
+/// request.add_trace(Trace::new_with_handle(0, Some(exporter.clone()), NOOP)?);
+/// // ...
+/// exporter.save("/tmp/chrome_trace.json")?;
+/// ```
+#[derive(Clone, Default)]
+pub struct ChromeTraceExporter {
+    records: Arc<Mutex<TraceRecords>>,
+}
+
+impl ChromeTraceExporter {
+    /// Create the exporter without traces.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TraceRecords> {
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the Chrome trace events of the collected traces.
+    pub fn to_json(&self) -> Value {
+        chrome_events(&self.lock())
+    }
+
+    /// Write the Chrome trace events of the collected traces to `writer`.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), Error> {
+        serde_json::to_writer(writer, &self.to_json())
+            .map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))
+    }
+
+    /// Write the Chrome trace events of the collected traces to the file `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let io_error = |err: std::io::Error| {
+            Error::new(
+                ErrorCode::Internal,
+                format!("Can't write traces to {:?}: {err}", path.as_ref()),
+            )
+        };
+        let mut writer = BufWriter::new(File::create(path.as_ref()).map_err(io_error)?);
+        self.write_to(&mut writer)?;
+        writer.flush().map_err(io_error)
+    }
+
+    /// Remove the collected traces.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+impl TraceHandler for ChromeTraceExporter {
+    fn trace_activity(&self, trace: &Trace, event: Activity, event_time: Duration) {
+        let id = match trace.id() {
+            Ok(id) => id,
+            Err(err) => {
+                log::warn!("Error getting ID of the trace: {err}");
+                return;
+            }
+        };
+        record_activity(
+            &mut self.lock(),
+            id,
+            || TraceRecord::of(trace),
+            event,
+            event_time,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let mut records = TraceRecords::new();
+        let record = || TraceRecord {
+            model_name: "simple".to_string(),
+            model_version: 1,
+            ..Default::default()
+        };
+        for (activity, us) in [
+            (Activity::RequestStart, 10),
+            (Activity::QueueStart, 12),
+            (Activity::ComputeStart, 20),
+            (Activity::ComputeEnd, 50),
+            (Activity::RequestEnd, 55),
+        ] {
+            record_activity(&mut records, 3, record, activity, Duration::from_micros(us));
+        }
+
+        let json = chrome_events(&records);
+        let events = json["traceEvents"].as_array().unwrap();
+        assert_eq!(events[0]["args"]["name"], "simple (version 1)");
+        let slices: Vec<_> = events
+            .iter()
+            .filter(|event| event["ph"] == "X")
+            .map(|event| {
+                (
+                    event["name"].as_str().unwrap(),
+                    event["dur"].as_f64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(slices, [("request", 45.), ("queue", 8.), ("compute", 30.)]);
+        assert!(events.iter().all(|event| event["pid"] == 1));
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::{Duration, SystemTime};
+
+/// Current time of the steady clock Triton reports the trace timestamps with (`CLOCK_MONOTONIC`).
+pub fn steady_now() -> Duration {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    Duration::new(now.tv_sec as u64, now.tv_nsec as u32)
+}
+
+/// Mapping between the Triton steady clock and the wall clock,
+/// so the trace timestamps can be correlated with the application events.
+///
+/// Both clocks are sampled at creation, the mapping stays valid until the wall clock is adjusted (e.g. by NTP),
+/// so resample it periodically for long-running processes.
+/// ```ignore
+/// let clock = ClockSync::now();
+/// let wall_time = clock.to_system_time(event_time);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClockSync {
+    steady: Duration,
+    wall: SystemTime,
+}
+
+impl ClockSync {
+    /// Sample both clocks. The steady time is taken in the middle of the wall clock reading to reduce the skew.
+    pub fn now() -> Self {
+        let before = steady_now();
+        let wall = SystemTime::now();
+        let after = steady_now();
+        ClockSync {
+            steady: before + (after.saturating_sub(before)) / 2,
+            wall,
+        }
+    }
+
+    /// Convert the steady clock timestamp (e.g. `event_time` of [super::TraceHandler::trace_activity]) to the wall clock time.
+    pub fn to_system_time(&self, steady: Duration) -> SystemTime {
+        if steady >= self.steady {
+            self.wall + (steady - self.steady)
+        } else {
+            self.wall - (self.steady - steady)
+        }
+    }
+
+    /// Convert the wall clock time to the steady clock timestamp, e.g. to report the application event
+    /// with [super::Trace::report_activity]. Times before the steady clock origin are clamped to zero.
+    pub fn to_steady(&self, time: SystemTime) -> Duration {
+        match time.duration_since(self.wall) {
+            Ok(after) => self.steady + after,
+            Err(err) => self.steady.saturating_sub(err.duration()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion() {
+        let wall = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = ClockSync {
+            steady: Duration::from_secs(100),
+            wall,
+        };
+        assert_eq!(
+            clock.to_system_time(Duration::from_millis(100_500)),
+            wall + Duration::from_millis(500)
+        );
+        assert_eq!(
+            clock.to_system_time(Duration::from_secs(90)),
+            wall - Duration::from_secs(10)
+        );
+        assert_eq!(
+            clock.to_steady(wall + Duration::from_secs(1)),
+            Duration::from_secs(101)
+        );
+        assert_eq!(
+            clock.to_steady(wall - Duration::from_secs(1000)),
+            Duration::ZERO
+        );
+
+        let now = ClockSync::now();
+        assert!(now.to_steady(SystemTime::now()) >= now.steady);
+    }
+}
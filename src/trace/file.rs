@@ -0,0 +1,295 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use super::{Activity, Trace, TraceHandler};
+use crate::error::{Error, ErrorCode};
+
+/// Name of the activity in the Triton trace file, e.g. `COMPUTE_START`.
+pub(super) fn activity_name(activity: Activity) -> &'static str {
+    match activity {
+        Activity::RequestStart => "REQUEST_START",
+        Activity::QueueStart => "QUEUE_START",
+        Activity::ComputeStart => "COMPUTE_START",
+        Activity::ComputeInputEnd => "COMPUTE_INPUT_END",
+        Activity::ComputeOutputStart => "COMPUTE_OUTPUT_START",
+        Activity::ComputeEnd => "COMPUTE_END",
+        Activity::RequestEnd => "REQUEST_END",
+        Activity::TensorQueueInput => "TENSOR_QUEUE_INPUT",
+        Activity::TensorBackendInput => "TENSOR_BACKEND_INPUT",
+        Activity::TensorBackendOutput => "TENSOR_BACKEND_OUTPUT",
+        Activity::CustomActivity => "CUSTOM_ACTIVITY",
+    }
+}
+
+/// Activities of one trace.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct TraceRecord {
+    pub(super) model_name: String,
+    pub(super) model_version: i64,
+    pub(super) request_id: String,
+    pub(super) parent_id: u64,
+    /// Activities with the steady clock timestamps, in the order of reporting.
+    pub(super) timestamps: Vec<(Activity, Duration)>,
+}
+
+impl TraceRecord {
+    /// Record of the `trace` without the activities.
+    pub(super) fn of(trace: &Trace) -> Self {
+        TraceRecord {
+            model_name: trace.model_name().unwrap_or_default(),
+            model_version: trace.model_version().unwrap_or(-1),
+            request_id: trace.request_id().unwrap_or_default(),
+            parent_id: trace.parent_id().unwrap_or_default(),
+            timestamps: Vec::new(),
+        }
+    }
+
+    pub(super) fn is_finished(&self) -> bool {
+        self.timestamps
+            .iter()
+            .any(|(activity, _)| *activity == Activity::RequestEnd)
+    }
+}
+
+/// Traces by their ids.
+pub(super) type TraceRecords = BTreeMap<u64, TraceRecord>;
+
+/// Add the activity of the trace `id`, the record is created with `record` on the first activity.
+pub(super) fn record_activity<F: FnOnce() -> TraceRecord>(
+    records: &mut TraceRecords,
+    id: u64,
+    record: F,
+    event: Activity,
+    event_time: Duration,
+) {
+    records
+        .entry(id)
+        .or_insert_with(record)
+        .timestamps
+        .push((event, event_time));
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Entry<'a> {
+    Meta {
+        id: u64,
+        model_name: &'a str,
+        model_version: i64,
+        request_id: &'a str,
+        parent_id: u64,
+    },
+    Timestamps {
+        id: u64,
+        timestamps: Vec<Timestamp>,
+    },
+}
+
+#[derive(Serialize)]
+struct Timestamp {
+    name: &'static str,
+    ns: u64,
+}
+
+/// Entries of the Triton trace file of the `records`.
+fn entries(records: &TraceRecords) -> Vec<Entry<'_>> {
+    let mut entries = Vec::with_capacity(records.len() * 2);
+    for (&id, record) in records {
+        entries.push(Entry::Meta {
+            id,
+            model_name: &record.model_name,
+            model_version: record.model_version,
+            request_id: &record.request_id,
+            parent_id: record.parent_id,
+        });
+        entries.push(Entry::Timestamps {
+            id,
+            timestamps: record
+                .timestamps
+                .iter()
+                .map(|(activity, time)| Timestamp {
+                    name: activity_name(*activity),
+                    ns: time.as_nanos() as u64,
+                })
+                .collect(),
+        });
+    }
+    entries
+}
+
+struct Collector {
+    path: Option<PathBuf>,
+    records: TraceRecords,
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = write_file(path, &self.records) {
+                log::warn!("Can't write traces to {path:?}: {err}");
+            }
+        }
+    }
+}
+
+fn write_file(path: &Path, records: &TraceRecords) -> Result<(), Error> {
+    let io_error = |err: std::io::Error| {
+        Error::new(
+            ErrorCode::Internal,
+            format!("Can't write traces to {path:?}: {err}"),
+        )
+    };
+    let mut writer = BufWriter::new(File::create(path).map_err(io_error)?);
+    serde_json::to_writer(&mut writer, &entries(records))
+        .map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))?;
+    writer.flush().map_err(io_error)
+}
+
+/// [TraceHandler] collecting the timestamps of the traced requests into the trace file of the Triton format
+/// (the one written by `tritonserver --trace-file` in `triton` trace mode),
+/// so the Triton tools (e.g. `trace_summary.py`) can analyze the traces collected with the crate.
+///
+/// Each trace is written as two entries: `{"id", "model_name", "model_version", "request_id", "parent_id"}`
+/// and `{"id", "timestamps": [{"name", "ns"}]}`. \
+/// The file is rewritten by [FileTraceCollector::flush] and when the last clone of the collector
+/// (including the ones owned by the traces) is dropped.
+///
+/// ```ignore
+/// let collector = FileTraceCollector::new("/tmp/trace.json");
+/// request.add_trace(Trace::new_with_handle(0, Some(collector.clone()), NOOP)?);
+/// // ...
+/// collector.flush()?;
+/// ```
+#[derive(Clone)]
+pub struct FileTraceCollector {
+    collector: Arc<Mutex<Collector>>,
+}
+
+impl FileTraceCollector {
+    /// Create the collector writing the traces to the file `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self::with_path(Some(path.as_ref().to_path_buf()))
+    }
+
+    /// Create the collector keeping the traces in memory. Check [FileTraceCollector::write_to].
+    pub fn in_memory() -> Self {
+        Self::with_path(None)
+    }
+
+    fn with_path(path: Option<PathBuf>) -> Self {
+        FileTraceCollector {
+            collector: Arc::new(Mutex::new(Collector {
+                path,
+                records: TraceRecords::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Collector> {
+        self.collector
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Number of the collected traces.
+    pub fn len(&self) -> usize {
+        self.lock().records.len()
+    }
+
+    /// Check if no traces are collected.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the collected traces as Triton trace JSON to `writer`.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), Error> {
+        serde_json::to_writer(writer, &entries(&self.lock().records))
+            .map_err(|err| Error::new(ErrorCode::Internal, err.to_string()))
+    }
+
+    /// Rewrite the trace file with the collected traces. Does nothing for [FileTraceCollector::in_memory].
+    pub fn flush(&self) -> Result<(), Error> {
+        let collector = self.lock();
+        match &collector.path {
+            Some(path) => write_file(path, &collector.records),
+            None => Ok(()),
+        }
+    }
+
+    /// Remove the finished traces (the ones that reported [Activity::RequestEnd]), e.g. after [FileTraceCollector::flush]
+    /// to start the new file.
+    pub fn clear_finished(&self) {
+        self.lock()
+            .records
+            .retain(|_, record| !record.is_finished());
+    }
+}
+
+impl TraceHandler for FileTraceCollector {
+    fn trace_activity(&self, trace: &Trace, event: Activity, event_time: Duration) {
+        let id = match trace.id() {
+            Ok(id) => id,
+            Err(err) => {
+                log::warn!("Error getting ID of the trace: {err}");
+                return;
+            }
+        };
+        record_activity(
+            &mut self.lock().records,
+            id,
+            || TraceRecord::of(trace),
+            event,
+            event_time,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triton_format() {
+        let mut records = TraceRecords::new();
+        let record = || TraceRecord {
+            model_name: "simple".to_string(),
+            model_version: 1,
+            ..Default::default()
+        };
+        record_activity(
+            &mut records,
+            7,
+            record,
+            Activity::RequestStart,
+            Duration::from_nanos(100),
+        );
+        record_activity(
+            &mut records,
+            7,
+            record,
+            Activity::RequestEnd,
+            Duration::from_nanos(250),
+        );
+        assert!(records[&7].is_finished());
+
+        let json = serde_json::to_value(entries(&records)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"id": 7, "model_name": "simple", "model_version": 1, "request_id": "", "parent_id": 0},
+                {"id": 7, "timestamps": [
+                    {"name": "REQUEST_START", "ns": 100},
+                    {"name": "REQUEST_END", "ns": 250},
+                ]},
+            ])
+        );
+    }
+}
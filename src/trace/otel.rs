@@ -0,0 +1,195 @@
+//! Propagation of the OpenTelemetry trace context through the Triton traces.
+//!
+//! The W3C `traceparent` (and `tracestate`) of the incoming HTTP/gRPC request is passed to Triton
+//! as the trace context (check [Trace::set_context]), so the models and the backends can continue the distributed trace.
+//! The activities reported by Triton are attached to the OpenTelemetry span as the events.
+//!
+//! ```ignore
+//! // Continue the trace of the incoming request.
+//! let cx = otel::context_from_headers(headers.iter().map(|(k, v)| (k.as_str(), v.to_str().unwrap())));
+//! let span = tracer.start_with_context("inference", &cx);
+//! let cx = cx.with_span(span);
+//!
+//! request.add_trace(otel::new_trace(&cx, 0)?);
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+use opentelemetry::{
+    trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId},
+    Context, KeyValue,
+};
+
+use super::{Activity, ClockSync, Trace, TraceHandler, NOOP};
+use crate::error::Error;
+
+/// Name of the W3C trace parent header.
+pub const TRACEPARENT: &str = "traceparent";
+/// Name of the W3C trace state header.
+pub const TRACESTATE: &str = "tracestate";
+
+/// Format the `span_context` as W3C `traceparent` header value. Returns None if the span context is invalid.
+pub fn traceparent(span_context: &SpanContext) -> Option<String> {
+    span_context.is_valid().then(|| {
+        format!(
+            "00-{:032x}-{:016x}-{:02x}",
+            span_context.trace_id(),
+            span_context.span_id(),
+            span_context.trace_flags()
+        )
+    })
+}
+
+/// Parse the W3C `traceparent` header value (and the optional `tracestate` one) to the remote span context.
+/// Returns None if the header is malformed.
+pub fn parse_traceparent(traceparent: &str, tracestate: Option<&str>) -> Option<SpanContext> {
+    let parts: Vec<_> = traceparent.trim().split('-').collect();
+    let [version, trace_id, span_id, flags] = parts[..] else {
+        return None;
+    };
+    if version.len() != 2 || version == "ff" || trace_id.len() != 32 || span_id.len() != 16 {
+        return None;
+    }
+    let span_context = SpanContext::new(
+        TraceId::from_hex(trace_id).ok()?,
+        SpanId::from_hex(span_id).ok()?,
+        TraceFlags::new(u8::from_str_radix(flags, 16).ok()?),
+        true,
+        tracestate
+            .and_then(|state| state.parse().ok())
+            .unwrap_or_default(),
+    );
+    span_context.is_valid().then_some(span_context)
+}
+
+/// Build the context of the remote span from the headers of the incoming request (header names are case-insensitive). \
+/// Returns the empty context if there is no valid `traceparent` header.
+pub fn context_from_headers<'a, I>(headers: I) -> Context
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let mut traceparent = None;
+    let mut tracestate = None;
+    for (name, value) in headers {
+        if name.eq_ignore_ascii_case(TRACEPARENT) {
+            traceparent = Some(value);
+        } else if name.eq_ignore_ascii_case(TRACESTATE) {
+            tracestate = Some(value);
+        }
+    }
+    traceparent
+        .and_then(|traceparent| parse_traceparent(traceparent, tracestate))
+        .map_or_else(Context::new, |span_context| {
+            Context::new().with_remote_span_context(span_context)
+        })
+}
+
+/// Headers (`traceparent` and `tracestate`) carrying the span context of `cx` to the outgoing request.
+/// Empty if `cx` has no valid span.
+pub fn headers_from_context(cx: &Context) -> HashMap<String, String> {
+    let span = cx.span();
+    let span_context = span.span_context();
+    let mut headers = HashMap::new();
+    if let Some(traceparent) = traceparent(span_context) {
+        headers.insert(TRACEPARENT.to_string(), traceparent);
+        let tracestate = span_context.trace_state().header();
+        if !tracestate.is_empty() {
+            headers.insert(TRACESTATE.to_string(), tracestate);
+        }
+    }
+    headers
+}
+
+/// Triton trace context of `cx`: JSON object of the W3C headers, as Triton OpenTelemetry tracing expects it.
+pub fn context_to_json(cx: &Context) -> String {
+    serde_json::to_string(&headers_from_context(cx)).unwrap_or_default()
+}
+
+/// Context of the remote span from the Triton trace `context` (check [context_to_json]).
+/// Returns the empty context if the `context` has no valid `traceparent`.
+pub fn context_from_json(context: &str) -> Context {
+    let headers: HashMap<String, String> = serde_json::from_str(context).unwrap_or_default();
+    context_from_headers(headers.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+}
+
+/// Create the trace of the `cx` span: the trace context is set to the W3C headers of the span,
+/// the activities are added as the events to the span (check [OtelEventHandler]). \
+/// `parent_id`: The parent trace id for this trace.
+/// A value of 0 indicates that there is not parent trace.
+pub fn new_trace(cx: &Context, parent_id: u64) -> Result<Trace, Error> {
+    let mut trace =
+        Trace::new_with_handle(parent_id, Some(OtelEventHandler::new(cx.clone())), NOOP)?;
+    trace.set_context(context_to_json(cx))?;
+    Ok(trace)
+}
+
+/// Context of the span the trace was created for (by [new_trace] or with [Trace::set_context]).
+pub fn trace_context(trace: &Trace) -> Result<Context, Error> {
+    trace.context().map(|context| context_from_json(&context))
+}
+
+/// [TraceHandler] adding the reported activities as the events (named after the [Activity])
+/// with the wall clock timestamps to the span of the context.
+/// The events have `triton.model`, `triton.model_version` and `triton.trace_id` attributes.
+pub struct OtelEventHandler {
+    context: Context,
+}
+
+impl OtelEventHandler {
+    /// Create the handler adding the events to the span of `context`.
+    pub fn new(context: Context) -> Self {
+        OtelEventHandler { context }
+    }
+}
+
+impl TraceHandler for OtelEventHandler {
+    fn trace_activity(&self, trace: &Trace, event: Activity, event_time: Duration) {
+        let mut attributes = Vec::with_capacity(3);
+        if let Ok(model) = trace.model_name() {
+            attributes.push(KeyValue::new("triton.model", model));
+        }
+        if let Ok(version) = trace.model_version() {
+            attributes.push(KeyValue::new("triton.model_version", version));
+        }
+        if let Ok(id) = trace.id() {
+            attributes.push(KeyValue::new("triton.trace_id", id as i64));
+        }
+        self.context.span().add_event_with_timestamp(
+            format!("{event:?}"),
+            ClockSync::now().to_system_time(event_time),
+            attributes,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn propagation() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let cx = context_from_headers([("TraceParent", header), ("tracestate", "vendor=value")]);
+        assert!(cx.has_active_span());
+        assert!(cx.span().span_context().is_sampled());
+
+        let headers = headers_from_context(&cx);
+        assert_eq!(headers[TRACEPARENT], header);
+        assert_eq!(headers[TRACESTATE], "vendor=value");
+
+        let json = context_to_json(&cx);
+        let back = context_from_json(&json);
+        assert_eq!(back.span().span_context(), cx.span().span_context());
+
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7", None)
+                .is_none()
+        );
+        assert!(parse_traceparent(
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            None
+        )
+        .is_none());
+        assert!(!context_from_json("").has_active_span());
+    }
+}
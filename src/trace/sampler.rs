@@ -0,0 +1,156 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use super::{Trace, TraceHandler, NOOP};
+use crate::error::Error;
+
+type TraceFactory = Arc<dyn Fn(&str) -> Result<Trace, Error> + Send + Sync>;
+
+/// Server-wide sampling of the traced requests, the crate counterpart of the Triton `--trace-config` options
+/// (`rate`, `count`, the level is chosen by the created traces). \
+/// Attached with [crate::Server::set_trace_sampler], it adds the trace to each `rate`-th request
+/// that has no trace added with [crate::Request::add_trace].
+///
+/// ```ignore
+/// let collector = FileTraceCollector::new("/tmp/trace.json");
+/// server.set_trace_sampler(Some(TraceSampler::with_handler(collector.clone()).rate(100).count(1000)));
+/// ```
+pub struct TraceSampler {
+    rate: u64,
+    count: Option<u64>,
+    models: Option<Vec<String>>,
+    factory: TraceFactory,
+    seen: AtomicU64,
+    sampled: AtomicU64,
+}
+
+impl fmt::Debug for TraceSampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceSampler")
+            .field("rate", &self.rate)
+            .field("count", &self.count)
+            .field("models", &self.models)
+            .field("seen", &self.seen)
+            .field("sampled", &self.sampled)
+            .finish()
+    }
+}
+
+impl TraceSampler {
+    /// Create the sampler tracing every request with the traces created by `factory`. \
+    /// `factory` gets the model name of the request.
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn(&str) -> Result<Trace, Error> + Send + Sync + 'static,
+    {
+        TraceSampler {
+            rate: 1,
+            count: None,
+            models: None,
+            factory: Arc::new(factory),
+            seen: AtomicU64::new(0),
+            sampled: AtomicU64::new(0),
+        }
+    }
+
+    /// Create the sampler reporting the activities (timestamps) of the sampled requests to the `handler`
+    /// (e.g. [super::FileTraceCollector]).
+    pub fn with_handler<H: TraceHandler + Clone>(handler: H) -> Self {
+        Self::new(move |_| Trace::new_with_handle(0, Some(handler.clone()), NOOP))
+    }
+
+    /// Trace one of each `rate` requests. Default is 1 (every request).
+    pub fn rate(mut self, rate: u64) -> Self {
+        self.rate = rate.max(1);
+        self
+    }
+
+    /// Stop tracing after `count` traced requests. Default is unlimited.
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Trace only the requests of the `models`. Default is all the models.
+    pub fn models<I, M>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = M>,
+        M: AsRef<str>,
+    {
+        self.models = Some(
+            models
+                .into_iter()
+                .map(|model| model.as_ref().to_string())
+                .collect(),
+        );
+        self
+    }
+
+    /// Number of the traced requests.
+    pub fn sampled(&self) -> u64 {
+        self.sampled.load(Ordering::Relaxed)
+    }
+
+    /// Check if the request to the `model` should be traced and count it.
+    fn should_sample(&self, model: &str) -> bool {
+        if let Some(models) = &self.models {
+            if !models.iter().any(|traced| traced == model) {
+                return false;
+            }
+        }
+        if !self
+            .seen
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.rate)
+        {
+            return false;
+        }
+        match self.count {
+            Some(count) => self
+                .sampled
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sampled| {
+                    (sampled < count).then_some(sampled + 1)
+                })
+                .is_ok(),
+            None => {
+                self.sampled.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+        }
+    }
+
+    /// Trace of the request to the `model`, if it's sampled.
+    pub(crate) fn sample(&self, model: &str) -> Option<Trace> {
+        if !self.should_sample(model) {
+            return None;
+        }
+        (self.factory)(model)
+            .map_err(|err| log::warn!("Can't create trace of the sampled request: {err}"))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling() {
+        let sampler = TraceSampler::new(|_| unreachable!())
+            .rate(3)
+            .count(2)
+            .models(["simple"]);
+        let sampled: Vec<_> = (0..10).map(|_| sampler.should_sample("simple")).collect();
+        assert_eq!(
+            sampled,
+            [true, false, false, true, false, false, false, false, false, false]
+        );
+        assert!(!sampler.should_sample("other"));
+        assert_eq!(sampler.sampled(), 2);
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tracing::{field::Empty, Span};
+
+use super::{Activity, TensorTraceHandler, Trace, TraceHandler};
+use crate::{message::Shape, Buffer};
+
+/// Spans of the trace in progress.
+struct TraceSpans {
+    request: Span,
+    request_start: Duration,
+    queue: Option<(Span, Duration)>,
+    compute: Option<(Span, Duration)>,
+    /// Time of the last compute activity: compute phases durations are measured from it.
+    compute_mark: Duration,
+}
+
+/// [TraceHandler] and [TensorTraceHandler] reporting the trace activities as [tracing] spans,
+/// so the Triton timelines are shown by the tracing subscribers (Jaeger, tokio-console, ...).
+///
+/// Each traced request is reported as `triton.request` span (fields: `model`, `version`, `request_id`, `trace_id`)
+/// with `triton.queue` and `triton.compute` child spans. Spans record `duration_ns` measured by Triton,
+/// compute span also records `input_ns`, `infer_ns` and `output_ns` of its phases. \
+/// Requests of the child traces (e.g. the composing models of the ensemble) are nested in the request span of the parent trace,
+/// tensor activities are reported as debug events of the request span.
+///
+/// Clones of the handler share the spans, so the same handler can be passed as both activity and tensor activity handlers:
+/// ```ignore
+/// let handler = TracingSubscriberHandler::new();
+/// request.add_trace(Trace::new_with_handle(0, Some(handler.clone()), Some(handler))?);
+/// ```
+/// Check [Trace::new_with_tracing] as well.
+#[derive(Clone)]
+pub struct TracingSubscriberHandler {
+    parent: Span,
+    traces: Arc<Mutex<HashMap<u64, TraceSpans>>>,
+}
+
+impl Default for TracingSubscriberHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TracingSubscriberHandler {
+    /// Create the handler nesting the request spans in the current span.
+    pub fn new() -> Self {
+        Self::with_parent(Span::current())
+    }
+
+    /// Create the handler nesting the request spans in the `parent` span.
+    pub fn with_parent(parent: Span) -> Self {
+        TracingSubscriberHandler {
+            parent,
+            traces: Default::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, TraceSpans>> {
+        self.traces.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn request_span(&self, traces: &HashMap<u64, TraceSpans>, trace: &Trace, id: u64) -> Span {
+        let parent = match trace.parent_id() {
+            Ok(parent_id) if parent_id != 0 => traces.get(&parent_id).map(|spans| &spans.request),
+            _ => None,
+        };
+        tracing::info_span!(
+            parent: parent.unwrap_or(&self.parent),
+            "triton.request",
+            model = %trace.model_name().unwrap_or_default(),
+            version = trace.model_version().unwrap_or(-1),
+            request_id = %trace.request_id().unwrap_or_default(),
+            trace_id = id,
+            duration_ns = Empty,
+        )
+    }
+}
+
+fn nanos(from: Duration, to: Duration) -> u64 {
+    to.saturating_sub(from).as_nanos() as u64
+}
+
+impl TraceHandler for TracingSubscriberHandler {
+    fn trace_activity(&self, trace: &Trace, event: Activity, event_time: Duration) {
+        let id = match trace.id() {
+            Ok(id) => id,
+            Err(err) => {
+                log::warn!("Error getting ID of the trace: {err}");
+                return;
+            }
+        };
+
+        let mut traces = self.lock();
+        if !traces.contains_key(&id) {
+            let request = self.request_span(&traces, trace, id);
+            traces.insert(
+                id,
+                TraceSpans {
+                    request,
+                    request_start: event_time,
+                    queue: None,
+                    compute: None,
+                    compute_mark: event_time,
+                },
+            );
+        }
+        let spans = traces.get_mut(&id).unwrap();
+
+        match event {
+            Activity::RequestStart => spans.request_start = event_time,
+            Activity::QueueStart => {
+                let span = tracing::info_span!(parent: &spans.request, "triton.queue", duration_ns = Empty);
+                spans.queue = Some((span, event_time));
+            }
+            Activity::ComputeStart => {
+                if let Some((span, start)) = spans.queue.take() {
+                    span.record("duration_ns", nanos(start, event_time));
+                }
+                let span = tracing::info_span!(
+                    parent: &spans.request,
+                    "triton.compute",
+                    input_ns = Empty,
+                    infer_ns = Empty,
+                    output_ns = Empty,
+                    duration_ns = Empty,
+                );
+                spans.compute = Some((span, event_time));
+                spans.compute_mark = event_time;
+            }
+            Activity::ComputeInputEnd | Activity::ComputeOutputStart | Activity::ComputeEnd => {
+                if let Some((span, start)) = spans.compute.as_ref() {
+                    let field = match event {
+                        Activity::ComputeInputEnd => "input_ns",
+                        Activity::ComputeOutputStart => "infer_ns",
+                        _ => "output_ns",
+                    };
+                    span.record(field, nanos(spans.compute_mark, event_time));
+                    if event == Activity::ComputeEnd {
+                        span.record("duration_ns", nanos(*start, event_time));
+                    }
+                }
+                spans.compute_mark = event_time;
+                if event == Activity::ComputeEnd {
+                    spans.compute = None;
+                }
+            }
+            Activity::RequestEnd => {
+                if let Some(spans) = traces.remove(&id) {
+                    spans
+                        .request
+                        .record("duration_ns", nanos(spans.request_start, event_time));
+                }
+            }
+            Activity::CustomActivity => {
+                tracing::info!(parent: &spans.request, timestamp_ns = event_time.as_nanos() as u64, "custom activity");
+            }
+            Activity::TensorQueueInput
+            | Activity::TensorBackendInput
+            | Activity::TensorBackendOutput => {}
+        }
+    }
+}
+
+impl TensorTraceHandler for TracingSubscriberHandler {
+    fn trace_tensor_activity(
+        &self,
+        trace: &Trace,
+        event: Activity,
+        tensor_data: &Buffer,
+        tensor_shape: Shape,
+    ) {
+        let traces = self.lock();
+        let span = trace
+            .id()
+            .ok()
+            .and_then(|id| traces.get(&id))
+            .map_or(&self.parent, |spans| &spans.request);
+        tracing::debug!(
+            parent: span,
+            activity = ?event,
+            tensor = %tensor_shape.name,
+            data_type = %tensor_shape.datatype,
+            dims = ?tensor_shape.dims,
+            memory_type = %tensor_data.memory_type(),
+            byte_size = tensor_data.size(),
+            "tensor activity"
+        );
+    }
+}